@@ -5,19 +5,23 @@ THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
 Copyright 2025 Vincent Maciejewski, & M2 Tech
 """
 
+import fnmatch
 import json
+import queue
+import random
 import subprocess
 import threading
 import time
+import uuid
 from dataclasses import dataclass, field
 from pathlib import Path
-from typing import Dict, List, Optional, Set
+from typing import Callable, Dict, List, Optional, Set
 import logging
 
 from actors import Actor, Manager, LocalActorRef
 from .registry_messages import (
-    RegisterActor, UnregisterActor, RegistrationOk, RegistrationFailed,
-    LookupActor, LookupResult, Heartbeat, HeartbeatAck,
+    RegisterActor, UnregisterActor, RegistrationOk, RegistrationFailed, NotOwner,
+    LookupActor, LookupResult, Heartbeat, HeartbeatAck, Announce,
     StartManager, StopManager, RestartManager, ManagerStatus
 )
 
@@ -29,6 +33,43 @@ class ActorEntry:
     """Registry entry for an actor."""
     endpoint: str
     manager_id: str
+    datacenter: str = ''
+    max_capacity: Optional[int] = None  # None = not load-tracked
+    current_load: int = 0
+    version: Optional[int] = None  # None = unversioned
+    priority: Optional[int] = None  # None = unweighted, 0 = standby only
+
+    def weight(self) -> int:
+        """Share of Weighted lookups: the priority, 1 if unweighted."""
+        return 1 if self.priority is None else self.priority
+
+    def remaining_capacity(self) -> Optional[int]:
+        """max_capacity - current_load, or None if not load-tracked."""
+        if self.max_capacity is None:
+            return None
+        return self.max_capacity - self.current_load
+
+    def is_instance(self) -> bool:
+        """Whether it is one of many instances of its name, one per endpoint:
+        weighted or load-tracked."""
+        return self.priority is not None or self.max_capacity is not None
+
+
+def versioned_key(actor_name: str, version: Optional[int] = None) -> str:
+    """Registry name of one version of an actor: 'name#v2'.
+
+    Versions of the same actor are registered side by side, and are only
+    found by lookups with a version constraint.
+    """
+    return f"{actor_name}#v{version}" if version is not None else actor_name
+
+
+@dataclass
+class PreparedTransaction:
+    """A transaction that passed the prepare phase and holds its actor names."""
+    manager_id: str
+    operations: List[dict]
+    prepared_at: float  # monotonic
 
 
 @dataclass
@@ -58,6 +99,7 @@ class GlobalRegistry(Actor):
 
     HEARTBEAT_TIMEOUT_S = 6.0  # 3 missed heartbeats (2s each)
     HEARTBEAT_CHECK_INTERVAL_S = 1.0
+    TRANSACTION_TIMEOUT_S = 30.0  # prepared transactions are aborted after this
 
     def __init__(self, config_path: Optional[str] = None):
         super().__init__()
@@ -65,9 +107,17 @@ class GlobalRegistry(Actor):
         # actor_name -> ActorEntry
         self._registry: Dict[str, ActorEntry] = {}
 
+        # actor_name -> its registrations by geo-aware managers, at most one
+        # per datacenter, and its weighted or load-tracked instances, one per
+        # endpoint (the plain registration is in _registry)
+        self._instances: Dict[str, List[ActorEntry]] = {}
+
         # manager_id -> last_heartbeat_time (monotonic)
         self._heartbeats: Dict[str, float] = {}
 
+        # manager_id -> metadata from its latest heartbeat
+        self._manager_metadata: Dict[str, Dict[str, str]] = {}
+
         # manager_id -> set of actor_names
         self._manager_actors: Dict[str, Set[str]] = {}
 
@@ -77,6 +127,27 @@ class GlobalRegistry(Actor):
         # manager_id -> host_id mapping
         self._manager_to_host: Dict[str, str] = {}
 
+        # group_name -> set of member actor_names
+        self._groups: Dict[str, Set[str]] = {}
+
+        # txn_id -> PreparedTransaction (prepared but not yet committed)
+        self._transactions: Dict[str, PreparedTransaction] = {}
+
+        # Called with each manager lifecycle event (see _emit_manager_event)
+        self._event_listeners: List[Callable[[dict], None]] = []
+
+        # actor_name -> managers subscribed to its events (see subscribe_actor)
+        self._actor_subscribers: Dict[str, Set[str]] = {}
+
+        # Called with each actor availability event (see _emit_actor_event)
+        self._actor_event_listeners: List[Callable[[dict], None]] = []
+
+        # Called with each manager announcement (see announce)
+        self._announce_listeners: List[Callable[[dict], None]] = []
+
+        # Glob patterns of actor names that may not register
+        self._name_blacklist: List[str] = []
+
         # Load config if provided
         if config_path:
             self._load_config(config_path)
@@ -104,6 +175,9 @@ class GlobalRegistry(Actor):
             for manager_id in host_data.get("managers", {}).keys():
                 self._manager_to_host[manager_id] = host_id
 
+        for pattern in config.get("name_blacklist", []):
+            self.add_name_blacklist(pattern)
+
         logger.info(f"Loaded config with {len(self._hosts)} hosts")
 
     def init(self) -> None:
@@ -141,21 +215,217 @@ class GlobalRegistry(Actor):
 
         for manager_id in stale_managers:
             logger.warning(f"Manager '{manager_id}' timed out, unregistering its actors")
-            self._unregister_manager(manager_id)
-
-    def _unregister_manager(self, manager_id: str) -> None:
-        """Unregister all actors belonging to a manager."""
-        # Get actors for this manager
-        actor_names = self._manager_actors.pop(manager_id, set())
-
-        # Remove each actor from registry
-        for actor_name in actor_names:
-            if actor_name in self._registry:
-                del self._registry[actor_name]
-                logger.info(f"Unregistered '{actor_name}' (manager '{manager_id}' timed out)")
+            self._unregister_manager(manager_id, graceful=False)
+
+        # Abort transactions whose client never sent CommitTxn/AbortTxn
+        stale_txns = [
+            txn_id for txn_id, txn in self._transactions.items()
+            if now - txn.prepared_at > self.TRANSACTION_TIMEOUT_S
+        ]
+        for txn_id in stale_txns:
+            logger.warning(f"Transaction '{txn_id}' timed out, aborting")
+            self.abort_transaction(txn_id)
+
+    def _unregister_manager(self, manager_id: str, graceful: bool = False) -> None:
+        """Unregister all actors belonging to a manager and announce that it left."""
+        # Actors of a timed-out manager went offline
+        self._remove_manager_actors(manager_id, 'Unregistered' if graceful else 'Offline')
 
         # Remove heartbeat tracking
-        self._heartbeats.pop(manager_id, None)
+        self._manager_metadata.pop(manager_id, None)
+        self._drop_subscriptions(manager_id)
+        if self._heartbeats.pop(manager_id, None) is not None:
+            self._emit_manager_event('Left', manager_id, graceful=graceful)
+
+    def deregister_manager(self, manager_id: str) -> int:
+        """Unregister all actors of a manager that is still alive. Returns how many were removed."""
+        return self._remove_manager_actors(manager_id, 'Unregistered')
+
+    def _remove_manager_actors(self, manager_id: str, event: str) -> int:
+        """Remove a manager's actors, announcing each with `event`. Returns how many were removed."""
+        removed = 0
+        for actor_name in self._manager_actors.pop(manager_id, set()):
+            for entry in self.entries(actor_name):
+                if entry.manager_id == manager_id:
+                    self._remove_entry(actor_name, entry, event)
+                    logger.info(f"Unregistered '{actor_name}' (manager '{manager_id}')")
+                    removed += 1
+        return removed
+
+    # Actor entries
+
+    def entries(self, actor_name: str) -> List[ActorEntry]:
+        """Every registration of actor_name: the plain one first, then those
+        of geo-aware managers and the weighted or load-tracked instances."""
+        plain = self._registry.get(actor_name)
+        return ([plain] if plain else []) + self._instances.get(actor_name, [])
+
+    def find_entry(self, actor_name: str, datacenter: str = '',
+                   endpoint: Optional[str] = None) -> Optional[ActorEntry]:
+        """The single registration of actor_name in a datacenter ('' for the
+        plain one), or with endpoint, its instance there (see is_instance)."""
+        by_endpoint = endpoint is not None
+        if not by_endpoint and not datacenter:
+            return self._registry.get(actor_name)
+        return next((entry for entry in self._instances.get(actor_name, [])
+                     if entry.datacenter == datacenter
+                     and entry.is_instance() == by_endpoint
+                     and (not by_endpoint or entry.endpoint == endpoint)), None)
+
+    def find_registration(self, msg: RegisterActor) -> Optional[ActorEntry]:
+        """The entry a RegisterActor message would register again, if any."""
+        is_instance = msg.priority is not None or msg.max_capacity is not None
+        endpoint = msg.actor_endpoint if is_instance else None
+        return self.find_entry(versioned_key(msg.actor_name, msg.version), msg.datacenter, endpoint)
+
+    def _all_entries(self):
+        """Every (actor_name, entry) registration."""
+        for actor_name in self.get_all_actors():
+            for entry in self.entries(actor_name):
+                yield actor_name, entry
+
+    def _store_entry(self, actor_name: str, entry: ActorEntry) -> None:
+        """Store a registration and track its manager."""
+        if entry.datacenter or entry.is_instance():
+            self._instances.setdefault(actor_name, []).append(entry)
+        else:
+            self._registry[actor_name] = entry
+        self._manager_actors.setdefault(entry.manager_id, set()).add(actor_name)
+
+    def _add_entry(self, actor_name: str, entry: ActorEntry) -> None:
+        """Store a new registration and announce it Online."""
+        self._store_entry(actor_name, entry)
+        self._touch_heartbeat(entry.manager_id)
+        self._emit_actor_event('Online', actor_name, endpoint=entry.endpoint, datacenter=entry.datacenter)
+
+    def _remove_entry(self, actor_name: str, entry: ActorEntry, event: str) -> None:
+        """Drop one registration of actor_name, announcing it with `event`.
+
+        The name leaves its groups once no registration of it is left.
+        """
+        if entry is self._registry.get(actor_name):
+            del self._registry[actor_name]
+        else:
+            instances = [other for other in self._instances[actor_name] if other is not entry]
+            if instances:
+                self._instances[actor_name] = instances
+            else:
+                del self._instances[actor_name]
+
+        remaining = self.entries(actor_name)
+        if entry.manager_id in self._manager_actors and \
+                not any(other.manager_id == entry.manager_id for other in remaining):
+            self._manager_actors[entry.manager_id].discard(actor_name)
+        if not remaining:
+            self._leave_groups(actor_name)
+        self._emit_actor_event(event, actor_name, datacenter=entry.datacenter)
+
+    # Actor subscriptions
+
+    def subscribe_actor(self, manager_id: str, actor_name: str) -> None:
+        """Record that a manager follows an actor's events, which it receives
+        on the actor's PUB topic."""
+        self._actor_subscribers.setdefault(actor_name, set()).add(manager_id)
+
+    def unsubscribe_actor(self, manager_id: str, actor_name: str) -> None:
+        """Forget a manager's subscription to an actor's events."""
+        subscribers = self._actor_subscribers.get(actor_name)
+        if subscribers is not None:
+            subscribers.discard(manager_id)
+            if not subscribers:
+                del self._actor_subscribers[actor_name]
+
+    def actor_subscribers(self, actor_name: str) -> List[str]:
+        """Managers subscribed to an actor's events."""
+        return sorted(self._actor_subscribers.get(actor_name, set()))
+
+    def _drop_subscriptions(self, manager_id: str) -> None:
+        """Forget all subscriptions of a manager that left."""
+        for actor_name in list(self._actor_subscribers):
+            self.unsubscribe_actor(manager_id, actor_name)
+
+    # Manager lifecycle events
+
+    def add_event_listener(self, listener: Callable[[dict], None]) -> None:
+        """Register a callback for manager Joined/Left events."""
+        self._event_listeners.append(listener)
+
+    def _emit_manager_event(self, event: str, manager_id: str, **extra) -> None:
+        """Notify listeners that a manager joined or left."""
+        payload = {
+            'event': event,
+            'manager_id': manager_id,
+            'timestamp_ms': int(time.time() * 1000),
+            **extra,
+        }
+        for listener in self._event_listeners:
+            listener(payload)
+
+    def add_actor_event_listener(self, listener: Callable[[dict], None]) -> None:
+        """Register a callback for actor Online/Offline/Unregistered events."""
+        self._actor_event_listeners.append(listener)
+
+    def _emit_actor_event(self, event: str, actor_name: str, **extra) -> None:
+        """Notify listeners that an actor's availability changed."""
+        payload = {
+            'event': event,
+            'actor_name': actor_name,
+            'timestamp_ms': int(time.time() * 1000),
+            **extra,
+        }
+        for listener in self._actor_event_listeners:
+            listener(payload)
+
+    def add_announce_listener(self, listener: Callable[[dict], None]) -> None:
+        """Register a callback for manager announcements."""
+        self._announce_listeners.append(listener)
+
+    def announce(self, msg: Announce) -> int:
+        """Fan a manager's announcement out to the listeners.
+
+        Only the actors registered here by that manager, at the announced
+        endpoint, are passed on, so a manager cannot announce another's.
+        Returns the number of actors announced.
+        """
+        actors = [[name, endpoint] for name, endpoint in msg.actors
+                  if any(entry.manager_id == msg.manager_id and entry.endpoint == endpoint
+                         for entry in self.entries(name))]
+        payload = {
+            'manager_id': msg.manager_id,
+            'actors': actors,
+            'timestamp_ms': int(time.time() * 1000),
+        }
+        for listener in self._announce_listeners:
+            listener(payload)
+        return len(actors)
+
+    def _touch_heartbeat(self, manager_id: str) -> None:
+        """Record that a manager is alive, announcing it if it was not known."""
+        joined = manager_id not in self._heartbeats
+        self._heartbeats[manager_id] = time.monotonic()
+        if joined:
+            self._emit_manager_event('Joined', manager_id)
+
+    def _record_heartbeat(self, manager_id: str, metadata: Optional[dict]) -> None:
+        """Handle a manager's heartbeat, replacing its metadata with the
+        metadata it carries (none, or an empty dict, clears it)."""
+        self._touch_heartbeat(manager_id)
+        if metadata:
+            self._manager_metadata[manager_id] = dict(metadata)
+        else:
+            self._manager_metadata.pop(manager_id, None)
+
+    def manager_metadata(self, manager_id: str) -> Dict[str, str]:
+        """Metadata from a manager's latest heartbeat (empty if it sent none)."""
+        return dict(self._manager_metadata.get(manager_id, {}))
+
+    def managers_matching(self, **filters: str) -> List[str]:
+        """Managers whose heartbeat metadata has all the given values, e.g.
+        managers_matching(tenant_id='acme', region='eu')."""
+        return sorted(
+            manager_id for manager_id, metadata in self._manager_metadata.items()
+            if all(metadata.get(key) == value for key, value in filters.items())
+        )
 
     def is_manager_online(self, manager_id: str) -> bool:
         """Check if a manager has recent heartbeat."""
@@ -171,59 +441,390 @@ class GlobalRegistry(Actor):
             return entry.endpoint
         return None
 
+    def resolve(self, actor_name: str, datacenter: str = '',
+                policy: str = '') -> Optional[ActorEntry]:
+        """Pick the entry to answer a lookup of actor_name with.
+
+        Considers the plain registration and every per-datacenter one. Online
+        entries win; among those, one in `datacenter` is preferred, then the
+        plain registration. Returns an offline entry only if none is online.
+        With policy 'LeastLoaded', the online entry with the most remaining
+        capacity wins, across datacenters and the instances within each;
+        entries without a capacity come last. With policy
+        'Weighted', an online entry is picked at random in proportion to its
+        priority (unweighted entries count 1). Standby entries (priority 0)
+        are only returned when no other entry is online.
+        """
+        candidates = self.entries(actor_name)
+        if not candidates:
+            return None
+
+        if policy == 'Weighted':
+            active = [entry for entry in candidates
+                      if entry.weight() > 0 and self.is_manager_online(entry.manager_id)]
+            if active:
+                return random.choices(active, weights=[entry.weight() for entry in active])[0]
+
+        plain = self._registry.get(actor_name)
+
+        def rank(entry):
+            remaining = entry.remaining_capacity()
+            load_rank = (remaining is None, -(remaining or 0)) if policy == 'LeastLoaded' else ()
+            return (
+                not self.is_manager_online(entry.manager_id),
+                entry.priority == 0,
+                *load_rank,
+                not (datacenter and entry.datacenter == datacenter),
+                entry is not plain,
+            )
+
+        return min(candidates, key=rank)
+
+    def resolve_version(self, actor_name: str, constraint: str, version: int = 0,
+                        datacenter: str = '') -> Optional[ActorEntry]:
+        """Pick the versioned entry of actor_name that satisfies a constraint.
+
+        constraint is 'Exact' (that version), 'AtLeast' (that version or
+        newer) or 'Latest' (any version). Online entries win; among those the
+        newest version, then one in `datacenter`. Returns an offline entry
+        only if none is online.
+        """
+        def satisfies(entry: ActorEntry) -> bool:
+            if constraint == 'Exact':
+                return entry.version == version
+            if constraint == 'AtLeast':
+                return entry.version >= version
+            return constraint == 'Latest'
+
+        candidates = [
+            entry for name, entry in self._all_entries()
+            if entry.version is not None
+            and name == versioned_key(actor_name, entry.version)
+            and satisfies(entry)
+        ]
+        if not candidates:
+            return None
+
+        return min(candidates, key=lambda entry: (
+            not self.is_manager_online(entry.manager_id),
+            -entry.version,
+            not (datacenter and entry.datacenter == datacenter),
+        ))
+
+    def reprioritize(self, msg: RegisterActor) -> bool:
+        """Apply a weighted instance's re-registration by its own manager as a
+        change of priority. Returns False if it is not such a re-registration."""
+        entry = self.find_registration(msg)
+        if entry is None or msg.priority is None or entry.manager_id != msg.manager_id:
+            return False
+        entry.priority = msg.priority
+        self._touch_heartbeat(msg.manager_id)
+        logger.info(f"Priority of '{msg.actor_name}' at {msg.actor_endpoint} set to {msg.priority}")
+        return True
+
+    def update_capacity(self, actor_name: str, current_load: int, datacenter: str = '',
+                        manager_id: Optional[str] = None) -> bool:
+        """Record the current load of the load-tracked instances of actor_name
+        that manager_id registered in a datacenter (with no manager_id, of
+        every instance there). Returns False if there is none."""
+        entries = [entry for entry in self.entries(actor_name)
+                   if entry.max_capacity is not None and entry.datacenter == datacenter
+                   and manager_id in (None, entry.manager_id)]
+        for entry in entries:
+            entry.current_load = current_load
+            self._touch_heartbeat(entry.manager_id)
+        return bool(entries)
+
     def get_all_actors(self) -> List[str]:
         """Get list of all registered actor names."""
-        return list(self._registry.keys())
+        return list(dict.fromkeys([*self._registry, *self._instances]))
 
     def get_all_managers(self) -> List[str]:
         """Get list of all registered manager IDs."""
         return list(self._manager_actors.keys())
 
+    def list_actors(self) -> List[dict]:
+        """Describe every registered actor (name, endpoint, manager, online,
+        datacenter), once per registration of its name."""
+        return [
+            {
+                'actor_name': actor_name,
+                'endpoint': entry.endpoint,
+                'manager_id': entry.manager_id,
+                'online': self.is_manager_online(entry.manager_id),
+                'datacenter': entry.datacenter,
+            }
+            for actor_name, entry in self._all_entries()
+        ]
+
+    # Name blacklist
+
+    def add_name_blacklist(self, pattern: str) -> None:
+        """Reject registrations of actor names matching a glob pattern."""
+        self._name_blacklist.append(pattern)
+
+    def is_name_blacklisted(self, actor_name: str) -> bool:
+        """Check if an actor name matches a blacklisted pattern."""
+        return any(fnmatch.fnmatchcase(actor_name, pattern) for pattern in self._name_blacklist)
+
+    # Actor groups
+
+    def add_group_member(self, manager_id: str, group_name: str, actor_name: str,
+                         endpoint: str) -> Optional[str]:
+        """Add an actor to a group, registering it first if needed.
+
+        Returns None on success or the reason the actor could not be added.
+        """
+        entry = self._registry.get(actor_name)
+        if entry is None:
+            if self.is_name_blacklisted(actor_name):
+                return "Blacklisted name"
+            if self.is_name_reserved(actor_name):
+                return "Name reserved by another transaction"
+            self._registry[actor_name] = ActorEntry(endpoint=endpoint, manager_id=manager_id)
+            self._manager_actors.setdefault(manager_id, set()).add(actor_name)
+            self._emit_actor_event('Online', actor_name, endpoint=endpoint)
+        elif entry.endpoint != endpoint or entry.manager_id != manager_id:
+            return "Name already registered"
+
+        self._groups.setdefault(group_name, set()).add(actor_name)
+        self._touch_heartbeat(manager_id)
+        logger.info(f"Added '{actor_name}' to group '{group_name}'")
+        return None
+
+    def group_members(self, group_name: str) -> List[dict]:
+        """Describe the online members of a group, as in list_actors()."""
+        members = self._groups.get(group_name, set())
+        return sorted(
+            (actor for actor in self.list_actors()
+             if actor['actor_name'] in members and actor['online']),
+            key=lambda actor: actor['actor_name']
+        )
+
+    def _leave_groups(self, actor_name: str) -> None:
+        """Remove an unregistered actor from every group, dropping empty groups."""
+        for group_name in list(self._groups):
+            self._groups[group_name].discard(actor_name)
+            if not self._groups[group_name]:
+                del self._groups[group_name]
+
+    # Transactions (two-phase commit)
+
+    def is_name_reserved(self, actor_name: str) -> bool:
+        """Check if a prepared transaction holds this actor name."""
+        return any(
+            op['actor_name'] == actor_name
+            for txn in self._transactions.values()
+            for op in txn.operations
+        )
+
+    def prepare_transaction(self, manager_id: str, operations: List[dict]):
+        """Phase one: validate all operations and reserve their actor names.
+
+        Returns (txn_id, None) on success or (None, (actor_name, reason)) if any
+        operation would fail, in which case nothing is reserved.
+        """
+        seen = set()
+        for op in operations:
+            actor_name = op.get('actor_name', '')
+            if actor_name in seen:
+                return None, (actor_name, "Name appears twice in transaction")
+            seen.add(actor_name)
+            if self.is_name_reserved(actor_name):
+                return None, (actor_name, "Name reserved by another transaction")
+            if op.get('op') == 'register':
+                entry = self._transaction_entry(manager_id, op)
+                endpoint = entry.endpoint if entry.is_instance() else None
+                if self.find_entry(versioned_key(actor_name, entry.version), entry.datacenter, endpoint):
+                    return None, (actor_name, "Name already registered")
+            elif op.get('op') == 'deregister':
+                if self.find_entry(actor_name, op.get('datacenter') or '') is None:
+                    return None, (actor_name, "Name not registered")
+            else:
+                return None, (actor_name, f"Unknown operation: {op.get('op')}")
+
+        txn_id = uuid.uuid4().hex
+        self._transactions[txn_id] = PreparedTransaction(
+            manager_id=manager_id,
+            operations=list(operations),
+            prepared_at=time.monotonic()
+        )
+        return txn_id, None
+
+    def commit_transaction(self, txn_id: str) -> bool:
+        """Phase two: apply a prepared transaction. Returns False if unknown."""
+        txn = self._transactions.pop(txn_id, None)
+        if txn is None:
+            return False
+
+        for op in txn.operations:
+            actor_name = op['actor_name']
+            if op['op'] == 'register':
+                entry = self._transaction_entry(txn.manager_id, op)
+                self._add_entry(versioned_key(actor_name, entry.version), entry)
+            else:
+                self._unregister(actor_name, datacenter=op.get('datacenter') or '')
+
+        self._touch_heartbeat(txn.manager_id)
+        logger.info(f"Committed transaction '{txn_id}' ({len(txn.operations)} operations)")
+        return True
+
+    @staticmethod
+    def _transaction_entry(manager_id: str, op: dict) -> ActorEntry:
+        """The entry a transaction's register operation adds, from the same
+        fields as a RegisterActor message."""
+        return ActorEntry(
+            endpoint=op['actor_endpoint'],
+            manager_id=manager_id,
+            datacenter=op.get('datacenter') or '',
+            max_capacity=op.get('max_capacity'),
+            version=op.get('version'),
+            priority=op.get('priority')
+        )
+
+    def abort_transaction(self, txn_id: str) -> None:
+        """Discard a prepared transaction and release its actor names."""
+        self._transactions.pop(txn_id, None)
+
+    # Snapshots (disaster recovery)
+
+    SNAPSHOT_VERSION = 1
+
+    def export_state(self) -> dict:
+        """Dump all actors, managers and groups as a JSON-compatible dict.
+
+        Each registration of a name is listed, with its datacenter. Versioned
+        registrations are listed under their versioned name (name#v2).
+        """
+        return {
+            'version': self.SNAPSHOT_VERSION,
+            'actors': [
+                {
+                    'actor_name': actor_name,
+                    'endpoint': entry.endpoint,
+                    'manager_id': entry.manager_id,
+                    'datacenter': entry.datacenter,
+                    'max_capacity': entry.max_capacity,
+                    'current_load': entry.current_load,
+                    'version': entry.version,
+                    'priority': entry.priority,
+                }
+                for actor_name, entry in sorted(self._all_entries(),
+                                                key=lambda item: (item[0], item[1].datacenter))
+            ],
+            'managers': sorted(self._manager_actors),
+            'groups': {name: sorted(members) for name, members in sorted(self._groups.items())},
+        }
+
+    def import_state(self, state: dict) -> int:
+        """Replace all actors, managers and groups with an export_state() dump.
+
+        Managers in the snapshot count as online until they miss their
+        heartbeats. Prepared transactions are discarded. Returns the number
+        of actors imported; raises ValueError (leaving the registry
+        unchanged) if the snapshot is malformed.
+        """
+        try:
+            if state['version'] != self.SNAPSHOT_VERSION:
+                raise ValueError(f"Unsupported snapshot version: {state['version']}")
+            entries = [
+                (actor['actor_name'], ActorEntry(
+                    endpoint=actor['endpoint'],
+                    manager_id=actor['manager_id'],
+                    datacenter=actor.get('datacenter', ''),
+                    max_capacity=actor.get('max_capacity'),
+                    current_load=actor.get('current_load', 0),
+                    version=actor.get('version'),
+                    priority=actor.get('priority')
+                ))
+                for actor in state['actors']
+            ]
+            managers = list(state.get('managers', []))
+            names = {actor_name for actor_name, _ in entries}
+            groups = {name: set(members) & names
+                      for name, members in state.get('groups', {}).items()}
+        except (KeyError, TypeError, AttributeError) as e:
+            raise ValueError(f"Malformed snapshot: missing or invalid {e}") from e
+
+        self._registry = {}
+        self._instances = {}
+        self._manager_actors = {manager_id: set() for manager_id in managers}
+        for actor_name, entry in entries:
+            self._store_entry(actor_name, entry)
+        self._groups = {name: members for name, members in groups.items() if members}
+        self._transactions.clear()
+        for manager_id in self._manager_actors:
+            self._touch_heartbeat(manager_id)
+        logger.info(f"Imported snapshot with {len(entries)} actors")
+        return len(entries)
+
     # Message handlers
 
     def _on_register(self, msg: RegisterActor, ctx) -> None:
         """Handle actor registration."""
-        if msg.actor_name in self._registry:
-            logger.warning(f"Registration failed: '{msg.actor_name}' already registered")
+        key = versioned_key(msg.actor_name, msg.version)
+        if self.is_name_blacklisted(msg.actor_name):
+            logger.warning(f"Registration failed: '{msg.actor_name}' is blacklisted")
+            ctx.reply(RegistrationFailed(
+                actor_name=msg.actor_name,
+                reason="Blacklisted name"
+            ))
+            return
+        if self.reprioritize(msg):
+            ctx.reply(RegistrationOk(actor_name=msg.actor_name))
+            return
+        if self.find_registration(msg) is not None:
+            logger.warning(f"Registration failed: '{key}' already registered")
             ctx.reply(RegistrationFailed(
                 actor_name=msg.actor_name,
                 reason="Name already registered"
             ))
             return
 
-        # Register the actor
-        self._registry[msg.actor_name] = ActorEntry(
+        # Register the actor (registration counts as heartbeat)
+        self._add_entry(key, ActorEntry(
             endpoint=msg.actor_endpoint,
-            manager_id=msg.manager_id
-        )
-
-        # Track which actors belong to which manager
-        if msg.manager_id not in self._manager_actors:
-            self._manager_actors[msg.manager_id] = set()
-        self._manager_actors[msg.manager_id].add(msg.actor_name)
-
-        # Registration counts as heartbeat
-        self._heartbeats[msg.manager_id] = time.monotonic()
+            manager_id=msg.manager_id,
+            datacenter=msg.datacenter,
+            max_capacity=msg.max_capacity,
+            version=msg.version,
+            priority=msg.priority
+        ))
 
-        logger.info(f"Registered '{msg.actor_name}' from manager '{msg.manager_id}'")
+        logger.info(f"Registered '{key}' from manager '{msg.manager_id}'")
         ctx.reply(RegistrationOk(actor_name=msg.actor_name))
 
     def _on_unregister(self, msg: UnregisterActor, ctx) -> None:
         """Handle actor unregistration."""
-        entry = self._registry.pop(msg.actor_name, None)
+        key = versioned_key(msg.actor_name, msg.version)
+        entry = self._unregister(key, msg.manager_id, msg.datacenter, msg.actor_endpoint)
         if entry is None:
-            logger.warning(f"Unregister failed: '{msg.actor_name}' not found")
-            return
-
-        # Remove from manager's actor set
-        if entry.manager_id in self._manager_actors:
-            self._manager_actors[entry.manager_id].discard(msg.actor_name)
-
-        logger.info(f"Unregistered '{msg.actor_name}'")
+            logger.warning(f"Unregister failed: '{key}' not found")
+        elif msg.manager_id and entry.manager_id != msg.manager_id:
+            logger.warning(f"Unregister of '{key}' by '{msg.manager_id}' refused: "
+                           f"owned by '{entry.manager_id}'")
+
+    def _unregister(self, key: str, owner: Optional[str] = None, datacenter: str = '',
+                    endpoint: Optional[str] = None) -> Optional[ActorEntry]:
+        """Remove an actor's entry in a datacenter ('' for the plain one), or
+        with endpoint, its instance there. With owner, an entry
+        registered by another manager is kept. Returns the entry found,
+        whether removed or not."""
+        entry = self.find_entry(key, datacenter, endpoint)
+        if entry is None or (owner and entry.manager_id != owner):
+            return entry
+        self._remove_entry(key, entry, 'Unregistered')
+
+        logger.info(f"Unregistered '{key}'")
+        return entry
 
     def _on_lookup(self, msg: LookupActor, ctx) -> None:
         """Handle actor lookup."""
-        entry = self._registry.get(msg.actor_name)
+        if msg.version:
+            entry = self.resolve_version(msg.actor_name, msg.version.get('constraint', ''),
+                                         msg.version.get('version', 0), msg.datacenter)
+        else:
+            entry = self.resolve(msg.actor_name, msg.datacenter, msg.policy)
 
         if entry is None:
             ctx.reply(LookupResult(
@@ -237,12 +838,14 @@ class GlobalRegistry(Actor):
         ctx.reply(LookupResult(
             actor_name=msg.actor_name,
             endpoint=entry.endpoint,
-            online=online
+            online=online,
+            datacenter=entry.datacenter,
+            version=entry.version
         ))
 
     def _on_heartbeat(self, msg: Heartbeat, ctx) -> None:
         """Handle heartbeat from manager."""
-        self._heartbeats[msg.manager_id] = time.monotonic()
+        self._record_heartbeat(msg.manager_id, msg.metadata)
         ctx.reply(HeartbeatAck())
 
     # Process management via SSH
@@ -323,12 +926,26 @@ class GlobalRegistry(Actor):
         self._systemctl_command(manager_id, "restart")
 
 
-def run_registry(endpoint: str = "tcp://0.0.0.0:5555", config_path: str = None):
+def default_events_endpoint(endpoint: str) -> str:
+    """Endpoint for manager lifecycle events: the registry endpoint's port + 1."""
+    host, _, port = endpoint.rpartition(':')
+    return f"{host}:{int(port) + 1}"
+
+
+def run_registry(endpoint: str = "tcp://0.0.0.0:5555", config_path: str = None,
+                 events_endpoint: str = None):
     """Run the GlobalRegistry as a standalone ZMQ server.
 
+    Manager Joined/Left events are published on a PUB socket under the
+    'ManagerEvent' topic as [topic, json] frames. Actor Online/Offline/
+    Unregistered events are published on the same socket under the topic
+    'ActorEvent:<actor_name>'. Manager announcements are published under
+    the 'Announce' topic.
+
     Args:
         endpoint: ZMQ endpoint to bind to (default: tcp://0.0.0.0:5555)
         config_path: Optional path to registry.json config file
+        events_endpoint: PUB endpoint for manager and actor events (default: endpoint port + 1)
     """
     import zmq
     import signal
@@ -353,6 +970,19 @@ def run_registry(endpoint: str = "tcp://0.0.0.0:5555", config_path: str = None):
     socket = context.socket(zmq.REP)
     socket.bind(endpoint)
 
+    # Events are raised on the monitor thread too, so they are queued and
+    # published from this thread (ZMQ sockets are not thread-safe)
+    events_endpoint = events_endpoint or default_events_endpoint(endpoint)
+    events_socket = context.socket(zmq.PUB)
+    events_socket.bind(events_endpoint)
+    pending_events: queue.Queue = queue.Queue()
+    registry.add_event_listener(lambda event: pending_events.put((b'ManagerEvent', event)))
+    registry.add_actor_event_listener(lambda event: pending_events.put(
+        (b'ActorEvent:' + event['actor_name'].encode('utf-8'), event)
+    ))
+    registry.add_announce_listener(lambda announcement: pending_events.put((b'Announce', announcement)))
+    logger.info(f"Publishing manager and actor events on {events_endpoint}")
+
     running = True
 
     def signal_handler(sig, frame):
@@ -367,6 +997,10 @@ def run_registry(endpoint: str = "tcp://0.0.0.0:5555", config_path: str = None):
 
     while running:
         try:
+            while not pending_events.empty():
+                topic, event = pending_events.get_nowait()
+                events_socket.send_multipart([topic, json.dumps(event).encode('utf-8')])
+
             # Poll with timeout so we can check running flag
             if socket.poll(1000):
                 msg_bytes = socket.recv()
@@ -379,42 +1013,71 @@ def run_registry(endpoint: str = "tcp://0.0.0.0:5555", config_path: str = None):
                     msg = RegisterActor(
                         manager_id=msg_json['manager_id'],
                         actor_name=msg_json['actor_name'],
-                        actor_endpoint=msg_json['actor_endpoint']
+                        actor_endpoint=msg_json['actor_endpoint'],
+                        datacenter=msg_json.get('datacenter', ''),
+                        max_capacity=msg_json.get('max_capacity'),
+                        version=msg_json.get('version'),
+                        priority=msg_json.get('priority')
                     )
-                    if msg.actor_name in registry._registry:
+                    key = versioned_key(msg.actor_name, msg.version)
+                    if registry.is_name_blacklisted(msg.actor_name):
+                        reply = RegistrationFailed(
+                            actor_name=msg.actor_name,
+                            reason="Blacklisted name"
+                        )
+                    elif registry.reprioritize(msg):
+                        reply = RegistrationOk(actor_name=msg.actor_name)
+                    elif registry.find_registration(msg) is not None:
                         reply = RegistrationFailed(
                             actor_name=msg.actor_name,
                             reason="Name already registered"
                         )
+                    elif registry.is_name_reserved(key):
+                        reply = RegistrationFailed(
+                            actor_name=msg.actor_name,
+                            reason="Name reserved by another transaction"
+                        )
                     else:
-                        registry._registry[msg.actor_name] = ActorEntry(
+                        registry._add_entry(key, ActorEntry(
                             endpoint=msg.actor_endpoint,
-                            manager_id=msg.manager_id
-                        )
-                        if msg.manager_id not in registry._manager_actors:
-                            registry._manager_actors[msg.manager_id] = set()
-                        registry._manager_actors[msg.manager_id].add(msg.actor_name)
-                        registry._heartbeats[msg.manager_id] = time.monotonic()
-                        logger.info(f"Registered '{msg.actor_name}' from '{msg.manager_id}'")
+                            manager_id=msg.manager_id,
+                            datacenter=msg.datacenter,
+                            max_capacity=msg.max_capacity,
+                            version=msg.version,
+                            priority=msg.priority
+                        ))
+                        logger.info(f"Registered '{key}' from '{msg.manager_id}'")
                         reply = RegistrationOk(actor_name=msg.actor_name)
 
                 elif msg_type == 'UnregisterActor':
                     actor_name = msg_json['actor_name']
-                    entry = registry._registry.pop(actor_name, None)
-                    if entry and entry.manager_id in registry._manager_actors:
-                        registry._manager_actors[entry.manager_id].discard(actor_name)
-                    logger.info(f"Unregistered '{actor_name}'")
-                    reply = RegistrationOk(actor_name=actor_name)
+                    key = versioned_key(actor_name, msg_json.get('version'))
+                    owner = msg_json.get('manager_id')
+                    entry = registry._unregister(key, owner, msg_json.get('datacenter', ''),
+                                                 msg_json.get('actor_endpoint'))
+                    if entry and owner and entry.manager_id != owner:
+                        reply = NotOwner(actor_name=actor_name, current_manager=entry.manager_id)
+                    else:
+                        reply = RegistrationOk(actor_name=actor_name)
 
                 elif msg_type == 'LookupActor':
                     actor_name = msg_json['actor_name']
-                    entry = registry._registry.get(actor_name)
+                    version = msg_json.get('version')
+                    if version:
+                        entry = registry.resolve_version(actor_name, version.get('constraint', ''),
+                                                         version.get('version', 0),
+                                                         msg_json.get('datacenter', ''))
+                    else:
+                        entry = registry.resolve(actor_name, msg_json.get('datacenter', ''),
+                                                 msg_json.get('policy', ''))
                     if entry:
                         online = registry.is_manager_online(entry.manager_id)
                         reply = LookupResult(
                             actor_name=actor_name,
                             endpoint=entry.endpoint,
-                            online=online
+                            online=online,
+                            datacenter=entry.datacenter,
+                            version=entry.version
                         )
                     else:
                         reply = LookupResult(
@@ -423,11 +1086,133 @@ def run_registry(endpoint: str = "tcp://0.0.0.0:5555", config_path: str = None):
                             online=False
                         )
 
+                elif msg_type == 'UpdateCapacity':
+                    actor_name = msg_json['actor_name']
+                    if registry.update_capacity(actor_name, msg_json['current_load'],
+                                                msg_json.get('datacenter', ''), msg_json.get('manager_id')):
+                        reply = RegistrationOk(actor_name=actor_name)
+                    else:
+                        reply = RegistrationFailed(actor_name=actor_name, reason="Actor not registered")
+
+                elif msg_type == 'LookupMany':
+                    results = []
+                    for actor_name in msg_json.get('actor_names', []):
+                        entry = registry.resolve(actor_name)
+                        results.append({
+                            'actor_name': actor_name,
+                            'endpoint': entry.endpoint if entry else None,
+                            'online': registry.is_manager_online(entry.manager_id) if entry else False
+                        })
+                    reply = {'message_type': 'LookupManyResult', 'results': results}
+
+                elif msg_type == 'ListActors':
+                    reply = {'message_type': 'ActorList', 'actors': registry.list_actors()}
+
+                elif msg_type == 'ExportState':
+                    reply = {'message_type': 'RegistryState', 'state': registry.export_state()}
+
+                elif msg_type == 'ImportState':
+                    try:
+                        count = registry.import_state(msg_json.get('state'))
+                        reply = {'message_type': 'StateImported', 'count': count}
+                    except ValueError as e:
+                        reply = {'message_type': 'ImportFailed', 'reason': str(e)}
+
+                elif msg_type == 'RegisterGroupMember':
+                    actor_name = msg_json['actor_name']
+                    failure = registry.add_group_member(
+                        msg_json['manager_id'], msg_json['group_name'],
+                        actor_name, msg_json['actor_endpoint']
+                    )
+                    if failure is None:
+                        reply = RegistrationOk(actor_name=actor_name)
+                    else:
+                        reply = RegistrationFailed(actor_name=actor_name, reason=failure)
+
+                elif msg_type == 'LookupGroup':
+                    group_name = msg_json['group_name']
+                    reply = {
+                        'message_type': 'GroupMembers',
+                        'group_name': group_name,
+                        'actors': registry.group_members(group_name),
+                    }
+
+                elif msg_type == 'BeginTxn':
+                    txn_id, failure = registry.prepare_transaction(
+                        msg_json['manager_id'],
+                        msg_json.get('operations', [])
+                    )
+                    if txn_id:
+                        reply = {'message_type': 'TxnPrepared', 'txn_id': txn_id}
+                    else:
+                        actor_name, reason = failure
+                        reply = {
+                            'message_type': 'TxnFailed',
+                            'actor_name': actor_name,
+                            'reason': reason
+                        }
+
+                elif msg_type == 'CommitTxn':
+                    if registry.commit_transaction(msg_json['txn_id']):
+                        reply = {'message_type': 'TxnCommitted', 'txn_id': msg_json['txn_id']}
+                    else:
+                        reply = {
+                            'message_type': 'TxnFailed',
+                            'actor_name': '',
+                            'reason': 'Unknown or expired transaction'
+                        }
+
+                elif msg_type == 'AbortTxn':
+                    registry.abort_transaction(msg_json['txn_id'])
+                    reply = {'message_type': 'TxnAborted', 'txn_id': msg_json['txn_id']}
+
                 elif msg_type == 'Heartbeat':
                     manager_id = msg_json['manager_id']
-                    registry._heartbeats[manager_id] = time.monotonic()
+                    registry._record_heartbeat(manager_id, msg_json.get('metadata'))
                     reply = HeartbeatAck()
 
+                elif msg_type == 'Ping':
+                    reply = {'message_type': 'Pong'}
+
+                elif msg_type == 'SubscribeActor':
+                    # Reply with the actor's current state; updates follow on its PUB topic
+                    actor_name = msg_json['actor_name']
+                    registry.subscribe_actor(msg_json['manager_id'], actor_name)
+                    entry = registry.resolve(actor_name, msg_json.get('datacenter', ''))
+                    reply = LookupResult(
+                        actor_name=actor_name,
+                        endpoint=entry.endpoint if entry else None,
+                        online=registry.is_manager_online(entry.manager_id) if entry else False,
+                        datacenter=entry.datacenter if entry else ''
+                    )
+
+                elif msg_type == 'UnsubscribeActor':
+                    actor_name = msg_json['actor_name']
+                    registry.unsubscribe_actor(msg_json['manager_id'], actor_name)
+                    reply = RegistrationOk(actor_name=actor_name)
+
+                elif msg_type == 'Announce':
+                    # Fanned out to subscribed managers on the 'Announce' topic
+                    manager_id = msg_json['manager_id']
+                    msg = Announce(
+                        manager_id=manager_id,
+                        actors=[(name, endpoint) for name, endpoint in msg_json.get('actors', [])]
+                    )
+                    count = registry.announce(msg)
+                    reply = {'message_type': 'Announced', 'manager_id': manager_id, 'count': count}
+
+                elif msg_type == 'ManagerLeave':
+                    manager_id = msg_json['manager_id']
+                    logger.info(f"Manager '{manager_id}' is leaving, unregistering its actors")
+                    registry._unregister_manager(manager_id, graceful=True)
+                    reply = {'message_type': 'ManagerLeft', 'manager_id': manager_id}
+
+                elif msg_type == 'DeregisterManager':
+                    manager_id = msg_json['manager_id']
+                    count = registry.deregister_manager(manager_id)
+                    logger.info(f"Deregistered {count} actors of manager '{manager_id}'")
+                    reply = {'message_type': 'ManagerDeregistered', 'manager_id': manager_id, 'count': count}
+
                 else:
                     logger.warning(f"Unknown message type: {msg_type}")
                     reply = {'error': f'Unknown message type: {msg_type}'}
@@ -437,6 +1222,12 @@ def run_registry(endpoint: str = "tcp://0.0.0.0:5555", config_path: str = None):
                     reply_json = reply.to_dict()
                 else:
                     reply_json = reply
+                # Echo the caller's correlation ID for end-to-end tracing, and
+                # the request ID DEALER clients use to match replies
+                if isinstance(reply_json, dict):
+                    for key in ('correlation_id', 'request_id'):
+                        if key in msg_json:
+                            reply_json[key] = msg_json[key]
                 socket.send(json.dumps(reply_json).encode('utf-8'))
 
         except zmq.ZMQError as e:
@@ -445,6 +1236,7 @@ def run_registry(endpoint: str = "tcp://0.0.0.0:5555", config_path: str = None):
 
     # Cleanup
     registry.end()
+    events_socket.close()
     socket.close()
     context.term()
     logger.info("GlobalRegistry stopped")
@@ -464,6 +1256,11 @@ if __name__ == "__main__":
         default=None,
         help="Path to registry.json config file"
     )
+    parser.add_argument(
+        "--events-endpoint",
+        default=None,
+        help="PUB endpoint for manager events (default: endpoint port + 1)"
+    )
 
     args = parser.parse_args()
-    run_registry(args.endpoint, args.config)
+    run_registry(args.endpoint, args.config, args.events_endpoint)
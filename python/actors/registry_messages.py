@@ -6,7 +6,7 @@ Copyright 2025 Vincent Maciejewski, & M2 Tech
 """
 
 from dataclasses import dataclass, asdict
-from typing import Optional
+from typing import List, Optional, Tuple
 import time
 
 
@@ -20,13 +20,44 @@ class RegisterActor:
     manager_id: str
     actor_name: str
     actor_endpoint: str  # ZMQ endpoint for reaching this actor
+    datacenter: str = ''  # set by geo-aware managers
+    max_capacity: Optional[int] = None  # for LeastLoaded lookups
+    version: Optional[int] = None  # None = unversioned
+    priority: Optional[int] = None  # for Weighted lookups; 0 = standby only
 
     def to_dict(self):
         return {
             'message_type': 'RegisterActor',
             'manager_id': self.manager_id,
             'actor_name': self.actor_name,
-            'actor_endpoint': self.actor_endpoint
+            'actor_endpoint': self.actor_endpoint,
+            'datacenter': self.datacenter,
+            'max_capacity': self.max_capacity,
+            'version': self.version,
+            'priority': self.priority
+        }
+
+
+@dataclass
+class UpdateCapacity:
+    """Report an actor's current load.
+
+    Sent periodically for actors registered with a max_capacity.
+    GlobalRegistry replies with RegistrationOk, or RegistrationFailed if
+    the actor is not registered.
+    """
+    actor_name: str
+    current_load: int
+    datacenter: str = ''  # as given at registration
+    manager_id: Optional[str] = None  # whose instances to update; None = all
+
+    def to_dict(self):
+        return {
+            'message_type': 'UpdateCapacity',
+            'actor_name': self.actor_name,
+            'current_load': self.current_load,
+            'datacenter': self.datacenter,
+            'manager_id': self.manager_id
         }
 
 
@@ -34,14 +65,26 @@ class RegisterActor:
 class UnregisterActor:
     """Remove an actor from the registry.
 
-    Sent when an actor is stopped or Manager shuts down.
+    Sent when an actor is stopped or Manager shuts down. With manager_id,
+    the actor is only removed if that manager registered it; otherwise
+    the registry replies NotOwner. A weighted or load-tracked instance
+    (registered with a priority or max_capacity) is removed by giving its
+    actor_endpoint.
     """
     actor_name: str
+    datacenter: str = ''  # as given at registration
+    version: Optional[int] = None  # as given at registration
+    manager_id: Optional[str] = None
+    actor_endpoint: Optional[str] = None  # weighted or load-tracked instances only
 
     def to_dict(self):
         return {
             'message_type': 'UnregisterActor',
-            'actor_name': self.actor_name
+            'actor_name': self.actor_name,
+            'datacenter': self.datacenter,
+            'version': self.version,
+            'manager_id': self.manager_id,
+            'actor_endpoint': self.actor_endpoint
         }
 
 
@@ -57,6 +100,20 @@ class RegistrationOk:
         }
 
 
+@dataclass
+class NotOwner:
+    """Conditional unregistration refused: another manager registered the actor."""
+    actor_name: str
+    current_manager: str
+
+    def to_dict(self):
+        return {
+            'message_type': 'NotOwner',
+            'actor_name': self.actor_name,
+            'current_manager': self.current_manager
+        }
+
+
 @dataclass
 class RegistrationFailed:
     """Registration was rejected.
@@ -79,14 +136,24 @@ class LookupActor:
     """Request endpoint for a named actor.
 
     Manager sends this when local lookup fails.
-    GlobalRegistry replies with LookupResult.
+    GlobalRegistry replies with LookupResult. A geo-aware manager sets
+    datacenter to prefer an instance in its own datacenter. policy
+    'LeastLoaded' prefers the instance with the most remaining capacity.
+    A version constraint, {'constraint': 'Exact' | 'AtLeast' | 'Latest',
+    'version': n}, looks up versioned registrations only.
     """
     actor_name: str
+    datacenter: str = ''
+    policy: str = ''
+    version: Optional[dict] = None
 
     def to_dict(self):
         return {
             'message_type': 'LookupActor',
-            'actor_name': self.actor_name
+            'actor_name': self.actor_name,
+            'datacenter': self.datacenter,
+            'policy': self.policy,
+            'version': self.version
         }
 
 
@@ -97,17 +164,23 @@ class LookupResult:
     Contains the endpoint if found, and online status.
     If endpoint is None, the actor was not found.
     If online is False, the actor's Manager has missed heartbeats.
+    datacenter is empty unless the actor was registered by a geo-aware manager.
+    version is set for versioned registrations.
     """
     actor_name: str
     endpoint: Optional[str]
     online: bool
+    datacenter: str = ''
+    version: Optional[int] = None
 
     def to_dict(self):
         return {
             'message_type': 'LookupResult',
             'actor_name': self.actor_name,
             'endpoint': self.endpoint,
-            'online': self.online
+            'online': self.online,
+            'datacenter': self.datacenter,
+            'version': self.version
         }
 
 
@@ -117,9 +190,12 @@ class Heartbeat:
 
     Managers send this every 2 seconds.
     GlobalRegistry marks Manager offline after 6 seconds without heartbeat.
+    metadata, e.g. {'tenant_id': 'acme', 'region': 'eu'}, is kept by the
+    registry for filtering managers (see GlobalRegistry.managers_matching).
     """
     manager_id: str
     timestamp_ms: int = 0
+    metadata: Optional[dict] = None
 
     def __post_init__(self):
         if self.timestamp_ms == 0:
@@ -129,7 +205,8 @@ class Heartbeat:
         return {
             'message_type': 'Heartbeat',
             'manager_id': self.manager_id,
-            'timestamp_ms': self.timestamp_ms
+            'timestamp_ms': self.timestamp_ms,
+            'metadata': self.metadata
         }
 
 
@@ -143,6 +220,57 @@ class HeartbeatAck:
         }
 
 
+@dataclass
+class Announce:
+    """A starting manager's actors, for peer discovery.
+
+    GlobalRegistry replies with Announced and publishes the announcement on
+    its 'Announce' PUB topic, so subscribed managers can fill their lookup
+    caches without asking for each actor.
+    """
+    manager_id: str
+    actors: List[Tuple[str, str]]  # (actor_name, endpoint) pairs
+
+    def to_dict(self):
+        return {
+            'message_type': 'Announce',
+            'manager_id': self.manager_id,
+            'actors': [[name, endpoint] for name, endpoint in self.actors]
+        }
+
+
+# Snapshot messages
+
+@dataclass
+class ExportState:
+    """Request a dump of the registry's full state.
+
+    GlobalRegistry replies with RegistryState, whose 'state' can be sent
+    back in ImportState.
+    """
+
+    def to_dict(self):
+        return {
+            'message_type': 'ExportState'
+        }
+
+
+@dataclass
+class ImportState:
+    """Replace the registry's state with an ExportState dump.
+
+    GlobalRegistry replies with StateImported, or ImportFailed if the
+    snapshot is malformed.
+    """
+    state: dict
+
+    def to_dict(self):
+        return {
+            'message_type': 'ImportState',
+            'state': self.state
+        }
+
+
 # Process management messages
 
 @dataclass
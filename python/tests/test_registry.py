@@ -2,8 +2,12 @@
 
 import pytest
 import time
+from types import SimpleNamespace
 from unittest.mock import patch
-from actors.registry import GlobalRegistry, ActorEntry
+from actors.registry import (
+    GlobalRegistry, ActorEntry, default_events_endpoint, versioned_key
+)
+from actors.registry_messages import Announce, RegisterActor, UnregisterActor
 
 
 class TestGlobalRegistryState:
@@ -44,6 +48,20 @@ class TestGlobalRegistryState:
         registry._heartbeats["mgr1"] = time.monotonic()
         assert registry.is_manager_online("mgr1") is True
 
+    def test_list_actors(self):
+        """list_actors describes each actor with its manager's status."""
+        registry = GlobalRegistry()
+        registry._registry["a"] = ActorEntry("tcp://host:5001", "mgr1")
+        registry._registry["b"] = ActorEntry("tcp://host:5002", "mgr2")
+        registry._heartbeats["mgr1"] = time.monotonic()
+
+        actors = {a["actor_name"]: a for a in registry.list_actors()}
+        assert actors["a"] == {
+            "actor_name": "a", "endpoint": "tcp://host:5001",
+            "manager_id": "mgr1", "online": True, "datacenter": "",
+        }
+        assert actors["b"]["online"] is False
+
     def test_is_manager_online_stale_heartbeat(self):
         """is_manager_online() returns False for stale heartbeats."""
         registry = GlobalRegistry()
@@ -125,6 +143,511 @@ class TestHeartbeatTimeout:
         assert "mgr1" in registry._heartbeats
 
 
+class TestManagerEvents:
+    """Tests for manager Joined/Left events."""
+
+    def test_first_heartbeat_emits_joined(self):
+        """Only a manager's first sign of life is announced."""
+        registry = GlobalRegistry()
+        events = []
+        registry.add_event_listener(events.append)
+
+        registry._touch_heartbeat("mgr1")
+        registry._touch_heartbeat("mgr1")
+        assert [(e["event"], e["manager_id"]) for e in events] == [("Joined", "mgr1")]
+
+    def test_unregister_emits_left(self):
+        """Leaving managers are announced with the graceful flag."""
+        registry = GlobalRegistry()
+        registry._touch_heartbeat("mgr1")
+        registry._touch_heartbeat("mgr2")
+        events = []
+        registry.add_event_listener(events.append)
+
+        registry._unregister_manager("mgr1", graceful=True)
+        registry._heartbeats["mgr2"] = time.monotonic() - 10.0
+        registry._check_heartbeats()
+        registry._unregister_manager("unknown")
+
+        assert [(e["event"], e["manager_id"], e["graceful"]) for e in events] == [
+            ("Left", "mgr1", True), ("Left", "mgr2", False)
+        ]
+
+    def test_heartbeat_metadata(self):
+        """Managers are filtered by the metadata of their latest heartbeat."""
+        registry = GlobalRegistry()
+        registry._record_heartbeat("mgr1", {"tenant_id": "acme", "region": "eu"})
+        registry._record_heartbeat("mgr2", {"tenant_id": "acme", "region": "us"})
+        registry._record_heartbeat("mgr3", None)
+
+        assert registry.managers_matching(tenant_id="acme") == ["mgr1", "mgr2"]
+        assert registry.managers_matching(tenant_id="acme", region="eu") == ["mgr1"]
+        assert registry.manager_metadata("mgr3") == {}
+
+        # Each heartbeat replaces the metadata; an empty one clears it
+        registry._record_heartbeat("mgr1", {"tenant_id": "acme", "region": "us"})
+        assert registry.managers_matching(region="us") == ["mgr1", "mgr2"]
+        registry._record_heartbeat("mgr1", {})
+        assert registry.manager_metadata("mgr1") == {}
+        assert registry.managers_matching(tenant_id="acme") == ["mgr2"]
+        registry._record_heartbeat("mgr2", None)
+        assert registry.managers_matching(tenant_id="acme") == []
+
+        registry._record_heartbeat("mgr1", {"tenant_id": "acme"})
+        registry._unregister_manager("mgr1")
+        assert registry.managers_matching(tenant_id="acme") == []
+
+    def test_announce_fans_out(self):
+        """Announcements reach every listener with the manager's own actors."""
+        registry = GlobalRegistry()
+        ctx = SimpleNamespace(reply=lambda reply: None)
+        registry._on_register(RegisterActor("mgr1", "pong", "tcp://host1:5001"), ctx)
+        registry._on_register(RegisterActor("mgr2", "ping", "tcp://host2:5001"), ctx)
+        received = []
+        registry.add_announce_listener(received.append)
+        registry.add_announce_listener(received.append)
+
+        # Another manager's actor, an unknown one and a wrong endpoint are left out
+        announced = [("pong", "tcp://host1:5001"), ("ping", "tcp://host2:5001"),
+                     ("ghost", "tcp://host1:5002"), ("pong", "tcp://evil:5001")]
+        count = registry.announce(Announce(manager_id="mgr1", actors=announced))
+
+        assert count == 1
+        assert len(received) == 2
+        assert received[0]["manager_id"] == "mgr1"
+        assert received[0]["actors"] == [["pong", "tcp://host1:5001"]]
+
+    def test_default_events_endpoint(self):
+        assert default_events_endpoint("tcp://0.0.0.0:5555") == "tcp://0.0.0.0:5556"
+
+
+class TestGeoRouting:
+    """Tests for datacenter-aware lookups."""
+
+    def test_prefers_same_datacenter(self):
+        registry = GlobalRegistry()
+        registry._store_entry("pong", ActorEntry("tcp://east:5001", "mgr1", "us-east-1"))
+        registry._store_entry("pong", ActorEntry("tcp://west:5001", "mgr2", "eu-west-1"))
+        registry._heartbeats["mgr1"] = time.monotonic()
+        registry._heartbeats["mgr2"] = time.monotonic()
+
+        assert registry.resolve("pong", "eu-west-1").endpoint == "tcp://west:5001"
+        assert registry.resolve("pong", "us-east-1").endpoint == "tcp://east:5001"
+        assert registry.resolve("pon") is None
+
+    def test_falls_back_to_any_online(self):
+        """An offline same-datacenter actor loses to an online one elsewhere."""
+        registry = GlobalRegistry()
+        registry._store_entry("pong", ActorEntry("tcp://east:5001", "mgr1", "us-east-1"))
+        registry._registry["pong"] = ActorEntry("tcp://plain:5001", "mgr2")
+        registry._heartbeats["mgr2"] = time.monotonic()
+
+        assert registry.resolve("pong", "us-east-1").endpoint == "tcp://plain:5001"
+        assert registry.resolve("pong", "ap-south-1").endpoint == "tcp://plain:5001"
+
+        del registry._heartbeats["mgr2"]
+        assert registry.resolve("pong").endpoint == "tcp://plain:5001"  # offline, plain name first
+
+    def test_geo_registrations_keep_their_name(self):
+        """Registrations in a datacenter are listed, announced and unregistered under the plain name."""
+        registry = GlobalRegistry()
+        events = []
+        registry.add_actor_event_listener(events.append)
+        replies = []
+        ctx = SimpleNamespace(reply=replies.append)
+
+        registry._on_register(RegisterActor("mgr1", "pong", "tcp://east:5001", datacenter="us-east-1"), ctx)
+        registry._on_register(RegisterActor("mgr2", "pong", "tcp://west:5001", datacenter="eu-west-1"), ctx)
+        registry._on_register(RegisterActor("mgr3", "pong", "tcp://other:5001", datacenter="eu-west-1"), ctx)
+        registry._on_register(RegisterActor("mgr3", "pong@eu-west-1", "tcp://odd:5001"), ctx)
+        assert [type(reply).__name__ for reply in replies] == [
+            "RegistrationOk", "RegistrationOk", "RegistrationFailed", "RegistrationOk"
+        ]
+        assert sorted((a["actor_name"], a["datacenter"]) for a in registry.list_actors()) == [
+            ("pong", "eu-west-1"), ("pong", "us-east-1"), ("pong@eu-west-1", "")
+        ]
+        # A name containing '@' is an actor of its own
+        assert registry.resolve("pong", "eu-west-1").endpoint == "tcp://west:5001"
+
+        registry._on_unregister(UnregisterActor("pong", datacenter="eu-west-1", manager_id="mgr2"), ctx)
+        assert [entry.endpoint for entry in registry.entries("pong")] == ["tcp://east:5001"]
+        registry._unregister_manager("mgr1")
+        assert registry.entries("pong") == []
+        assert registry.get_all_actors() == ["pong@eu-west-1"]
+        assert [(e["event"], e["actor_name"], e["datacenter"]) for e in events] == [
+            ("Online", "pong", "us-east-1"), ("Online", "pong", "eu-west-1"), ("Online", "pong@eu-west-1", ""),
+            ("Unregistered", "pong", "eu-west-1"), ("Offline", "pong", "us-east-1"),
+        ]
+
+
+class TestLoadBalancing:
+    """Tests for capacity tracking and LeastLoaded lookups."""
+
+    def test_least_loaded_prefers_most_remaining(self):
+        registry = GlobalRegistry()
+        registry._store_entry("pong", ActorEntry("tcp://east:5001", "mgr1", "us-east-1", 10))
+        registry._store_entry("pong", ActorEntry("tcp://west:5001", "mgr2", "eu-west-1", 10))
+        registry._registry["pong"] = ActorEntry("tcp://plain:5001", "mgr3")
+        for manager_id in ("mgr1", "mgr2", "mgr3"):
+            registry._heartbeats[manager_id] = time.monotonic()
+
+        assert registry.update_capacity("pong", 8, "us-east-1")
+        assert registry.update_capacity("pong", 3, "eu-west-1")
+        assert not registry.update_capacity("missing", 1)
+
+        assert registry.resolve("pong", "us-east-1", "LeastLoaded").endpoint == "tcp://west:5001"
+        assert registry.resolve("pong", "us-east-1").endpoint == "tcp://east:5001"
+
+    def test_untracked_entries_come_last(self):
+        registry = GlobalRegistry()
+        registry._registry["pong"] = ActorEntry("tcp://plain:5001", "mgr1")
+        registry._store_entry("pong", ActorEntry("tcp://west:5001", "mgr1", "eu-west-1", 5))
+        registry._heartbeats["mgr1"] = time.monotonic()
+        registry.update_capacity("pong", 5, "eu-west-1")
+
+        # Full, but still preferred over an actor without a capacity
+        assert registry.resolve("pong", policy="LeastLoaded").endpoint == "tcp://west:5001"
+        assert registry.find_entry("pong", "eu-west-1", "tcp://west:5001").remaining_capacity() == 0
+
+    def test_least_loaded_ranks_instances_in_one_datacenter(self):
+        registry = GlobalRegistry()
+        replies = []
+        ctx = SimpleNamespace(reply=replies.append)
+        for manager_id, endpoint in (("mgr1", "tcp://a:5001"), ("mgr2", "tcp://b:5001")):
+            registry._on_register(RegisterActor(manager_id, "pong", endpoint, max_capacity=10), ctx)
+        assert [type(reply).__name__ for reply in replies] == ["RegistrationOk", "RegistrationOk"]
+        assert [entry.endpoint for entry in registry.entries("pong")] == ["tcp://a:5001", "tcp://b:5001"]
+
+        # Each manager reports the load of its own instance
+        assert registry.update_capacity("pong", 9, manager_id="mgr1")
+        assert registry.update_capacity("pong", 2, manager_id="mgr2")
+        assert not registry.update_capacity("pong", 1, manager_id="mgr3")
+        assert registry.resolve("pong", policy="LeastLoaded").endpoint == "tcp://b:5001"
+        registry.update_capacity("pong", 0, manager_id="mgr1")
+        assert registry.resolve("pong", policy="LeastLoaded").endpoint == "tcp://a:5001"
+
+        # Removed like weighted instances, by endpoint
+        registry._on_unregister(UnregisterActor("pong", actor_endpoint="tcp://a:5001"), ctx)
+        assert [entry.endpoint for entry in registry.entries("pong")] == ["tcp://b:5001"]
+
+
+class TestWeightedRouting:
+    """Tests for priority-weighted instances and Weighted lookups."""
+
+    def test_weighted_instances_share_a_name(self):
+        registry = GlobalRegistry()
+        events = []
+        registry.add_actor_event_listener(events.append)
+        replies = []
+        ctx = SimpleNamespace(reply=replies.append)
+
+        registry._on_register(RegisterActor("mgr1", "pong", "tcp://blue:5001", priority=90), ctx)
+        registry._on_register(RegisterActor("mgr2", "pong", "tcp://green:5001", priority=10), ctx)
+        registry._on_register(RegisterActor("mgr3", "pong", "tcp://blue:5001", priority=5), ctx)
+        assert [type(reply).__name__ for reply in replies] == [
+            "RegistrationOk", "RegistrationOk", "RegistrationFailed"
+        ]
+        assert [(a["actor_name"], a["endpoint"]) for a in registry.list_actors()] == [
+            ("pong", "tcp://blue:5001"), ("pong", "tcp://green:5001")
+        ]
+        assert [entry.weight() for entry in registry.entries("pong")] == [90, 10]
+
+        # Each instance is unregistered by its endpoint, or with its manager
+        registry._on_unregister(UnregisterActor("pong", actor_endpoint="tcp://blue:5001"), ctx)
+        assert [entry.endpoint for entry in registry.entries("pong")] == ["tcp://green:5001"]
+        registry._unregister_manager("mgr2", graceful=True)
+        assert registry.get_all_actors() == []
+        assert [(e["event"], e["actor_name"]) for e in events] == [
+            ("Online", "pong"), ("Online", "pong"), ("Unregistered", "pong"), ("Unregistered", "pong")
+        ]
+
+    def test_weighted_lookup_follows_priority(self):
+        registry = GlobalRegistry()
+        instances = [("tcp://blue", "mgr1", 3), ("tcp://green", "mgr2", 1), ("tcp://spare", "mgr3", 0)]
+        for endpoint, manager_id, priority in instances:
+            registry._store_entry("pong", ActorEntry(endpoint, manager_id, priority=priority))
+            registry._heartbeats[manager_id] = time.monotonic()
+
+        picks = [registry.resolve("pong", policy="Weighted").endpoint for _ in range(400)]
+        assert picks.count("tcp://spare") == 0
+        assert 200 < picks.count("tcp://blue") < 400
+        assert registry.resolve("pong").endpoint != "tcp://spare"
+
+        # The standby only serves once nothing else is online
+        del registry._heartbeats["mgr1"], registry._heartbeats["mgr2"]
+        assert registry.resolve("pong", policy="Weighted").endpoint == "tcp://spare"
+
+    def test_reregistering_changes_priority(self):
+        registry = GlobalRegistry()
+        msg = RegisterActor("mgr1", "pong", "tcp://blue:5001", priority=50)
+        assert not registry.reprioritize(msg)
+        registry._store_entry("pong", ActorEntry("tcp://blue:5001", "mgr1", priority=50))
+
+        assert registry.reprioritize(RegisterActor("mgr1", "pong", "tcp://blue:5001", priority=0))
+        assert registry.find_entry("pong", endpoint="tcp://blue:5001").priority == 0
+        # Another manager cannot change it
+        assert not registry.reprioritize(RegisterActor("mgr2", "pong", "tcp://blue:5001", priority=9))
+        assert registry.find_entry("pong", endpoint="tcp://blue:5001").priority == 0
+
+
+class TestVersioning:
+    """Tests for versioned registrations."""
+
+    def test_resolve_version_constraints(self):
+        registry = GlobalRegistry()
+        registry._registry[versioned_key("pong", 1)] = ActorEntry("tcp://v1:5001", "mgr1", version=1)
+        registry._registry[versioned_key("pong", 2)] = ActorEntry("tcp://v2:5001", "mgr2", version=2)
+        registry._registry["pong"] = ActorEntry("tcp://plain:5001", "mgr1")
+        registry._heartbeats["mgr1"] = time.monotonic()
+        registry._heartbeats["mgr2"] = time.monotonic()
+
+        assert registry.resolve_version("pong", "Exact", 1).endpoint == "tcp://v1:5001"
+        assert registry.resolve_version("pong", "AtLeast", 1).endpoint == "tcp://v2:5001"
+        assert registry.resolve_version("pong", "Latest").endpoint == "tcp://v2:5001"
+        assert registry.resolve_version("pong", "Exact", 3) is None
+        # Unversioned lookups only see the plain registration
+        assert registry.resolve("pong").endpoint == "tcp://plain:5001"
+
+    def test_latest_prefers_online_version(self):
+        registry = GlobalRegistry()
+        registry._registry[versioned_key("pong", 1)] = ActorEntry("tcp://v1:5001", "mgr1", version=1)
+        registry._registry[versioned_key("pong", 2)] = ActorEntry("tcp://v2:5001", "mgr2", version=2)
+        registry._heartbeats["mgr1"] = time.monotonic()
+
+        assert registry.resolve_version("pong", "Latest").endpoint == "tcp://v1:5001"
+
+
+class TestSnapshots:
+    """Tests for exporting and importing registry state."""
+
+    def test_export_import_round_trip(self):
+        registry = GlobalRegistry()
+        registry._store_entry("pong", ActorEntry("tcp://west:5001", "mgr1", "eu-west-1", 10))
+        registry.add_group_member("mgr2", "payments", "p1", "tcp://host:5002")
+        state = registry.export_state()
+
+        restored = GlobalRegistry()
+        assert restored.import_state(state) == 2
+        assert restored.export_state() == state
+        assert restored.resolve("pong", "eu-west-1").max_capacity == 10
+        assert [a["actor_name"] for a in restored.group_members("payments")] == ["p1"]
+        assert restored.is_manager_online("mgr1")
+
+    def test_malformed_snapshot_leaves_state_unchanged(self):
+        registry = GlobalRegistry()
+        registry._registry["pong"] = ActorEntry("tcp://host:5001", "mgr1")
+
+        for state in ({}, {'version': 1, 'actors': [{'actor_name': 'x'}]}, {'version': 2, 'actors': []}, None):
+            with pytest.raises(ValueError):
+                registry.import_state(state)
+        assert registry.lookup("pong") == "tcp://host:5001"
+
+
+class TestGroups:
+    """Tests for registry-maintained actor groups."""
+
+    def test_group_members_are_online_members(self):
+        registry = GlobalRegistry()
+        assert registry.add_group_member("mgr1", "payments", "p1", "tcp://host:5001") is None
+        assert registry.add_group_member("mgr2", "payments", "p2", "tcp://host:5002") is None
+        assert registry.lookup("p1") == "tcp://host:5001"
+
+        registry._heartbeats["mgr2"] = time.monotonic() - 10.0
+        assert [a["actor_name"] for a in registry.group_members("payments")] == ["p1"]
+        assert registry.group_members("unknown") == []
+
+    def test_add_group_member_rejects_other_endpoint(self):
+        registry = GlobalRegistry()
+        registry.add_group_member("mgr1", "payments", "p1", "tcp://host:5001")
+        assert registry.add_group_member("mgr1", "refunds", "p1", "tcp://host:5001") is None
+        assert registry.add_group_member("mgr1", "refunds", "p1", "tcp://host:9999") == "Name already registered"
+
+    def test_deregister_manager_keeps_manager(self):
+        """deregister_manager removes the actors but the manager stays joined."""
+        registry = GlobalRegistry()
+        registry.add_group_member("mgr1", "payments", "p1", "tcp://host:5001")
+        registry._registry["p2"] = ActorEntry("tcp://host:5002", "mgr1")
+        registry._manager_actors["mgr1"].add("p2")
+
+        assert registry.deregister_manager("mgr1") == 2
+        assert registry.get_all_actors() == []
+        assert registry._groups == {}
+        assert registry.is_manager_online("mgr1") is True
+        assert registry.deregister_manager("mgr1") == 0
+
+    def test_unregistered_actor_leaves_groups(self):
+        registry = GlobalRegistry()
+        registry.add_group_member("mgr1", "payments", "p1", "tcp://host:5001")
+        registry._unregister_manager("mgr1", graceful=True)
+        assert registry._groups == {}
+
+
+class TestNameBlacklist:
+    """Tests for the registration name blacklist."""
+
+    def test_blacklisted_name_is_not_registered(self):
+        registry = GlobalRegistry()
+        registry.add_name_blacklist("test_*")
+        assert registry.is_name_blacklisted("test_actor") is True
+        assert registry.is_name_blacklisted("prod_actor") is False
+
+        assert registry.add_group_member("mgr1", "workers", "test_w1", "tcp://host:5001") == "Blacklisted name"
+        assert registry.add_group_member("mgr1", "workers", "w1", "tcp://host:5001") is None
+        assert registry.get_all_actors() == ["w1"]
+
+    def test_blacklist_loaded_from_config(self, tmp_path):
+        config = tmp_path / "registry.json"
+        config.write_text('{"hosts": {}, "name_blacklist": ["tmp?", "*_debug"]}')
+        registry = GlobalRegistry(str(config))
+        assert registry.is_name_blacklisted("tmp1") is True
+        assert registry.is_name_blacklisted("pong_debug") is True
+        assert registry.is_name_blacklisted("tmp12") is False
+
+
+class TestActorEvents:
+    """Tests for actor Online/Offline/Unregistered events."""
+
+    def test_commit_emits_online_and_unregistered(self):
+        registry = GlobalRegistry()
+        events = []
+        registry.add_actor_event_listener(events.append)
+
+        txn_id, _ = registry.prepare_transaction("mgr1", [
+            {"op": "register", "actor_name": "a", "actor_endpoint": "tcp://host:5001"},
+        ])
+        registry.commit_transaction(txn_id)
+        txn_id, _ = registry.prepare_transaction("mgr1", [
+            {"op": "deregister", "actor_name": "a"},
+        ])
+        registry.commit_transaction(txn_id)
+
+        assert [(e["event"], e["actor_name"], e.get("endpoint")) for e in events] == [
+            ("Online", "a", "tcp://host:5001"), ("Unregistered", "a", None)
+        ]
+
+    def test_manager_timeout_emits_offline(self):
+        """Actors of a timed-out manager go Offline; a graceful leave unregisters them."""
+        registry = GlobalRegistry()
+        registry._registry["a"] = ActorEntry("tcp://host:5001", "mgr1")
+        registry._manager_actors["mgr1"] = {"a"}
+        registry._registry["b"] = ActorEntry("tcp://host:5002", "mgr2")
+        registry._manager_actors["mgr2"] = {"b"}
+        registry._touch_heartbeat("mgr2")
+        events = []
+        registry.add_actor_event_listener(events.append)
+
+        registry._unregister_manager("mgr1")
+        registry._unregister_manager("mgr2", graceful=True)
+
+        assert [(e["event"], e["actor_name"]) for e in events] == [
+            ("Offline", "a"), ("Unregistered", "b")
+        ]
+
+
+class TestConditionalUnregister:
+    """Tests for unregistering only actors a manager still owns."""
+
+    def test_unregister_if_owner(self):
+        registry = GlobalRegistry()
+        registry._registry["pong"] = ActorEntry("tcp://host:5001", "mgr2")
+        registry._manager_actors["mgr2"] = {"pong"}
+
+        # Re-registered by mgr2: mgr1's unregister must not remove it
+        entry = registry._unregister("pong", owner="mgr1")
+        assert entry.manager_id == "mgr2"
+        assert registry.lookup("pong") == "tcp://host:5001"
+
+        registry._unregister("pong", owner="mgr2")
+        assert registry.lookup("pong") is None
+        assert registry._manager_actors["mgr2"] == set()
+
+
+class TestActorSubscriptions:
+    """Tests for per-actor subscriber lists."""
+
+    def test_subscribe_and_leave(self):
+        registry = GlobalRegistry()
+        registry.subscribe_actor("mgr1", "pong")
+        registry.subscribe_actor("mgr2", "pong")
+        registry.subscribe_actor("mgr1", "ping")
+        assert registry.actor_subscribers("pong") == ["mgr1", "mgr2"]
+
+        registry.unsubscribe_actor("mgr2", "pong")
+        assert registry.actor_subscribers("pong") == ["mgr1"]
+
+        # A manager that leaves loses its subscriptions
+        registry._touch_heartbeat("mgr1")
+        registry._unregister_manager("mgr1")
+        assert registry.actor_subscribers("pong") == []
+        assert registry.actor_subscribers("ping") == []
+
+
+class TestTransactions:
+    """Tests for two-phase commit of registry transactions."""
+
+    def test_prepare_and_commit_registers_all(self):
+        """A committed transaction registers every actor."""
+        registry = GlobalRegistry()
+        txn_id, failure = registry.prepare_transaction("mgr1", [
+            {"op": "register", "actor_name": "a", "actor_endpoint": "tcp://host:5001"},
+            {"op": "register", "actor_name": "b", "actor_endpoint": "tcp://host:5002"},
+        ])
+        assert failure is None
+        assert registry.lookup("a") is None  # not applied before commit
+
+        assert registry.commit_transaction(txn_id) is True
+        assert registry.lookup("a") == "tcp://host:5001"
+        assert registry.lookup("b") == "tcp://host:5002"
+        assert registry._manager_actors["mgr1"] == {"a", "b"}
+
+    def test_prepare_fails_if_any_name_taken(self):
+        """Nothing is reserved when one operation would fail."""
+        registry = GlobalRegistry()
+        registry._registry["b"] = ActorEntry("tcp://host:5009", "mgr2")
+
+        txn_id, failure = registry.prepare_transaction("mgr1", [
+            {"op": "register", "actor_name": "a", "actor_endpoint": "tcp://host:5001"},
+            {"op": "register", "actor_name": "b", "actor_endpoint": "tcp://host:5002"},
+        ])
+        assert txn_id is None
+        assert failure == ("b", "Name already registered")
+        assert registry.is_name_reserved("a") is False
+
+    def test_abort_releases_reservations(self):
+        """An aborted transaction applies nothing and frees its names."""
+        registry = GlobalRegistry()
+        txn_id, _ = registry.prepare_transaction("mgr1", [
+            {"op": "register", "actor_name": "a", "actor_endpoint": "tcp://host:5001"},
+        ])
+        assert registry.is_name_reserved("a") is True
+
+        registry.abort_transaction(txn_id)
+        assert registry.is_name_reserved("a") is False
+        assert registry.commit_transaction(txn_id) is False
+        assert registry.lookup("a") is None
+
+    def test_commit_keeps_datacenter(self):
+        """Transactional registrations keep their datacenter, like RegisterActor."""
+        registry = GlobalRegistry()
+        registry._registry["a"] = ActorEntry("tcp://host:5009", "mgr2")
+        txn_id, failure = registry.prepare_transaction("mgr1", [
+            {"op": "register", "actor_name": "a", "actor_endpoint": "tcp://east:5001",
+             "datacenter": "us-east"},
+        ])
+        assert failure is None
+        assert registry.commit_transaction(txn_id) is True
+        assert registry.find_entry("a", "us-east").endpoint == "tcp://east:5001"
+        assert registry.find_entry("a").endpoint == "tcp://host:5009"
+
+        txn_id, failure = registry.prepare_transaction("mgr1", [
+            {"op": "deregister", "actor_name": "a", "datacenter": "us-east"},
+        ])
+        assert failure is None
+        assert registry.commit_transaction(txn_id) is True
+        assert registry.find_entry("a", "us-east") is None
+        assert registry.find_entry("a") is not None
+
+
 class TestGlobalRegistryLifecycle:
     """Tests for GlobalRegistry init/end lifecycle."""
 
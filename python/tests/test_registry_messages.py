@@ -4,7 +4,7 @@ import pytest
 import time
 from actors.registry_messages import (
     RegisterActor, UnregisterActor, RegistrationOk, RegistrationFailed,
-    LookupActor, LookupResult, Heartbeat, HeartbeatAck
+    LookupActor, LookupResult, Heartbeat, HeartbeatAck, NotOwner, Announce
 )
 
 
@@ -35,6 +35,13 @@ class TestUnregisterActor:
 
         assert result["message_type"] == "UnregisterActor"
         assert result["actor_name"] == "pong"
+        assert result["manager_id"] is None
+
+    def test_not_owner_to_dict(self):
+        result = NotOwner(actor_name="pong", current_manager="mgr2").to_dict()
+
+        assert result["message_type"] == "NotOwner"
+        assert result["current_manager"] == "mgr2"
 
 
 class TestRegistrationOk:
@@ -115,6 +122,11 @@ class TestHeartbeat:
 
         assert result["timestamp_ms"] == 12345
 
+    def test_metadata(self):
+        assert Heartbeat(manager_id="mgr1").to_dict()["metadata"] is None
+        msg = Heartbeat(manager_id="mgr1", metadata={"tenant_id": "acme"})
+        assert msg.to_dict()["metadata"] == {"tenant_id": "acme"}
+
 
 class TestHeartbeatAck:
     """Tests for HeartbeatAck message."""
@@ -124,3 +136,15 @@ class TestHeartbeatAck:
         result = msg.to_dict()
 
         assert result["message_type"] == "HeartbeatAck"
+
+
+class TestAnnounce:
+    """Tests for Announce message."""
+
+    def test_to_dict(self):
+        msg = Announce(manager_id="mgr1", actors=[("ping", "tcp://host1:5001"), ("pong", "tcp://host1:5002")])
+        result = msg.to_dict()
+
+        assert result["message_type"] == "Announce"
+        assert result["manager_id"] == "mgr1"
+        assert result["actors"] == [["ping", "tcp://host1:5001"], ["pong", "tcp://host1:5002"]]
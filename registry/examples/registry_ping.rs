@@ -14,6 +14,7 @@ Copyright 2025 Vincent Maciejewski, & M2 Tech
 */
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
@@ -69,8 +70,8 @@ impl PingActor {
     fn on_start(&mut self, _msg: &Start, ctx: &mut ActorContext) {
         println!("PingActor: Starting ping-pong...");
 
-        // Look up pong actor via registry
-        match self.registry_client.lookup("pong") {
+        // Look up pong actor via registry (it may still be starting)
+        match self.registry_client.wait_for_actor("pong", Duration::from_millis(200), Duration::from_secs(5)) {
             Ok(endpoint) => {
                 println!("PingActor: Found 'pong' at {}", endpoint);
 
@@ -84,15 +85,11 @@ impl PingActor {
                     pong.send(Box::new(Ping { count: 1 }), ctx.self_ref());
                 }
             }
-            Err(RegistryError::NotFound(_)) => {
+            Err(RegistryError::Timeout(_)) => {
                 eprintln!("PingActor: Failed to find 'pong'");
                 eprintln!("Make sure registry_pong is running first!");
                 self.manager_handle.terminate();
             }
-            Err(RegistryError::Offline(_)) => {
-                eprintln!("PingActor: 'pong' is offline");
-                self.manager_handle.terminate();
-            }
             Err(e) => {
                 eprintln!("PingActor: Registry error: {}", e);
                 self.manager_handle.terminate();
@@ -0,0 +1,112 @@
+/*
+Batch Benchmark
+
+Measures remote message throughput with and without ZmqSender::send_batch.
+
+The same number of small messages is sent to a sink actor behind a
+ZmqReceiver, first one ZMQ send per message with send_to, then packed
+BATCH_SIZE to a frame with send_batch. Each run is timed from the first
+send until the sink has received the last message.
+
+Usage:
+    cargo run --release --example batch_benchmark
+
+THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
+
+Copyright 2025 Vincent Maciejewski, & M2 Tech
+*/
+
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use actors::{
+    define_message, handle_messages, register_remote_message, ActorContext, Manager, Message, ThreadConfig,
+    ZmqReceiver, ZmqSender,
+};
+
+const COUNT: u64 = 50_000;
+const BATCH_SIZE: usize = 100;
+
+const SINK_ENDPOINT: &str = "tcp://127.0.0.1:5104";
+
+#[derive(Serialize, Deserialize, Default)]
+struct Tick {
+    seq: u64,
+    last: bool,
+}
+define_message!(Tick);
+
+/// Signals `done` when the last message of a run arrives.
+struct Sink {
+    done: Sender<()>,
+}
+
+handle_messages!(Sink,
+    Tick => on_tick
+);
+
+impl Sink {
+    fn on_tick(&mut self, msg: &Tick, _ctx: &mut ActorContext) {
+        if msg.last {
+            let _ = self.done.send(());
+        }
+    }
+}
+
+fn tick(seq: u64) -> Box<dyn Message> {
+    Box::new(Tick { seq, last: seq + 1 == COUNT })
+}
+
+/// Print the throughput of a run started at `start` once the sink got its last message.
+fn report(name: &str, start: Instant, done: &std::sync::mpsc::Receiver<()>) -> f64 {
+    done.recv_timeout(Duration::from_secs(60)).expect("sink did not receive the last message");
+    let elapsed = start.elapsed();
+    let rate = COUNT as f64 / elapsed.as_secs_f64();
+    println!("{:<12} {:>8} messages in {:>8.1?} ({:>10.0} msg/s)", name, COUNT, elapsed, rate);
+    rate
+}
+
+fn main() {
+    println!("=== Batch Benchmark ===");
+
+    register_remote_message::<Tick>("Tick");
+
+    let zmq_sender = Arc::new(ZmqSender::new("tcp://127.0.0.1:5105"));
+
+    let (done_tx, done_rx) = channel();
+    let mut mgr = Manager::new();
+    let sink_ref = mgr.manage("sink", Box::new(Sink { done: done_tx }), ThreadConfig::default());
+    let receiver = ZmqReceiver::new(SINK_ENDPOINT, Arc::clone(&zmq_sender));
+    receiver.register("sink", sink_ref);
+    let mut receiver_handle = receiver.start();
+    mgr.init();
+
+    // Give the receiver time to bind, then connect with a first message
+    thread::sleep(Duration::from_millis(100));
+    zmq_sender.send_to(SINK_ENDPOINT, "sink", Box::new(Tick { seq: 0, last: true }), None);
+    done_rx.recv_timeout(Duration::from_secs(5)).expect("sink is not reachable");
+
+    let start = Instant::now();
+    for seq in 0..COUNT {
+        zmq_sender.send_to(SINK_ENDPOINT, "sink", tick(seq), None);
+    }
+    let single = report("send_to", start, &done_rx);
+
+    let start = Instant::now();
+    let seqs: Vec<u64> = (0..COUNT).collect();
+    for chunk in seqs.chunks(BATCH_SIZE) {
+        let batch = chunk.iter().map(|&seq| ("sink", tick(seq))).collect();
+        zmq_sender.send_batch(SINK_ENDPOINT, batch, None).expect("sender thread is gone");
+    }
+    let batched = report("send_batch", start, &done_rx);
+
+    println!("batches of {}: {:.1}x the throughput of single sends", BATCH_SIZE, batched / single);
+
+    receiver_handle.stop();
+    mgr.end();
+    println!("=== Batch Benchmark Complete ===");
+}
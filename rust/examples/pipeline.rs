@@ -0,0 +1,151 @@
+/*
+Pipeline Example
+
+A three-stage PUSH/PULL pipeline in one process:
+
+    source --push--> square (x2 workers) --push--> sum
+
+Each stage listens with its own ZmqPullReceiver, as it would in separate
+processes. The source spreads numbers over the two square workers, which
+push their results on to the sum stage. Nothing is replied to.
+
+Usage:
+    cargo run --example pipeline
+
+THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
+
+Copyright 2025 Vincent Maciejewski, & M2 Tech
+*/
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use actors::{
+    ActorContext, Manager, ManagerHandle, Start, ThreadConfig, ZmqPullReceiver, ZmqPushSender, ZmqSender,
+    define_message, handle_messages, register_remote_message,
+};
+
+const COUNT: u64 = 10;
+
+const SQUARE_ENDPOINTS: [&str; 2] = ["tcp://127.0.0.1:5101", "tcp://127.0.0.1:5102"];
+const SUM_ENDPOINT: &str = "tcp://127.0.0.1:5103";
+
+#[derive(Serialize, Deserialize, Default)]
+struct Number {
+    value: u64,
+}
+define_message!(Number);
+
+#[derive(Serialize, Deserialize, Default)]
+struct Squared {
+    value: u64,
+    worker: String,
+}
+define_message!(Squared);
+
+/// Stage 1: pushes the numbers 1..=COUNT to the square workers.
+struct Source {
+    push: ZmqPushSender,
+}
+
+handle_messages!(Source,
+    Start => on_start
+);
+
+impl Source {
+    fn on_start(&mut self, _msg: &Start, _ctx: &mut ActorContext) {
+        for value in 1..=COUNT {
+            self.push.send("square", Box::new(Number { value }));
+        }
+        println!("source: pushed {} numbers", COUNT);
+    }
+}
+
+/// Stage 2: squares each number and pushes it on to the sum stage.
+struct Square {
+    name: String,
+    push: ZmqPushSender,
+}
+
+handle_messages!(Square,
+    Number => on_number
+);
+
+impl Square {
+    fn on_number(&mut self, msg: &Number, _ctx: &mut ActorContext) {
+        let squared = Squared {
+            value: msg.value * msg.value,
+            worker: self.name.clone(),
+        };
+        self.push.send("sum", Box::new(squared));
+    }
+}
+
+/// Stage 3: adds up the squares and ends the example once all arrived.
+struct Sum {
+    manager_handle: ManagerHandle,
+    total: u64,
+    received: u64,
+}
+
+handle_messages!(Sum,
+    Squared => on_squared
+);
+
+impl Sum {
+    fn on_squared(&mut self, msg: &Squared, _ctx: &mut ActorContext) {
+        self.total += msg.value;
+        self.received += 1;
+        println!("sum: {} from {} (total {})", msg.value, msg.worker, self.total);
+        if self.received == COUNT {
+            self.manager_handle.terminate();
+        }
+    }
+}
+
+fn main() {
+    println!("=== Pipeline Example ===");
+
+    register_remote_message::<Number>("Number");
+    register_remote_message::<Squared>("Squared");
+
+    // Receivers only need a ZmqSender for replies, which pipelines do not send
+    let zmq_sender = Arc::new(ZmqSender::new(""));
+
+    let mut mgr = Manager::new();
+    let handle = mgr.get_handle();
+
+    // Stage 3
+    let sum = Sum { manager_handle: handle, total: 0, received: 0 };
+    let sum_ref = mgr.manage("sum", Box::new(sum), ThreadConfig::default());
+    let sum_receiver = ZmqPullReceiver::new(SUM_ENDPOINT, Arc::clone(&zmq_sender));
+    sum_receiver.register("sum", sum_ref);
+    let _sum_handle = sum_receiver.start();
+
+    // Stage 2: one receiver per worker, each registering its worker as "square"
+    let mut square_handles = Vec::new();
+    for (i, endpoint) in SQUARE_ENDPOINTS.iter().enumerate() {
+        let name = format!("square{}", i + 1);
+        let square = Square { name: name.clone(), push: ZmqPushSender::new(&[SUM_ENDPOINT]) };
+        let square_ref = mgr.manage(&name, Box::new(square), ThreadConfig::default());
+        let receiver = ZmqPullReceiver::new(endpoint, Arc::clone(&zmq_sender));
+        receiver.register("square", square_ref);
+        square_handles.push(receiver.start());
+    }
+
+    // Stage 1
+    let source = Source { push: ZmqPushSender::new(&SQUARE_ENDPOINTS) };
+    mgr.manage("source", Box::new(source), ThreadConfig::default());
+
+    // Give the receivers time to bind before the source starts pushing
+    thread::sleep(Duration::from_millis(100));
+
+    mgr.init();
+    mgr.run();
+    mgr.end();
+
+    println!("=== Pipeline Example Complete (expected total {}) ===", (1..=COUNT).map(|n| n * n).sum::<u64>());
+}
@@ -16,9 +16,32 @@ http://m2te.ch/
 //! Actors are independent entities that process messages sequentially.
 //! Each actor runs in its own thread with isolated state.
 
-use std::sync::mpsc::{channel, Receiver, Sender};
-use std::sync::{Arc, Mutex};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::pin::Pin;
+use std::sync::mpsc::{self, channel, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
 
+use tokio::sync::oneshot;
+
+use crate::dead_letters::DeadLetter;
+use crate::manager::{ActorStatus, ChildSpawner, ThreadConfig};
+use crate::messages::{Shutdown, Start};
+use crate::metrics::{ActorMetrics, Counter, MailboxStats};
+use crate::recording::MessageRecorder;
+use crate::supervision::{
+    ActorFactory, ChildFailed, Directive, RestartActor, RestartReason, SetSupervisor, SupervisedActorRef, SupervisionStrategy, Supervisor,
+    UpgradeError,
+};
+use crate::timer::{shared_timers, TimerHandle, TimerService};
+use crate::watch::{DeathWatch, TerminationReason};
 use crate::Message;
 
 /// Envelope wraps a message with sender metadata.
@@ -57,6 +80,17 @@ impl Envelope {
     }
 }
 
+/// A message with an expiry time, sent by `ActorRef::send_with_ttl`.
+///
+/// Unwrapped by `ActorRuntime::run`: the actor only ever sees `inner`, and
+/// only if it is taken from the mailbox before `expires_at`. Expired
+/// messages go to the dead letter queue with reason "Expired".
+pub struct TimedEnvelope {
+    pub expires_at: Instant,
+    pub inner: Box<dyn Message>,
+}
+crate::define_message!(TimedEnvelope);
+
 /// Local reference to an actor, used for sending messages via channel.
 ///
 /// LocalActorRef is a cloneable handle that acts as the "address" of a local actor.
@@ -65,12 +99,150 @@ impl Envelope {
 pub struct LocalActorRef {
     sender: Sender<Envelope>,
     name: String,
+    /// Mailbox counters of the target actor (refs created by its runtime only)
+    metrics: Option<Arc<ActorMetrics>>,
+    /// The Manager's dead letter queue (refs to managed actors only)
+    dead_letters: Option<Arc<LocalActorRef>>,
+    /// Cleared by the runtime when the actor's thread exits
+    alive: Arc<AtomicBool>,
+    /// Called on the sending thread when a send through this ref fails
+    error_handler: Option<ErrorHandler>,
+    /// Generation of a pooled mailbox this ref was created for, see `ActorRefPool`
+    generation: Option<(Generation, u64)>,
+    /// Pause state of the target actor (refs created by its runtime only)
+    pause: Option<Arc<PauseGate>>,
+    /// Called after each message sent through this ref (an `ask`'s reply
+    /// actor, or a dead letter queue started by its first message)
+    on_send: Option<SendHook>,
 }
 
 impl LocalActorRef {
     /// Create a new LocalActorRef from a sender
     pub fn new(sender: Sender<Envelope>, name: String) -> Self {
-        LocalActorRef { sender, name }
+        LocalActorRef {
+            sender,
+            name,
+            metrics: None,
+            dead_letters: None,
+            alive: Arc::new(AtomicBool::new(true)),
+            error_handler: None,
+            generation: None,
+            pause: None,
+            on_send: None,
+        }
+    }
+
+    /// Create a LocalActorRef that counts sends in the actor's metrics and
+    /// shares its runtime's `alive` flag
+    pub(crate) fn with_metrics(
+        sender: Sender<Envelope>,
+        name: String,
+        metrics: Arc<ActorMetrics>,
+        alive: Arc<AtomicBool>,
+    ) -> Self {
+        LocalActorRef {
+            sender,
+            name,
+            metrics: Some(metrics),
+            dead_letters: None,
+            alive,
+            error_handler: None,
+            generation: None,
+            pause: None,
+            on_send: None,
+        }
+    }
+
+    /// Call `hook` after each message sent through this ref or its clones.
+    pub(crate) fn with_on_send(mut self, hook: SendHook) -> Self {
+        self.on_send = Some(hook);
+        self
+    }
+
+    /// Put an envelope in the mailbox, failing if it is closed or, for
+    /// messages other than control messages, full.
+    ///
+    /// An envelope that cannot be delivered is reported to the dead letter queue.
+    pub(crate) fn enqueue(&self, envelope: Envelope) -> Result<(), SendError> {
+        self.enqueue_within(envelope, Duration::ZERO)
+    }
+
+    /// `enqueue`, waiting up to `timeout` for room in a full mailbox.
+    fn enqueue_within(&self, envelope: Envelope, timeout: Duration) -> Result<(), SendError> {
+        let control = is_control(envelope.msg.as_ref());
+        if let Some(metrics) = &self.metrics {
+            if control {
+                metrics.record_enqueued();
+            } else {
+                let mut deadline = None;
+                while !metrics.try_record_enqueued() {
+                    // None if the timeout is too long to represent: wait forever
+                    let deadline = *deadline.get_or_insert_with(|| Instant::now().checked_add(timeout));
+                    let remaining = deadline.map_or(FULL_MAILBOX_POLL, |d| d.saturating_duration_since(Instant::now()));
+                    if remaining.is_zero() {
+                        metrics.record_dropped();
+                        send_dead_letter(self.dead_letters.as_ref(), &self.name, &envelope, "mailbox full");
+                        self.report(ActorError::MailboxFull);
+                        return Err(SendError::Full);
+                    }
+                    thread::sleep(remaining.min(FULL_MAILBOX_POLL));
+                }
+            }
+        }
+        // A pooled mailbox given back to its pool is closed to this ref. The
+        // lock is held across the send, so the pool cannot reuse the channel meanwhile.
+        let current = self.generation.as_ref().map(|(current, generation)| (current.read().unwrap(), *generation));
+        let sent = match &current {
+            Some((current, generation)) if **current != *generation => Err(mpsc::SendError(envelope)),
+            _ => self.sender.send(envelope),
+        };
+        drop(current);
+        match sent {
+            Ok(()) => {
+                if let (true, Some(pause)) = (control, &self.pause) {
+                    pause.control_sent();
+                }
+                if let Some(on_send) = &self.on_send {
+                    on_send();
+                }
+                Ok(())
+            }
+            Err(undelivered) => {
+                if let Some(metrics) = &self.metrics {
+                    metrics.record_unsent();
+                    metrics.record_dropped();
+                }
+                send_dead_letter(self.dead_letters.as_ref(), &self.name, &undelivered.0, "actor stopped");
+                self.report(ActorError::Disconnected);
+                Err(SendError::Disconnected)
+            }
+        }
+    }
+
+    /// Pause or resume the target actor (see `Manager::pause_actor`).
+    ///
+    /// Returns false if this ref was not created by the actor's runtime.
+    pub(crate) fn set_paused(&self, paused: bool) -> bool {
+        match &self.pause {
+            Some(pause) => {
+                pause.set_paused(paused);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Pass a send failure to the error handler, if one is set.
+    fn report(&self, error: ActorError) {
+        if let Some(handler) = &self.error_handler {
+            (handler.lock().unwrap())(error);
+        }
+    }
+
+    /// Call `f` on the sending thread when a send through this ref fails
+    /// (see `ActorRef::set_error_handler`).
+    pub fn set_error_handler(&mut self, f: impl Fn(ActorError) + Send + 'static) {
+        self.error_handler = Some(Arc::new(Mutex::new(Box::new(f))));
     }
 
     /// Get the actor's name
@@ -78,21 +250,56 @@ impl LocalActorRef {
         &self.name
     }
 
+    /// Whether the actor's thread is still running (always true for refs
+    /// not created by a Manager)
+    pub fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::SeqCst)
+    }
+
+    /// Counters of the actor's mailbox (all zero for refs not created by
+    /// the actor's runtime)
+    pub fn mailbox_stats(&self) -> MailboxStats {
+        self.metrics.as_ref().map(|m| m.mailbox_stats()).unwrap_or_default()
+    }
+
     /// Send a message to this actor (async, fire-and-forget)
+    ///
+    /// # Panics
+    /// If the actor's mailbox is bounded and full (see `ActorRef::send`).
     pub fn send(&self, msg: Box<dyn Message>, sender: Option<ActorRef>) {
-        let envelope = Envelope::new(msg, sender);
-        let _ = self.sender.send(envelope);
+        if let Err(SendError::Full) = self.enqueue(Envelope::new(msg, sender)) {
+            panic!("mailbox of actor '{}' is full", self.name);
+        }
+    }
+
+    /// Send a message, failing at once if the mailbox is full or closed
+    pub fn try_send(&self, msg: Box<dyn Message>, sender: Option<ActorRef>) -> Result<(), SendError> {
+        self.enqueue(Envelope::new(msg, sender))
+    }
+
+    /// Send a message, waiting up to `timeout` for room in a full mailbox
+    pub fn send_timeout(
+        &self,
+        msg: Box<dyn Message>,
+        sender: Option<ActorRef>,
+        timeout: Duration,
+    ) -> Result<(), SendError> {
+        self.enqueue_within(Envelope::new(msg, sender), timeout)
     }
 
     /// Send a message and wait for a reply (synchronous)
     pub fn fast_send(&self, msg: Box<dyn Message>, sender: Option<ActorRef>) -> Option<Box<dyn Message>> {
         let (reply_tx, reply_rx) = channel();
         let envelope = Envelope::new_sync(msg, sender, reply_tx);
-        if self.sender.send(envelope).is_ok() {
-            reply_rx.recv().ok()
-        } else {
-            None
+        if self.enqueue(envelope).is_err() {
+            return None;
         }
+        let reply = reply_rx.recv().ok();
+        if reply.is_none() {
+            // The actor stopped before replying
+            self.report(ActorError::Disconnected);
+        }
+        reply
     }
 
     /// Convert to an ActorRef enum
@@ -101,6 +308,32 @@ impl LocalActorRef {
     }
 }
 
+/// How often `send_timeout` checks a full mailbox for room
+const FULL_MAILBOX_POLL: Duration = Duration::from_micros(100);
+
+/// Whether `msg` controls the actor's lifecycle, so it is accepted even by a
+/// full mailbox.
+fn is_control(msg: &dyn Message) -> bool {
+    let msg = msg.as_any();
+    msg.is::<Start>()
+        || msg.is::<Shutdown>()
+        || msg.is::<InspectActor>()
+        || msg.is::<TakeActor>()
+        || msg.is::<SetSupervisor>()
+        || msg.is::<RestartActor>()
+        || msg.is::<ChildFailed>()
+        || msg.is::<Drain>()
+}
+
+/// Report a message that was not delivered to `target` to the dead letter
+/// queue, if there is one.
+fn send_dead_letter(dead_letters: Option<&Arc<LocalActorRef>>, target: &str, envelope: &Envelope, reason: &str) {
+    if let Some(dead_letters) = dead_letters {
+        let letter = DeadLetter::from_envelope(target, envelope, reason);
+        let _ = dead_letters.enqueue(Envelope::new(Box::new(letter), None));
+    }
+}
+
 /// Type for the FFI send function that dispatches messages to C++ actors.
 ///
 /// The send function takes:
@@ -151,6 +384,174 @@ impl CppActorRef {
     }
 }
 
+/// Error returned by `ActorRef::ask`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AskError {
+    /// No reply of the requested type arrived in time
+    Timeout,
+    /// The target actor's mailbox is closed
+    ActorDead(String),
+    /// The target actor's mailbox is full
+    MailboxFull(String),
+}
+
+impl std::fmt::Display for AskError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AskError::Timeout => write!(f, "No reply before the ask timeout"),
+            AskError::ActorDead(name) => write!(f, "Actor not running: {}", name),
+            AskError::MailboxFull(name) => write!(f, "Actor mailbox full: {}", name),
+        }
+    }
+}
+
+impl std::error::Error for AskError {}
+
+/// Error returned by `ActorContext::spawn_temporary` and
+/// `ActorContext::create_child`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpawnError {
+    /// The spawning actor is not run by a Manager
+    NotManaged,
+    /// The temporary actor did not reply in time
+    Timeout,
+    /// The temporary actor handled the message without replying
+    NoReply,
+    /// The Manager already has an actor of this name
+    NameTaken(String),
+}
+
+impl std::fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpawnError::NotManaged => write!(f, "Actor is not run by a Manager"),
+            SpawnError::Timeout => write!(f, "No reply before the timeout"),
+            SpawnError::NoReply => write!(f, "Temporary actor did not reply"),
+            SpawnError::NameTaken(name) => write!(f, "Actor name already taken: {}", name),
+        }
+    }
+}
+
+impl std::error::Error for SpawnError {}
+
+/// Numbers the actors started by `ActorContext::spawn_temporary`
+static NEXT_TEMPORARY: AtomicU64 = AtomicU64::new(0);
+
+/// Error returned by `ActorRef::try_send` and `ActorRef::send_timeout`, and
+/// for each failed endpoint of `ZmqSender::broadcast`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendError {
+    /// The mailbox is at its capacity
+    Full,
+    /// The mailbox is closed because the actor has stopped
+    Disconnected,
+    /// A broadcast send did not complete within the sender's
+    /// `broadcast_timeout`
+    Timeout,
+    /// A broadcast endpoint could not be connected or the send failed
+    Unreachable,
+}
+
+impl std::fmt::Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendError::Full => write!(f, "Actor mailbox full"),
+            SendError::Disconnected => write!(f, "Actor not running"),
+            SendError::Timeout => write!(f, "Send timed out"),
+            SendError::Unreachable => write!(f, "Endpoint unreachable"),
+        }
+    }
+}
+
+impl std::error::Error for SendError {}
+
+/// A send failure, passed to the handler set with `ActorRef::set_error_handler`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActorError {
+    /// The target's mailbox is full (see `ThreadConfigBuilder::mailbox_capacity`)
+    MailboxFull,
+    /// The target's mailbox is closed because the actor has stopped
+    Disconnected,
+    /// No reply arrived before an `ask` timed out
+    Timeout,
+}
+
+impl std::fmt::Display for ActorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActorError::MailboxFull => write!(f, "Actor mailbox full"),
+            ActorError::Disconnected => write!(f, "Actor not running"),
+            ActorError::Timeout => write!(f, "No reply before the timeout"),
+        }
+    }
+}
+
+impl std::error::Error for ActorError {}
+
+/// Handler for the send failures of one `LocalActorRef`
+type ErrorHandler = Arc<Mutex<Box<dyn Fn(ActorError) + Send>>>;
+
+/// Called after each message sent through a `LocalActorRef`
+pub(crate) type SendHook = Arc<dyn Fn() + Send + Sync>;
+
+/// Error returned by a message handler, see `HandlerResult`
+pub type HandlerError = Box<dyn std::error::Error + Send>;
+
+/// The Manager's handler for errors returned by message handlers, see
+/// `Manager::set_message_error_handler`
+pub(crate) type MessageErrorHandler = Arc<Mutex<Option<Box<dyn Fn(&str, &dyn Message, HandlerError) + Send>>>>;
+
+/// The task waiting for an `ask`'s reply, woken when its reply actor gets a message
+#[derive(Default)]
+struct AskWaker(Mutex<Option<Waker>>);
+
+impl AskWaker {
+    fn wake(&self) {
+        if let Some(waker) = self.0.lock().unwrap().take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Sent to an `ask`'s reply actor when its timeout has passed
+struct AskExpired;
+crate::define_message!(AskExpired);
+
+/// The hidden reply actor of one `ask`: resolves with the first message of
+/// type `R` in `mailbox`, or `AskError::Timeout` once `AskExpired` arrives
+/// or nothing can reply any more. Replies of other types are discarded.
+struct AskReply<R> {
+    mailbox: Receiver<Envelope>,
+    waker: Arc<AskWaker>,
+    /// Cancels the timeout when the reply comes first or the ask is dropped
+    _timeout: Option<TimerHandle>,
+    reply: PhantomData<fn() -> R>,
+}
+
+impl<R: Message> Future for AskReply<R> {
+    type Output = Result<Box<R>, AskError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Registered before looking, so a reply sent meanwhile wakes the task
+        *self.waker.0.lock().unwrap() = Some(cx.waker().clone());
+        loop {
+            match self.mailbox.try_recv() {
+                Ok(envelope) => {
+                    let msg: Box<dyn Any> = envelope.msg;
+                    if msg.is::<AskExpired>() {
+                        return Poll::Ready(Err(AskError::Timeout));
+                    }
+                    if let Ok(reply) = msg.downcast::<R>() {
+                        return Poll::Ready(Ok(reply));
+                    }
+                }
+                Err(TryRecvError::Empty) => return Poll::Pending,
+                Err(TryRecvError::Disconnected) => return Poll::Ready(Err(AskError::Timeout)),
+            }
+        }
+    }
+}
+
 /// Reference to an actor (local, remote, or C++ via FFI).
 ///
 /// ActorRef is an enum that can hold either a local, remote, or C++ actor reference.
@@ -180,10 +581,38 @@ impl ActorRef {
         }
     }
 
+    /// Whether the actor is still running.
+    ///
+    /// Only local actors run by a Manager are tracked; for other refs
+    /// (remote, C++, or made with `ActorRef::new`) this is always true.
+    pub fn is_alive(&self) -> bool {
+        match self {
+            ActorRef::Local(r) => r.is_alive(),
+            _ => true,
+        }
+    }
+
+    /// Counters of the actor's mailbox: depth, messages received and
+    /// processed, peak depth and messages refused.
+    ///
+    /// Only local actors run by a Manager are tracked; for other refs
+    /// (remote, C++, or made with `ActorRef::new`) all counts are zero.
+    pub fn mailbox_stats(&self) -> MailboxStats {
+        match self {
+            ActorRef::Local(r) => r.mailbox_stats(),
+            _ => MailboxStats::default(),
+        }
+    }
+
     /// Send a message to this actor (async, fire-and-forget)
     ///
     /// The message is queued and processed later by the receiver's thread.
     /// For Cpp actors, the message is dispatched via FFI transparently.
+    ///
+    /// # Panics
+    /// If the target is a local actor with a bounded mailbox (see
+    /// `ThreadConfigBuilder::mailbox_capacity`) that is full. Use `try_send`
+    /// or `send_timeout` to send to bounded mailboxes without panicking.
     pub fn send(&self, msg: Box<dyn Message>, sender: Option<ActorRef>) {
         match self {
             ActorRef::Local(r) => r.send(msg, sender),
@@ -195,6 +624,52 @@ impl ActorRef {
         }
     }
 
+    /// Pass on an envelope received by another actor, keeping its sender and
+    /// reply channel (the reply channel only reaches local actors).
+    pub(crate) fn forward(&self, envelope: Envelope) -> Result<(), SendError> {
+        match self {
+            ActorRef::Local(r) => r.enqueue(envelope),
+            _ => {
+                self.send(envelope.msg, envelope.sender);
+                Ok(())
+            }
+        }
+    }
+
+    /// Send a message, failing at once if it was certainly not accepted.
+    ///
+    /// Only local actors can report failure, `SendError::Full` if their
+    /// mailbox is at capacity or `SendError::Disconnected` if it is closed;
+    /// remote and C++ sends always succeed. The rejected message goes to the
+    /// dead letter queue.
+    pub fn try_send(&self, msg: Box<dyn Message>, sender: Option<ActorRef>) -> Result<(), SendError> {
+        match self {
+            ActorRef::Local(r) => r.try_send(msg, sender),
+            _ => {
+                self.send(msg, sender);
+                Ok(())
+            }
+        }
+    }
+
+    /// Send a message, blocking up to `timeout` while the target's mailbox
+    /// is full.
+    ///
+    /// Fails with `SendError::Full` if there is still no room after
+    /// `timeout`. Only local actors have bounded mailboxes; other sends
+    /// behave as `try_send`.
+    pub fn send_timeout(
+        &self,
+        msg: Box<dyn Message>,
+        sender: Option<ActorRef>,
+        timeout: Duration,
+    ) -> Result<(), SendError> {
+        match self {
+            ActorRef::Local(r) => r.send_timeout(msg, sender, timeout),
+            _ => self.try_send(msg, sender),
+        }
+    }
+
     /// Send a message and wait for a reply (synchronous)
     ///
     /// The handler runs in the receiver's thread, but the caller blocks
@@ -215,6 +690,106 @@ impl ActorRef {
         }
     }
 
+    /// Call `f` for every send through this ref (and its later clones) that
+    /// fails, on the thread that sent the message.
+    ///
+    /// Failed sends are still reported by `try_send`'s result and to
+    /// the dead letter queue. `ask` reports its timeout from the thread that
+    /// polls its future. Only local refs can detect failures; for remote and
+    /// C++ refs this does nothing. The handler must not send through this ref.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::Manager;
+    /// # fn example(manager: &Manager) {
+    /// let mut pong = manager.get_actor("pong").unwrap();
+    /// pong.set_error_handler(|e| eprintln!("pong: {}", e));
+    /// # }
+    /// ```
+    pub fn set_error_handler(&mut self, f: impl Fn(ActorError) + Send + 'static) {
+        if let ActorRef::Local(r) = self {
+            r.set_error_handler(f);
+        }
+    }
+
+    /// Send a message that is dropped to the dead letter queue if it is still
+    /// in the mailbox after `ttl`.
+    ///
+    /// `send` is equivalent to a `ttl` of `Duration::MAX` (never expires).
+    /// Expiry is only checked by local actors; remote and C++ actors receive
+    /// the message as a plain `send`.
+    pub fn send_with_ttl(&self, msg: Box<dyn Message>, sender: Option<ActorRef>, ttl: Duration) {
+        match (self, Instant::now().checked_add(ttl)) {
+            (ActorRef::Local(r), Some(expires_at)) => r.send(Box::new(TimedEnvelope { expires_at, inner: msg }), sender),
+            _ => self.send(msg, sender),
+        }
+    }
+
+    /// Send a message and get a future for the reply of type `R`.
+    ///
+    /// The message is sent with a hidden, short-lived reply actor as its
+    /// sender, so the target answers with `ctx.reply` as usual. The future
+    /// resolves with the first reply of type `R`, or `AskError::Timeout`
+    /// after `timeout`. It does not need a tokio runtime to be polled, and
+    /// no thread waits for it: one shared timer thread times out every ask.
+    ///
+    /// Only local targets can reply: remote and C++ actors cannot address
+    /// the reply actor, so asking them always times out.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actors::{define_message, ActorRef, AskError};
+    /// # struct GetCount;
+    /// # define_message!(GetCount);
+    /// # struct Count(u64);
+    /// # define_message!(Count);
+    /// # async fn example(counter: ActorRef) -> Result<(), AskError> {
+    /// let count: Box<Count> = counter.ask::<Count>(Box::new(GetCount), Duration::from_secs(1)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ask<R: Message>(
+        &self,
+        msg: Box<dyn Message>,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Box<R>, AskError>> {
+        let (reply_tx, reply_rx) = channel();
+        let waker = Arc::new(AskWaker::default());
+        let wake = Arc::clone(&waker);
+        let reply_ref =
+            LocalActorRef::new(reply_tx, format!("$ask:{}", self.name())).with_on_send(Arc::new(move || wake.wake()));
+        let reply_ref = ActorRef::Local(reply_ref);
+
+        let sent = match self.try_send(msg, Some(reply_ref.clone())) {
+            Ok(()) => Ok(()),
+            Err(SendError::Full) => Err(AskError::MailboxFull(self.name().to_string())),
+            Err(SendError::Disconnected | SendError::Unreachable) => Err(AskError::ActorDead(self.name().to_string())),
+            Err(SendError::Timeout) => Err(AskError::Timeout),
+        };
+        // No timeout if it is too long to represent
+        let timeout = match (&sent, Instant::now().checked_add(timeout)) {
+            (Ok(()), Some(deadline)) => Some(shared_timers().schedule_at(reply_ref, deadline, Box::new(AskExpired))),
+            _ => None,
+        };
+        let reply = AskReply::<R> { mailbox: reply_rx, waker, _timeout: timeout, reply: PhantomData };
+
+        let local = match self {
+            ActorRef::Local(r) => Some(r.clone()),
+            _ => None,
+        };
+        async move {
+            let reply = match sent {
+                Ok(()) => reply.await,
+                Err(err) => Err(err),
+            };
+            if let (Err(AskError::Timeout), Some(local)) = (&reply, &local) {
+                local.report(ActorError::Timeout);
+            }
+            reply
+        }
+    }
+
     /// Check if this is a local actor reference
     pub fn is_local(&self) -> bool {
         matches!(self, ActorRef::Local(_))
@@ -243,8 +818,165 @@ impl ActorRef {
     pub fn cpp(target: &str, sender: &str, send_fn: CppSendFn) -> Self {
         ActorRef::Cpp(CppActorRef::new(target, sender, send_fn))
     }
+
+    /// Restrict this reference to messages of type `M`.
+    pub fn into_typed<M: Message>(self) -> TypedActorRef<M> {
+        TypedActorRef {
+            inner: self,
+            _message: PhantomData,
+        }
+    }
+
+    /// Supervise an already running local actor with `strategy`, replacing
+    /// the one it was created with.
+    ///
+    /// `factory` creates the new instance for `SupervisionStrategy::Restart`.
+    /// Takes effect once the actor has handled the messages already in its
+    /// mailbox; the returned ref counts restarts from then on.
+    pub fn upgrade_to_supervised<F>(
+        &self,
+        strategy: SupervisionStrategy,
+        factory: F,
+    ) -> Result<SupervisedActorRef, UpgradeError>
+    where
+        F: Fn() -> Box<dyn Actor> + Send + 'static,
+    {
+        if !matches!(self, ActorRef::Local(_)) {
+            return Err(UpgradeError::NotLocal(self.name().to_string()));
+        }
+
+        let restart_count = Arc::default();
+        let factory: ActorFactory = Box::new(factory);
+        let set = SetSupervisor {
+            strategy,
+            factory: Some(factory),
+            restart_count: Arc::clone(&restart_count),
+        };
+        if self.try_send(Box::new(set), None).is_err() {
+            return Err(UpgradeError::ActorStopped(self.name().to_string()));
+        }
+        Ok(SupervisedActorRef::new(self.clone(), restart_count))
+    }
+}
+
+/// Reference to an actor that only accepts messages of type `M`.
+///
+/// Sending any other type is a compile error. Works for local, remote and
+/// C++ actors; create one with `ActorRef::into_typed` or `RemoteActorRef::into_typed`.
+///
+/// # Example
+/// ```
+/// use actors::{define_message, ActorRef, TypedActorRef};
+///
+/// struct Ping;
+/// define_message!(Ping);
+///
+/// let (tx, _rx) = std::sync::mpsc::channel();
+/// let pong: TypedActorRef<Ping> = ActorRef::new(tx, "pong".to_string()).into_typed();
+/// pong.send(Ping, None);
+/// ```
+///
+/// ```compile_fail
+/// use actors::{define_message, ActorRef, TypedActorRef};
+///
+/// struct Ping;
+/// define_message!(Ping);
+/// struct Pong;
+/// define_message!(Pong);
+///
+/// let (tx, _rx) = std::sync::mpsc::channel();
+/// let pong: TypedActorRef<Ping> = ActorRef::new(tx, "pong".to_string()).into_typed();
+/// pong.send(Pong, None); // wrong message type
+/// ```
+pub struct TypedActorRef<M: Message> {
+    inner: ActorRef,
+    _message: PhantomData<fn(M)>,
+}
+
+impl<M: Message> TypedActorRef<M> {
+    /// Get the actor's name
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    /// Send a message to this actor (async, fire-and-forget)
+    pub fn send(&self, msg: M, sender: Option<ActorRef>) {
+        self.inner.send(Box::new(msg), sender);
+    }
+
+    /// Convert back to an untyped ActorRef
+    pub fn into_untyped(self) -> ActorRef {
+        self.inner
+    }
+}
+
+// Manual impl: deriving would require `M: Clone`
+impl<M: Message> Clone for TypedActorRef<M> {
+    fn clone(&self) -> Self {
+        TypedActorRef {
+            inner: self.inner.clone(),
+            _message: PhantomData,
+        }
+    }
+}
+
+/// Reference to an actor whose type `A` is known, returned by `ActorBuilder::build`.
+///
+/// Unlike `TypedActorRef`, which is keyed by the one message type it accepts,
+/// this is keyed by the actor type and sends any message without boxing at
+/// the call site.
+pub struct ActorHandle<A: Actor> {
+    inner: ActorRef,
+    _actor: PhantomData<fn() -> A>,
+}
+
+impl<A: Actor> ActorHandle<A> {
+    pub(crate) fn new(inner: ActorRef) -> Self {
+        ActorHandle {
+            inner,
+            _actor: PhantomData,
+        }
+    }
+
+    /// Get the actor's name
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    /// Send a message to this actor (async, fire-and-forget)
+    pub fn send<M: Message>(&self, msg: M) {
+        self.inner.send(Box::new(msg), None);
+    }
+
+    /// Send a message with a sender for replies
+    pub fn send_from<M: Message>(&self, msg: M, sender: ActorRef) {
+        self.inner.send(Box::new(msg), Some(sender));
+    }
+
+    /// Get an untyped ActorRef for this actor
+    pub fn actor_ref(&self) -> ActorRef {
+        self.inner.clone()
+    }
+
+    /// Convert to an untyped ActorRef
+    pub fn into_untyped(self) -> ActorRef {
+        self.inner
+    }
+}
+
+// Manual impl: deriving would require `A: Clone`
+impl<A: Actor> Clone for ActorHandle<A> {
+    fn clone(&self) -> Self {
+        ActorHandle::new(self.inner.clone())
+    }
 }
 
+/// Message handler installed with `ActorContext::become_handler`.
+///
+/// Called with the actor's state (downcast it to the actor's type), the
+/// message and the context, in place of `Actor::process_message`.
+pub type Behavior = Box<dyn Fn(&mut dyn Any, &dyn Message, &mut ActorContext) + Send>;
+
 /// Context passed to actors for sending messages and replies.
 pub struct ActorContext {
     /// This actor's reference (for passing as sender)
@@ -253,6 +985,26 @@ pub struct ActorContext {
     sender: Option<ActorRef>,
     /// Reply channel from fast_send
     reply_channel: Option<Sender<Box<dyn Message>>>,
+    /// Messages put aside with `stash`
+    stash: VecDeque<Envelope>,
+    /// Messages returned by `unstash_all`, handled before the mailbox
+    pub(crate) unstashed: VecDeque<Envelope>,
+    /// Where `discard_stash` sends stashed messages
+    dead_letters: Option<Arc<LocalActorRef>>,
+    /// Handler replacing `process_message`, from `become_handler`
+    behavior: Option<Behavior>,
+    /// Set when the current message called `become_handler` or `become_default`
+    behavior_changed: bool,
+    /// The Manager's child registry, for `spawn_child`
+    pub(crate) spawner: Option<ChildSpawner>,
+    /// The Manager's watchers, for `watch`
+    pub(crate) death_watch: Option<DeathWatch>,
+    /// The Manager's timer thread, for `schedule_once` and `schedule_periodic`
+    pub(crate) timers: Option<TimerService>,
+    /// This actor's metrics, for `metrics_counter`
+    pub(crate) metrics: Option<Arc<ActorMetrics>>,
+    /// Set by `stop_self`; the runtime stops after the current handler
+    pub(crate) stop_requested: bool,
 }
 
 impl ActorContext {
@@ -261,53 +1013,419 @@ impl ActorContext {
             self_ref: None,
             sender: None,
             reply_channel: None,
+            stash: VecDeque::new(),
+            unstashed: VecDeque::new(),
+            dead_letters: None,
+            behavior: None,
+            behavior_changed: false,
+            spawner: None,
+            death_watch: None,
+            timers: None,
+            metrics: None,
+            stop_requested: false,
         }
     }
 
     /// Reply to the current message
     ///
     /// For fast_send, sends through the reply channel.
-    /// For regular send, sends to the sender's mailbox.
+    /// For regular send, sends to the sender's mailbox, or to the dead
+    /// letter queue if that mailbox is full.
     pub fn reply(&mut self, msg: Box<dyn Message>) {
         if let Some(tx) = self.reply_channel.take() {
             // fast_send: send reply through dedicated channel
             let _ = tx.send(msg);
         } else if let Some(ref sender) = self.sender {
             // Regular send: send to sender's mailbox
-            sender.send(msg, self.self_ref.clone());
+            let _ = sender.try_send(msg, self.self_ref.clone());
         }
         // else: no sender provided, reply is dropped
     }
 
-    /// Get this actor's reference (mailbox address)
-    pub fn self_ref(&self) -> Option<ActorRef> {
-        self.self_ref.clone()
-    }
-
-    /// Set this actor's reference (called by runtime)
-    pub fn set_self_ref(&mut self, actor_ref: ActorRef) {
-        self.self_ref = Some(actor_ref);
+    /// Send `msg` to `target` with this actor as the sender, instead of
+    /// replying to the sender of the current message.
+    ///
+    /// For routing a reply to a third party; its replies come back here.
+    /// Like `reply`, does not block on a full mailbox.
+    pub fn reply_to(&mut self, msg: Box<dyn Message>, target: ActorRef) {
+        let _ = target.try_send(msg, self.self_ref.clone());
     }
 
-    /// Set up context for processing an envelope
-    pub fn prepare_for_envelope(&mut self, sender: Option<ActorRef>, reply_channel: Option<Sender<Box<dyn Message>>>) {
-        self.sender = sender;
-        self.reply_channel = reply_channel;
+    /// Start a child actor, registered with this actor's Manager as
+    /// `"<this actor>/<name>"`.
+    ///
+    /// The child is sent Start and runs on its own thread right away. When
+    /// this actor handles Shutdown, its children are stopped first, each
+    /// after its own children.
+    ///
+    /// Fails with `SpawnError::NotManaged` if this actor is not run by a
+    /// Manager, or `SpawnError::NameTaken` if the Manager already has an
+    /// actor of that name, e.g. a live child spawned earlier.
+    pub fn spawn_child(
+        &mut self,
+        name: &str,
+        actor: Box<dyn Actor>,
+        config: ThreadConfig,
+    ) -> Result<ActorRef, SpawnError> {
+        let (Some(spawner), Some(parent)) = (&self.spawner, &self.self_ref) else {
+            return Err(SpawnError::NotManaged);
+        };
+        spawner.spawn(parent, name, actor, config)
     }
-}
 
-impl Default for ActorContext {
-    fn default() -> Self {
-        Self::new()
+    /// Start a child actor with the default ThreadConfig, as `spawn_child`.
+    ///
+    /// The child is managed like an actor passed to `Manager::manage`: it is
+    /// listed by `Manager::get_names`, found by `Manager::get_actor` and can
+    /// be paused or restarted by name. It lives until this actor stops, or
+    /// until it stops itself.
+    ///
+    /// The child is only known to this process: the Manager does not
+    /// announce it to a global registry, as it has no endpoint for it. To
+    /// reach it remotely, register it with a `ZmqReceiver` and a
+    /// `RegistryClient` like any other actor.
+    ///
+    /// Fails like `spawn_child`.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{define_message, Actor, ActorContext, SpawnError};
+    /// # struct WorkerActor;
+    /// # impl WorkerActor { fn new() -> Self { WorkerActor } }
+    /// # impl Actor for WorkerActor {}
+    /// # struct Job { id: u32 }
+    /// # define_message!(Job);
+    /// # fn example(ctx: &mut ActorContext) -> Result<(), SpawnError> {
+    /// let worker = ctx.create_child("worker-1", Box::new(WorkerActor::new()))?;
+    /// worker.send(Box::new(Job { id: 1 }), ctx.self_ref());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_child(&mut self, name: &str, actor: Box<dyn Actor>) -> Result<ActorRef, SpawnError> {
+        self.spawn_child(name, actor, ThreadConfig::default())
     }
-}
 
-/// Trait for all actors in the system.
-///
-/// Implement this trait to create your own actors.
-/// Use the `handle_messages!` macro to register message handlers.
-pub trait Actor: Send + 'static {
-    /// Called once when the actor starts, before processing any messages.
+    /// Start a short-lived child actor, send it `msg` and resolve with its
+    /// first reply.
+    ///
+    /// The child is named `"<this actor>/$tempN"` and is stopped and
+    /// unregistered once it replies, returns from the handler without
+    /// keeping the reply address (`SpawnError::NoReply`) or `timeout`
+    /// passes (`SpawnError::Timeout`). Like `ask`, the future does not need a
+    /// tokio runtime; do not block this actor's handler on it if the child
+    /// messages this actor.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actors::{define_message, Actor, ActorContext, SpawnError};
+    /// # struct Resolver;
+    /// # impl Resolver { fn new() -> Self { Resolver } }
+    /// # impl Actor for Resolver {}
+    /// # struct Resolve { host: String }
+    /// # define_message!(Resolve);
+    /// # async fn example(ctx: &mut ActorContext, host: String) -> Result<(), SpawnError> {
+    /// let reply = ctx.spawn_temporary(Box::new(Resolver::new()), Box::new(Resolve { host }), Duration::from_secs(1));
+    /// let addr = reply.await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn spawn_temporary(
+        &mut self,
+        actor: Box<dyn Actor>,
+        msg: Box<dyn Message>,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Box<dyn Message>, SpawnError>> {
+        let (result_tx, result_rx) = oneshot::channel();
+        let spawned = match (&self.spawner, &self.self_ref) {
+            (Some(spawner), Some(parent)) => {
+                let name = format!("$temp{}", NEXT_TEMPORARY.fetch_add(1, Ordering::Relaxed));
+                spawner.spawn(parent, &name, actor, ThreadConfig::default()).map(|temporary| (spawner, temporary))
+            }
+            _ => Err(SpawnError::NotManaged),
+        };
+        match spawned {
+            Ok((spawner, temporary)) => {
+                let (reply_tx, reply_rx) = channel();
+                let reply_ref = ActorRef::new(reply_tx, format!("$reply:{}", temporary.name()));
+                let _ = temporary.try_send(msg, Some(reply_ref));
+
+                let spawner = spawner.clone();
+                thread::spawn(move || {
+                    let reply = match reply_rx.recv_timeout(timeout) {
+                        Ok(envelope) => Ok(envelope.msg),
+                        Err(RecvTimeoutError::Timeout) => Err(SpawnError::Timeout),
+                        Err(RecvTimeoutError::Disconnected) => Err(SpawnError::NoReply),
+                    };
+                    let _ = result_tx.send(reply);
+                    let _ = temporary.try_send(Box::new(Shutdown), None);
+                    spawner.unregister(temporary.name());
+                });
+            }
+            Err(err) => {
+                let _ = result_tx.send(Err(err));
+            }
+        }
+
+        async move { result_rx.await.unwrap_or(Err(SpawnError::Timeout)) }
+    }
+
+    /// Send `msg` to this actor after `delay`.
+    ///
+    /// The timer is cancelled when the returned handle is dropped. If this
+    /// actor has stopped by then, the message is dropped.
+    ///
+    /// # Panics
+    /// If this actor is not run by a Manager.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actors::{define_message, ActorContext, TimerHandle};
+    /// # struct Retry;
+    /// # define_message!(Retry);
+    /// # struct Client { retry: Option<TimerHandle> }
+    /// # impl Client { fn example(&mut self, ctx: &mut ActorContext) {
+    /// self.retry = Some(ctx.schedule_once(Duration::from_secs(1), Box::new(Retry)));
+    /// # } }
+    /// ```
+    pub fn schedule_once(&mut self, delay: Duration, msg: Box<dyn Message>) -> TimerHandle {
+        let (Some(timers), Some(self_ref)) = (&self.timers, &self.self_ref) else {
+            panic!("schedule_once is only available to actors run by a Manager");
+        };
+        timers.schedule_once(self_ref.clone(), delay, msg)
+    }
+
+    /// Send this actor a message from `msg_factory` every `interval`, until
+    /// the returned handle is dropped or this actor stops.
+    ///
+    /// Ticks missed while the actor's mailbox was full are dropped, not
+    /// sent late. If `msg_factory` panics, the timer stops.
+    ///
+    /// # Panics
+    /// If this actor is not run by a Manager, or `interval` is zero.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actors::{define_message, ActorContext, TimerHandle};
+    /// # struct Heartbeat;
+    /// # define_message!(Heartbeat);
+    /// # struct Node { heartbeat: Option<TimerHandle> }
+    /// # impl Node { fn example(&mut self, ctx: &mut ActorContext) {
+    /// self.heartbeat = Some(ctx.schedule_periodic(Duration::from_millis(500), || Box::new(Heartbeat)));
+    /// # } }
+    /// ```
+    pub fn schedule_periodic<F>(&mut self, interval: Duration, msg_factory: F) -> TimerHandle
+    where
+        F: Fn() -> Box<dyn Message> + Send + 'static,
+    {
+        let (Some(timers), Some(self_ref)) = (&self.timers, &self.self_ref) else {
+            panic!("schedule_periodic is only available to actors run by a Manager");
+        };
+        timers.schedule_periodic(self_ref.clone(), interval, Box::new(msg_factory))
+    }
+
+    /// The custom counter `name` of this actor, created at zero on first use.
+    ///
+    /// Counters appear in `Manager::actor_custom_metrics` and on the metrics
+    /// server as `actor_custom_total{actor="...",metric="..."}`. Keep the
+    /// returned counter rather than looking it up for every message.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::ActorContext;
+    /// # fn example(ctx: &mut ActorContext) {
+    /// let processed = ctx.metrics_counter("payments_processed");
+    /// processed.increment();
+    /// # }
+    /// ```
+    pub fn metrics_counter(&mut self, name: &str) -> Counter {
+        match &self.metrics {
+            Some(metrics) => metrics.counter(name),
+            // Not run by an ActorRuntime: count, but report nowhere
+            None => Counter::default(),
+        }
+    }
+
+    /// Report that handling `msg` failed, without panicking.
+    ///
+    /// The error goes to the Manager's message error handler (see
+    /// `Manager::set_message_error_handler`), or to the dead letter queue as
+    /// a `DeadLetter` whose reason is the error if none is set. The actor
+    /// keeps running. `handle_messages!` calls this when a handler returns
+    /// `Err`.
+    pub fn report_message_error(&self, msg: &dyn Message, error: HandlerError) {
+        let name = self.self_ref.as_ref().map_or("", |r| r.name());
+        let unhandled = match &self.spawner {
+            Some(spawner) => spawner.report_message_error(name, msg, error),
+            None => Some(error),
+        };
+        if let (Some(error), Some(dead_letters)) = (unhandled, &self.dead_letters) {
+            let letter = DeadLetter::new(name, msg.type_name(), &error.to_string());
+            let _ = dead_letters.enqueue(Envelope::new(Box::new(letter), None));
+        }
+    }
+
+    /// Receive a `Terminated` message when `target` stops, normally or not.
+    ///
+    /// `target` must be run by this actor's Manager; if it has already
+    /// stopped, `Terminated` is sent right away. Does nothing for actors
+    /// not run by a Manager.
+    pub fn watch(&mut self, target: ActorRef) {
+        if let (Some(death_watch), Some(self_ref)) = (&self.death_watch, &self.self_ref) {
+            death_watch.watch(target, self_ref.clone());
+        }
+    }
+
+    /// Stop watching `target`.
+    pub fn unwatch(&mut self, target: &ActorRef) {
+        if let (Some(death_watch), Some(self_ref)) = (&self.death_watch, &self.self_ref) {
+            death_watch.unwatch(target.name(), self_ref.name());
+        }
+    }
+
+    /// Stop this actor once the current handler returns.
+    ///
+    /// The actor's children are stopped, `post_stop` and `end` are called and
+    /// watchers receive `Terminated` with `TerminationReason::Normal`. Its name
+    /// is removed from the Manager, and messages still queued or sent to it
+    /// later go to the dead letter queue as "actor stopped".
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{define_message, ActorContext};
+    /// # struct Request { id: u32 }
+    /// # define_message!(Request);
+    /// # struct Response { id: u32 }
+    /// # define_message!(Response);
+    /// # struct Server;
+    /// # impl Server {
+    /// fn on_request(&mut self, msg: &Request, ctx: &mut ActorContext) {
+    ///     ctx.reply(Box::new(Response { id: msg.id }));
+    ///     ctx.stop_self();
+    /// }
+    /// # }
+    /// ```
+    pub fn stop_self(&mut self) {
+        self.stop_requested = true;
+    }
+
+    /// Handle subsequent messages with `handler` instead of `process_message`.
+    ///
+    /// Lets an actor act as a state machine, with one handler per state.
+    /// Takes effect from the next message; calling it again replaces the
+    /// handler. (`become` itself is a reserved word in Rust.)
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{define_message, ActorContext};
+    /// # struct Open;
+    /// # define_message!(Open);
+    /// # struct Enter;
+    /// # define_message!(Enter);
+    /// # struct Close;
+    /// # define_message!(Close);
+    /// # struct Door { visitors: u32 }
+    /// # impl Door {
+    /// fn on_open(&mut self, _msg: &Open, ctx: &mut ActorContext) {
+    ///     ctx.become_handler(Box::new(|state, msg, ctx| {
+    ///         let door = state.downcast_mut::<Door>().unwrap();
+    ///         if msg.as_any().is::<Enter>() {
+    ///             door.visitors += 1;
+    ///         } else if msg.as_any().is::<Close>() {
+    ///             ctx.become_default();
+    ///         }
+    ///     }));
+    /// }
+    /// # }
+    /// ```
+    pub fn become_handler(&mut self, handler: Behavior) {
+        self.behavior = Some(handler);
+        self.behavior_changed = true;
+    }
+
+    /// Go back to handling messages with the actor's `process_message`.
+    pub fn become_default(&mut self) {
+        self.behavior = None;
+        self.behavior_changed = true;
+    }
+
+    /// Put a message aside to handle later, e.g. while waiting for a reply
+    /// needed to handle it.
+    ///
+    /// The message keeps the current message's sender, so replying after
+    /// `unstash_all` still reaches it.
+    pub fn stash(&mut self, msg: Box<dyn Message>) {
+        self.stash.push_back(Envelope::new(msg, self.sender.clone()));
+    }
+
+    /// Return all stashed messages to the front of the mailbox, in the order
+    /// they were stashed, ahead of messages already waiting.
+    pub fn unstash_all(&mut self) {
+        while let Some(envelope) = self.stash.pop_back() {
+            self.unstashed.push_front(envelope);
+        }
+    }
+
+    /// Number of stashed messages
+    pub fn stash_count(&self) -> usize {
+        self.stash.len()
+    }
+
+    /// Drop all stashed messages, sending them to the dead letter queue
+    pub fn discard_stash(&mut self) {
+        let target = self.self_ref.as_ref().map_or("", |r| r.name()).to_string();
+        for envelope in self.stash.drain(..) {
+            send_dead_letter(self.dead_letters.as_ref(), &target, &envelope, "discarded from stash");
+        }
+    }
+
+    /// Get this actor's reference (mailbox address)
+    pub fn self_ref(&self) -> Option<ActorRef> {
+        self.self_ref.clone()
+    }
+
+    /// Get the sender of the message being handled, if it gave one
+    pub fn sender(&self) -> Option<ActorRef> {
+        self.sender.clone()
+    }
+
+    /// Set this actor's reference (called by runtime)
+    pub fn set_self_ref(&mut self, actor_ref: ActorRef) {
+        self.self_ref = Some(actor_ref);
+    }
+
+    /// Set up context for processing an envelope
+    pub fn prepare_for_envelope(&mut self, sender: Option<ActorRef>, reply_channel: Option<Sender<Box<dyn Message>>>) {
+        self.sender = sender;
+        self.reply_channel = reply_channel;
+    }
+}
+
+impl Default for ActorContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decision returned by `Actor::peek` for an incoming message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeekResult {
+    /// Process the message now
+    Accept,
+    /// Refuse the message; it is sent to the dead letter queue with `reason`
+    Reject { reason: String },
+    /// Put the message back in the mailbox after `retry_after`
+    Defer { retry_after: Duration },
+}
+
+/// Trait for all actors in the system.
+///
+/// Implement this trait to create your own actors.
+/// Use the `handle_messages!` macro to register message handlers.
+pub trait Actor: Any + Send + 'static {
+    /// Called once when the actor starts, before processing any messages.
     ///
     /// Use this for initialization that doesn't require messaging.
     fn init(&mut self) {}
@@ -317,12 +1435,82 @@ pub trait Actor: Send + 'static {
     /// Use this for cleanup.
     fn end(&mut self) {}
 
+    /// Called on the actor's thread after `init`, before the first message
+    /// is taken from the mailbox.
+    ///
+    /// Unlike `init`, has the context, e.g. to send messages or spawn children.
+    fn pre_start(&mut self, _ctx: &mut ActorContext) {}
+
+    /// Called on the actor's thread after the last message is processed,
+    /// just before `end`.
+    fn post_stop(&mut self, _ctx: &mut ActorContext) {}
+
+    /// Called on a new instance that replaces a failed or explicitly
+    /// restarted one, after `init` and before `pre_start` and the first
+    /// message.
+    ///
+    /// A new instance starts from its factory's state; use this to restore
+    /// state, e.g. from an external store.
+    fn on_restart(&mut self, _reason: &RestartReason, _ctx: &mut ActorContext) {}
+
+    /// Look at a message before it is processed and decide what to do with it.
+    ///
+    /// Called on the actor's thread for every message taken from the mailbox,
+    /// including messages routed in by a `ZmqReceiver`, except `Shutdown`.
+    /// Rejected messages go to the Manager's dead letter queue (and are
+    /// dropped for actors not run by a Manager); deferred messages are
+    /// re-sent to this actor after the delay, behind anything queued since.
+    ///
+    /// The default accepts everything.
+    fn peek(&self, _msg: &dyn Message) -> PeekResult {
+        PeekResult::Accept
+    }
+
+    /// Pick an actor to pass an accepted message on to, instead of
+    /// processing it.
+    ///
+    /// The message is forwarded as it is, with its sender and `fast_send`
+    /// reply channel, so the chosen actor replies to the original sender.
+    /// The default processes everything.
+    fn route(&mut self, _msg: &dyn Message) -> Option<ActorRef> {
+        None
+    }
+
+    /// State to show for the `inspect` command of the management server
+    /// (see `Manager::start_management_server`).
+    ///
+    /// Called on the actor's thread between messages. The default shows
+    /// nothing (`null`).
+    fn inspect_state(&self) -> Option<serde_json::Value> {
+        None
+    }
+
     /// Process a message.
     ///
     /// Use the `handle_messages!` macro to implement this method cleanly.
     fn process_message(&mut self, _msg: &dyn Message, _ctx: &mut ActorContext) {}
 }
 
+/// What a handler given to `handle_messages!` returns: `()`, or
+/// `Result<(), HandlerError>` to report a failure without panicking.
+///
+/// An `Err` is passed to `ActorContext::report_message_error`.
+pub trait HandlerResult {
+    fn into_result(self) -> Result<(), HandlerError>;
+}
+
+impl HandlerResult for () {
+    fn into_result(self) -> Result<(), HandlerError> {
+        Ok(())
+    }
+}
+
+impl HandlerResult for Result<(), HandlerError> {
+    fn into_result(self) -> Result<(), HandlerError> {
+        self
+    }
+}
+
 /// Macro to cleanly dispatch messages to handler methods.
 ///
 /// This macro generates the `process_message` implementation with type-safe
@@ -330,7 +1518,7 @@ pub trait Actor: Send + 'static {
 ///
 /// # Example
 /// ```rust
-/// use actors::{Actor, ActorContext, Message, define_message, handle_messages};
+/// use actors::{ActorContext, ActorRef, Message, define_message, handle_messages};
 ///
 /// struct Ping { count: i32 }
 /// define_message!(Ping);
@@ -342,8 +1530,6 @@ pub trait Actor: Send + 'static {
 ///     other_actor: ActorRef,
 /// }
 ///
-/// impl Actor for MyActor {}
-///
 /// handle_messages!(MyActor,
 ///     Ping => on_ping,
 ///     Pong => on_pong
@@ -360,14 +1546,56 @@ pub trait Actor: Send + 'static {
 ///     }
 /// }
 /// ```
+///
+/// Handlers may also return `Result<(), HandlerError>`; errors go to the
+/// Manager's message error handler (see `Manager::set_message_error_handler`):
+/// ```
+/// # use actors::{define_message, ActorContext, HandlerError};
+/// # struct Ping;
+/// # define_message!(Ping);
+/// # struct Pong { count: i32 }
+/// # define_message!(Pong);
+/// # struct Store;
+/// # impl Store { fn load(&self) -> Result<i32, HandlerError> { Ok(0) } }
+/// # struct MyActor { store: Store }
+/// # impl MyActor {
+/// fn on_ping(&mut self, msg: &Ping, ctx: &mut ActorContext) -> Result<(), HandlerError> {
+///     let count = self.store.load()?;
+///     ctx.reply(Box::new(Pong { count }));
+///     Ok(())
+/// }
+/// # }
+/// ```
+///
+/// Lifecycle hooks can be given first, as methods taking the context. Either
+/// may be given without the other:
+/// ```
+/// # use actors::{define_message, handle_messages, ActorContext};
+/// # struct Ping;
+/// # define_message!(Ping);
+/// # struct MyActor;
+/// # impl MyActor {
+/// #     fn on_pre_start(&mut self, _ctx: &mut ActorContext) {}
+/// #     fn on_post_stop(&mut self, _ctx: &mut ActorContext) {}
+/// #     fn on_ping(&mut self, _msg: &Ping, _ctx: &mut ActorContext) {}
+/// # }
+/// handle_messages!(MyActor,
+///     pre_start => on_pre_start,
+///     post_stop => on_post_stop,
+///     Ping => on_ping
+/// );
+/// ```
 #[macro_export]
 macro_rules! handle_messages {
-    ($actor_type:ty, $($msg_type:ty => $handler:ident),+ $(,)?) => {
+    (@impl $actor_type:ty, [$($pre_start:ident)?], [$($post_stop:ident)?],
+     $($msg_type:ty => $handler:ident),+ $(,)?) => {
         impl $actor_type {
             fn dispatch_message(&mut self, msg: &dyn $crate::Message, ctx: &mut $crate::ActorContext) -> bool {
                 $(
                     if let Some(typed_msg) = msg.as_any().downcast_ref::<$msg_type>() {
-                        self.$handler(typed_msg, ctx);
+                        if let Err(error) = $crate::actor::HandlerResult::into_result(self.$handler(typed_msg, ctx)) {
+                            ctx.report_message_error(msg, error);
+                        }
                         return true;
                     }
                 )+
@@ -376,13 +1604,212 @@ macro_rules! handle_messages {
         }
 
         impl $crate::Actor for $actor_type {
+            $(
+                fn pre_start(&mut self, ctx: &mut $crate::ActorContext) {
+                    self.$pre_start(ctx);
+                }
+            )?
+
+            $(
+                fn post_stop(&mut self, ctx: &mut $crate::ActorContext) {
+                    self.$post_stop(ctx);
+                }
+            )?
+
             fn process_message(&mut self, msg: &dyn $crate::Message, ctx: &mut $crate::ActorContext) {
                 self.dispatch_message(msg, ctx);
             }
         }
     };
+    ($actor_type:ty, pre_start => $pre_start:ident, post_stop => $post_stop:ident, $($handlers:tt)+) => {
+        $crate::handle_messages!(@impl $actor_type, [$pre_start], [$post_stop], $($handlers)+);
+    };
+    ($actor_type:ty, pre_start => $pre_start:ident, $($handlers:tt)+) => {
+        $crate::handle_messages!(@impl $actor_type, [$pre_start], [], $($handlers)+);
+    };
+    ($actor_type:ty, post_stop => $post_stop:ident, $($handlers:tt)+) => {
+        $crate::handle_messages!(@impl $actor_type, [], [$post_stop], $($handlers)+);
+    };
+    ($actor_type:ty, $($handlers:tt)+) => {
+        $crate::handle_messages!(@impl $actor_type, [], [], $($handlers)+);
+    };
+}
+
+/// Generation of a pooled mailbox channel, bumped each time the channel is
+/// given back to its pool
+type Generation = Arc<RwLock<u64>>;
+
+/// A mailbox channel: the sending half used by ActorRefs, the receiving half
+/// owned by the actor's runtime and the channel's generation.
+type MailboxChannel = (Sender<Envelope>, Receiver<Envelope>, Generation);
+
+/// The Manager's services a context needs, kept aside while a handler runs on
+/// its own thread, see `handle_with_timeout`
+type ContextHandles = (Option<ChildSpawner>, Option<DeathWatch>, Option<TimerService>);
+
+/// Pool of pre-allocated mailbox channels.
+///
+/// Actors created with `Manager::manage_pooled` take their mailbox from the pool
+/// and give it back when they stop, which avoids a channel allocation per actor
+/// when actors are created and destroyed frequently.
+///
+/// Each channel carries a generation, moved on when the channel is given back.
+/// Refs to the stopped actor fail like refs to any stopped actor, so their
+/// messages go to the dead letter queue and never reach the channel's next
+/// actor. Messages left in the channel are dead letters too.
+#[derive(Clone)]
+pub struct ActorRefPool {
+    channels: Arc<Mutex<Vec<MailboxChannel>>>,
+    capacity: usize,
+}
+
+impl ActorRefPool {
+    /// Create a pool with `capacity` pre-allocated channels.
+    pub fn new(capacity: usize) -> Self {
+        let channels = (0..capacity).map(|_| new_mailbox_channel()).collect();
+        ActorRefPool {
+            channels: Arc::new(Mutex::new(channels)),
+            capacity,
+        }
+    }
+
+    /// Maximum number of idle channels held by the pool
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of idle channels currently in the pool
+    pub fn available(&self) -> usize {
+        self.channels.lock().unwrap().len()
+    }
+
+    /// Take a channel from the pool, allocating a new one if the pool is empty.
+    pub(crate) fn acquire(&self) -> MailboxChannel {
+        self.channels.lock().unwrap().pop().unwrap_or_else(new_mailbox_channel)
+    }
+
+    /// Return a drained channel to the pool, in its next generation.
+    ///
+    /// The channel is dropped if the pool is full.
+    pub(crate) fn release(&self, channel: MailboxChannel) {
+        let mut channels = self.channels.lock().unwrap();
+        if channels.len() < self.capacity {
+            channels.push(channel);
+        }
+    }
+}
+
+fn new_mailbox_channel() -> MailboxChannel {
+    let (sender, receiver) = channel();
+    (sender, receiver, Arc::new(RwLock::new(0)))
+}
+
+/// Closure run against an actor's state by `Manager::inspect_actor`.
+pub(crate) type InspectFn = Box<dyn FnOnce(&dyn Actor) + Send>;
+
+/// Internal message asking an actor thread to run an `InspectFn` between messages.
+///
+/// Intercepted by `ActorRuntime::run`; never reaches `process_message`.
+pub(crate) struct InspectActor {
+    pub(crate) f: Option<InspectFn>,
+}
+
+impl Message for InspectActor {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Channel a stopped runtime is handed back on by `Manager::take_actor`.
+pub(crate) type TakeReply = Sender<(ActorRuntime, crate::manager::ThreadConfig)>;
+
+/// Internal message asking an actor thread to stop and hand its runtime back.
+///
+/// Intercepted by `ActorRuntime::run`; never reaches `process_message`.
+pub(crate) struct TakeActor {
+    pub(crate) reply: Option<TakeReply>,
+}
+crate::define_message!(TakeActor);
+
+/// Whether an actor is paused (see `Manager::pause_actor`), shared by its
+/// runtime and the refs it creates.
+///
+/// A paused runtime does not take messages from its mailbox, so they count
+/// against its capacity. It only wakes for control messages, such as
+/// Shutdown, sent through those refs.
+#[derive(Default)]
+pub(crate) struct PauseGate {
+    state: Mutex<PauseState>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct PauseState {
+    paused: bool,
+    /// Control messages sent through the refs and not yet received
+    pending_control: usize,
+}
+
+impl PauseGate {
+    fn set_paused(&self, paused: bool) {
+        self.state.lock().unwrap().paused = paused;
+        self.changed.notify_all();
+    }
+
+    fn is_paused(&self) -> bool {
+        self.state.lock().unwrap().paused
+    }
+
+    fn control_sent(&self) {
+        self.state.lock().unwrap().pending_control += 1;
+        self.changed.notify_all();
+    }
+
+    fn control_received(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.pending_control = state.pending_control.saturating_sub(1);
+    }
+
+    /// Block while paused with no control message waiting in the mailbox
+    fn wait(&self) {
+        let state = self.state.lock().unwrap();
+        let _state = self
+            .changed
+            .wait_while(state, |state| state.paused && state.pending_control == 0)
+            .unwrap();
+    }
 }
 
+/// Internal message starting a drain, see `Manager::drain_and_shutdown`.
+///
+/// Intercepted by `ActorRuntime::run`, which signals `done` (everything sent
+/// before it has been handled) and rejects the messages after it.
+pub(crate) struct Drain {
+    pub(crate) done: Sender<()>,
+}
+crate::define_message!(Drain);
+
+/// Describe a caught panic for the dead letter queue
+fn panic_reason(panic: &(dyn Any + Send)) -> String {
+    let detail = panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str));
+    match detail {
+        Some(detail) => format!("handler panicked: {}", detail),
+        None => "handler panicked".to_string(),
+    }
+}
+
+/// Stand-in for an actor whose handler was abandoned after a timeout
+struct AbandonedActor;
+
+impl Actor for AbandonedActor {}
+
 /// Runtime for a single actor, manages message loop
 pub struct ActorRuntime {
     pub actor: Box<dyn Actor>,
@@ -391,66 +1818,480 @@ pub struct ActorRuntime {
     pub name: String,
     pub context: ActorContext,
     pub running: Arc<Mutex<bool>>,
+    /// Pool the mailbox channel is returned to when the actor stops
+    pub pool: Option<ActorRefPool>,
+    /// Generation of the pooled mailbox channel, given to this actor's refs
+    generation: Option<(Generation, u64)>,
+    /// Mailbox and processing counters, shared with refs from `get_ref`
+    pub(crate) metrics: Arc<ActorMetrics>,
+    /// Where refs from `get_ref` report undeliverable messages
+    dead_letters: Option<Arc<LocalActorRef>>,
+    /// Whether `init` has run (a restored actor is not initialised again)
+    pub(crate) initialized: bool,
+    /// Set when the loop stopped for a `TakeActor` message
+    pub(crate) take_reply: Option<TakeReply>,
+    /// Longest a handler may run, from `Manager::with_default_handler_timeout`
+    pub(crate) handler_timeout: Option<Duration>,
+    /// What to do when a handler fails
+    pub(crate) supervisor: Supervisor,
+    /// Parent of an actor from `spawn_child`, told when the actor escalates
+    pub(crate) parent: Option<ActorRef>,
+    /// Reported to watchers when the thread exits
+    pub(crate) termination: TerminationReason,
+    /// Shared with this actor's refs, see `ActorRef::is_alive`
+    alive: Arc<AtomicBool>,
+    /// The Manager's recorder, see `Manager::start_message_recording`
+    pub(crate) recorder: Option<MessageRecorder>,
+    /// Shared with this actor's refs, see `Manager::pause_actor`
+    pause: Arc<PauseGate>,
+    /// Messages taken from the mailbox while paused, to reach a control
+    /// message behind them; handled first once resumed
+    held: VecDeque<Envelope>,
+    /// Set by `Drain`: messages other than Shutdown become dead letters
+    draining: bool,
 }
 
 impl ActorRuntime {
     /// Create a new actor runtime
     pub fn new(name: String, actor: Box<dyn Actor>) -> Self {
         let (sender, receiver) = channel();
-        let actor_ref = ActorRef::new(sender.clone(), name.clone());
+        Self::with_channel(name, actor, sender, receiver)
+    }
+
+    /// Create a new actor runtime whose mailbox is borrowed from a pool
+    pub fn new_pooled(name: String, actor: Box<dyn Actor>, pool: &ActorRefPool) -> Self {
+        let (sender, receiver, generation) = pool.acquire();
+        let mut runtime = Self::with_channel(name, actor, sender, receiver);
+        runtime.pool = Some(pool.clone());
+        let current = *generation.read().unwrap();
+        runtime.generation = Some((generation, current));
+        // Refs from here on carry the generation
+        let self_ref = runtime.get_ref();
+        runtime.context.set_self_ref(self_ref);
+        runtime
+    }
 
+    /// Create a new actor runtime using an existing mailbox channel
+    fn with_channel(
+        name: String,
+        actor: Box<dyn Actor>,
+        sender: Sender<Envelope>,
+        receiver: Receiver<Envelope>,
+    ) -> Self {
+        let metrics = Arc::new(ActorMetrics::default());
         let mut context = ActorContext::new();
-        context.set_self_ref(actor_ref);
+        context.metrics = Some(Arc::clone(&metrics));
 
-        ActorRuntime {
+        let mut runtime = ActorRuntime {
             actor,
             receiver,
             sender,
             name,
             context,
             running: Arc::new(Mutex::new(true)),
+            pool: None,
+            generation: None,
+            metrics,
+            dead_letters: None,
+            initialized: false,
+            take_reply: None,
+            handler_timeout: None,
+            supervisor: Supervisor::default(),
+            parent: None,
+            termination: TerminationReason::Normal,
+            alive: Arc::new(AtomicBool::new(true)),
+            recorder: None,
+            pause: Arc::new(PauseGate::default()),
+            held: VecDeque::new(),
+            draining: false,
+        };
+        let self_ref = runtime.get_ref();
+        runtime.context.set_self_ref(self_ref);
+        runtime
+    }
+
+    /// Route undeliverable sends through this actor's refs to `dead_letters`.
+    ///
+    /// Must be called before `get_ref` for the refs to be affected.
+    pub(crate) fn set_dead_letters(&mut self, dead_letters: &ActorRef) {
+        if let ActorRef::Local(dlq) = dead_letters {
+            self.dead_letters = Some(Arc::new(dlq.clone()));
+            self.context.dead_letters = self.dead_letters.clone();
+            let self_ref = self.get_ref();
+            self.context.set_self_ref(self_ref);
         }
     }
 
     /// Get an ActorRef for this actor
     pub fn get_ref(&self) -> ActorRef {
-        ActorRef::new(self.sender.clone(), self.name.clone())
+        ActorRef::Local(self.local_ref())
+    }
+
+    fn local_ref(&self) -> LocalActorRef {
+        let mut actor_ref = LocalActorRef::with_metrics(
+            self.sender.clone(),
+            self.name.clone(),
+            Arc::clone(&self.metrics),
+            Arc::clone(&self.alive),
+        );
+        actor_ref.dead_letters = self.dead_letters.clone();
+        actor_ref.generation = self.generation.clone();
+        actor_ref.pause = Some(Arc::clone(&self.pause));
+        actor_ref
     }
 
     /// Run the actor's message loop
+    ///
+    /// Returns after the actor has handled a Shutdown message, or without
+    /// calling `end` when it is taken by `Manager::take_actor`.
     pub fn run(&mut self) {
-        self.actor.init();
+        if !self.initialized {
+            self.actor.init();
+            self.actor.pre_start(&mut self.context);
+            self.initialized = true;
+        }
 
         while *self.running.lock().unwrap() {
-            match self.receiver.recv() {
-                Ok(envelope) => {
-                    self.dispatch(envelope);
+            if self.context.stop_requested {
+                self.stop_self();
+                break;
+            }
+            let paused = self.pause.is_paused();
+            if !paused {
+                // Held messages go before anything still in the mailbox
+                while let Some(held) = self.held.pop_back() {
+                    self.metrics.record_dequeued();
+                    self.context.unstashed.push_front(held);
+                }
+            }
+            // Unstashed messages go before anything still in the mailbox
+            let unstashed = if paused { None } else { self.context.unstashed.pop_front() };
+            let mut envelope = match unstashed {
+                Some(envelope) => envelope,
+                None => {
+                    self.pause.wait();
+                    if !self.held.is_empty() && !self.pause.is_paused() {
+                        // Resumed: handle the held messages first
+                        continue;
+                    }
+                    match self.receiver.recv() {
+                        Ok(envelope) => {
+                            self.metrics.record_dequeued();
+                            if is_control(envelope.msg.as_ref()) {
+                                self.pause.control_received();
+                            }
+                            envelope
+                        }
+                        Err(_) => {
+                            // Channel closed, exit
+                            break;
+                        }
+                    }
+                }
+            };
+            if let Some(inspect) = envelope.msg.as_any_mut().downcast_mut::<InspectActor>() {
+                self.inspect(inspect.f.take());
+                continue;
+            }
+            if let Some(take) = envelope.msg.as_any_mut().downcast_mut::<TakeActor>() {
+                self.take_reply = take.reply.take();
+                return;
+            }
+            if let Some(set) = envelope.msg.as_any_mut().downcast_mut::<SetSupervisor>() {
+                self.supervisor.strategy = set.strategy;
+                self.supervisor.factory = set.factory.take();
+                self.supervisor.restart_count = Arc::clone(&set.restart_count);
+                continue;
+            }
+            if envelope.msg.as_any().is::<RestartActor>() {
+                if let Some(actor) = self.supervisor.restart_now() {
+                    // The old instance stops as it would on Shutdown
+                    self.actor.post_stop(&mut self.context);
+                    self.actor.end();
+                    self.restart(actor, RestartReason::Explicit);
+                }
+                continue;
+            }
+            if let Some(failed) = envelope.msg.as_any().downcast_ref::<ChildFailed>() {
+                let reason = format!("child '{}' failed: {}", failed.child, failed.reason);
+                self.fail(None, &reason, TerminationReason::Killed);
+                continue;
+            }
+            if let Some(drain) = envelope.msg.as_any().downcast_ref::<Drain>() {
+                self.draining = true;
+                let _ = drain.done.send(());
+                continue;
+            }
+            if self.draining && !envelope.msg.as_any().is::<Shutdown>() {
+                self.reject(envelope, "actor draining");
+                continue;
+            }
+            // A paused actor still handles Shutdown, so it can be stopped
+            if self.pause.is_paused() && !envelope.msg.as_any().is::<Shutdown>() {
+                self.metrics.record_held();
+                self.held.push_back(envelope);
+                continue;
+            }
+            if envelope.msg.as_any().is::<TimedEnvelope>() {
+                let msg: Box<dyn Any> = envelope.msg;
+                let timed = msg.downcast::<TimedEnvelope>().expect("checked above");
+                envelope.msg = timed.inner;
+                if Instant::now() >= timed.expires_at {
+                    self.reject(envelope, "Expired");
+                    continue;
                 }
-                Err(_) => {
-                    // Channel closed, exit
-                    break;
+            }
+            let is_shutdown = envelope.msg.as_any().is::<Shutdown>();
+            if is_shutdown {
+                self.metrics.set_status(ActorStatus::Stopping);
+                if let Some(spawner) = &self.context.spawner {
+                    spawner.stop_children(&self.name);
                 }
+                self.supervised_dispatch(envelope);
+                break;
+            }
+            match self.actor.peek(envelope.msg.as_ref()) {
+                PeekResult::Accept => match self.actor.route(envelope.msg.as_ref()) {
+                    Some(target) => {
+                        let _ = target.forward(envelope);
+                    }
+                    None => self.supervised_dispatch(envelope),
+                },
+                PeekResult::Reject { reason } => self.reject(envelope, &reason),
+                PeekResult::Defer { retry_after } => self.defer(envelope, retry_after),
             }
         }
 
+        if !*self.running.lock().unwrap() {
+            // Stopped from outside (e.g. a drain that timed out): queued messages are dead letters
+            self.stop_and_drain();
+        }
+        for envelope in std::mem::take(&mut self.held) {
+            self.metrics.record_dequeued();
+            self.reject(envelope, "actor stopped");
+        }
+        self.actor.post_stop(&mut self.context);
         self.actor.end();
     }
 
+    /// Run an inspection closure against the actor's state
+    pub(crate) fn inspect(&self, f: Option<InspectFn>) {
+        if let Some(f) = f {
+            f(self.actor.as_ref());
+        }
+    }
+
     /// Dispatch a message to the actor
     fn dispatch(&mut self, envelope: Envelope) {
         let msg = envelope.msg;
+        if let Some(recorder) = &self.recorder {
+            recorder.record(&self.name, msg.as_ref());
+        }
 
         // Set up context for this message
         self.context.prepare_for_envelope(envelope.sender, envelope.reply_channel);
 
-        // Call the actor's process_message
-        self.actor.process_message(msg.as_ref(), &mut self.context);
+        // Call the actor's current handler
+        let start = Instant::now();
+        self.metrics.record_processing();
+        match self.handler_timeout {
+            Some(timeout) => self.handle_with_timeout(msg, timeout),
+            None => Self::handle(self.actor.as_mut(), msg.as_ref(), &mut self.context),
+        }
+        self.metrics.record_processed(start.elapsed());
+        // Release the sender, so waiters for a reply see it will not come
+        self.context.prepare_for_envelope(None, None);
+    }
+
+    /// Pass a message to the actor's current handler
+    fn handle(actor: &mut dyn Actor, msg: &dyn Message, context: &mut ActorContext) {
+        context.behavior_changed = false;
+        match context.behavior.take() {
+            Some(handler) => {
+                handler(actor as &mut dyn Any, msg, context);
+                if !context.behavior_changed {
+                    context.behavior = Some(handler);
+                }
+            }
+            None => actor.process_message(msg, context),
+        }
+    }
+
+    /// Dispatch a message, applying the supervision strategy if the handler panics
+    fn supervised_dispatch(&mut self, envelope: Envelope) {
+        let message_type = envelope.msg.type_name();
+        let start = Instant::now();
+        if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(|| self.dispatch(envelope))) {
+            self.metrics.record_processed(start.elapsed());
+            self.fail(Some(message_type), &panic_reason(panic.as_ref()), TerminationReason::Panicked);
+        }
+    }
+
+    /// Handle a failed handler (or an escalating child) according to the
+    /// supervision strategy. The failed message, if any, is a dead letter;
+    /// `cause` is reported to watchers if the actor is stopped.
+    fn fail(&mut self, message_type: Option<&str>, reason: &str, cause: TerminationReason) {
+        if let (Some(dead_letters), Some(message_type)) = (&self.dead_letters, message_type) {
+            let letter = DeadLetter::new(&self.name, message_type, reason);
+            let _ = dead_letters.enqueue(Envelope::new(Box::new(letter), None));
+        }
+
+        match self.supervisor.on_failure(Instant::now()) {
+            Directive::Restart(actor) => {
+                let reason = RestartReason::Panic { message: reason.to_string() };
+                self.restart(actor, reason);
+            }
+            Directive::Stop => {
+                self.termination = cause;
+                self.stop_and_drain();
+            }
+            Directive::Escalate => {
+                self.termination = cause;
+                self.stop_and_drain();
+                if let Some(parent) = &self.parent {
+                    let failed = ChildFailed {
+                        child: self.name.clone(),
+                        reason: reason.to_string(),
+                    };
+                    parent.send(Box::new(failed), None);
+                }
+            }
+        }
+    }
+
+    /// Replace the actor with a new instance, which starts like the first
+    /// one (init, pre_start, then Start) with `on_restart` after init
+    fn restart(&mut self, actor: Box<dyn Actor>, reason: RestartReason) {
+        self.actor = actor;
+        self.context.become_default();
+        self.actor.init();
+        self.actor.on_restart(&reason, &mut self.context);
+        self.actor.pre_start(&mut self.context);
+        self.context.unstashed.push_front(Envelope::new(Box::new(Start), None));
+    }
+
+    /// Leave the Manager and stop, for `ActorContext::stop_self`
+    fn stop_self(&mut self) {
+        if let Some(spawner) = &self.context.spawner {
+            spawner.unregister(&self.name);
+        }
+        self.stop_and_drain();
+    }
+
+    /// Stop the loop and children of the actor; queued messages become dead letters
+    fn stop_and_drain(&mut self) {
+        self.stop();
+        if let Some(spawner) = &self.context.spawner {
+            spawner.stop_children(&self.name);
+        }
+        for envelope in std::mem::take(&mut self.context.unstashed) {
+            self.reject(envelope, "actor stopped");
+        }
+        while let Ok(envelope) = self.receiver.try_recv() {
+            self.metrics.record_dequeued();
+            self.reject(envelope, "actor stopped");
+        }
+    }
+
+    /// Run the handler on its own thread, giving up on it after `timeout`.
+    ///
+    /// Threads cannot be interrupted, so a handler that times out is left
+    /// running with the actor and its context. The actor then fails like a
+    /// panicking one: the message is a dead letter and the supervision
+    /// strategy decides whether a new instance replaces it.
+    fn handle_with_timeout(&mut self, msg: Box<dyn Message>, timeout: Duration) {
+        let message_type = msg.type_name();
+        let handles = (self.context.spawner.clone(), self.context.death_watch.clone(), self.context.timers.clone());
+        let mut actor = std::mem::replace(&mut self.actor, Box::new(AbandonedActor));
+        let mut context = std::mem::take(&mut self.context);
+
+        let (done_tx, done_rx) = channel();
+        let handler_thread = thread::spawn(move || {
+            Self::handle(actor.as_mut(), msg.as_ref(), &mut context);
+            let _ = done_tx.send((actor, context));
+        });
+
+        match done_rx.recv_timeout(timeout) {
+            Ok((actor, context)) => {
+                self.actor = actor;
+                self.context = context;
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                // The handler panicked: fail this thread as if it had run here
+                self.reset_context(handles);
+                if let Err(panic) = handler_thread.join() {
+                    panic::resume_unwind(panic);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                self.reset_context(handles);
+                self.fail(Some(message_type), "handler timed out", TerminationReason::Killed);
+            }
+        }
+    }
+
+    /// Give a fresh context the runtime's refs, after the old one was lost
+    /// with an abandoned or panicked handler thread
+    fn reset_context(&mut self, (spawner, death_watch, timers): ContextHandles) {
+        self.context.dead_letters = self.dead_letters.clone();
+        self.context.spawner = spawner;
+        self.context.death_watch = death_watch;
+        self.context.timers = timers;
+        self.context.metrics = Some(Arc::clone(&self.metrics));
+        let self_ref = self.get_ref();
+        self.context.set_self_ref(self_ref);
+    }
+
+    /// Send a message refused by `peek` to the dead letter queue
+    fn reject(&self, envelope: Envelope, reason: &str) {
+        send_dead_letter(self.dead_letters.as_ref(), &self.name, &envelope, reason);
+    }
+
+    /// Put a message deferred by `peek` back in the mailbox after `retry_after`,
+    /// on the Manager's timer thread (or the shared one outside a Manager)
+    fn defer(&self, envelope: Envelope, retry_after: Duration) {
+        let Some(due) = Instant::now().checked_add(retry_after) else {
+            self.reject(envelope, "deferred too long");
+            return;
+        };
+        let timers = self.context.timers.as_ref().unwrap_or_else(|| shared_timers());
+        timers.schedule_deferred(self.local_ref(), due, envelope);
+    }
+
+    /// Record that the actor's thread has exited: its refs stop reporting it
+    /// alive, its status becomes Stopped (or Panicked) and its watchers are
+    /// sent `Terminated`.
+    pub(crate) fn mark_terminated(&self, reason: TerminationReason) {
+        self.alive.store(false, Ordering::SeqCst);
+        self.metrics.set_status(match reason {
+            TerminationReason::Panicked => ActorStatus::Panicked,
+            TerminationReason::Normal | TerminationReason::Killed => ActorStatus::Stopped,
+        });
+        if let Some(death_watch) = &self.context.death_watch {
+            death_watch.notify(self.get_ref(), reason);
+        }
     }
 
     /// Signal the actor to stop
     pub fn stop(&self) {
+        self.metrics.set_status(ActorStatus::Stopping);
         *self.running.lock().unwrap() = false;
     }
+
+    /// Give the mailbox channel back to its pool (if any).
+    ///
+    /// Called after `run` returns. Refs to this actor fail from then on, and
+    /// messages left in the mailbox go to the dead letter queue.
+    pub fn release(self) {
+        let (Some(pool), Some((generation, _))) = (self.pool, self.generation) else {
+            return;
+        };
+        *generation.write().unwrap() += 1;
+        for envelope in self.receiver.try_iter() {
+            send_dead_letter(self.dead_letters.as_ref(), &self.name, &envelope, "actor stopped");
+        }
+        pool.release((self.sender, self.receiver, generation));
+    }
 }
 
 #[cfg(test)]
@@ -484,6 +2325,476 @@ mod tests {
         assert_eq!(actor_ref.name(), cloned.name());
     }
 
+    #[test]
+    fn test_actor_ref_pool_reuse() {
+        let pool = ActorRefPool::new(2);
+        assert_eq!(pool.available(), 2);
+
+        let mut runtime = ActorRuntime::new_pooled("a".to_string(), Box::new(TestActor { received: 0 }), &pool);
+        assert_eq!(pool.available(), 1);
+        let (dlq_tx, dlq_rx) = channel();
+        runtime.set_dead_letters(&ActorRef::new(dlq_tx, "dlq".to_string()));
+
+        let stale = runtime.get_ref();
+        stale.send(Box::new(TestMessage { value: 1 }), None);
+        runtime.release();
+        assert_eq!(pool.available(), 2);
+        // Messages left in the mailbox are dead letters
+        assert_eq!(dlq_rx.try_iter().count(), 1);
+
+        // The channel is reused, but refs to the stopped actor do not reach the new one
+        let runtime = ActorRuntime::new_pooled("b".to_string(), Box::new(TestActor { received: 0 }), &pool);
+        assert_eq!(pool.available(), 1);
+        assert!(matches!(stale.try_send(Box::new(TestMessage { value: 2 }), None), Err(SendError::Disconnected)));
+        assert_eq!(dlq_rx.try_iter().count(), 1);
+        assert!(runtime.receiver.try_recv().is_err());
+        runtime.get_ref().send(Box::new(TestMessage { value: 3 }), None);
+        assert!(runtime.receiver.try_recv().is_ok());
+    }
+
+    #[test]
+    fn test_runtime_stops_on_shutdown() {
+        let mut runtime = ActorRuntime::new("a".to_string(), Box::new(TestActor { received: 0 }));
+        let actor_ref = runtime.get_ref();
+        actor_ref.send(Box::new(TestMessage { value: 7 }), None);
+        actor_ref.send(Box::new(Shutdown), None);
+        runtime.run();
+    }
+
+    #[test]
+    fn test_send_with_ttl() {
+        let (dead_tx, dead_rx) = channel();
+        let mut runtime = ActorRuntime::new("a".to_string(), Box::new(TestActor { received: 0 }));
+        runtime.set_dead_letters(&ActorRef::new(dead_tx, "$dead_letters".to_string()));
+        let actor_ref = runtime.get_ref();
+
+        actor_ref.send_with_ttl(Box::new(TestMessage { value: 1 }), None, Duration::from_secs(60));
+        actor_ref.send_with_ttl(Box::new(TestMessage { value: 2 }), None, Duration::ZERO);
+        actor_ref.send(Box::new(Shutdown), None);
+        runtime.run();
+
+        let actor: &dyn Any = runtime.actor.as_ref();
+        assert_eq!(actor.downcast_ref::<TestActor>().unwrap().received, 1);
+        let envelope = dead_rx.try_recv().unwrap();
+        let letter = envelope.msg.as_any().downcast_ref::<DeadLetter>().unwrap();
+        assert_eq!(letter.reason, "Expired");
+        assert!(letter.message_type.ends_with("::TestMessage"));
+
+        // Duration::MAX never expires
+        let mut runtime = ActorRuntime::new("b".to_string(), Box::new(TestActor { received: 0 }));
+        let actor_ref = runtime.get_ref();
+        actor_ref.send_with_ttl(Box::new(TestMessage { value: 3 }), None, Duration::MAX);
+        actor_ref.send(Box::new(Shutdown), None);
+        runtime.run();
+        let actor: &dyn Any = runtime.actor.as_ref();
+        assert_eq!(actor.downcast_ref::<TestActor>().unwrap().received, 3);
+    }
+
+    #[test]
+    fn test_peek_reject_and_defer() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        struct PeekingActor {
+            seen: Arc<Mutex<Vec<i32>>>,
+            deferred: AtomicBool,
+        }
+
+        impl Actor for PeekingActor {
+            fn peek(&self, msg: &dyn Message) -> PeekResult {
+                match msg.as_any().downcast_ref::<TestMessage>() {
+                    Some(m) if m.value < 0 => PeekResult::Reject { reason: "negative".to_string() },
+                    Some(m) if m.value == 0 && !self.deferred.swap(true, Ordering::SeqCst) => PeekResult::Defer {
+                        retry_after: Duration::from_millis(10),
+                    },
+                    _ => PeekResult::Accept,
+                }
+            }
+
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if let Some(m) = msg.as_any().downcast_ref::<TestMessage>() {
+                    self.seen.lock().unwrap().push(m.value);
+                }
+            }
+        }
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let actor = PeekingActor {
+            seen: Arc::clone(&seen),
+            deferred: AtomicBool::new(false),
+        };
+        let (dead_tx, dead_rx) = channel();
+        let mut runtime = ActorRuntime::new("peeker".to_string(), Box::new(actor));
+        runtime.set_dead_letters(&ActorRef::new(dead_tx, "$dead_letters".to_string()));
+        let actor_ref = runtime.get_ref();
+        let thread = thread::spawn(move || runtime.run());
+
+        for value in [-1, 0, 1] {
+            actor_ref.send(Box::new(TestMessage { value }), None);
+        }
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while seen.lock().unwrap().len() < 2 {
+            assert!(Instant::now() < deadline, "peeked messages were never handled");
+            thread::sleep(Duration::from_millis(1));
+        }
+        actor_ref.send(Box::new(Shutdown), None);
+        thread.join().unwrap();
+
+        assert_eq!(*seen.lock().unwrap(), vec![1, 0]);
+        let envelope = dead_rx.try_recv().unwrap();
+        let letter = envelope.msg.as_any().downcast_ref::<DeadLetter>().unwrap();
+        assert_eq!(letter.target, "peeker");
+        assert_eq!(letter.reason, "negative");
+        assert!(dead_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_become_and_become_default() {
+        struct Open;
+        define_message!(Open);
+        struct Close;
+        define_message!(Close);
+
+        // Records values only while open
+        struct Gate {
+            seen: Vec<i32>,
+        }
+
+        impl Actor for Gate {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                if msg.as_any().is::<Open>() {
+                    ctx.become_handler(Box::new(|state, msg, ctx| {
+                        if let Some(m) = msg.as_any().downcast_ref::<TestMessage>() {
+                            state.downcast_mut::<Gate>().unwrap().seen.push(m.value);
+                        } else if msg.as_any().is::<Close>() {
+                            ctx.become_default();
+                        }
+                    }));
+                }
+            }
+        }
+
+        let mut runtime = ActorRuntime::new("gate".to_string(), Box::new(Gate { seen: vec![] }));
+        let actor_ref = runtime.get_ref();
+        actor_ref.send(Box::new(TestMessage { value: 1 }), None);
+        actor_ref.send(Box::new(Open), None);
+        actor_ref.send(Box::new(TestMessage { value: 2 }), None);
+        actor_ref.send(Box::new(TestMessage { value: 3 }), None);
+        actor_ref.send(Box::new(Close), None);
+        actor_ref.send(Box::new(TestMessage { value: 4 }), None);
+        actor_ref.send(Box::new(Shutdown), None);
+        runtime.run();
+
+        let actor: &dyn Any = runtime.actor.as_ref();
+        assert_eq!(actor.downcast_ref::<Gate>().unwrap().seen, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_lifecycle_hooks() {
+        struct Lifecycle {
+            events: Vec<String>,
+        }
+
+        handle_messages!(Lifecycle,
+            pre_start => on_pre_start,
+            post_stop => on_post_stop,
+            TestMessage => on_test
+        );
+
+        impl Lifecycle {
+            fn on_pre_start(&mut self, ctx: &mut ActorContext) {
+                self.events.push("pre_start".to_string());
+                // The context is usable: queue messages to ourselves
+                let self_ref = ctx.self_ref().unwrap().clone();
+                self_ref.send(Box::new(TestMessage { value: 1 }), None);
+                self_ref.send(Box::new(Shutdown), None);
+            }
+
+            fn on_post_stop(&mut self, _ctx: &mut ActorContext) {
+                self.events.push("post_stop".to_string());
+            }
+
+            fn on_test(&mut self, msg: &TestMessage, _ctx: &mut ActorContext) {
+                self.events.push(format!("message {}", msg.value));
+            }
+        }
+
+        let mut runtime = ActorRuntime::new("lifecycle".to_string(), Box::new(Lifecycle { events: vec![] }));
+        runtime.run();
+
+        let actor: &dyn Any = runtime.actor.as_ref();
+        let events = &actor.downcast_ref::<Lifecycle>().unwrap().events;
+        assert_eq!(events, &["pre_start", "message 1", "post_stop"]);
+
+        // Either hook may be given on its own
+        struct StopOnly {
+            stopped: bool,
+        }
+
+        handle_messages!(StopOnly,
+            post_stop => on_post_stop,
+            TestMessage => on_test
+        );
+
+        impl StopOnly {
+            fn on_post_stop(&mut self, _ctx: &mut ActorContext) {
+                self.stopped = true;
+            }
+
+            fn on_test(&mut self, _msg: &TestMessage, _ctx: &mut ActorContext) {}
+        }
+
+        struct StartOnly;
+
+        handle_messages!(StartOnly,
+            pre_start => on_pre_start,
+            TestMessage => on_test,
+        );
+
+        impl StartOnly {
+            fn on_pre_start(&mut self, ctx: &mut ActorContext) {
+                ctx.self_ref().unwrap().send(Box::new(Shutdown), None);
+            }
+
+            fn on_test(&mut self, _msg: &TestMessage, _ctx: &mut ActorContext) {}
+        }
+
+        let mut runtime = ActorRuntime::new("stop_only".to_string(), Box::new(StopOnly { stopped: false }));
+        runtime.get_ref().send(Box::new(Shutdown), None);
+        runtime.run();
+        let actor: &dyn Any = runtime.actor.as_ref();
+        assert!(actor.downcast_ref::<StopOnly>().unwrap().stopped);
+
+        // Stops itself from pre_start
+        ActorRuntime::new("start_only".to_string(), Box::new(StartOnly)).run();
+    }
+
+    #[test]
+    fn test_upgrade_to_supervised() {
+        struct Crash;
+        define_message!(Crash);
+
+        struct Crashy;
+        impl Actor for Crashy {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if msg.as_any().is::<Crash>() {
+                    panic!("boom");
+                }
+            }
+        }
+
+        let mut runtime = ActorRuntime::new("crashy".to_string(), Box::new(Crashy));
+        let strategy = SupervisionStrategy::Restart {
+            max_retries: 5,
+            window: Duration::from_secs(60),
+        };
+        let supervised = runtime.get_ref().upgrade_to_supervised(strategy, || Box::new(Crashy)).unwrap();
+        supervised.actor_ref().send(Box::new(Crash), None);
+        supervised.actor_ref().send(Box::new(Crash), None);
+        supervised.actor_ref().send(Box::new(Shutdown), None);
+        runtime.run();
+        assert_eq!(supervised.restart_count(), 2);
+
+        let (tx, rx) = channel();
+        drop(rx);
+        let stopped = ActorRef::new(tx, "stopped".to_string());
+        let result = stopped.upgrade_to_supervised(strategy, || Box::new(Crashy));
+        assert_eq!(result.err(), Some(UpgradeError::ActorStopped("stopped".to_string())));
+    }
+
+    #[test]
+    fn test_sender_and_reply_to() {
+        // Forwards each message to `target`, remembering who sent it
+        struct Router {
+            target: ActorRef,
+            senders: Vec<String>,
+        }
+
+        impl Actor for Router {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                if let Some(m) = msg.as_any().downcast_ref::<TestMessage>() {
+                    self.senders.push(ctx.sender().map_or("none".to_string(), |s| s.name().to_string()));
+                    ctx.reply_to(Box::new(TestMessage { value: m.value }), self.target.clone());
+                }
+            }
+        }
+
+        let (target_tx, target_rx) = channel();
+        let (client_tx, client_rx) = channel();
+        let target = ActorRef::new(target_tx, "target".to_string());
+        let client = ActorRef::new(client_tx, "client".to_string());
+
+        let router = Router { target, senders: vec![] };
+        let mut runtime = ActorRuntime::new("router".to_string(), Box::new(router));
+        let actor_ref = runtime.get_ref();
+        actor_ref.send(Box::new(TestMessage { value: 7 }), Some(client));
+        actor_ref.send(Box::new(TestMessage { value: 8 }), None);
+        actor_ref.send(Box::new(Shutdown), None);
+        runtime.run();
+
+        let actor: &dyn Any = runtime.actor.as_ref();
+        assert_eq!(actor.downcast_ref::<Router>().unwrap().senders, vec!["client", "none"]);
+
+        // Both went to the target, from the router; nothing back to the client
+        let forwarded = target_rx.try_recv().unwrap();
+        assert_eq!(forwarded.sender.unwrap().name(), "router");
+        assert!(target_rx.try_recv().is_ok());
+        assert!(client_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_stash_and_unstash() {
+        struct Ready;
+        define_message!(Ready);
+
+        // Stashes messages until Ready, discarding the stash when it reaches 3
+        struct Stasher {
+            ready: bool,
+            seen: Vec<i32>,
+        }
+
+        impl Actor for Stasher {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                if msg.as_any().is::<Ready>() {
+                    self.ready = true;
+                    ctx.unstash_all();
+                } else if let Some(m) = msg.as_any().downcast_ref::<TestMessage>() {
+                    if self.ready {
+                        self.seen.push(m.value);
+                    } else if ctx.stash_count() < 2 {
+                        ctx.stash(Box::new(TestMessage { value: m.value }));
+                    } else {
+                        ctx.stash(Box::new(TestMessage { value: m.value }));
+                        ctx.discard_stash();
+                    }
+                }
+            }
+        }
+
+        let (dead_tx, dead_rx) = channel();
+        let mut runtime = ActorRuntime::new("stasher".to_string(), Box::new(Stasher { ready: false, seen: vec![] }));
+        runtime.set_dead_letters(&ActorRef::new(dead_tx, "$dead_letters".to_string()));
+        let actor_ref = runtime.get_ref();
+
+        for value in 1..=5 {
+            actor_ref.send(Box::new(TestMessage { value }), None);
+        }
+        actor_ref.send(Box::new(Ready), None);
+        actor_ref.send(Box::new(TestMessage { value: 6 }), None);
+        actor_ref.send(Box::new(Shutdown), None);
+        runtime.run();
+
+        // 1-3 were discarded, 4 and 5 stashed and handled before 6
+        let actor: &dyn Any = runtime.actor.as_ref();
+        assert_eq!(actor.downcast_ref::<Stasher>().unwrap().seen, vec![4, 5, 6]);
+        assert_eq!(dead_rx.try_iter().count(), 3);
+        assert_eq!(runtime.context.stash_count(), 0);
+    }
+
+    #[test]
+    fn test_typed_actor_ref() {
+        let (tx, rx) = channel();
+        let typed: TypedActorRef<TestMessage> = ActorRef::new(tx, "test".to_string()).into_typed();
+        assert_eq!(typed.name(), "test");
+
+        typed.clone().send(TestMessage { value: 3 }, None);
+        let envelope = rx.try_recv().unwrap();
+        assert_eq!(envelope.msg.as_any().downcast_ref::<TestMessage>().unwrap().value, 3);
+
+        assert!(typed.into_untyped().is_local());
+    }
+
+    #[test]
+    fn test_ask() {
+        struct Doubler;
+        impl Actor for Doubler {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                if let Some(m) = msg.as_any().downcast_ref::<TestMessage>() {
+                    ctx.reply(Box::new(TestMessage { value: m.value * 2 }));
+                }
+            }
+        }
+
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let mut runtime = ActorRuntime::new("doubler".to_string(), Box::new(Doubler));
+        let doubler = runtime.get_ref();
+        let thread = thread::spawn(move || runtime.run());
+
+        let reply = rt.block_on(doubler.ask::<TestMessage>(Box::new(TestMessage { value: 21 }), Duration::from_secs(5)));
+        assert_eq!(reply.unwrap().value, 42);
+
+        // Replies of another type are ignored
+        let reply = rt.block_on(doubler.ask::<Shutdown>(Box::new(TestMessage { value: 1 }), Duration::from_millis(50)));
+        assert_eq!(reply.err(), Some(AskError::Timeout));
+
+        // A timeout too long to represent waits without one
+        let reply = rt.block_on(doubler.ask::<TestMessage>(Box::new(TestMessage { value: 4 }), Duration::MAX));
+        assert_eq!(reply.unwrap().value, 8);
+
+        doubler.send(Box::new(Shutdown), None);
+        thread.join().unwrap();
+
+        let (tx, rx) = channel();
+        drop(rx);
+        let dead = ActorRef::new(tx, "dead".to_string());
+        let reply = rt.block_on(dead.ask::<TestMessage>(Box::new(TestMessage { value: 1 }), Duration::from_secs(5)));
+        assert_eq!(reply.err(), Some(AskError::ActorDead("dead".to_string())));
+    }
+
+    #[test]
+    fn test_error_handler() {
+        let (tx, rx) = channel();
+        let mut actor_ref = ActorRef::new(tx, "a".to_string());
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&errors);
+        actor_ref.set_error_handler(move |e| seen.lock().unwrap().push(e));
+
+        // An ask without a reply times out
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let reply = rt.block_on(actor_ref.ask::<TestMessage>(Box::new(TestMessage { value: 1 }), Duration::from_millis(10)));
+        assert_eq!(reply.err(), Some(AskError::Timeout));
+
+        drop(rx);
+        assert_eq!(actor_ref.try_send(Box::new(TestMessage { value: 2 }), None), Err(SendError::Disconnected));
+        actor_ref.send(Box::new(TestMessage { value: 3 }), None);
+        assert_eq!(*errors.lock().unwrap(), vec![ActorError::Timeout, ActorError::Disconnected, ActorError::Disconnected]);
+    }
+
+    #[test]
+    fn test_bounded_mailbox_backpressure() {
+        let runtime = ActorRuntime::new("a".to_string(), Box::new(TestActor { received: 0 }));
+        runtime.metrics.set_mailbox_capacity(Some(1));
+        let actor_ref = runtime.get_ref();
+        assert_eq!(actor_ref.try_send(Box::new(TestMessage { value: 1 }), None), Ok(()));
+
+        let start = Instant::now();
+        let full = actor_ref.send_timeout(Box::new(TestMessage { value: 2 }), None, Duration::from_millis(20));
+        assert_eq!(full, Err(SendError::Full));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        // Room made while waiting is taken
+        let reader = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            runtime.receiver.recv().unwrap();
+            runtime.metrics.record_dequeued();
+            runtime
+        });
+        assert_eq!(actor_ref.send_timeout(Box::new(TestMessage { value: 3 }), None, Duration::from_secs(5)), Ok(()));
+        let _runtime = reader.join().unwrap();
+
+        // Control messages are always accepted; send panics on a full mailbox
+        assert_eq!(actor_ref.try_send(Box::new(Shutdown), None), Ok(()));
+        let sent = panic::catch_unwind(AssertUnwindSafe(|| actor_ref.send(Box::new(TestMessage { value: 4 }), None)));
+        assert!(sent.is_err());
+
+        // Replies to a full mailbox are dropped instead
+        let dropped = actor_ref.mailbox_stats().dropped_count;
+        let mut ctx = ActorContext::new();
+        ctx.prepare_for_envelope(Some(actor_ref.clone()), None);
+        ctx.reply(Box::new(TestMessage { value: 5 }));
+        ctx.reply_to(Box::new(TestMessage { value: 6 }), actor_ref.clone());
+        assert_eq!(actor_ref.mailbox_stats().dropped_count, dropped + 2);
+    }
+
     #[test]
     fn test_envelope_creation() {
         let msg = Box::new(TestMessage { value: 42 });
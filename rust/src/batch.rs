@@ -0,0 +1,73 @@
+/*
+
+THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
+
+Copyright 2025 Vincent Maciejewski, & M2 Tech
+Contact:
+v@m2te.ch
+mayeski@gmail.com
+https://www.linkedin.com/in/vmayeski/
+http://m2te.ch/
+
+*/
+
+//! Batches of envelopes sent as one ZMQ frame (see `ZmqSender::send_batch`).
+//!
+//! A batch frame is a one-byte header followed by each envelope, prefixed
+//! with its length as a big-endian u32. The header is neither a compression
+//! header nor the `{` of a bare envelope, so receivers tell batches apart
+//! from single messages. A compressed batch is a batch frame compressed as
+//! a whole.
+
+/// Header byte of a batch frame
+const HEADER_BATCH: u8 = 0x03;
+
+/// Pack envelopes into a batch frame.
+pub(crate) fn pack(envelopes: &[Vec<u8>]) -> Vec<u8> {
+    let size = 1 + envelopes.iter().map(|e| 4 + e.len()).sum::<usize>();
+    let mut frame = Vec::with_capacity(size);
+    frame.push(HEADER_BATCH);
+    for envelope in envelopes {
+        frame.extend_from_slice(&(envelope.len() as u32).to_be_bytes());
+        frame.extend_from_slice(envelope);
+    }
+    frame
+}
+
+/// The envelopes of a batch frame, in the order they were packed.
+///
+/// Returns None if the frame is not a batch or is truncated.
+pub(crate) fn unpack(frame: &[u8]) -> Option<Vec<&[u8]>> {
+    let (&HEADER_BATCH, mut rest) = frame.split_first()? else {
+        return None;
+    };
+    let mut envelopes = Vec::new();
+    while !rest.is_empty() {
+        let (len, tail) = rest.split_first_chunk::<4>()?;
+        let len = u32::from_be_bytes(*len) as usize;
+        if tail.len() < len {
+            return None;
+        }
+        let (envelope, tail) = tail.split_at(len);
+        envelopes.push(envelope);
+        rest = tail;
+    }
+    Some(envelopes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_round_trip() {
+        let envelopes = vec![br#"{"receiver": "a"}"#.to_vec(), Vec::new(), br#"{"receiver": "b"}"#.to_vec()];
+        let frame = pack(&envelopes);
+        assert_eq!(unpack(&frame).unwrap(), envelopes);
+
+        // Bare envelopes and truncated batches are not batches
+        assert!(unpack(br#"{"receiver": "a"}"#).is_none());
+        assert!(unpack(&frame[..frame.len() - 1]).is_none());
+        assert_eq!(unpack(&[HEADER_BATCH]).unwrap(), Vec::<&[u8]>::new());
+    }
+}
@@ -0,0 +1,230 @@
+/*
+
+THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
+
+Copyright 2025 Vincent Maciejewski, & M2 Tech
+Contact:
+v@m2te.ch
+mayeski@gmail.com
+https://www.linkedin.com/in/vmayeski/
+http://m2te.ch/
+
+*/
+
+//! Codecs of remote message bodies (see `SerializationBackend` and
+//! `register_remote_message_proto`).
+//!
+//! Messages sent as JSON travel as bare envelopes. A message encoded with a
+//! binary codec travels in a codec frame instead: a one-byte header, a
+//! one-byte codec tag, the envelope without its `message` as JSON prefixed
+//! with its length (a big-endian u32), then the encoded message. The header
+//! is neither a compression nor a batch header, nor the `{` of a bare
+//! envelope, so JSON and binary senders can share a receiver. A receiver
+//! that does not know a frame's tag, e.g. one from a newer sender, tries
+//! every codec in turn, its own backend first.
+//!
+//! Messages are encoded straight from their typed value, and a message a
+//! binary codec carried is decoded once, to the value its actor receives.
+//! The envelope also holds the form its type's registration serializes it
+//! to, JSON for serde types and a hex string for protobuf types, so routing,
+//! signatures and the replay log handle every envelope alike. A sender only
+//! fills it in for a type with a binary codec of its own when it signs the
+//! message.
+
+use serde_json::Value;
+
+use crate::serialization::{decode_message, encode_message, is_message_registered, message_codec};
+use crate::Message;
+
+/// Header byte of a codec frame
+const HEADER_CODEC: u8 = 0x04;
+
+/// How a `ZmqSender` serializes messages registered with
+/// `register_remote_message` (see `ZmqSenderConfig::with_serialization`).
+///
+/// Types registered with `register_remote_message_proto` or
+/// `register_remote_message_msgpack` always use their own codec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SerializationBackend {
+    /// JSON, readable by peers in every language
+    #[default]
+    Json,
+    /// MessagePack, via rmp-serde: smaller than JSON, keeps field names
+    MessagePack,
+    /// Bincode: the most compact, but both ends need the same field order
+    Bincode,
+}
+
+impl SerializationBackend {
+    fn codec(self) -> Codec {
+        match self {
+            SerializationBackend::Json => Codec::Json,
+            SerializationBackend::MessagePack => Codec::MessagePack,
+            SerializationBackend::Bincode => Codec::Bincode,
+        }
+    }
+}
+
+/// How a remote message's body is encoded. The value is its codec tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Codec {
+    /// JSON, sent as a bare envelope
+    Json = 0,
+    /// Protocol Buffers, via prost
+    Protobuf = 1,
+    /// MessagePack, via rmp-serde
+    MessagePack = 2,
+    /// Bincode
+    Bincode = 3,
+}
+
+impl Codec {
+    /// Every codec, in the order they are tried for an unknown tag
+    const ALL: [Codec; 4] = [Codec::Json, Codec::MessagePack, Codec::Bincode, Codec::Protobuf];
+
+    fn from_tag(tag: u8) -> Option<Codec> {
+        Codec::ALL.into_iter().find(|&codec| codec as u8 == tag)
+    }
+}
+
+/// A received envelope and, if a binary codec carried it, its message
+pub(crate) type Decoded = (Value, Option<Box<dyn Message>>);
+
+/// Whether messages of `message_type` always travel in a codec frame, so
+/// the sender need not serialize them into the envelope.
+pub(crate) fn has_binary_codec(message_type: &str) -> bool {
+    message_codec(message_type).is_some_and(|codec| codec != Codec::Json)
+}
+
+/// Encode the envelope of `msg` for sending, with its message type's own
+/// codec or else `backend`.
+///
+/// The envelope is sent as bare JSON if that is the codec, or if its
+/// message cannot be encoded with the codec.
+pub(crate) fn encode(envelope: &Value, msg: &dyn Message, backend: SerializationBackend) -> Vec<u8> {
+    let message_type = envelope["message_type"].as_str().unwrap_or("");
+    let codec = message_codec(message_type).unwrap_or(backend.codec());
+    let body = match codec {
+        Codec::Json => None,
+        _ => encode_message(message_type, codec, msg),
+    };
+    let Some(body) = body else {
+        return envelope.to_string().into_bytes();
+    };
+
+    let mut header = envelope.clone();
+    if let Some(fields) = header.as_object_mut() {
+        fields.remove("message");
+    }
+    let header = header.to_string().into_bytes();
+
+    let mut frame = Vec::with_capacity(6 + header.len() + body.len());
+    frame.push(HEADER_CODEC);
+    frame.push(codec as u8);
+    frame.extend_from_slice(&(header.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&header);
+    frame.extend_from_slice(&body);
+    frame
+}
+
+/// The envelope in a codec frame, with its decoded message.
+///
+/// Returns None if the frame is not a codec frame, is truncated or its
+/// message cannot be decoded. The message of a type not registered here is
+/// kept as a hex string, so the receiver rejects it like any unknown type.
+pub(crate) fn decode(frame: &[u8], backend: SerializationBackend) -> Option<Decoded> {
+    let (&HEADER_CODEC, rest) = frame.split_first()? else {
+        return None;
+    };
+    let (&tag, rest) = rest.split_first()?;
+    let (len, rest) = rest.split_first_chunk::<4>()?;
+    let len = u32::from_be_bytes(*len) as usize;
+    if rest.len() < len {
+        return None;
+    }
+    let (header, body) = rest.split_at(len);
+
+    let mut envelope: Value = serde_json::from_slice(header).ok()?;
+    let message_type = envelope["message_type"].as_str().unwrap_or("").to_string();
+    let (message, decoded) = if !is_message_registered(&message_type) {
+        (Value::String(to_hex(body)), None)
+    } else if let Some(codec) = Codec::from_tag(tag) {
+        let (message, decoded) = decode_message(&message_type, codec, body)?;
+        (message, Some(decoded))
+    } else {
+        let first = backend.codec();
+        let (message, decoded) = std::iter::once(first)
+            .chain(Codec::ALL.into_iter().filter(|&codec| codec != first))
+            .find_map(|codec| decode_message(&message_type, codec, body))?;
+        (message, Some(decoded))
+    };
+    envelope.as_object_mut()?.insert("message".to_string(), message);
+    Some((envelope, decoded))
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub(crate) fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{define_message, register_remote_message};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Serialize, Deserialize, Default)]
+    struct CodecTick {
+        seq: u64,
+        symbol: String,
+    }
+    define_message!(CodecTick);
+
+    #[test]
+    fn test_codec_frame_round_trip() {
+        register_remote_message::<CodecTick>("CodecTick");
+        let envelope = json!({"receiver": "sink", "message_type": "CodecTick", "message": {"seq": 7, "symbol": "abc"}});
+        let tick = CodecTick { seq: 7, symbol: "abc".to_string() };
+
+        // JSON envelopes are sent bare, and are not codec frames
+        let json_frame = encode(&envelope, &tick, SerializationBackend::Json);
+        assert_eq!(json_frame, envelope.to_string().into_bytes());
+        assert!(decode(&json_frame, SerializationBackend::Json).is_none());
+
+        for backend in [SerializationBackend::MessagePack, SerializationBackend::Bincode] {
+            let frame = encode(&envelope, &tick, backend);
+            assert_eq!(&frame[..2], &[HEADER_CODEC, backend.codec() as u8]);
+            assert!(frame.len() < json_frame.len());
+            // Whichever backend the receiver uses
+            let (decoded, msg) = decode(&frame, SerializationBackend::Json).unwrap();
+            assert_eq!(decoded, envelope);
+            assert_eq!(msg.unwrap().as_any().downcast_ref::<CodecTick>().unwrap().seq, 7);
+            assert!(decode(&frame[..8], backend).is_none());
+
+            // An unknown tag is decoded by trying the receiver's backend first
+            let mut unknown = frame.clone();
+            unknown[1] = 0x7f;
+            assert_eq!(decode(&unknown, backend).map(|(decoded, _)| decoded), Some(envelope.clone()));
+        }
+
+        // Messages of unregistered types are kept encoded, to be rejected
+        let header = json!({"receiver": "sink", "message_type": "Unregistered"}).to_string().into_bytes();
+        let mut frame = vec![HEADER_CODEC, Codec::Bincode as u8];
+        frame.extend_from_slice(&(header.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&header);
+        frame.extend_from_slice(&[0x01, 0x02, 0x03]);
+        let (decoded, msg) = decode(&frame, SerializationBackend::Json).unwrap();
+        assert_eq!(decoded["message"], "010203");
+        assert!(msg.is_none());
+    }
+}
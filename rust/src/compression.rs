@@ -0,0 +1,131 @@
+/*
+
+THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
+
+Copyright 2025 Vincent Maciejewski, & M2 Tech
+Contact:
+v@m2te.ch
+mayeski@gmail.com
+https://www.linkedin.com/in/vmayeski/
+http://m2te.ch/
+
+*/
+
+//! Compression of ZMQ message frames (see `ZmqSenderConfig::with_compression`).
+//!
+//! A compressed frame is a one-byte header naming the algorithm followed by
+//! the compressed envelope. Uncompressed frames carry no header: they are
+//! the bare JSON envelope, which always starts with `{`, so receivers tell
+//! the two apart and peers in other languages keep working when
+//! compression is off.
+
+use std::borrow::Cow;
+use std::io::Read;
+
+/// Header byte of a zstd-compressed frame
+const HEADER_ZSTD: u8 = 0x01;
+
+/// Header byte of an LZ4-compressed frame
+const HEADER_LZ4: u8 = 0x02;
+
+/// Largest envelope a received frame may decompress to. Frames come from
+/// the network, so a small frame must not be able to claim any more memory.
+const MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024;
+
+/// How a ZmqSender compresses the messages it sends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompressionAlgorithm {
+    /// Send the JSON envelope as is
+    #[default]
+    None,
+    /// zstd at the given level (1-22; 3 is zstd's default)
+    Zstd(i32),
+    /// LZ4: faster than zstd, compresses less
+    Lz4,
+}
+
+/// Compress an envelope for sending, adding the header.
+///
+/// Falls back to the uncompressed envelope if compression fails.
+pub(crate) fn compress(algorithm: CompressionAlgorithm, data: Vec<u8>) -> Vec<u8> {
+    let (header, compressed) = match algorithm {
+        CompressionAlgorithm::None => return data,
+        CompressionAlgorithm::Zstd(level) => match zstd::bulk::compress(&data, level) {
+            Ok(compressed) => (HEADER_ZSTD, compressed),
+            Err(_) => return data,
+        },
+        CompressionAlgorithm::Lz4 => (HEADER_LZ4, lz4_flex::compress_prepend_size(&data)),
+    };
+    let mut frame = Vec::with_capacity(compressed.len() + 1);
+    frame.push(header);
+    frame.extend_from_slice(&compressed);
+    frame
+}
+
+/// The envelope in a received frame, decompressing it if it has a header.
+///
+/// Returns None for a compressed frame that cannot be decompressed, or
+/// that decompresses to more than `MAX_DECOMPRESSED_SIZE` bytes.
+pub(crate) fn decompress(frame: &[u8]) -> Option<Cow<'_, [u8]>> {
+    decompress_within(frame, MAX_DECOMPRESSED_SIZE)
+}
+
+/// `decompress`, with envelopes limited to `max_size` bytes.
+fn decompress_within(frame: &[u8], max_size: usize) -> Option<Cow<'_, [u8]>> {
+    match frame.split_first() {
+        Some((&HEADER_ZSTD, compressed)) => {
+            // Read one byte past the limit to tell a full-size envelope from a larger one
+            let mut envelope = Vec::new();
+            let decoder = zstd::stream::read::Decoder::new(compressed).ok()?;
+            decoder.take(max_size as u64 + 1).read_to_end(&mut envelope).ok()?;
+            (envelope.len() <= max_size).then_some(Cow::Owned(envelope))
+        }
+        Some((&HEADER_LZ4, compressed)) => {
+            // The size prefix is allocated up front, so check it first
+            let size = u32::from_le_bytes(*compressed.first_chunk::<4>()?) as usize;
+            if size > max_size {
+                return None;
+            }
+            lz4_flex::decompress_size_prepended(compressed).ok().map(Cow::Owned)
+        }
+        _ => Some(Cow::Borrowed(frame)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compression_round_trip() {
+        let envelope = format!(r#"{{"receiver": "pong", "message": {{"data": "{}"}}}}"#, "x".repeat(10_000)).into_bytes();
+
+        for algorithm in [CompressionAlgorithm::Zstd(3), CompressionAlgorithm::Lz4] {
+            let frame = compress(algorithm, envelope.clone());
+            assert!(frame.len() < envelope.len() / 10);
+            assert_eq!(decompress(&frame).unwrap(), envelope.as_slice());
+        }
+
+        // Uncompressed envelopes pass through without a header
+        assert_eq!(compress(CompressionAlgorithm::None, envelope.clone()), envelope);
+        assert!(matches!(decompress(&envelope), Some(Cow::Borrowed(_))));
+        assert!(decompress(&[HEADER_ZSTD, 0xff, 0xff]).is_none());
+    }
+
+    #[test]
+    fn test_decompression_is_bounded() {
+        // A forged LZ4 size prefix is rejected before anything is allocated
+        let mut forged = vec![HEADER_LZ4];
+        forged.extend_from_slice(&u32::MAX.to_le_bytes());
+        forged.extend_from_slice(&[0x10, b'x']);
+        assert!(decompress(&forged).is_none());
+        assert!(decompress(&[HEADER_LZ4, 0x01]).is_none());
+
+        let envelope = vec![b'x'; 1000];
+        for algorithm in [CompressionAlgorithm::Zstd(3), CompressionAlgorithm::Lz4] {
+            let frame = compress(algorithm, envelope.clone());
+            assert_eq!(decompress_within(&frame, 1000).unwrap(), envelope.as_slice());
+            assert!(decompress_within(&frame, 999).is_none());
+        }
+    }
+}
@@ -0,0 +1,224 @@
+/*
+
+THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
+
+Copyright 2025 Vincent Maciejewski, & M2 Tech
+Contact:
+v@m2te.ch
+mayeski@gmail.com
+https://www.linkedin.com/in/vmayeski/
+http://m2te.ch/
+
+*/
+
+//! Dead letter queue for messages that could not be delivered.
+//!
+//! Every Manager runs a `DeadLetterQueue` actor under the reserved name
+//! `"$dead_letters"`. A send to a local actor whose mailbox is closed (the
+//! actor has stopped), or a message the actor refused in `Actor::peek`, is
+//! turned into a `DeadLetter` and routed there instead of being dropped. The
+//! queue forwards each dead letter to the watchers added with
+//! `Manager::subscribe_dead_letters`. A Manager created with
+//! `Manager::new_with_dead_letter_store` also keeps the most recent ones for
+//! `Manager::take_dead_letters`.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::actor::{Actor, ActorContext, ActorRef, Envelope, SendError};
+use crate::serialization::{get_type_name, serialize_message};
+use crate::{define_message, Message};
+
+/// Name the dead letter queue is registered under in every Manager
+pub const DEAD_LETTERS_NAME: &str = "$dead_letters";
+
+/// A message that could not be delivered.
+///
+/// Sent to the dead letter queue, which forwards a copy to each watcher.
+/// Fields may be added in later versions: build one with `new` and the
+/// `with_*` methods.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct DeadLetter {
+    /// Name of the actor the message was sent to
+    pub target: String,
+    /// Rust type name of the undelivered message
+    pub message_type: String,
+    /// Why the message was not delivered
+    pub reason: String,
+    /// When the send failed
+    pub timestamp: Instant,
+    /// Name of the actor that sent the message, if known
+    pub sender: Option<String>,
+    /// The message as JSON, if its type is registered with
+    /// `register_remote_message` (not kept for messages lost with a failed handler)
+    pub payload: Option<Vec<u8>>,
+}
+define_message!(DeadLetter);
+
+impl DeadLetter {
+    /// Dead letter for a message of `message_type` not delivered to `target`,
+    /// without sender or payload.
+    pub fn new(target: &str, message_type: &str, reason: &str) -> Self {
+        DeadLetter {
+            target: target.to_string(),
+            message_type: message_type.to_string(),
+            reason: reason.to_string(),
+            timestamp: Instant::now(),
+            sender: None,
+            payload: None,
+        }
+    }
+
+    /// Set the name of the actor that sent the message
+    pub fn with_sender(mut self, sender: &str) -> Self {
+        self.sender = Some(sender.to_string());
+        self
+    }
+
+    /// Set the message as JSON
+    pub fn with_payload(mut self, payload: Vec<u8>) -> Self {
+        self.payload = Some(payload);
+        self
+    }
+
+    /// Dead letter for an undelivered envelope, with its sender and payload.
+    pub(crate) fn from_envelope(target: &str, envelope: &Envelope, reason: &str) -> Self {
+        let msg = envelope.msg.as_ref();
+        let payload = get_type_name(msg)
+            .and_then(|msg_type| serde_json::to_vec(&serialize_message(msg, &msg_type)).ok());
+        DeadLetter {
+            sender: envelope.sender.as_ref().map(|sender| sender.name().to_string()),
+            payload,
+            ..DeadLetter::new(target, msg.type_name(), reason)
+        }
+    }
+}
+
+/// The most recent dead letters, kept by the dead letter queue of a Manager
+/// created with `Manager::new_with_dead_letter_store`.
+#[derive(Clone)]
+pub(crate) struct DeadLetterStore {
+    letters: Arc<Mutex<VecDeque<DeadLetter>>>,
+    capacity: usize,
+}
+
+impl DeadLetterStore {
+    pub(crate) fn new(capacity: usize) -> Self {
+        DeadLetterStore {
+            letters: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Keep `letter`, dropping the oldest one if the store is full.
+    fn push(&self, letter: DeadLetter) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut letters = self.letters.lock().unwrap();
+        if letters.len() == self.capacity {
+            letters.pop_front();
+        }
+        letters.push_back(letter);
+    }
+
+    /// Remove and return the stored letters, oldest first.
+    pub(crate) fn take(&self) -> Vec<DeadLetter> {
+        self.letters.lock().unwrap().drain(..).collect()
+    }
+}
+
+/// Sent by `Manager::subscribe_dead_letters` to add a watcher.
+pub(crate) struct SubscribeDeadLetters {
+    pub(crate) watcher: ActorRef,
+}
+define_message!(SubscribeDeadLetters);
+
+/// Actor that forwards dead letters to its watchers.
+///
+/// A watcher whose own mailbox is closed is removed, so a stopped watcher
+/// does not keep generating dead letters.
+#[derive(Default)]
+pub struct DeadLetterQueue {
+    watchers: Vec<ActorRef>,
+    store: Option<DeadLetterStore>,
+}
+
+impl DeadLetterQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue that also keeps each dead letter in `store`.
+    pub(crate) fn with_store(store: DeadLetterStore) -> Self {
+        DeadLetterQueue {
+            watchers: Vec::new(),
+            store: Some(store),
+        }
+    }
+
+    fn on_dead_letter(&mut self, letter: &DeadLetter) {
+        self.watchers
+            .retain(|watcher| watcher.try_send(Box::new(letter.clone()), None) != Err(SendError::Disconnected));
+        if let Some(store) = &self.store {
+            store.push(letter.clone());
+        }
+    }
+}
+
+impl Actor for DeadLetterQueue {
+    fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+        if let Some(letter) = msg.as_any().downcast_ref::<DeadLetter>() {
+            self.on_dead_letter(letter);
+        } else if let Some(subscribe) = msg.as_any().downcast_ref::<SubscribeDeadLetters>() {
+            self.watchers.push(subscribe.watcher.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_forwards_to_watchers_and_drops_dead_ones() {
+        let (live_tx, live_rx) = channel();
+        let (dead_tx, dead_rx) = channel();
+        drop(dead_rx);
+
+        let mut dlq = DeadLetterQueue::new();
+        let mut ctx = ActorContext::new();
+        for tx in [live_tx, dead_tx] {
+            let watcher = ActorRef::new(tx, "watcher".to_string());
+            dlq.process_message(&SubscribeDeadLetters { watcher }, &mut ctx);
+        }
+
+        let letter = DeadLetter::new("gone", "Ping", "actor stopped").with_sender("client");
+        dlq.process_message(&letter.with_payload(b"{}".to_vec()), &mut ctx);
+        assert_eq!(dlq.watchers.len(), 1);
+
+        let envelope = live_rx.try_recv().unwrap();
+        let letter = envelope.msg.as_any().downcast_ref::<DeadLetter>().unwrap();
+        assert_eq!(letter.target, "gone");
+        assert_eq!(letter.message_type, "Ping");
+        assert_eq!(letter.sender.as_deref(), Some("client"));
+        assert_eq!(letter.payload.as_deref(), Some(&b"{}"[..]));
+    }
+
+    #[test]
+    fn test_store_keeps_most_recent() {
+        let store = DeadLetterStore::new(2);
+        let mut dlq = DeadLetterQueue::with_store(store.clone());
+        let mut ctx = ActorContext::new();
+        for target in ["a", "b", "c"] {
+            dlq.process_message(&DeadLetter::new(target, "Ping", "actor stopped"), &mut ctx);
+        }
+
+        let targets: Vec<String> = store.take().into_iter().map(|letter| letter.target).collect();
+        assert_eq!(targets, vec!["b".to_string(), "c".to_string()]);
+        assert!(store.take().is_empty());
+    }
+}
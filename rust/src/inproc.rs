@@ -0,0 +1,172 @@
+/*
+
+THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
+
+Copyright 2025 Vincent Maciejewski, & M2 Tech
+Contact:
+v@m2te.ch
+mayeski@gmail.com
+https://www.linkedin.com/in/vmayeski/
+http://m2te.ch/
+
+*/
+
+//! In-process transport with the `ZmqSender`/`ZmqReceiver` API.
+//!
+//! Components written against a sender and a receiver can run in one
+//! process without going through ZMQ: messages are handed to the target
+//! actor's mailbox as they are, with no serialization and no copy. The
+//! `zeromq` crate has no `inproc://` transport, so this does not use ZMQ.
+
+use std::sync::Arc;
+
+use crate::actor::ActorRef;
+use crate::messages::Reject;
+use crate::remote::ActorRegistry;
+use crate::serialization::get_type_name;
+use crate::Message;
+
+/// Creates connected `InprocSender`/`InprocReceiver` pairs.
+pub struct InprocTransport;
+
+impl InprocTransport {
+    /// Create a sender and a receiver connected to each other.
+    ///
+    /// `name` identifies the pair in diagnostics, like an endpoint. The
+    /// sender can be cloned to send from several places.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{define_message, ActorRef, InprocTransport};
+    /// # struct Ping { count: i32 }
+    /// # define_message!(Ping);
+    /// # fn example(pong_ref: ActorRef, ping_ref: ActorRef) {
+    /// let (sender, receiver) = InprocTransport::pair("orders");
+    /// receiver.register("pong", pong_ref);
+    /// sender.send_to("pong", Box::new(Ping { count: 1 }), Some(ping_ref));
+    /// # }
+    /// ```
+    pub fn pair(name: &str) -> (InprocSender, InprocReceiver) {
+        let name: Arc<str> = Arc::from(name);
+        let registry = ActorRegistry::new();
+        let sender = InprocSender {
+            name: Arc::clone(&name),
+            registry: registry.clone(),
+        };
+        (sender, InprocReceiver { name, registry })
+    }
+}
+
+/// Sending end of an in-process pair, see `InprocTransport::pair`.
+#[derive(Clone)]
+pub struct InprocSender {
+    name: Arc<str>,
+    registry: ActorRegistry,
+}
+
+impl InprocSender {
+    /// Name of the pair
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Send a message to `actor_name` at the receiver.
+    ///
+    /// The message is moved to the actor's mailbox, so it does not have to
+    /// be registered with `register_remote_message`. `sender` is passed
+    /// through for replies. If no actor of that name is registered, or its
+    /// mailbox is full or closed, the sender gets a Reject, as from a
+    /// `ZmqReceiver`; a message the mailbox refused also goes to the dead
+    /// letter queue.
+    pub fn send_to(&self, actor_name: &str, msg: Box<dyn Message>, sender: Option<ActorRef>) {
+        // The message is moved into the mailbox, so name its type first
+        let msg_type = sender
+            .as_ref()
+            .map(|_| get_type_name(msg.as_ref()).unwrap_or_else(|| msg.type_name().to_string()));
+        let sent = match self.registry.get(actor_name) {
+            Some(target) => target.try_send(msg, sender.clone()).map_err(|err| err.to_string()),
+            None => Err(format!("Actor '{}' not found", actor_name)),
+        };
+        if let (Err(reason), Some(sender), Some(msg_type)) = (sent, sender, msg_type) {
+            let _ = sender.try_send(Box::new(Reject::new(&msg_type, &reason, actor_name)), None);
+        }
+    }
+}
+
+/// Receiving end of an in-process pair, see `InprocTransport::pair`.
+///
+/// Nothing needs to be started: messages reach registered actors as soon
+/// as they are sent.
+pub struct InprocReceiver {
+    name: Arc<str>,
+    registry: ActorRegistry,
+}
+
+impl InprocReceiver {
+    /// Name of the pair
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Register a local actor to receive messages.
+    pub fn register(&self, name: &str, actor_ref: ActorRef) {
+        self.registry.register(name, actor_ref);
+    }
+
+    /// Get the registry (for sharing with other components).
+    pub fn registry(&self) -> &ActorRegistry {
+        &self.registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actor::{ActorRuntime, Envelope};
+    use crate::messages::Timeout;
+    use crate::Actor;
+    use std::sync::mpsc::channel;
+
+    struct Sink;
+    impl Actor for Sink {}
+
+    #[test]
+    fn test_inproc_pair() {
+        let (sender, receiver) = InprocTransport::pair("test");
+        assert_eq!(sender.name(), "test");
+        let (pong_tx, pong_rx) = channel::<Envelope>();
+        receiver.register("pong", ActorRef::new(pong_tx, "pong".to_string()));
+        let (ping_tx, ping_rx) = channel::<Envelope>();
+        let ping = ActorRef::new(ping_tx, "ping".to_string());
+
+        sender.clone().send_to("pong", Box::new(Timeout::new(7)), Some(ping.clone()));
+        let envelope = pong_rx.try_recv().unwrap();
+        assert_eq!(envelope.msg.as_any().downcast_ref::<Timeout>().unwrap().id, 7);
+        assert_eq!(envelope.sender.unwrap().name(), "ping");
+
+        sender.send_to("missing", Box::new(Timeout::new(8)), Some(ping));
+        let reject = ping_rx.try_recv().unwrap();
+        let reject = reject.msg.as_any().downcast_ref::<Reject>().unwrap();
+        assert_eq!(reject.reason, "Actor 'missing' not found");
+        assert_eq!(reject.rejected_by, "missing");
+    }
+
+    #[test]
+    fn test_inproc_full_mailbox() {
+        let (sender, receiver) = InprocTransport::pair("test");
+        let runtime = ActorRuntime::new("pong".to_string(), Box::new(Sink));
+        runtime.metrics.set_mailbox_capacity(Some(1));
+        receiver.register("pong", runtime.get_ref());
+        let (ping_tx, ping_rx) = channel::<Envelope>();
+        let ping = ActorRef::new(ping_tx, "ping".to_string());
+
+        // A full mailbox rejects the message instead of blocking or panicking
+        sender.send_to("pong", Box::new(Timeout::new(1)), Some(ping.clone()));
+        sender.send_to("pong", Box::new(Timeout::new(2)), Some(ping));
+        let reject = ping_rx.try_recv().unwrap();
+        let reject = reject.msg.as_any().downcast_ref::<Reject>().unwrap();
+        assert_eq!(reject.reason, "Actor mailbox full");
+        assert_eq!(reject.rejected_by, "pong");
+        assert!(ping_rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,212 @@
+/*
+
+THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
+
+Copyright 2025 Vincent Maciejewski, & M2 Tech
+Contact:
+v@m2te.ch
+mayeski@gmail.com
+https://www.linkedin.com/in/vmayeski/
+http://m2te.ch/
+
+*/
+
+//! Kafka as a source of actor messages (feature `kafka`).
+//!
+//! A `KafkaActorBridge` consumes a Kafka topic and hands each record to the
+//! `ZmqSender` it was given, addressed to the sender's own `ZmqReceiver`, so
+//! records reach actors like any other remote message. The record key is
+//! the target actor's name and the value is the message:
+//!
+//! ```json
+//! {"message_type": "Ping", "message": {"count": 1}}
+//! ```
+//!
+//! The message type must be registered with `register_remote_message`.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::error::KafkaError;
+use rdkafka::Message as _;
+
+use crate::remote::ZmqSender;
+use crate::serialization::try_deserialize_message;
+
+/// How long each poll of the consumer waits, bounding how long `stop` takes
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Routes the records of a Kafka topic to actors through a `ZmqSender`.
+///
+/// Consumption runs on a background thread until the bridge is stopped or
+/// dropped. Records without a key, with an invalid value or of an
+/// unregistered message type are skipped and counted, see `skipped_records`.
+/// Failed polls are counted, see `consume_errors`.
+///
+/// # Example
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use actors::{ActorRef, KafkaActorBridge, ZmqReceiver, ZmqSender};
+/// # fn example(pong_ref: ActorRef) -> Result<(), rdkafka::error::KafkaError> {
+/// let zmq_sender = Arc::new(ZmqSender::new("tcp://localhost:5001"));
+/// let receiver = ZmqReceiver::new("tcp://*:5001", Arc::clone(&zmq_sender));
+/// receiver.register("pong", pong_ref);
+///
+/// // Records keyed "pong" are delivered to the pong actor
+/// let bridge = KafkaActorBridge::new("localhost:9092", "pings", "actors", zmq_sender)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct KafkaActorBridge {
+    running: Arc<AtomicBool>,
+    /// Polls of the consumer that returned an error
+    consume_errors: Arc<AtomicU64>,
+    /// Records that could not be routed to an actor
+    skipped_records: Arc<AtomicU64>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl KafkaActorBridge {
+    /// Start consuming `topic` as a member of consumer group `group_id`.
+    ///
+    /// # Arguments
+    /// * `bootstrap_servers` - Kafka brokers, e.g. "localhost:9092"
+    /// * `topic` - Topic to consume
+    /// * `group_id` - Consumer group; offsets are committed automatically
+    /// * `zmq_sender` - Sender whose `local_endpoint` receives the messages
+    pub fn new(
+        bootstrap_servers: &str,
+        topic: &str,
+        group_id: &str,
+        zmq_sender: Arc<ZmqSender>,
+    ) -> Result<Self, KafkaError> {
+        let consumer: BaseConsumer = ClientConfig::new()
+            .set("bootstrap.servers", bootstrap_servers)
+            .set("group.id", group_id)
+            .create()?;
+        consumer.subscribe(&[topic])?;
+
+        let running = Arc::new(AtomicBool::new(true));
+        let running_flag = Arc::clone(&running);
+        let consume_errors = Arc::new(AtomicU64::new(0));
+        let error_count = Arc::clone(&consume_errors);
+        let skipped_records = Arc::new(AtomicU64::new(0));
+        let skipped_count = Arc::clone(&skipped_records);
+        let thread = thread::spawn(move || {
+            while running_flag.load(Ordering::SeqCst) {
+                let record = match consumer.poll(POLL_INTERVAL) {
+                    Some(Ok(record)) => record,
+                    Some(Err(_)) => {
+                        error_count.fetch_add(1, Ordering::Relaxed);
+                        continue;
+                    }
+                    None => continue,
+                };
+                if !route_record(&zmq_sender, record.key(), record.payload()) {
+                    skipped_count.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+
+        Ok(KafkaActorBridge {
+            running,
+            consume_errors,
+            skipped_records,
+            thread: Some(thread),
+        })
+    }
+
+    /// How many polls of the consumer have failed, e.g. because a broker
+    /// could not be reached. The bridge keeps polling after an error.
+    pub fn consume_errors(&self) -> u64 {
+        self.consume_errors.load(Ordering::Relaxed)
+    }
+
+    /// How many records were skipped because they had no key, an invalid
+    /// value or a message of an unregistered type, or a body that is not a
+    /// valid message of its type. The bridge moves on to the next record.
+    pub fn skipped_records(&self) -> u64 {
+        self.skipped_records.load(Ordering::Relaxed)
+    }
+
+    /// Stop consuming and wait for the consumer thread to finish.
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for KafkaActorBridge {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Split a record into the target actor's name, message type and message.
+fn decode_record(key: Option<&[u8]>, payload: Option<&[u8]>) -> Option<(String, String, serde_json::Value)> {
+    let actor_name = std::str::from_utf8(key?).ok()?;
+    let mut value: serde_json::Value = serde_json::from_slice(payload?).ok()?;
+    let msg_type = value.get("message_type")?.as_str()?.to_string();
+    let message = value.get_mut("message").map(serde_json::Value::take).unwrap_or_default();
+    Some((actor_name.to_string(), msg_type, message))
+}
+
+/// Send a record to its actor through the local ZmqReceiver.
+fn route_record(zmq_sender: &ZmqSender, key: Option<&[u8]>, payload: Option<&[u8]>) -> bool {
+    let Some((actor_name, msg_type, message)) = decode_record(key, payload) else {
+        return false;
+    };
+    match try_deserialize_message(&msg_type, message) {
+        Ok(msg) => {
+            zmq_sender.send_to(zmq_sender.local_endpoint(), &actor_name, msg, None);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::Reject;
+    use crate::serialization::register_remote_message;
+
+    #[test]
+    fn test_decode_record() {
+        let payload = br#"{"message_type": "Ping", "message": {"count": 1}}"#;
+        let (actor, msg_type, message) = decode_record(Some(b"pong"), Some(payload)).unwrap();
+        assert_eq!(actor, "pong");
+        assert_eq!(msg_type, "Ping");
+        assert_eq!(message["count"], 1);
+
+        assert!(decode_record(None, Some(payload)).is_none());
+        assert!(decode_record(Some(b"pong"), Some(b"not json")).is_none());
+        assert!(decode_record(Some(b"pong"), Some(br#"{"message": {}}"#)).is_none());
+    }
+
+    #[test]
+    fn test_route_record() {
+        register_remote_message::<Reject>("Reject");
+        let zmq_sender = ZmqSender::new_test_recorder("tcp://localhost:5001");
+
+        let payload = br#"{"message_type": "Reject", "message": {"message_type": "Ping", "reason": "busy", "rejected_by": "pong"}}"#;
+        assert!(route_record(&zmq_sender, Some(b"audit"), Some(payload)));
+        assert!(!route_record(&zmq_sender, Some(b"audit"), Some(br#"{"message_type": "Unknown"}"#)));
+        // A registered type with a malformed body is skipped, not a panic
+        let malformed = br#"{"message_type": "Reject", "message": {"reason": 5}}"#;
+        assert!(!route_record(&zmq_sender, Some(b"audit"), Some(malformed)));
+
+        let sends = zmq_sender.recorded_sends();
+        assert_eq!(sends.len(), 1);
+        assert_eq!(sends[0].0, "tcp://localhost:5001");
+        let envelope: serde_json::Value = serde_json::from_slice(&sends[0].1).unwrap();
+        assert_eq!(envelope["receiver"], "audit");
+        assert_eq!(envelope["message"]["reason"], "busy");
+    }
+}
@@ -41,6 +41,11 @@ http://m2te.ch/
 //!
 //! ```rust
 //! use actors::{Actor, ActorContext, Message};
+//! # use actors::define_message;
+//! # struct Ping { count: i32 }
+//! # define_message!(Ping);
+//! # struct Pong { count: i32 }
+//! # define_message!(Pong);
 //!
 //! struct PongActor;
 //!
@@ -58,6 +63,12 @@ http://m2te.ch/
 //!
 //! ```rust
 //! use actors::{Manager, ThreadConfig};
+//! # use actors::{Actor, ActorRef};
+//! # struct PongActor;
+//! # impl Actor for PongActor {}
+//! # struct PingActor;
+//! # impl PingActor { fn new(_pong: ActorRef) -> Self { PingActor } }
+//! # impl Actor for PingActor {}
 //!
 //! let mut mgr = Manager::new();
 //! let pong_ref = mgr.manage("pong", Box::new(PongActor), ThreadConfig::default());
@@ -72,44 +83,107 @@ http://m2te.ch/
 //!
 //! ### Async Send (Fire-and-Forget)
 //! ```rust
+//! # use actors::{define_message, ActorContext, ActorRef};
+//! # struct MyMessage { data: i32 }
+//! # define_message!(MyMessage);
+//! # fn send(other_actor: &ActorRef, ctx: &ActorContext) {
 //! other_actor.send(Box::new(MyMessage { data: 42 }), ctx.self_ref());
+//! # }
 //! ```
 //!
 //! ### Sync Send (RPC-style)
 //! ```rust
+//! # use actors::{define_message, ActorContext, ActorRef};
+//! # struct Request { id: i32 }
+//! # define_message!(Request);
+//! # struct Response;
+//! # define_message!(Response);
+//! # fn ask(other_actor: &ActorRef, ctx: &ActorContext) {
 //! if let Some(reply) = other_actor.fast_send(Box::new(Request { id: 1 }), ctx.self_ref()) {
 //!     if let Some(response) = reply.as_any().downcast_ref::<Response>() {
 //!         // Handle response
 //!     }
 //! }
+//! # }
 //! ```
 //!
 //! ### Reply
 //! ```rust
+//! # use actors::{define_message, Actor, ActorContext, Message};
+//! # struct Response { result: i32 }
+//! # define_message!(Response);
+//! # struct MyActor;
+//! # impl Actor for MyActor {
 //! fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
 //!     ctx.reply(Box::new(Response { result: 42 }));
 //! }
+//! # }
 //! ```
 
 pub mod actor;
+mod batch;
+mod codec;
+mod compression;
+pub mod dead_letters;
+pub mod inproc;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+mod management;
 pub mod manager;
 pub mod message;
 pub mod messages;
+mod metrics;
+mod rate_limit;
+mod recording;
 pub mod registry;
 pub mod registry_messages;
 pub mod remote;
+mod replay;
+pub mod router;
 pub mod serialization;
+pub mod supervision;
 pub mod timer;
+pub mod watch;
 
 // Re-export commonly used types
-pub use actor::{Actor, ActorContext, ActorRef, ActorRuntime, CppActorRef, CppSendFn, Envelope, LocalActorRef};
-pub use manager::{register_cpp_lookup, CppActorLookupFn, Manager, ManagerHandle, ThreadConfig};
+pub use actor::{
+    Actor, ActorContext, ActorError, ActorHandle, ActorRef, ActorRefPool, ActorRuntime, AskError, Behavior,
+    CppActorRef, CppSendFn, Envelope, HandlerError, HandlerResult, LocalActorRef, PeekResult, SendError, SpawnError,
+    TimedEnvelope, TypedActorRef,
+};
+pub use manager::{
+    register_cpp_lookup, ActorBuilder, ActorStatus, ConfigError, CppActorLookupFn, GetOrCreateError, IdleTimeout,
+    InspectError, Manager, ManagerHandle, TakeError, ThreadConfig, ThreadConfigBuilder,
+};
+pub use codec::SerializationBackend;
+pub use compression::CompressionAlgorithm;
+pub use dead_letters::{DeadLetter, DeadLetterQueue};
+pub use inproc::{InprocReceiver, InprocSender, InprocTransport};
 pub use message::Message;
 pub use messages::{Continue, Reject, Shutdown, Start, Timeout};
-pub use remote::{ActorRegistry, RemoteActorRef, ZmqReceiver, ZmqReceiverHandle, ZmqSender};
+pub use management::ManagementHandle;
+pub use metrics::{Counter, MailboxStats, MetricsServerHandle};
+pub use recording::RecordingHandle;
+pub use remote::{
+    ActorRegistry, BroadcastResult, ChannelData, DeadLetterReason, OnTimeout, ProbeError, RemoteActorRef,
+    RemoteDeadLetter, ReplayHandle, SendSemantics, TestProbe, ZmqPublisher, ZmqPullReceiver, ZmqPushSender,
+    ZmqReceiver, ZmqReceiverConfig, ZmqReceiverHandle, ZmqSender, ZmqSenderConfig, ZmqSenderPool, ZmqStreamWriter,
+};
+#[cfg(feature = "kafka")]
+pub use kafka::KafkaActorBridge;
+pub use router::{AddRoutee, KeyExtractor, RemoveRoutee, RouterActor};
 pub use serialization::{
-    deserialize_message, get_type_name, register_remote_message,
-    serialize_message,
+    deserialize_message, get_type_name, register_remote_message, register_remote_message_msgpack,
+    register_remote_message_proto, serialize_message,
 };
-pub use timer::{next_timer_id, Timer};
-pub use registry::{RegistryClient, RegistryError};
+pub use supervision::{ActorFactory, RestartReason, SupervisedActorRef, SupervisionStrategy, UpgradeError};
+pub use timer::{next_timer_id, Timer, TimerHandle};
+pub use watch::{Terminated, TerminationReason};
+pub use registry::{
+    ActorEvent, ActorInfo, CircuitBreakerConfig, CircuitState, DeregisterResult, ExportError, HeartbeatStats,
+    JsonSerializer, LoadBalancePolicy, ManagerLifecycleEvent, RegistryClient, RegistryClientConfig, RegistryError,
+    RegistrySerializer, RegistryTransaction, RetryPolicy, SubscriptionHandle, VersionConstraint, WaitOptions,
+};
+
+// Correlation IDs passed to `RegistryClient::with_correlation_id`
+pub use uuid::Uuid;
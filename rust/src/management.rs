@@ -0,0 +1,325 @@
+/*
+
+THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
+
+Copyright 2025 Vincent Maciejewski, & M2 Tech
+Contact:
+v@m2te.ch
+mayeski@gmail.com
+https://www.linkedin.com/in/vmayeski/
+http://m2te.ch/
+
+*/
+
+//! The management server started by `Manager::start_management_server`.
+//!
+//! Clients send one JSON command per line over TCP and get one JSON reply
+//! per line:
+//!
+//! ```text
+//! {"command": "list_actors"}
+//! {"actors": [{"name": "ping", "status": "Running", "mailbox_depth": 0}]}
+//! {"command": "inspect", "actor": "ping"}
+//! {"state": {"count": 3}}
+//! {"command": "send", "actor": "ping", "message_type": "Ping", "message": {"count": 1}}
+//! {"sent": true}
+//! ```
+//!
+//! Failed commands reply `{"error": "..."}`. A line longer than
+//! `MAX_LINE_LEN` gets an error reply and closes the connection.
+//!
+//! The server has no authentication, so it only binds loopback addresses.
+
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::actor::{ActorRef, InspectActor, InspectFn};
+use crate::manager::ChildSpawner;
+use crate::metrics::MetricsRegistry;
+use crate::serialization::try_deserialize_message;
+
+/// How often the server checks whether it was stopped
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Longest command line the server reads, in bytes
+const MAX_LINE_LEN: usize = 64 * 1024;
+
+/// How long `inspect` waits for the actor to run the inspection
+const INSPECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// What the server needs from its Manager to answer commands.
+#[derive(Clone)]
+pub(crate) struct ManagementContext {
+    pub(crate) spawner: ChildSpawner,
+    pub(crate) metrics: MetricsRegistry,
+}
+
+impl ManagementContext {
+    /// Answer one command line.
+    fn handle(&self, line: &str) -> Value {
+        let command: Value = match serde_json::from_str(line) {
+            Ok(command) => command,
+            Err(e) => return error(format!("invalid JSON: {}", e)),
+        };
+        let actor = command["actor"].as_str();
+        match (command["command"].as_str(), actor) {
+            (Some("list_actors"), _) => self.list_actors(),
+            (Some("inspect"), Some(actor)) => self.inspect(actor),
+            (Some("send"), Some(actor)) => self.send(actor, &command),
+            (Some("inspect" | "send"), None) => error("missing \"actor\"".to_string()),
+            (Some(other), _) => error(format!("unknown command '{}'", other)),
+            (None, _) => error("missing \"command\"".to_string()),
+        }
+    }
+
+    fn list_actors(&self) -> Value {
+        let actors: Vec<Value> = self
+            .metrics
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, metrics)| {
+                json!({
+                    "name": name,
+                    "status": format!("{:?}", metrics.status()),
+                    "mailbox_depth": metrics.mailbox_depth(),
+                })
+            })
+            .collect();
+        json!({ "actors": actors })
+    }
+
+    fn inspect(&self, name: &str) -> Value {
+        let actor_ref = match self.local_actor(name) {
+            Ok(actor_ref) => actor_ref,
+            Err(e) => return e,
+        };
+        let (state_tx, state_rx) = channel();
+        let inspect: InspectFn = Box::new(move |actor| {
+            let _ = state_tx.send(actor.inspect_state());
+        });
+        actor_ref.send(Box::new(InspectActor { f: Some(inspect) }), None);
+
+        match state_rx.recv_timeout(INSPECT_TIMEOUT) {
+            Ok(state) => json!({ "state": state }),
+            Err(_) => error(format!("actor '{}' did not respond", name)),
+        }
+    }
+
+    fn send(&self, name: &str, command: &Value) -> Value {
+        let actor_ref = match self.local_actor(name) {
+            Ok(actor_ref) => actor_ref,
+            Err(e) => return e,
+        };
+        let Some(msg_type) = command["message_type"].as_str() else {
+            return error("missing \"message_type\"".to_string());
+        };
+        match try_deserialize_message(msg_type, command["message"].clone()) {
+            Ok(msg) => match actor_ref.try_send(msg, None) {
+                Ok(()) => json!({ "sent": true }),
+                Err(e) => error(format!("{}: {}", e, name)),
+            },
+            Err(reason) => error(reason),
+        }
+    }
+
+    /// The running local actor `name`, or the error reply
+    fn local_actor(&self, name: &str) -> Result<ActorRef, Value> {
+        match self.spawner.get_actor(name) {
+            Some(actor_ref @ ActorRef::Local(_)) => Ok(actor_ref),
+            _ => Err(error(format!("no local actor '{}'", name))),
+        }
+    }
+}
+
+fn error(message: String) -> Value {
+    json!({ "error": message })
+}
+
+/// Handle to a running management server.
+///
+/// The server stops accepting connections when `stop()` is called or the
+/// handle is dropped; open connections are closed within a moment.
+pub struct ManagementHandle {
+    running: Arc<AtomicBool>,
+    addr: SocketAddr,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ManagementHandle {
+    /// Address the server is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stop the server and wait for its accept thread to exit.
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for ManagementHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Serve management commands on `addr` from background threads, one per connection.
+///
+/// Fails with `InvalidInput` if `addr` is not a loopback address.
+pub(crate) fn start_server(addr: &str, context: ManagementContext) -> io::Result<ManagementHandle> {
+    let listener = TcpListener::bind(addr)?;
+    let addr = listener.local_addr()?;
+    if !addr.ip().is_loopback() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("management server must bind a loopback address, not {}", addr),
+        ));
+    }
+    listener.set_nonblocking(true)?;
+    let running = Arc::new(AtomicBool::new(true));
+
+    let accept_running = Arc::clone(&running);
+    let thread = thread::spawn(move || {
+        while accept_running.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let context = context.clone();
+                    let running = Arc::clone(&accept_running);
+                    thread::spawn(move || serve_connection(stream, &context, &running));
+                }
+                // No pending connection (or a failed one): check `running` again
+                Err(_) => thread::sleep(POLL_INTERVAL),
+            }
+        }
+    });
+
+    Ok(ManagementHandle {
+        running,
+        addr,
+        thread: Some(thread),
+    })
+}
+
+/// Answer each line received on `stream` until the client disconnects or the server stops.
+fn serve_connection(stream: TcpStream, context: &ManagementContext, running: &AtomicBool) {
+    if stream.set_nonblocking(false).is_err() || stream.set_read_timeout(Some(POLL_INTERVAL)).is_err() {
+        return;
+    }
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    while running.load(Ordering::SeqCst) {
+        // Read at most one byte past the limit, so an overlong line is detected
+        let limit = (MAX_LINE_LEN + 1).saturating_sub(line.len()) as u64;
+        match (&mut reader).take(limit).read_line(&mut line) {
+            Ok(0) => return,
+            Ok(_) if line.len() > MAX_LINE_LEN && !line.ends_with('\n') => {
+                let _ = writeln!(writer, "{}", error(format!("line longer than {} bytes", MAX_LINE_LEN)));
+                return;
+            }
+            Ok(_) => {
+                if !line.trim().is_empty() {
+                    let reply = context.handle(line.trim());
+                    if writeln!(writer, "{}", reply).is_err() {
+                        return;
+                    }
+                }
+                line.clear();
+            }
+            // Timed out: keep any partial line and check `running` again
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(_) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::messages::Reject;
+    use crate::serialization::register_remote_message;
+    use crate::{Actor, ActorContext, Manager, Message};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpStream;
+
+    struct RejectCounter {
+        rejects: u32,
+    }
+
+    impl Actor for RejectCounter {
+        fn inspect_state(&self) -> Option<serde_json::Value> {
+            Some(serde_json::json!({ "rejects": self.rejects }))
+        }
+
+        fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+            if msg.as_any().is::<Reject>() {
+                self.rejects += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_management_server() {
+        register_remote_message::<Reject>("Reject");
+        let mut mgr = Manager::new();
+        mgr.manage("counter", Box::new(RejectCounter { rejects: 0 }), Default::default());
+        mgr.init();
+        let mut handle = mgr.start_management_server("127.0.0.1:0").unwrap();
+
+        let stream = TcpStream::connect(handle.local_addr()).unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut request = |command: &str| {
+            writeln!(writer, "{}", command).unwrap();
+            let mut reply = String::new();
+            reader.read_line(&mut reply).unwrap();
+            serde_json::from_str::<serde_json::Value>(&reply).unwrap()
+        };
+
+        let list = request(r#"{"command": "list_actors"}"#);
+        assert!(list["actors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|a| a["name"] == "counter" && a["status"] == "Running"));
+
+        let send = r#"{"command": "send", "actor": "counter", "message_type": "Reject",
+            "message": {"message_type": "Ping", "reason": "test", "rejected_by": "pong"}}"#;
+        assert_eq!(request(&send.replace('\n', " "))["sent"], true);
+        // Inspection runs after the message already in the mailbox
+        assert_eq!(request(r#"{"command": "inspect", "actor": "counter"}"#)["state"]["rejects"], 1);
+
+        assert_eq!(request(r#"{"command": "inspect", "actor": "missing"}"#)["error"], "no local actor 'missing'");
+        assert_eq!(request(r#"{"command": "reboot"}"#)["error"], "unknown command 'reboot'");
+        assert!(request("not json")["error"].as_str().unwrap().starts_with("invalid JSON"));
+        let malformed = r#"{"command": "send", "actor": "counter", "message_type": "Reject", "message": {}}"#;
+        assert!(request(malformed)["error"].as_str().unwrap().starts_with("Invalid message"));
+
+        // An overlong line is refused and the connection closed
+        let mut stream = TcpStream::connect(handle.local_addr()).unwrap();
+        stream.write_all(&vec![b'x'; super::MAX_LINE_LEN + 1]).unwrap();
+        let mut reply = String::new();
+        BufReader::new(&stream).read_line(&mut reply).unwrap();
+        assert!(reply.contains("line longer than"));
+        assert_eq!(BufReader::new(&stream).read_line(&mut reply).unwrap(), 0);
+
+        handle.stop();
+        assert_eq!(
+            mgr.start_management_server("0.0.0.0:0").err().map(|e| e.kind()),
+            Some(std::io::ErrorKind::InvalidInput)
+        );
+        mgr.end();
+    }
+}
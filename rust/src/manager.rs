@@ -20,13 +20,424 @@ http://m2te.ch/
 //! - Provides actor registry for name-based lookup
 
 use std::collections::HashMap;
+use std::io;
+use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, RwLock};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
-use crate::actor::{ActorRef, ActorRuntime};
+use crate::actor::{
+    ActorHandle, ActorRef, ActorRefPool, ActorRuntime, Drain, HandlerError, InspectActor, InspectFn,
+    MessageErrorHandler, SpawnError, TakeActor,
+};
+use crate::dead_letters::{DeadLetter, DeadLetterQueue, DeadLetterStore, SubscribeDeadLetters, DEAD_LETTERS_NAME};
 use crate::messages::{Shutdown, Start};
-use crate::Actor;
+use crate::management::{self, ManagementContext, ManagementHandle};
+use crate::metrics::{self, MailboxStats, MetricsRegistry, MetricsServerHandle};
+use crate::recording::{self, MessageRecorder, RecordingHandle};
+use crate::router::RouterActor;
+use crate::supervision::{RestartActor, SetSupervisor, SupervisionStrategy};
+use crate::timer::TimerService;
+use crate::watch::{DeathWatch, TerminationReason};
+use crate::{Actor, Message};
+
+/// Error returned by `Manager::inspect_actor`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InspectError {
+    /// No local actor with this name
+    NotFound(String),
+    /// The actor is not of the requested type
+    WrongType(String),
+    /// The actor's thread has stopped
+    NotRunning(String),
+    /// The actor did not get to the inspection in time, e.g. because it is
+    /// stuck in a handler or has a long backlog
+    Timeout(String),
+}
+
+impl std::fmt::Display for InspectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InspectError::NotFound(name) => write!(f, "Actor not found: {}", name),
+            InspectError::WrongType(name) => write!(f, "Actor '{}' is not of the requested type", name),
+            InspectError::NotRunning(name) => write!(f, "Actor not running: {}", name),
+            InspectError::Timeout(name) => write!(f, "Actor '{}' did not respond in time", name),
+        }
+    }
+}
+
+impl std::error::Error for InspectError {}
+
+/// Error returned by `Manager::take_actor` and `Manager::restore_actor`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TakeError {
+    /// No local actor with this name
+    NotFound(String),
+    /// The actor is handling a message or has messages waiting
+    ActorBusy(String),
+    /// The actor's thread has stopped, or the actor is already taken
+    NotRunning(String),
+    /// `restore_actor` was called for an actor that was not taken
+    NotTaken(String),
+}
+
+impl std::fmt::Display for TakeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TakeError::NotFound(name) => write!(f, "Actor not found: {}", name),
+            TakeError::ActorBusy(name) => write!(f, "Actor busy: {}", name),
+            TakeError::NotRunning(name) => write!(f, "Actor not running: {}", name),
+            TakeError::NotTaken(name) => write!(f, "Actor was not taken: {}", name),
+        }
+    }
+}
+
+impl std::error::Error for TakeError {}
+
+/// Error returned by `ManagerHandle::wait_for_idle` when the actors are
+/// still busy after the timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdleTimeout;
+
+impl std::fmt::Display for IdleTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Actors did not become idle in time")
+    }
+}
+
+impl std::error::Error for IdleTimeout {}
+
+/// Error returned by `Manager::get_or_create_actor` and
+/// `ManagerHandle::get_or_create_actor`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GetOrCreateError {
+    /// An actor with this name is registered but its thread has stopped
+    ActorStopped(String),
+}
+
+impl std::fmt::Display for GetOrCreateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GetOrCreateError::ActorStopped(name) => write!(f, "Actor stopped: {}", name),
+        }
+    }
+}
+
+impl std::error::Error for GetOrCreateError {}
+
+/// Lets actors add children to their Manager at runtime, see
+/// `ActorContext::spawn_child`, and `ManagerHandle` add top-level actors.
+#[derive(Clone)]
+pub(crate) struct ChildSpawner {
+    children: Arc<Mutex<Children>>,
+    /// The Manager's top-level actors, for `unregister`
+    registry: Arc<Mutex<HashMap<String, ActorRef>>>,
+    launcher: Arc<Mutex<Launcher>>,
+    dead_letters: ActorRef,
+    metrics: MetricsRegistry,
+    /// Longest any handler may run, see `Manager::with_default_handler_timeout`
+    handler_timeout: Option<Duration>,
+    /// Watchers of each actor, see `ActorContext::watch`
+    death_watch: DeathWatch,
+    recorder: MessageRecorder,
+    timers: TimerService,
+    message_error_handler: MessageErrorHandler,
+}
+
+/// Actors created with `spawn_child`, by hierarchical name
+#[derive(Default)]
+struct Children {
+    /// Parent name -> names of its children
+    links: HashMap<String, Vec<String>>,
+    refs: HashMap<String, ActorRef>,
+    threads: HashMap<String, JoinHandle<()>>,
+    /// Each child's run flag, cleared by `drain_and_shutdown` to stop it early
+    running: HashMap<String, Arc<Mutex<bool>>>,
+}
+
+/// Threads of the Manager's top-level actors, shared with its
+/// `ManagerHandle`s so they can register actors too
+#[derive(Default)]
+struct Launcher {
+    /// Actor runtimes (owned until init())
+    runtimes: Vec<(ActorRuntime, ThreadConfig)>,
+    /// Thread handles (after init())
+    threads: Vec<JoinHandle<()>>,
+    /// Actor refs for shutdown
+    actor_refs: Vec<ActorRef>,
+    /// Each actor's run flag, cleared by `drain_and_shutdown` to stop it early
+    running_flags: HashMap<String, Arc<Mutex<bool>>>,
+    /// Set by `init()`
+    started: bool,
+}
+
+impl Launcher {
+    /// Keep `runtime` for `init`, or start its thread if `init` was called.
+    fn launch(&mut self, runtime: ActorRuntime, config: ThreadConfig) {
+        if !self.started {
+            self.runtimes.push((runtime, config));
+        } else {
+            if !runtime.initialized {
+                runtime.get_ref().send(Box::new(Start), None);
+            }
+            self.threads.push(spawn_with_config(runtime, config));
+        }
+    }
+}
+
+impl ChildSpawner {
+    /// Pass the error `actor`'s handler returned for `msg` to the message
+    /// error handler; gives it back if none is set.
+    pub(crate) fn report_message_error(
+        &self,
+        actor: &str,
+        msg: &dyn Message,
+        error: HandlerError,
+    ) -> Option<HandlerError> {
+        match self.message_error_handler.lock().unwrap().as_ref() {
+            Some(handler) => {
+                handler(actor, msg, error);
+                None
+            }
+            None => Some(error),
+        }
+    }
+
+    /// Connect `runtime` to this Manager, as for every actor it runs,
+    /// top-level or child.
+    fn set_up(&self, runtime: &mut ActorRuntime, config: &ThreadConfig) {
+        runtime.set_dead_letters(&self.dead_letters);
+        runtime.context.spawner = Some(self.clone());
+        runtime.context.death_watch = Some(self.death_watch.clone());
+        runtime.context.timers = Some(self.timers.clone());
+        runtime.recorder = Some(self.recorder.clone());
+        runtime.handler_timeout = self.handler_timeout;
+        runtime.supervisor.strategy = config.supervision;
+        runtime.metrics.set_mailbox_capacity(config.mailbox_capacity);
+        self.metrics.lock().unwrap().push((runtime.name.clone(), Arc::clone(&runtime.metrics)));
+    }
+
+    /// Register `runtime` as a top-level actor, as `Manager::manage` does,
+    /// for callers holding the registry lock.
+    fn manage_locked(
+        &self,
+        registry: &mut HashMap<String, ActorRef>,
+        mut runtime: ActorRuntime,
+        config: ThreadConfig,
+    ) -> ActorRef {
+        self.set_up(&mut runtime, &config);
+        let actor_ref = runtime.get_ref();
+
+        registry.insert(runtime.name.clone(), actor_ref.clone());
+        let mut launcher = self.launcher.lock().unwrap();
+        launcher.actor_refs.push(actor_ref.clone());
+        launcher.running_flags.insert(runtime.name.clone(), Arc::clone(&runtime.running));
+        launcher.launch(runtime, config);
+
+        actor_ref
+    }
+
+    /// Get the top-level actor `name`, or register the one `factory` makes,
+    /// under one lock of the registry.
+    fn get_or_create(
+        &self,
+        name: &str,
+        factory: impl FnOnce() -> Box<dyn Actor>,
+        config: ThreadConfig,
+    ) -> Result<ActorRef, GetOrCreateError> {
+        let mut registry = self.registry.lock().unwrap();
+        if let Some(actor_ref) = registry.get(name) {
+            return if actor_ref.is_alive() {
+                Ok(actor_ref.clone())
+            } else {
+                Err(GetOrCreateError::ActorStopped(name.to_string()))
+            };
+        }
+
+        let runtime = ActorRuntime::new(name.to_string(), factory());
+        Ok(self.manage_locked(&mut registry, runtime, config))
+    }
+
+    /// Start `actor` as a child of `parent`, named `"<parent>/<name>"`.
+    ///
+    /// Fails with `SpawnError::NameTaken` if the Manager already has an actor
+    /// of that name, e.g. a live child spawned earlier.
+    pub(crate) fn spawn(
+        &self,
+        parent: &ActorRef,
+        name: &str,
+        actor: Box<dyn Actor>,
+        config: ThreadConfig,
+    ) -> Result<ActorRef, SpawnError> {
+        let full_name = format!("{}/{}", parent.name(), name);
+        // Only the parent creates children under its name, so nothing can take it meanwhile
+        if self.get_actor(&full_name).is_some() {
+            return Err(SpawnError::NameTaken(full_name));
+        }
+        let mut runtime = ActorRuntime::new(full_name.clone(), actor);
+        self.set_up(&mut runtime, &config);
+        runtime.parent = Some(parent.clone());
+        let actor_ref = runtime.get_ref();
+        let running = Arc::clone(&runtime.running);
+
+        actor_ref.send(Box::new(Start), None);
+        let thread = spawn_with_config(runtime, config);
+
+        let mut children = self.children.lock().unwrap();
+        children.links.entry(parent.name().to_string()).or_default().push(full_name.clone());
+        children.refs.insert(full_name.clone(), actor_ref.clone());
+        children.running.insert(full_name.clone(), running);
+        children.threads.insert(full_name, thread);
+        Ok(actor_ref)
+    }
+
+    /// Stop the children of `parent` and wait for them.
+    ///
+    /// Each child stops its own children before handling its Shutdown, so
+    /// the whole subtree is stopped depth-first.
+    pub(crate) fn stop_children(&self, parent: &str) {
+        let stopping: Vec<(Option<ActorRef>, Option<JoinHandle<()>>)> = {
+            let mut children = self.children.lock().unwrap();
+            let names = children.links.remove(parent).unwrap_or_default();
+            names
+                .iter()
+                .map(|name| {
+                    children.running.remove(name);
+                    (children.refs.remove(name), children.threads.remove(name))
+                })
+                .collect()
+        };
+
+        for (actor_ref, _) in &stopping {
+            if let Some(actor_ref) = actor_ref {
+                actor_ref.send(Box::new(Shutdown), None);
+            }
+        }
+        for (_, thread) in stopping {
+            if let Some(thread) = thread {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    /// Remove the actor `name` (top-level or child) from the Manager's names.
+    pub(crate) fn unregister(&self, name: &str) {
+        self.registry.lock().unwrap().remove(name);
+        let mut children = self.children.lock().unwrap();
+        if children.refs.remove(name).is_some() {
+            // Its thread is left to finish on its own
+            children.threads.remove(name);
+            children.running.remove(name);
+            for siblings in children.links.values_mut() {
+                siblings.retain(|sibling| sibling != name);
+            }
+        }
+    }
+
+    fn get_ref(&self, name: &str) -> Option<ActorRef> {
+        self.children.lock().unwrap().refs.get(name).cloned()
+    }
+
+    /// Stop the child `name` after the message it is handling, if any.
+    fn stop_child_early(&self, name: &str) {
+        if let Some(running) = self.children.lock().unwrap().running.get(name) {
+            *running.lock().unwrap() = false;
+        }
+    }
+
+    /// Ref to the registered actor or child `name`
+    pub(crate) fn get_actor(&self, name: &str) -> Option<ActorRef> {
+        let actor_ref = self.registry.lock().unwrap().get(name).cloned();
+        actor_ref.or_else(|| self.get_ref(name))
+    }
+
+    /// Pause or resume the running local actor `name`.
+    fn set_paused(&self, name: &str, paused: bool) -> bool {
+        match self.get_actor(name) {
+            Some(ActorRef::Local(actor_ref)) => actor_ref.is_alive() && actor_ref.set_paused(paused),
+            _ => false,
+        }
+    }
+
+    /// Refs to every registered actor and child, except the dead letter queue
+    fn all_refs(&self) -> Vec<ActorRef> {
+        let mut refs: Vec<ActorRef> = self
+            .registry
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(name, _)| name.as_str() != DEAD_LETTERS_NAME)
+            .map(|(_, actor_ref)| actor_ref.clone())
+            .collect();
+        refs.extend(self.children.lock().unwrap().refs.values().cloned());
+        refs
+    }
+
+    /// Send a message from `msg_factory` to every actor, returning the
+    /// names of those that have stopped.
+    fn broadcast_all(&self, msg_factory: impl Fn() -> Box<dyn Message>) -> Vec<String> {
+        let mut unreachable: Vec<String> = self
+            .all_refs()
+            .into_iter()
+            .filter(|actor_ref| !actor_ref.is_alive() || actor_ref.try_send(msg_factory(), None).is_err())
+            .map(|actor_ref| actor_ref.name().to_string())
+            .collect();
+        unreachable.sort();
+        unreachable
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.children.lock().unwrap().refs.keys().cloned().collect()
+    }
+}
+
+/// The thread of an actor most Managers never use (the dead letter queue),
+/// started by the first message sent to it.
+struct LazyThread {
+    /// Until the thread is started
+    runtime: Mutex<Option<ActorRuntime>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl LazyThread {
+    fn new(runtime: ActorRuntime) -> Self {
+        LazyThread {
+            runtime: Mutex::new(Some(runtime)),
+            thread: Mutex::new(None),
+        }
+    }
+
+    fn start(&self) {
+        // Held while spawning, so `shutdown` never sees a started actor without its thread
+        let mut runtime = self.runtime.lock().unwrap();
+        if let Some(runtime) = runtime.take() {
+            *self.thread.lock().unwrap() = Some(spawn_with_config(runtime, ThreadConfig::default()));
+        }
+    }
+
+    /// Send Shutdown through `actor_ref`, or drop the actor if it never started.
+    fn shutdown(&self, actor_ref: &ActorRef) {
+        let never_started = self.runtime.lock().unwrap().take().is_some();
+        if !never_started {
+            actor_ref.send(Box::new(Shutdown), None);
+        }
+    }
+
+    fn join(&self) {
+        if let Some(thread) = self.thread.lock().unwrap().take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Stand-in for an actor removed by `Manager::take_actor`
+struct TakenActor;
+
+impl Actor for TakenActor {}
 
 /// Type for the C++ actor lookup function
 /// Returns Some(ActorRef) if the actor exists in C++, None otherwise
@@ -51,8 +462,45 @@ fn lookup_cpp_actor(name: &str, sender: &str) -> Option<ActorRef> {
     }
 }
 
-/// Configuration for an actor's thread
+/// Stack size of actor threads unless configured, the same as Rust's default
+const DEFAULT_STACK_SIZE: usize = 2 * 1024 * 1024;
+
+/// How often `drain_and_shutdown` checks whether the drained actors have stopped
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Error returned by `ThreadConfigBuilder::build` for an invalid configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// `thread_priority` is outside the nice range -20..=19
+    InvalidNiceValue(i32),
+    /// `thread_priority` has no effect under a real-time scheduling policy
+    NiceWithRealtimePolicy,
+    /// `thread_priority` is only supported on Unix
+    PriorityUnsupported,
+    /// `mailbox_capacity` must allow at least one message
+    ZeroMailboxCapacity,
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::InvalidNiceValue(nice) => write!(f, "Nice value out of range -20..=19: {}", nice),
+            ConfigError::NiceWithRealtimePolicy => write!(f, "Nice value has no effect with a real-time policy"),
+            ConfigError::PriorityUnsupported => write!(f, "Thread priority is only supported on Unix"),
+            ConfigError::ZeroMailboxCapacity => write!(f, "Mailbox capacity must be at least 1"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Configuration for an actor's thread.
+///
+/// Fields may be added in later versions, so build a config with
+/// `ThreadConfig::builder`, one of the `with_*` constructors or `default`,
+/// then set fields on it.
 #[derive(Clone)]
+#[non_exhaustive]
 pub struct ThreadConfig {
     /// CPU cores to pin the thread to (empty = no pinning)
     pub affinity: Vec<usize>,
@@ -60,6 +508,18 @@ pub struct ThreadConfig {
     pub priority: i32,
     /// Scheduling policy (SCHED_OTHER, SCHED_FIFO, SCHED_RR)
     pub sched_policy: i32,
+    /// What to do when the actor's handler panics (default: stop the actor)
+    pub supervision: SupervisionStrategy,
+    /// Stack size of the thread in bytes (default: 2 MiB)
+    pub stack_size: usize,
+    /// Most messages the mailbox holds; further messages are rejected to
+    /// the dead letter queue (default: None, unbounded)
+    pub mailbox_capacity: Option<usize>,
+    /// The thread is named `"{name_prefix}-{actor}"`, or after the actor
+    /// if empty (default: empty)
+    pub name_prefix: String,
+    /// Nice value of the thread, -20..=19, under SCHED_OTHER (default: 0)
+    pub thread_priority: i32,
 }
 
 impl Default for ThreadConfig {
@@ -68,6 +528,11 @@ impl Default for ThreadConfig {
             affinity: vec![],
             priority: 0,
             sched_policy: libc::SCHED_OTHER,
+            supervision: SupervisionStrategy::default(),
+            stack_size: DEFAULT_STACK_SIZE,
+            mailbox_capacity: None,
+            name_prefix: String::new(),
+            thread_priority: 0,
         }
     }
 }
@@ -90,13 +555,149 @@ impl ThreadConfig {
         }
     }
 
+    /// Create config with a supervision strategy
+    pub fn with_supervision(strategy: SupervisionStrategy) -> Self {
+        ThreadConfig {
+            supervision: strategy,
+            ..Default::default()
+        }
+    }
+
     /// Create config with both affinity and priority
     pub fn new(affinity: Vec<usize>, priority: i32, policy: i32) -> Self {
         ThreadConfig {
             affinity,
             priority,
             sched_policy: policy,
+            ..Default::default()
+        }
+    }
+
+    /// Start building a config from the defaults, checked by `build`.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{ConfigError, ThreadConfig};
+    /// # fn example() -> Result<(), ConfigError> {
+    /// let config = ThreadConfig::builder()
+    ///     .stack_size(4 * 1024 * 1024)
+    ///     .mailbox_capacity(1024)
+    ///     .name_prefix("worker")
+    ///     .thread_priority(5)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder() -> ThreadConfigBuilder {
+        ThreadConfigBuilder { config: ThreadConfig::default() }
+    }
+}
+
+/// Builder for a `ThreadConfig`, from `ThreadConfig::builder`.
+pub struct ThreadConfigBuilder {
+    config: ThreadConfig,
+}
+
+impl ThreadConfigBuilder {
+    /// Set the thread's stack size in bytes
+    pub fn stack_size(mut self, bytes: usize) -> Self {
+        self.config.stack_size = bytes;
+        self
+    }
+
+    /// Bound the mailbox to `capacity` messages
+    pub fn mailbox_capacity(mut self, capacity: usize) -> Self {
+        self.config.mailbox_capacity = Some(capacity);
+        self
+    }
+
+    /// Name the thread `"{prefix}-{actor}"`
+    pub fn name_prefix(mut self, prefix: &str) -> Self {
+        self.config.name_prefix = prefix.to_string();
+        self
+    }
+
+    /// Set the thread's nice value (-20..=19; negative values need privileges)
+    pub fn thread_priority(mut self, nice: i32) -> Self {
+        self.config.thread_priority = nice;
+        self
+    }
+
+    /// Pin the thread to CPU cores
+    pub fn affinity(mut self, cores: Vec<usize>) -> Self {
+        self.config.affinity = cores;
+        self
+    }
+
+    /// Run the thread under a real-time policy (SCHED_FIFO, SCHED_RR)
+    pub fn realtime_priority(mut self, priority: i32, policy: i32) -> Self {
+        self.config.priority = priority;
+        self.config.sched_policy = policy;
+        self
+    }
+
+    /// Set what to do when the actor's handler panics
+    pub fn supervision(mut self, strategy: SupervisionStrategy) -> Self {
+        self.config.supervision = strategy;
+        self
+    }
+
+    /// Check the options and build the config.
+    pub fn build(self) -> Result<ThreadConfig, ConfigError> {
+        let config = self.config;
+        if config.mailbox_capacity == Some(0) {
+            return Err(ConfigError::ZeroMailboxCapacity);
         }
+        if config.thread_priority != 0 {
+            if cfg!(not(unix)) {
+                return Err(ConfigError::PriorityUnsupported);
+            }
+            if !(-20..=19).contains(&config.thread_priority) {
+                return Err(ConfigError::InvalidNiceValue(config.thread_priority));
+            }
+            if config.sched_policy != libc::SCHED_OTHER {
+                return Err(ConfigError::NiceWithRealtimePolicy);
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Builder for registering an actor of a known type, from `Manager::builder`.
+///
+/// # Example
+/// ```
+/// # use actors::{define_message, Actor, ActorHandle, Manager, ThreadConfig};
+/// # #[derive(Default)]
+/// # struct Counter;
+/// # impl Actor for Counter {}
+/// # struct Increment;
+/// # define_message!(Increment);
+/// # fn example(mgr: &mut Manager) {
+/// let counter: ActorHandle<Counter> = mgr
+///     .builder::<Counter>("counter")
+///     .with_config(ThreadConfig::with_affinity(vec![2]))
+///     .build(Counter::default());
+/// counter.send(Increment);
+/// # }
+/// ```
+pub struct ActorBuilder<'a, A: Actor> {
+    manager: &'a mut Manager,
+    name: String,
+    config: ThreadConfig,
+    _actor: PhantomData<fn() -> A>,
+}
+
+impl<A: Actor> ActorBuilder<'_, A> {
+    /// Set the actor's thread configuration (default: `ThreadConfig::default()`)
+    pub fn with_config(mut self, config: ThreadConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Register the actor with the manager, like `Manager::manage`.
+    pub fn build(self, actor: A) -> ActorHandle<A> {
+        ActorHandle::new(self.manager.manage(&self.name, Box::new(actor), self.config))
     }
 }
 
@@ -107,6 +708,8 @@ impl ThreadConfig {
 #[derive(Clone)]
 pub struct ManagerHandle {
     terminate_flag: Arc<AtomicBool>,
+    metrics: MetricsRegistry,
+    spawner: ChildSpawner,
 }
 
 impl ManagerHandle {
@@ -121,13 +724,130 @@ impl ManagerHandle {
     pub fn is_terminated(&self) -> bool {
         self.terminate_flag.load(Ordering::SeqCst)
     }
+
+    /// Get the status of the actor `name`, as `Manager::actor_status`.
+    pub fn actor_status(&self, name: &str) -> Option<ActorStatus> {
+        actor_status(&self.metrics, name)
+    }
+
+    /// Send a message to every actor, as `Manager::broadcast_all`.
+    pub fn broadcast_all(&self, msg_factory: impl Fn() -> Box<dyn Message>) -> Vec<String> {
+        self.spawner.broadcast_all(msg_factory)
+    }
+
+    /// Get the ActorRef of a managed actor, as `Manager::get_actor`.
+    pub fn get_actor(&self, name: &str) -> Option<ActorRef> {
+        self.spawner.get_actor(name)
+    }
+
+    /// Get the actor registered as `name`, or create it with `factory`, as
+    /// `Manager::get_or_create_actor`.
+    ///
+    /// Threads calling this at once with the same name all get the same
+    /// actor: the name is checked and registered under the Manager's
+    /// registry lock, so `factory` is called once.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{Actor, GetOrCreateError, ThreadConfig};
+    /// # struct Cache;
+    /// # impl Cache { fn new() -> Self { Cache } }
+    /// # impl Actor for Cache {}
+    /// # fn example(handle: &actors::ManagerHandle) -> Result<(), GetOrCreateError> {
+    /// let cache = handle.get_or_create_actor("cache", || Box::new(Cache::new()), ThreadConfig::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_or_create_actor(
+        &self,
+        name: &str,
+        factory: impl FnOnce() -> Box<dyn Actor>,
+        config: ThreadConfig,
+    ) -> Result<ActorRef, GetOrCreateError> {
+        self.spawner.get_or_create(name, factory, config)
+    }
+
+    /// Block until every running actor's mailbox is empty and none is
+    /// handling a message, e.g. before a test inspects state.
+    ///
+    /// Polls every millisecond and returns once all actors, including
+    /// spawned children, have been idle for two polls in a row. Messages a
+    /// paused actor holds count as waiting; actors that have stopped are
+    /// ignored. Fails with `IdleTimeout` if that has not happened within
+    /// `timeout`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actors::{define_message, ActorRef, IdleTimeout, ManagerHandle};
+    /// # struct Produce { count: u32 }
+    /// # define_message!(Produce);
+    /// # fn example(handle: &ManagerHandle, producer: &ActorRef) -> Result<(), IdleTimeout> {
+    /// producer.send(Box::new(Produce { count: 100 }), None);
+    /// handle.wait_for_idle(Duration::from_secs(1))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn wait_for_idle(&self, timeout: Duration) -> Result<(), IdleTimeout> {
+        // None if the timeout is too long to represent: wait forever
+        let deadline = Instant::now().checked_add(timeout);
+        let mut idle_polls = 0;
+        loop {
+            let idle = self.metrics.lock().unwrap().iter().all(|(_, metrics)| {
+                matches!(metrics.status(), ActorStatus::Stopped | ActorStatus::Panicked)
+                    || (metrics.mailbox_depth() == 0 && !metrics.is_processing())
+            });
+            idle_polls = if idle { idle_polls + 1 } else { 0 };
+            if idle_polls == 2 {
+                return Ok(());
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(IdleTimeout);
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Pause an actor, as `Manager::pause_actor`.
+    pub fn pause_actor(&self, name: &str) -> bool {
+        self.spawner.set_paused(name, true)
+    }
+
+    /// Resume a paused actor, as `Manager::resume_actor`.
+    pub fn resume_actor(&self, name: &str) -> bool {
+        self.spawner.set_paused(name, false)
+    }
+}
+
+/// Lifecycle state of a managed actor, see `Manager::actor_status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActorStatus {
+    /// Started (or waiting for `init`) and handling messages
+    Running,
+    /// Handling Shutdown, or stopping after `stop_self` or a failure
+    Stopping,
+    /// Its thread has exited normally, or it was stopped by its supervision strategy
+    Stopped,
+    /// Its thread has exited after a handler panicked
+    Panicked,
+}
+
+/// Status of the most recently registered actor named `name`
+fn actor_status(metrics: &MetricsRegistry, name: &str) -> Option<ActorStatus> {
+    metrics
+        .lock()
+        .unwrap()
+        .iter()
+        .rev()
+        .find(|(actor_name, _)| actor_name == name)
+        .map(|(_, metrics)| metrics.status())
 }
 
 /// Manages the lifecycle of actors.
 ///
 /// # Example
 /// ```
-/// use actors::{Manager, Actor, ActorContext, ThreadConfig};
+/// use actors::{Manager, ManagerHandle, Actor, ActorContext, Message, ThreadConfig};
 ///
 /// struct MyActor {
 ///     manager: ManagerHandle,
@@ -148,42 +868,152 @@ impl ManagerHandle {
 /// mgr.end();
 /// ```
 pub struct Manager {
-    /// Actor name -> ActorRef registry
-    registry: HashMap<String, ActorRef>,
-    /// Actor runtimes (owned until init())
-    runtimes: Vec<(ActorRuntime, ThreadConfig)>,
-    /// Thread handles (after init())
-    threads: Vec<JoinHandle<()>>,
-    /// Actor refs for shutdown
-    actor_refs: Vec<ActorRef>,
+    /// Actor name -> ActorRef registry (shared with `spawner`, which
+    /// removes actors that stop themselves)
+    registry: Arc<Mutex<HashMap<String, ActorRef>>>,
+    /// Runtimes and threads of the top-level actors (shared with `spawner`)
+    launcher: Arc<Mutex<Launcher>>,
     /// Termination flag
     terminate_flag: Arc<AtomicBool>,
+    /// Per-actor metrics, shared with the metrics server
+    metrics: MetricsRegistry,
+    /// The dead letter queue (also in `registry` as "$dead_letters")
+    dead_letters: ActorRef,
+    /// Started by the first message sent to `dead_letters`
+    dead_letters_thread: Arc<LazyThread>,
+    /// Runtimes of actors removed by `take_actor`, until restored
+    taken: HashMap<String, (ActorRuntime, ThreadConfig)>,
+    /// Sets up every actor's runtime; keeps the actors spawned at runtime
+    /// by other actors, and their parent links
+    spawner: ChildSpawner,
+    /// Records handled messages, see `start_message_recording`
+    recorder: MessageRecorder,
+    /// Runs the timers of `ActorContext::schedule_once` and `schedule_periodic`
+    timers: TimerService,
+    /// Recent dead letters, see `new_with_dead_letter_store`
+    dead_letter_store: Option<DeadLetterStore>,
 }
 
 impl Manager {
     /// Create a new Manager
+    ///
+    /// The Manager starts with one actor, the dead letter queue, registered
+    /// under the reserved name `"$dead_letters"`. Its thread is only started
+    /// by the first message sent to it.
     pub fn new() -> Self {
+        Self::with_dead_letter_queue(DeadLetterQueue::new(), None)
+    }
+
+    /// Create a Manager that keeps the last `capacity` dead letters for
+    /// post-mortem analysis, see `take_dead_letters`.
+    pub fn new_with_dead_letter_store(capacity: usize) -> Self {
+        let store = DeadLetterStore::new(capacity);
+        Self::with_dead_letter_queue(DeadLetterQueue::with_store(store.clone()), Some(store))
+    }
+
+    fn with_dead_letter_queue(queue: DeadLetterQueue, dead_letter_store: Option<DeadLetterStore>) -> Self {
+        let dead_letters_runtime = ActorRuntime::new(DEAD_LETTERS_NAME.to_string(), Box::new(queue));
+        let dead_letters = dead_letters_runtime.get_ref();
+        let dead_letters_thread = Arc::new(LazyThread::new(dead_letters_runtime));
+        let dead_letters = match dead_letters {
+            ActorRef::Local(local) => {
+                let lazy = Arc::clone(&dead_letters_thread);
+                ActorRef::Local(local.with_on_send(Arc::new(move || lazy.start())))
+            }
+            other => other,
+        };
+        let metrics: MetricsRegistry = Arc::new(Mutex::new(Vec::new()));
+        let death_watch = DeathWatch::default();
+        let recorder = MessageRecorder::default();
+        let timers = TimerService::default();
+        let registry = Arc::new(Mutex::new(HashMap::from([(DEAD_LETTERS_NAME.to_string(), dead_letters.clone())])));
+        let launcher: Arc<Mutex<Launcher>> = Arc::default();
+        let spawner = ChildSpawner {
+            children: Arc::default(),
+            registry: Arc::clone(&registry),
+            launcher: Arc::clone(&launcher),
+            dead_letters: dead_letters.clone(),
+            metrics: Arc::clone(&metrics),
+            handler_timeout: None,
+            death_watch,
+            recorder: recorder.clone(),
+            timers: timers.clone(),
+            message_error_handler: Arc::default(),
+        };
+
         Manager {
-            registry: HashMap::new(),
-            runtimes: Vec::new(),
-            threads: Vec::new(),
-            actor_refs: Vec::new(),
+            registry,
+            launcher,
             terminate_flag: Arc::new(AtomicBool::new(false)),
+            metrics,
+            dead_letters,
+            dead_letters_thread,
+            taken: HashMap::new(),
+            spawner,
+            recorder,
+            timers,
+            dead_letter_store,
         }
     }
 
+    /// Give up on any handler that runs longer than `timeout`.
+    ///
+    /// Each handler then runs on its own thread. One that does not return in
+    /// time (e.g. blocked on a request that never completes) is abandoned:
+    /// threads cannot be interrupted, so it keeps running, but its message
+    /// is routed to the dead letter queue as "handler timed out" and the
+    /// actor fails as if it had panicked. Its `SupervisionStrategy` decides
+    /// whether it is restarted or stopped.
+    ///
+    /// Applies to actors registered after this call, and to their children.
+    pub fn with_default_handler_timeout(mut self, timeout: Duration) -> Self {
+        self.spawner.handler_timeout = Some(timeout);
+        self
+    }
+
     /// Get a handle for actors to signal termination.
     ///
     /// Clone this handle and pass it to actors that need to trigger shutdown.
     pub fn get_handle(&self) -> ManagerHandle {
         ManagerHandle {
             terminate_flag: Arc::clone(&self.terminate_flag),
+            metrics: Arc::clone(&self.metrics),
+            spawner: self.spawner.clone(),
         }
     }
 
+    /// Send a message to every registered actor, including spawned children
+    /// but not the dead letter queue.
+    ///
+    /// `msg_factory` is called once per actor, so each gets its own message.
+    /// Returns the names of actors that could not be reached because they
+    /// have already stopped.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{define_message, Manager};
+    /// # struct ReloadConfig;
+    /// # define_message!(ReloadConfig);
+    /// # fn example(mgr: &Manager) {
+    /// let stopped = mgr.broadcast_all(|| Box::new(ReloadConfig));
+    /// # }
+    /// ```
+    pub fn broadcast_all(&self, msg_factory: impl Fn() -> Box<dyn Message>) -> Vec<String> {
+        self.spawner.broadcast_all(msg_factory)
+    }
+
+    /// Get the status of the actor `name`, including spawned children.
+    ///
+    /// Actors that stopped, even by `stop_self`, keep their last status.
+    /// Returns None if no actor with that name was ever managed.
+    pub fn actor_status(&self, name: &str) -> Option<ActorStatus> {
+        actor_status(&self.metrics, name)
+    }
+
     /// Register an actor to be managed.
     ///
     /// Returns an ActorRef that can be used to send messages to the actor.
+    /// An actor registered after `init` is started at once.
     pub fn manage(
         &mut self,
         name: &str,
@@ -191,35 +1021,130 @@ impl Manager {
         config: ThreadConfig,
     ) -> ActorRef {
         let runtime = ActorRuntime::new(name.to_string(), actor);
-        let actor_ref = runtime.get_ref();
+        let mut registry = self.registry.lock().unwrap();
+        self.spawner.manage_locked(&mut registry, runtime, config)
+    }
+
+    /// Get the actor registered as `name`, or create it with `factory` and
+    /// register it as `manage` does.
+    ///
+    /// `factory` is only called if no actor has that name, so the same
+    /// actor can be requested from several code paths. After `init` the
+    /// actor is started at once. Fails with `ActorStopped` if the actor
+    /// exists but its thread has stopped.
+    ///
+    /// The name is checked and registered under one lock of the registry,
+    /// so actors stopping or spawning children meanwhile cannot interfere.
+    /// `factory` runs under that lock, so it must not use the Manager or
+    /// its handles. From other threads, use `ManagerHandle::get_or_create_actor`,
+    /// which takes the same lock.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{Actor, GetOrCreateError, ThreadConfig};
+    /// # struct Cache;
+    /// # impl Cache { fn new() -> Self { Cache } }
+    /// # impl Actor for Cache {}
+    /// # fn example(mgr: &actors::Manager) -> Result<(), GetOrCreateError> {
+    /// let cache = mgr.get_or_create_actor("cache", || Box::new(Cache::new()), ThreadConfig::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_or_create_actor(
+        &self,
+        name: &str,
+        factory: impl FnOnce() -> Box<dyn Actor>,
+        config: ThreadConfig,
+    ) -> Result<ActorRef, GetOrCreateError> {
+        self.spawner.get_or_create(name, factory, config)
+    }
 
-        self.registry.insert(name.to_string(), actor_ref.clone());
-        self.actor_refs.push(actor_ref.clone());
-        self.runtimes.push((runtime, config));
+    /// Start registering an actor of type `A` under `name`.
+    ///
+    /// The returned builder keeps the actor's type, so `build` gives back an
+    /// `ActorHandle<A>` instead of an untyped ActorRef.
+    pub fn builder<A: Actor>(&mut self, name: &str) -> ActorBuilder<'_, A> {
+        ActorBuilder {
+            manager: self,
+            name: name.to_string(),
+            config: ThreadConfig::default(),
+            _actor: PhantomData,
+        }
+    }
 
-        actor_ref
+    /// Register several actors at once.
+    ///
+    /// Returns their ActorRefs in the same order as `actors`.
+    pub fn manage_many(&mut self, actors: Vec<(String, Box<dyn Actor>, ThreadConfig)>) -> Vec<ActorRef> {
+        actors
+            .into_iter()
+            .map(|(name, actor, config)| self.manage(&name, actor, config))
+            .collect()
+    }
+
+    /// Register a router that forwards each message it receives to the next
+    /// of `routees` in turn, see `RouterActor::round_robin`.
+    ///
+    /// Routees run by this Manager are dropped from the rotation when they
+    /// stop; send `RouterActor::add_routee` or `RouterActor::remove_routee`
+    /// to the returned ref to change the rotation.
+    pub fn manage_router(&mut self, name: &str, routees: Vec<ActorRef>) -> ActorRef {
+        self.manage(name, RouterActor::round_robin(name, routees), ThreadConfig::default())
+    }
+
+    /// Register an actor whose mailbox channel is borrowed from `pool`.
+    ///
+    /// The channel is returned to the pool when the actor stops.
+    pub fn manage_pooled(
+        &mut self,
+        name: &str,
+        actor: Box<dyn Actor>,
+        config: ThreadConfig,
+        pool: &ActorRefPool,
+    ) -> ActorRef {
+        let runtime = ActorRuntime::new_pooled(name.to_string(), actor, pool);
+        let mut registry = self.registry.lock().unwrap();
+        self.spawner.manage_locked(&mut registry, runtime, config)
     }
 
     /// Get an ActorRef by name.
     ///
-    /// Checks local Rust actors first, then C++ actors if a lookup function is registered.
+    /// Checks local Rust actors first (including children spawned by actors,
+    /// named `"parent/child"`), then C++ actors if a lookup function is registered.
     /// Returns None if no actor with that name is registered in either.
     pub fn get_ref(&self, name: &str) -> Option<ActorRef> {
         // Check local Rust actors first
-        if let Some(actor_ref) = self.registry.get(name) {
-            return Some(actor_ref.clone());
+        if let Some(actor_ref) = self.get_actor(name) {
+            return Some(actor_ref);
         }
 
         // Check C++ actors (if lookup function registered)
         lookup_cpp_actor(name, "")
     }
 
+    /// Get the ActorRef of an actor managed by this Manager, including
+    /// children spawned by actors (named `"parent/child"`).
+    ///
+    /// Unlike `get_ref`, this does not look up C++ actors. Returns None if no
+    /// actor with that name is registered, or it has stopped with `stop_self`.
+    ///
+    /// # Example
+    /// ```
+    /// # fn example(mgr: &actors::Manager) {
+    /// // Wire actors together after they were created from a config file
+    /// let db = mgr.get_actor("db").expect("db is configured");
+    /// # }
+    /// ```
+    pub fn get_actor(&self, name: &str) -> Option<ActorRef> {
+        self.spawner.get_actor(name)
+    }
+
     /// Get an ActorRef by name with a sender name for C++ actors.
     ///
     /// Use this when you need to specify the sender for cross-language messaging.
     pub fn get_ref_with_sender(&self, name: &str, sender: &str) -> Option<ActorRef> {
         // Check local Rust actors first
-        if let Some(actor_ref) = self.registry.get(name) {
+        if let Some(actor_ref) = self.registry.lock().unwrap().get(name) {
             return Some(actor_ref.clone());
         }
 
@@ -227,27 +1152,373 @@ impl Manager {
         lookup_cpp_actor(name, sender)
     }
 
-    /// Get all registered actor names.
+    /// Get all registered actor names, including spawned children but not
+    /// `"$dead_letters"`.
     pub fn get_names(&self) -> Vec<String> {
-        self.registry.keys().cloned().collect()
+        let mut names: Vec<String> = self
+            .registry
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|name| name.as_str() != DEAD_LETTERS_NAME)
+            .cloned()
+            .collect();
+        names.extend(self.spawner.names());
+        names
     }
 
-    /// Start all managed actors.
+    /// Stop the actor `name` from handling messages until `resume_actor`,
+    /// e.g. for backpressure or maintenance.
     ///
-    /// Sends Start message to each actor and launches their threads.
-    pub fn init(&mut self) {
-        // Take ownership of runtimes
-        let runtimes = std::mem::take(&mut self.runtimes);
-
-        for (runtime, config) in runtimes {
-            let actor_ref = runtime.get_ref();
+    /// Takes effect after the message being handled. Messages sent meanwhile
+    /// stay in the mailbox, apart from one an idle actor may already be
+    /// receiving, so a bounded mailbox fills up and senders get
+    /// `SendError::Full`. A paused actor still handles Shutdown, so it can be
+    /// stopped (its pending messages then go to the dead letter queue), and
+    /// its watchers are still told when it stops. Returns false if no
+    /// running local actor has that name.
+    pub fn pause_actor(&self, name: &str) -> bool {
+        self.spawner.set_paused(name, true)
+    }
+
+    /// Let a paused actor handle its pending messages, in the order received,
+    /// and new ones again.
+    ///
+    /// Returns false if no running local actor has that name.
+    pub fn resume_actor(&self, name: &str) -> bool {
+        self.spawner.set_paused(name, false)
+    }
+
+    /// Remove and return the dead letters kept so far, oldest first.
+    ///
+    /// Letters are stored once the dead letter queue has handled them, so
+    /// very recent ones may not be included yet. Always empty for a Manager
+    /// not created with `new_with_dead_letter_store`.
+    pub fn take_dead_letters(&self) -> Vec<DeadLetter> {
+        self.dead_letter_store.as_ref().map_or_else(Vec::new, DeadLetterStore::take)
+    }
+
+    /// Call `f` with the actor's name, the message and the error whenever a
+    /// handler returns `Err` (see `HandlerResult`), instead of sending a
+    /// `DeadLetter` to the dead letter queue.
+    ///
+    /// Unlike a panic, an error does not stop the actor or involve its
+    /// `SupervisionStrategy`. `f` runs on the failing actor's thread and
+    /// applies to all of the Manager's actors, including children.
+    ///
+    /// # Example
+    /// ```
+    /// # fn example(mgr: &actors::Manager) {
+    /// mgr.set_message_error_handler(|actor, msg, error| {
+    ///     eprintln!("{} failed to handle {}: {}", actor, msg.type_name(), error);
+    /// });
+    /// # }
+    /// ```
+    pub fn set_message_error_handler(&self, f: impl Fn(&str, &dyn Message, HandlerError) + Send + 'static) {
+        *self.spawner.message_error_handler.lock().unwrap() = Some(Box::new(f));
+    }
+
+    /// Set how the actor `name` is supervised, replacing the strategy from
+    /// its ThreadConfig.
+    ///
+    /// `factory` creates the new instance for `SupervisionStrategy::Restart`.
+    /// Takes effect once the actor has handled the messages already in its
+    /// mailbox. Returns false if there is no local actor with that name.
+    pub fn set_supervisor<F>(&self, name: &str, strategy: SupervisionStrategy, factory: F) -> bool
+    where
+        F: Fn() -> Box<dyn Actor> + Send + 'static,
+    {
+        let actor_ref = self.registry.lock().unwrap().get(name).cloned();
+        match actor_ref.or_else(|| self.spawner.get_ref(name)) {
+            Some(actor_ref @ ActorRef::Local(_)) => {
+                let set = SetSupervisor {
+                    strategy,
+                    factory: Some(Box::new(factory)),
+                    restart_count: Arc::default(),
+                };
+                actor_ref.send(Box::new(set), None);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Replace the actor `name` with a new instance from its supervisor's
+    /// factory (see `set_supervisor`), as if it had failed, but with
+    /// `RestartReason::Explicit`.
+    ///
+    /// The old instance gets `post_stop` and `end` first. Takes effect once
+    /// the actor has handled the messages already in its mailbox, and does
+    /// not count against the strategy's restart limit. Does nothing to an
+    /// actor without a factory. Returns false if there is no local actor
+    /// with that name.
+    pub fn restart_actor(&self, name: &str) -> bool {
+        let actor_ref = self.registry.lock().unwrap().get(name).cloned();
+        match actor_ref.or_else(|| self.spawner.get_ref(name)) {
+            Some(actor_ref @ ActorRef::Local(_)) => {
+                actor_ref.send(Box::new(RestartActor), None);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Forward every dead letter to `watcher`.
+    ///
+    /// A message sent to a managed actor after it has stopped is delivered to
+    /// the dead letter queue, which sends a `DeadLetter` to each watcher.
+    /// Watchers are dropped once their own mailbox is closed.
+    pub fn subscribe_dead_letters(&self, watcher: ActorRef) {
+        self.dead_letters.send(Box::new(SubscribeDeadLetters { watcher }), None);
+    }
+
+    /// Read an actor's state from another thread.
+    ///
+    /// The actor runs `f` on its own thread in place of one message dispatch,
+    /// so no message is processed while `f` borrows the state. `name` may be
+    /// a spawned child (`"parent/child"`). Blocks until `f` has returned. Before
+    /// `init()` the state is borrowed directly.
+    ///
+    /// The actor gets to `f` after the messages already in its mailbox. Fails
+    /// with `Timeout` if `f` has not returned within `timeout`; `f` may
+    /// still run later. `Duration::MAX` waits forever.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actors::{Actor, InspectError, Manager};
+    /// # struct Counter { count: u64 }
+    /// # impl Actor for Counter {}
+    /// # fn example(mgr: &Manager) -> Result<(), InspectError> {
+    /// mgr.inspect_actor::<Counter, _>("counter", Duration::from_secs(1), |c| println!("count = {}", c.count))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn inspect_actor<A, F>(&self, name: &str, timeout: Duration, f: F) -> Result<(), InspectError>
+    where
+        A: Actor,
+        F: FnOnce(&A) + Send + 'static,
+    {
+        let (done_tx, done_rx) = channel();
+        let actor_name = name.to_string();
+        let inspect: InspectFn = Box::new(move |actor| {
+            let result = match (actor as &dyn std::any::Any).downcast_ref::<A>() {
+                Some(state) => {
+                    f(state);
+                    Ok(())
+                }
+                None => Err(InspectError::WrongType(actor_name)),
+            };
+            let _ = done_tx.send(result);
+        });
+
+        // Not started yet: the runtime is still owned here
+        let launcher = self.launcher.lock().unwrap();
+        if let Some((runtime, _)) = launcher.runtimes.iter().find(|(rt, _)| rt.name == name) {
+            runtime.inspect(Some(inspect));
+        } else {
+            // Never wait for the registry lock while holding the launcher's
+            drop(launcher);
+            if self.taken.contains_key(name) {
+                return Err(InspectError::NotRunning(name.to_string()));
+            }
+            match self.spawner.get_actor(name) {
+                Some(actor_ref) => actor_ref.send(Box::new(InspectActor { f: Some(inspect) }), None),
+                None => return Err(InspectError::NotFound(name.to_string())),
+            }
+        }
+
+        done_rx.recv_timeout(timeout).unwrap_or_else(|e| match e {
+            RecvTimeoutError::Timeout => Err(InspectError::Timeout(name.to_string())),
+            RecvTimeoutError::Disconnected => Err(InspectError::NotRunning(name.to_string())),
+        })
+    }
+
+    /// Stop an actor and take it out of the manager, e.g. to checkpoint or
+    /// modify its state.
+    ///
+    /// Fails with `TakeError::ActorBusy` if the actor is handling a message or
+    /// has messages waiting; retry once it is idle. The actor's `end` is not
+    /// called. Messages sent to it while it is taken stay in its mailbox and
+    /// are handled after `restore_actor`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::any::Any;
+    /// # use actors::{Actor, Manager, TakeError};
+    /// # struct Counter { count: u64 }
+    /// # impl Actor for Counter {}
+    /// # fn example(mgr: &mut Manager) -> Result<(), TakeError> {
+    /// let actor = mgr.take_actor("counter")?;
+    /// let mut counter = (actor as Box<dyn Any>).downcast::<Counter>().unwrap();
+    /// counter.count = 0;
+    /// mgr.restore_actor("counter", counter)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_actor(&mut self, name: &str) -> Result<Box<dyn Actor>, TakeError> {
+        // Not started yet: the runtime is still owned here
+        let mut launcher = self.launcher.lock().unwrap();
+        if let Some(index) = launcher.runtimes.iter().position(|(rt, _)| rt.name == name) {
+            let (runtime, config) = launcher.runtimes.remove(index);
+            drop(launcher);
+            return Ok(self.park(runtime, config));
+        }
+        drop(launcher);
+        if self.taken.contains_key(name) {
+            return Err(TakeError::NotRunning(name.to_string()));
+        }
+
+        let metrics = self
+            .metrics
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(actor_name, _)| actor_name == name)
+            .map(|(_, metrics)| Arc::clone(metrics));
+        let actor_ref = self.registry.lock().unwrap().get(name).cloned();
+        let (Some(metrics), Some(actor_ref)) = (metrics, actor_ref) else {
+            return Err(TakeError::NotFound(name.to_string()));
+        };
+        if metrics.is_processing() || metrics.mailbox_depth() > 0 {
+            return Err(TakeError::ActorBusy(name.to_string()));
+        }
+
+        let (reply_tx, reply_rx) = channel();
+        actor_ref.send(Box::new(TakeActor { reply: Some(reply_tx) }), None);
+        let (runtime, config) = reply_rx
+            .recv()
+            .map_err(|_| TakeError::NotRunning(name.to_string()))?;
+        Ok(self.park(runtime, config))
+    }
+
+    /// Put an actor removed by `take_actor` back and resume its thread.
+    ///
+    /// `actor` replaces the taken one and keeps its name, mailbox and refs.
+    /// Its `init` is not called again if the taken actor had started.
+    pub fn restore_actor(&mut self, name: &str, actor: Box<dyn Actor>) -> Result<(), TakeError> {
+        let (mut runtime, config) = self
+            .taken
+            .remove(name)
+            .ok_or_else(|| TakeError::NotTaken(name.to_string()))?;
+        runtime.actor = actor;
+        self.launcher.lock().unwrap().launch(runtime, config);
+        Ok(())
+    }
+
+    /// Keep a taken actor's runtime for `restore_actor`, returning the actor
+    fn park(&mut self, mut runtime: ActorRuntime, config: ThreadConfig) -> Box<dyn Actor> {
+        let actor = std::mem::replace(&mut runtime.actor, Box::new(TakenActor));
+        self.taken.insert(runtime.name.clone(), (runtime, config));
+        actor
+    }
+
+    /// Mailbox counters of every managed actor, including spawned children,
+    /// by name.
+    pub fn all_mailbox_stats(&self) -> HashMap<String, MailboxStats> {
+        self.metrics
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, metrics)| (name.clone(), metrics.mailbox_stats()))
+            .collect()
+    }
+
+    /// Custom counters of actor `name` (see `ActorContext::metrics_counter`),
+    /// by counter name. Empty if there is no such actor.
+    pub fn actor_custom_metrics(&self, name: &str) -> HashMap<String, u64> {
+        self.metrics
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(actor_name, _)| actor_name == name)
+            .map(|(_, metrics)| metrics.custom_metrics())
+            .unwrap_or_default()
+    }
+
+    /// Serve Prometheus metrics for all managed actors on `GET /metrics`.
+    ///
+    /// Binds `0.0.0.0:port` (0 picks a free port, see `MetricsServerHandle::port`)
+    /// and serves from a background thread until the handle is stopped or dropped.
+    /// Actors managed after the server starts are included.
+    pub fn start_metrics_server(&self, port: u16) -> std::io::Result<MetricsServerHandle> {
+        metrics::start_server(port, Arc::clone(&self.metrics))
+    }
+
+    /// Serve debugging commands for the managed actors on `addr` (e.g.
+    /// "127.0.0.1:9000"; port 0 picks a free port, see
+    /// `ManagementHandle::local_addr`).
+    ///
+    /// Clients send newline-delimited JSON commands over TCP and get one JSON
+    /// reply line each:
+    /// - `{"command": "list_actors"}` lists every actor with its status and mailbox depth
+    /// - `{"command": "inspect", "actor": "ping"}` returns the actor's `Actor::inspect_state`
+    /// - `{"command": "send", "actor": "ping", "message_type": "Ping", "message": {...}}`
+    ///   sends a message, which must be registered with `register_remote_message`
+    ///
+    /// There is no authentication, so `addr` must be a loopback address;
+    /// others fail with `InvalidInput`. Serves from background threads until
+    /// the handle is stopped or dropped.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # fn example(mgr: &actors::Manager) -> std::io::Result<()> {
+    /// let _management = mgr.start_management_server("127.0.0.1:9000")?;
+    /// // $ echo '{"command": "inspect", "actor": "ping"}' | nc 127.0.0.1 9000
+    /// // {"state":{"count":3}}
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn start_management_server(&self, addr: &str) -> std::io::Result<ManagementHandle> {
+        let context = ManagementContext {
+            spawner: self.spawner.clone(),
+            metrics: Arc::clone(&self.metrics),
+        };
+        management::start_server(addr, context)
+    }
+
+    /// Write every message the managed actors handle to `path`, e.g. to
+    /// reproduce an intermittent bug with `replay_from_file`.
+    ///
+    /// Each record holds the actor name, the message as JSON and when it was
+    /// handled. Only message types registered with `register_remote_message`
+    /// are recorded. Recording stops when the handle is stopped or dropped;
+    /// starting another recording replaces this one.
+    pub fn start_message_recording(&self, path: PathBuf) -> io::Result<RecordingHandle> {
+        self.recorder.start(&path)
+    }
+
+    /// Send the messages recorded by `start_message_recording` to the actors
+    /// with the recorded names, at `speed` times the recorded pace (2.0 is
+    /// twice as fast).
+    ///
+    /// Blocks until the whole recording has been sent and returns the number
+    /// of messages sent. Messages are sent without a sender, so replies are
+    /// dropped. Records for unknown actors or message types are skipped.
+    /// Fails if `speed` is NaN or not above `f64::EPSILON`.
+    pub fn replay_from_file(&self, path: PathBuf, speed: f64) -> io::Result<usize> {
+        recording::replay(&path, speed, |name| self.get_ref(name))
+    }
+
+    /// Start all managed actors.
+    ///
+    /// Sends Start message to each actor and launches their threads.
+    pub fn init(&mut self) {
+        let mut launcher = self.launcher.lock().unwrap();
+        launcher.started = true;
+
+        // Take ownership of runtimes
+        let runtimes = std::mem::take(&mut launcher.runtimes);
+
+        for (runtime, config) in runtimes {
+            let actor_ref = runtime.get_ref();
 
             // Send Start message
             actor_ref.send(Box::new(Start), None);
 
             // Spawn thread with config
             let handle = spawn_with_config(runtime, config);
-            self.threads.push(handle);
+            launcher.threads.push(handle);
         }
     }
 
@@ -262,19 +1533,27 @@ impl Manager {
 
     /// Signal all actors to shut down.
     ///
-    /// Sends Shutdown message to each actor.
+    /// Sends Shutdown message to each actor, and to the dead letter queue last.
+    /// Actors that stopped themselves (see `ActorContext::stop_self`) are skipped.
     pub fn shutdown(&self) {
-        for actor_ref in &self.actor_refs {
-            actor_ref.send(Box::new(Shutdown), None);
+        let registry = self.registry.lock().unwrap().clone();
+        let actor_refs = self.launcher.lock().unwrap().actor_refs.clone();
+        for actor_ref in &actor_refs {
+            if registry.contains_key(actor_ref.name()) {
+                actor_ref.send(Box::new(Shutdown), None);
+            }
         }
+        self.dead_letters_thread.shutdown(&self.dead_letters);
     }
 
-    /// Wait for all actor threads to finish.
+    /// Wait for all actor threads to finish, then stop the timer thread.
     pub fn wait(&mut self) {
-        let threads = std::mem::take(&mut self.threads);
+        let threads = std::mem::take(&mut self.launcher.lock().unwrap().threads);
         for handle in threads {
             let _ = handle.join();
         }
+        self.dead_letters_thread.join();
+        self.timers.stop();
     }
 
     /// Shutdown and wait for all actors.
@@ -282,6 +1561,91 @@ impl Manager {
         self.shutdown();
         self.wait();
     }
+
+    /// Shut down without losing messages: each actor handles what is already
+    /// in its mailbox before it stops.
+    ///
+    /// Every actor, including spawned children, is sent a `Drain` signal.
+    /// Messages sent to an actor after that are rejected to the dead letter
+    /// queue. An actor that has not handled everything sent before its
+    /// signal within `per_actor_timeout` of it is stopped anyway, after the
+    /// message it is handling, and the messages it had not handled go to the
+    /// dead letter queue too. Returns when all actors, and then the dead
+    /// letter queue, have stopped. An actor still in a handler another
+    /// `per_actor_timeout` after it was told to stop is not waited for: its
+    /// thread is left to finish on its own.
+    ///
+    /// Actors taken with `take_actor` are skipped, as they have no thread to
+    /// drain them. Before `init`, no actor is running, so this is `end`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::time::Duration;
+    /// # fn example(mgr: &mut actors::Manager) {
+    /// mgr.drain_and_shutdown(Duration::from_secs(5));
+    /// let unhandled = mgr.take_dead_letters();
+    /// # }
+    /// ```
+    pub fn drain_and_shutdown(&mut self, per_actor_timeout: Duration) {
+        if !self.launcher.lock().unwrap().started {
+            self.end();
+            return;
+        }
+        // Children too, so they reject late messages like their parents
+        let draining: Vec<(ActorRef, Receiver<()>, Option<Instant>)> = self
+            .spawner
+            .all_refs()
+            .into_iter()
+            .filter(|actor_ref| !self.taken.contains_key(actor_ref.name()))
+            .map(|actor_ref| {
+                let (done_tx, done_rx) = channel();
+                actor_ref.send(Box::new(Drain { done: done_tx }), None);
+                // None if the timeout is too long to represent: wait forever
+                let deadline = Instant::now().checked_add(per_actor_timeout);
+                (actor_ref, done_rx, deadline)
+            })
+            .collect();
+
+        for (actor_ref, done, deadline) in &draining {
+            let drained = match deadline {
+                Some(deadline) => done.recv_timeout(deadline.saturating_duration_since(Instant::now())).is_ok(),
+                None => done.recv().is_ok(),
+            };
+            if !drained {
+                let running = self.launcher.lock().unwrap().running_flags.get(actor_ref.name()).cloned();
+                match running {
+                    Some(running) => *running.lock().unwrap() = false,
+                    None => self.spawner.stop_child_early(actor_ref.name()),
+                }
+            }
+        }
+        // Each actor stops its children before it stops
+        let registry = self.registry.lock().unwrap().clone();
+        for (actor_ref, _, _) in &draining {
+            if registry.contains_key(actor_ref.name()) {
+                actor_ref.send(Box::new(Shutdown), None);
+            }
+        }
+
+        // Stop the dead letter queue last, so it gets every rejected message
+        let deadline = Instant::now().checked_add(per_actor_timeout);
+        let mut stuck = false;
+        for (actor_ref, _, _) in &draining {
+            while actor_ref.is_alive() {
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    stuck = true;
+                    break;
+                }
+                thread::sleep(DRAIN_POLL_INTERVAL);
+            }
+        }
+        self.dead_letters_thread.shutdown(&self.dead_letters);
+        if stuck {
+            // Joining the thread of an actor stuck in a handler would never return
+            self.launcher.lock().unwrap().threads.retain(|thread| thread.is_finished());
+        }
+        self.wait();
+    }
 }
 
 impl Default for Manager {
@@ -292,7 +1656,14 @@ impl Default for Manager {
 
 /// Spawn an actor thread with the given configuration
 fn spawn_with_config(mut runtime: ActorRuntime, config: ThreadConfig) -> JoinHandle<()> {
-    thread::spawn(move || {
+    let thread_name = if config.name_prefix.is_empty() {
+        runtime.name.clone()
+    } else {
+        format!("{}-{}", config.name_prefix, runtime.name)
+    };
+    let builder = thread::Builder::new().name(thread_name).stack_size(config.stack_size);
+    // Like thread::spawn, panic if the OS cannot create the thread
+    builder.spawn(move || {
         // Set CPU affinity if specified
         if !config.affinity.is_empty() {
             set_affinity(&config.affinity);
@@ -302,10 +1673,27 @@ fn spawn_with_config(mut runtime: ActorRuntime, config: ThreadConfig) -> JoinHan
         if config.priority > 0 {
             set_priority(config.priority, config.sched_policy);
         }
+        if config.thread_priority != 0 {
+            set_nice(config.thread_priority);
+        }
 
-        // Run the actor
-        runtime.run();
+        // Run the actor; watchers are told when it stops, even by a panic
+        if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(|| runtime.run())) {
+            runtime.mark_terminated(TerminationReason::Panicked);
+            runtime.release();
+            panic::resume_unwind(panic);
+        }
+        match runtime.take_reply.take() {
+            Some(reply) => {
+                let _ = reply.send((runtime, config));
+            }
+            None => {
+                runtime.mark_terminated(runtime.termination);
+                runtime.release();
+            }
+        }
     })
+    .expect("failed to spawn actor thread")
 }
 
 /// Set CPU affinity for the current thread
@@ -331,10 +1719,20 @@ fn set_priority(priority: i32, policy: i32) {
     }
 }
 
+/// Set the nice value of the current thread
+fn set_nice(nice: i32) {
+    // On Linux the nice value is per thread, and 0 means the calling thread
+    #[cfg(target_os = "linux")]
+    unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, 0, nice);
+    }
+}
+
 #[cfg(test)]
 #[allow(dead_code)]
 mod tests {
     use super::*;
+    use crate::actor::{ActorContext, ActorError, SendError};
     use std::sync::atomic::{AtomicI32, Ordering};
 
     struct CountingActor {
@@ -366,14 +1764,1725 @@ mod tests {
     }
 
     #[test]
-    fn test_thread_config() {
-        let config = ThreadConfig::with_affinity(vec![0, 1]);
-        assert_eq!(config.affinity, vec![0, 1]);
-        assert_eq!(config.priority, 0);
+    fn test_inspect_actor() {
+        struct OtherActor;
+        impl Actor for OtherActor {}
 
-        let config2 = ThreadConfig::with_priority(50, libc::SCHED_FIFO);
-        assert_eq!(config2.priority, 50);
-        assert_eq!(config2.sched_policy, libc::SCHED_FIFO);
+        let count = Arc::new(AtomicI32::new(0));
+        let mut mgr = Manager::new();
+        mgr.manage("counter", Box::new(CountingActor { count: Arc::clone(&count) }), Default::default());
+
+        // Before init the state is borrowed in place
+        let seen = Arc::new(AtomicI32::new(-1));
+        let seen_clone = Arc::clone(&seen);
+        mgr.inspect_actor::<CountingActor, _>("counter", Duration::from_secs(5), move |a| {
+            seen_clone.store(a.count.load(Ordering::SeqCst), Ordering::SeqCst);
+        })
+        .unwrap();
+        assert_eq!(seen.load(Ordering::SeqCst), 0);
+
+        mgr.init();
+        let seen_clone = Arc::clone(&seen);
+        mgr.inspect_actor::<CountingActor, _>("counter", Duration::from_secs(5), move |a| {
+            seen_clone.store(a.count.load(Ordering::SeqCst), Ordering::SeqCst);
+        })
+        .unwrap();
+        assert_eq!(seen.load(Ordering::SeqCst), 1);
+
+        assert_eq!(
+            mgr.inspect_actor::<OtherActor, _>("counter", Duration::from_secs(5), |_| {}),
+            Err(InspectError::WrongType("counter".to_string()))
+        );
+        assert_eq!(
+            mgr.inspect_actor::<CountingActor, _>("missing", Duration::from_secs(5), |_| {}),
+            Err(InspectError::NotFound("missing".to_string()))
+        );
+
+        mgr.end();
+        assert_eq!(
+            mgr.inspect_actor::<CountingActor, _>("counter", Duration::from_secs(5), |_| {}),
+            Err(InspectError::NotRunning("counter".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_inspect_child_and_stuck_actor() {
+        use crate::{define_message, ActorContext};
+
+        struct Block;
+        define_message!(Block);
+
+        struct Leaf {
+            seen: usize,
+        }
+        impl Actor for Leaf {}
+        struct Parent;
+        impl Actor for Parent {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                if msg.as_any().is::<Start>() {
+                    ctx.create_child("child", Box::new(Leaf { seen: 7 })).unwrap();
+                }
+            }
+        }
+        struct Stuck {
+            release: Receiver<()>,
+        }
+        impl Actor for Stuck {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if msg.as_any().is::<Block>() {
+                    let _ = self.release.recv();
+                }
+            }
+        }
+
+        let (release_tx, release_rx) = channel();
+        let mut mgr = Manager::new();
+        mgr.manage("parent", Box::new(Parent), Default::default());
+        let stuck = mgr.manage("stuck", Box::new(Stuck { release: release_rx }), Default::default());
+        mgr.init();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while mgr.get_actor("parent/child").is_none() {
+            assert!(Instant::now() < deadline, "child was never created");
+            thread::sleep(Duration::from_millis(1));
+        }
+        let seen = Arc::new(AtomicI32::new(0));
+        let seen_clone = Arc::clone(&seen);
+        mgr.inspect_actor::<Leaf, _>("parent/child", Duration::from_secs(5), move |leaf| {
+            seen_clone.store(leaf.seen as i32, Ordering::SeqCst)
+        })
+        .unwrap();
+        assert_eq!(seen.load(Ordering::SeqCst), 7);
+
+        // An actor stuck in a handler never gets to the inspection
+        stuck.send(Box::new(Block), None);
+        assert_eq!(
+            mgr.inspect_actor::<Stuck, _>("stuck", Duration::from_millis(50), |_| {}),
+            Err(InspectError::Timeout("stuck".to_string()))
+        );
+        release_tx.send(()).unwrap();
+        mgr.end();
+    }
+
+    #[test]
+    fn test_spawn_child_stops_depth_first() {
+        use crate::{ActorContext, Message};
+
+        // Spawns one child on Start while depth > 0; records when it ends
+        struct Node {
+            depth: u32,
+            name: String,
+            ended: Arc<Mutex<Vec<String>>>,
+        }
+        impl Actor for Node {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                if msg.as_any().is::<Start>() {
+                    self.name = ctx.self_ref().unwrap().name().to_string();
+                    if self.depth > 0 {
+                        let child = Node {
+                            depth: self.depth - 1,
+                            name: String::new(),
+                            ended: Arc::clone(&self.ended),
+                        };
+                        ctx.spawn_child("child", Box::new(child), ThreadConfig::default()).unwrap();
+                    }
+                }
+            }
+            fn end(&mut self) {
+                self.ended.lock().unwrap().push(self.name.clone());
+            }
+        }
+
+        let ended = Arc::new(Mutex::new(Vec::new()));
+        let mut mgr = Manager::new();
+        let root = Node { depth: 2, name: String::new(), ended: Arc::clone(&ended) };
+        mgr.manage("root", Box::new(root), Default::default());
+        mgr.init();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while mgr.get_ref("root/child/child").is_none() {
+            assert!(Instant::now() < deadline, "grandchild was never created");
+            thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert_eq!(mgr.get_names().len(), 3);
+
+        mgr.end();
+        assert_eq!(*ended.lock().unwrap(), vec!["root/child/child", "root/child", "root"]);
+    }
+
+    #[test]
+    fn test_create_child_is_managed() {
+        use crate::actor::SpawnError;
+        use crate::{ActorContext, Message};
+
+        struct Parent {
+            count: Arc<AtomicI32>,
+            duplicate: Arc<Mutex<Option<SpawnError>>>,
+        }
+        impl Actor for Parent {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                if msg.as_any().is::<Start>() {
+                    let count = Arc::clone(&self.count);
+                    ctx.create_child("worker-1", Box::new(CountingActor { count })).unwrap();
+                    let count = Arc::clone(&self.count);
+                    let duplicate = ctx.create_child("worker-1", Box::new(CountingActor { count }));
+                    *self.duplicate.lock().unwrap() = duplicate.err();
+                }
+            }
+        }
+
+        let count = Arc::new(AtomicI32::new(0));
+        let duplicate = Arc::new(Mutex::new(None));
+        let mut mgr = Manager::new();
+        let parent = Parent { count: Arc::clone(&count), duplicate: Arc::clone(&duplicate) };
+        mgr.manage("parent", Box::new(parent), Default::default());
+        mgr.init();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let worker = loop {
+            if let Some(worker) = mgr.get_actor("parent/worker-1") {
+                break worker;
+            }
+            assert!(Instant::now() < deadline, "child was never created");
+            thread::sleep(Duration::from_millis(1));
+        };
+        while duplicate.lock().unwrap().is_none() {
+            assert!(Instant::now() < deadline, "second child was never attempted");
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(*duplicate.lock().unwrap(), Some(SpawnError::NameTaken("parent/worker-1".to_string())));
+        let orphan = Box::new(CountingActor { count: Arc::clone(&count) });
+        let unmanaged = ActorContext::new().create_child("worker-2", orphan);
+        assert_eq!(unmanaged.err(), Some(SpawnError::NotManaged));
+        let mut names = mgr.get_names();
+        names.sort();
+        assert_eq!(names, vec!["parent".to_string(), "parent/worker-1".to_string()]);
+        assert!(mgr.pause_actor("parent/worker-1"));
+        assert!(mgr.resume_actor("parent/worker-1"));
+
+        mgr.end();
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+        assert!(!worker.is_alive());
+        assert!(mgr.get_actor("parent/worker-1").is_none());
+    }
+
+    #[test]
+    fn test_spawn_temporary() {
+        use crate::actor::SpawnError;
+        use crate::{ActorContext, Message};
+
+        struct Query(i32);
+        crate::define_message!(Query);
+
+        // Doubles a Query if `replies`, otherwise ignores it
+        struct Worker {
+            replies: bool,
+        }
+        impl Actor for Worker {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                if let (Some(Query(n)), true) = (msg.as_any().downcast_ref::<Query>(), self.replies) {
+                    ctx.reply(Box::new(Query(n * 2)));
+                }
+            }
+        }
+
+        // Asks a temporary worker on Start and records the outcome
+        struct Client {
+            replies: bool,
+            outcome: Arc<Mutex<Option<Result<i32, SpawnError>>>>,
+        }
+        impl Actor for Client {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                if msg.as_any().is::<Start>() {
+                    let worker = Box::new(Worker { replies: self.replies });
+                    let reply = ctx.spawn_temporary(worker, Box::new(Query(21)), Duration::from_secs(5));
+                    let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+                    let reply = rt.block_on(reply).map(|m| m.as_any().downcast_ref::<Query>().unwrap().0);
+                    *self.outcome.lock().unwrap() = Some(reply);
+                }
+            }
+        }
+
+        for (replies, expected) in [(true, Ok(42)), (false, Err(SpawnError::NoReply))] {
+            let outcome = Arc::new(Mutex::new(None));
+            let mut mgr = Manager::new();
+            mgr.manage("client", Box::new(Client { replies, outcome: Arc::clone(&outcome) }), Default::default());
+            mgr.init();
+
+            // The temporary actor is unregistered after the outcome
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while outcome.lock().unwrap().is_none() || mgr.get_names().len() > 1 {
+                assert!(Instant::now() < deadline, "temporary actor never finished");
+                thread::sleep(Duration::from_millis(1));
+            }
+            assert_eq!(outcome.lock().unwrap().take(), Some(expected));
+            mgr.end();
+        }
+    }
+
+    #[test]
+    fn test_manage_router() {
+        use crate::Message;
+
+        struct Job;
+        crate::define_message!(Job);
+
+        struct Worker(Arc<AtomicI32>);
+        impl Actor for Worker {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut crate::ActorContext) {
+                if msg.as_any().is::<Job>() {
+                    self.0.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        }
+
+        let counts = [Arc::new(AtomicI32::new(0)), Arc::new(AtomicI32::new(0))];
+        let mut mgr = Manager::new();
+        let worker1 = mgr.manage("worker1", Box::new(Worker(Arc::clone(&counts[0]))), Default::default());
+        let worker2 = mgr.manage("worker2", Box::new(Worker(Arc::clone(&counts[1]))), Default::default());
+        let router = mgr.manage_router("workers", vec![worker1.clone(), worker2]);
+        mgr.init();
+
+        for _ in 0..4 {
+            router.send(Box::new(Job), None);
+        }
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while counts.iter().map(|c| c.load(Ordering::SeqCst)).sum::<i32>() < 4 {
+            assert!(Instant::now() < deadline, "router did not deliver the first jobs");
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(counts[0].load(Ordering::SeqCst), 2);
+
+        // A stopped routee leaves the rotation
+        worker1.send(Box::new(Shutdown), None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while worker1.is_alive() {
+            assert!(Instant::now() < deadline, "routee did not stop");
+            thread::sleep(Duration::from_millis(1));
+        }
+        for _ in 0..4 {
+            router.send(Box::new(Job), None);
+        }
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while counts[1].load(Ordering::SeqCst) < 6 {
+            assert!(Instant::now() < deadline, "router did not deliver the later jobs");
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(counts[0].load(Ordering::SeqCst), 2);
+        mgr.end();
+    }
+
+    #[test]
+    fn test_schedule_timers() {
+        use crate::timer::TimerHandle;
+        use crate::{ActorContext, Message};
+
+        struct Tick;
+        crate::define_message!(Tick);
+        struct Done;
+        crate::define_message!(Done);
+
+        // Counts ticks until Done, then drops the periodic timer
+        struct Ticker {
+            ticks: Arc<AtomicI32>,
+            done: Arc<AtomicBool>,
+            periodic: Option<TimerHandle>,
+            once: Option<TimerHandle>,
+        }
+        impl Actor for Ticker {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                if msg.as_any().is::<Start>() {
+                    self.periodic = Some(ctx.schedule_periodic(Duration::from_millis(5), || Box::new(Tick)));
+                    self.once = Some(ctx.schedule_once(Duration::from_millis(50), Box::new(Done)));
+                } else if msg.as_any().is::<Tick>() {
+                    self.ticks.fetch_add(1, Ordering::SeqCst);
+                } else if msg.as_any().is::<Done>() {
+                    self.periodic = None;
+                    self.done.store(true, Ordering::SeqCst);
+                }
+            }
+        }
+
+        let ticks = Arc::new(AtomicI32::new(0));
+        let done = Arc::new(AtomicBool::new(false));
+        let mut mgr = Manager::new();
+        let ticker = Ticker { ticks: Arc::clone(&ticks), done: Arc::clone(&done), periodic: None, once: None };
+        mgr.manage("ticker", Box::new(ticker), Default::default());
+        mgr.init();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done.load(Ordering::SeqCst) {
+            assert!(Instant::now() < deadline, "timer never finished");
+            thread::sleep(Duration::from_millis(1));
+        }
+        // At most one tick sent before the timer was dropped is still to come
+        thread::sleep(Duration::from_millis(20));
+        let after_done = ticks.load(Ordering::SeqCst);
+        assert!(after_done >= 3);
+        thread::sleep(Duration::from_millis(30));
+        assert_eq!(ticks.load(Ordering::SeqCst), after_done);
+        mgr.end();
+    }
+
+    #[test]
+    fn test_take_and_restore_actor() {
+        use std::time::Duration;
+
+        struct Slow(Duration);
+        crate::define_message!(Slow);
+
+        struct Tally {
+            inits: Arc<AtomicI32>,
+            handled: u32,
+        }
+        impl Actor for Tally {
+            fn init(&mut self) {
+                self.inits.fetch_add(1, Ordering::SeqCst);
+            }
+            fn process_message(&mut self, msg: &dyn crate::Message, _ctx: &mut crate::ActorContext) {
+                if let Some(Slow(delay)) = msg.as_any().downcast_ref::<Slow>() {
+                    thread::sleep(*delay);
+                }
+                self.handled += 1;
+            }
+        }
+
+        let inits = Arc::new(AtomicI32::new(0));
+        let mut mgr = Manager::new();
+        let tally = mgr.manage("tally", Box::new(Tally { inits: Arc::clone(&inits), handled: 0 }), Default::default());
+        mgr.init();
+
+        tally.send(Box::new(Slow(Duration::from_millis(100))), None);
+        assert_eq!(mgr.take_actor("tally").err(), Some(TakeError::ActorBusy("tally".to_string())));
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let actor = loop {
+            assert!(Instant::now() < deadline, "actor stayed busy");
+            match mgr.take_actor("tally") {
+                Ok(actor) => break actor,
+                Err(TakeError::ActorBusy(_)) => thread::sleep(Duration::from_millis(1)),
+                Err(e) => panic!("{}", e),
+            }
+        };
+        let actor: Box<dyn std::any::Any> = actor;
+        let mut state = actor.downcast::<Tally>().unwrap();
+        assert_eq!(state.handled, 2);
+        state.handled = 10;
+
+        assert_eq!(mgr.take_actor("tally").err(), Some(TakeError::NotRunning("tally".to_string())));
+        assert_eq!(mgr.take_actor("missing").err(), Some(TakeError::NotFound("missing".to_string())));
+
+        // Queued while taken, handled once restored
+        tally.send(Box::new(Start), None);
+        mgr.restore_actor("tally", state).unwrap();
+        assert_eq!(mgr.restore_actor("tally", Box::new(TakenActor)), Err(TakeError::NotTaken("tally".to_string())));
+
+        let handled = Arc::new(AtomicI32::new(0));
+        let handled_clone = Arc::clone(&handled);
+        mgr.inspect_actor::<Tally, _>("tally", Duration::from_secs(5), move |t| {
+            handled_clone.store(t.handled as i32, Ordering::SeqCst)
+        })
+        .unwrap();
+        assert_eq!(handled.load(Ordering::SeqCst), 11);
+
+        mgr.end();
+        assert_eq!(inits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_actor_builder() {
+        let count = Arc::new(AtomicI32::new(0));
+        let mut mgr = Manager::new();
+        let handle: ActorHandle<CountingActor> = mgr
+            .builder::<CountingActor>("counter")
+            .with_config(ThreadConfig::with_affinity(vec![]))
+            .build(CountingActor { count: Arc::clone(&count) });
+
+        assert_eq!(handle.name(), "counter");
+        assert!(mgr.get_ref("counter").is_some());
+        handle.send(Start);
+
+        mgr.init();
+        mgr.end();
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_dead_letters() {
+        use crate::dead_letters::DeadLetter;
+
+        struct DummyActor;
+        impl Actor for DummyActor {}
+
+        struct Watcher {
+            letters: Arc<Mutex<Vec<DeadLetter>>>,
+        }
+        impl Actor for Watcher {
+            fn process_message(&mut self, msg: &dyn crate::Message, _ctx: &mut crate::ActorContext) {
+                if let Some(letter) = msg.as_any().downcast_ref::<DeadLetter>() {
+                    self.letters.lock().unwrap().push(letter.clone());
+                }
+            }
+        }
+
+        let letters = Arc::new(Mutex::new(Vec::new()));
+        let mut mgr = Manager::new();
+        assert!(mgr.get_ref(DEAD_LETTERS_NAME).is_some());
+        let victim = mgr.manage("victim", Box::new(DummyActor), Default::default());
+        let watcher = mgr.manage("watcher", Box::new(Watcher { letters: Arc::clone(&letters) }), Default::default());
+        mgr.subscribe_dead_letters(watcher);
+        mgr.init();
+
+        // Stop the victim, then send until its mailbox is closed
+        victim.send(Box::new(Shutdown), None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while victim.try_send(Box::new(Start), None).is_ok() {
+            assert!(Instant::now() < deadline, "victim did not stop");
+            thread::sleep(std::time::Duration::from_millis(1));
+        }
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while letters.lock().unwrap().is_empty() {
+            assert!(Instant::now() < deadline, "no dead letter arrived");
+            thread::sleep(std::time::Duration::from_millis(1));
+        }
+        mgr.end();
+
+        let letters = letters.lock().unwrap();
+        assert_eq!(letters[0].target, "victim");
+        assert!(letters[0].message_type.ends_with("::Start"));
+    }
+
+    #[test]
+    fn test_dead_letter_store() {
+        use crate::messages::Reject;
+        use crate::serialization::register_remote_message;
+
+        struct DummyActor;
+        impl Actor for DummyActor {}
+
+        register_remote_message::<Reject>("Reject");
+
+        let mut mgr = Manager::new_with_dead_letter_store(10);
+        let victim = mgr.manage("victim", Box::new(DummyActor), Default::default());
+        let sender = mgr.manage("sender", Box::new(DummyActor), Default::default());
+        mgr.init();
+
+        victim.send(Box::new(Shutdown), None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while victim.is_alive() {
+            assert!(Instant::now() < deadline, "victim did not stop");
+            thread::sleep(Duration::from_millis(1));
+        }
+        victim.send(Box::new(Reject::new("Ping", "busy", "victim")), Some(sender));
+
+        let mut letters = mgr.take_dead_letters();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while letters.is_empty() {
+            assert!(Instant::now() < deadline, "no dead letter arrived");
+            thread::sleep(Duration::from_millis(1));
+            letters = mgr.take_dead_letters();
+        }
+        assert!(mgr.dead_letters_thread.thread.lock().unwrap().is_some());
+        mgr.end();
+
+        assert_eq!(letters[0].target, "victim");
+        assert_eq!(letters[0].sender.as_deref(), Some("sender"));
+        let payload: serde_json::Value = serde_json::from_slice(letters[0].payload.as_ref().unwrap()).unwrap();
+        assert_eq!(payload["reason"], "busy");
+
+        // The queue's thread only starts with its first message
+        let idle = Manager::new();
+        assert!(idle.take_dead_letters().is_empty());
+        assert!(idle.dead_letters_thread.thread.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_handler_timeout() {
+        use crate::dead_letters::DeadLetter;
+        use crate::{define_message, ActorContext, Message};
+
+        struct Ping;
+        define_message!(Ping);
+        struct Block;
+        define_message!(Block);
+
+        struct BlockingActor {
+            pings: Arc<AtomicI32>,
+        }
+        impl Actor for BlockingActor {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if msg.as_any().is::<Ping>() {
+                    self.pings.fetch_add(1, Ordering::SeqCst);
+                } else if msg.as_any().is::<Block>() {
+                    thread::sleep(Duration::from_millis(500));
+                }
+            }
+        }
+
+        struct Watcher {
+            letters: Arc<Mutex<Vec<DeadLetter>>>,
+        }
+        impl Actor for Watcher {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if let Some(letter) = msg.as_any().downcast_ref::<DeadLetter>() {
+                    self.letters.lock().unwrap().push(letter.clone());
+                }
+            }
+        }
+
+        let pings = Arc::new(AtomicI32::new(0));
+        let letters = Arc::new(Mutex::new(Vec::new()));
+        let mut mgr = Manager::new().with_default_handler_timeout(Duration::from_millis(50));
+        let actor = mgr.manage("blocking", Box::new(BlockingActor { pings: Arc::clone(&pings) }), Default::default());
+        let watcher = mgr.manage("watcher", Box::new(Watcher { letters: Arc::clone(&letters) }), Default::default());
+        mgr.subscribe_dead_letters(watcher);
+        mgr.init();
+
+        actor.send(Box::new(Ping), None);
+        actor.send(Box::new(Block), None);
+        actor.send(Box::new(Ping), None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while letters.lock().unwrap().len() < 2 {
+            assert!(Instant::now() < deadline, "timed-out messages never became dead letters");
+            thread::sleep(Duration::from_millis(1));
+        }
+        mgr.end();
+
+        // The blocked message and the one queued behind it are dead letters
+        let letters = letters.lock().unwrap();
+        assert!(letters[0].message_type.ends_with("::Block"));
+        assert_eq!(letters[0].reason, "handler timed out");
+        assert!(letters[1].message_type.ends_with("::Ping"));
+        assert_eq!(letters[1].reason, "actor stopped");
+        assert_eq!(pings.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_supervisor_restarts_then_stops() {
+        use crate::dead_letters::DeadLetter;
+        use crate::{define_message, ActorContext, Message};
+
+        struct Crash;
+        define_message!(Crash);
+        struct Ping;
+        define_message!(Ping);
+
+        #[derive(Clone)]
+        struct Crashy {
+            starts: Arc<AtomicI32>,
+            pings: Arc<AtomicI32>,
+        }
+        impl Actor for Crashy {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if msg.as_any().is::<Start>() {
+                    self.starts.fetch_add(1, Ordering::SeqCst);
+                } else if msg.as_any().is::<Ping>() {
+                    self.pings.fetch_add(1, Ordering::SeqCst);
+                } else if msg.as_any().is::<Crash>() {
+                    panic!("boom");
+                }
+            }
+        }
+
+        struct Watcher {
+            letters: Arc<Mutex<Vec<DeadLetter>>>,
+        }
+        impl Actor for Watcher {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if let Some(letter) = msg.as_any().downcast_ref::<DeadLetter>() {
+                    self.letters.lock().unwrap().push(letter.clone());
+                }
+            }
+        }
+
+        let starts = Arc::new(AtomicI32::new(0));
+        let pings = Arc::new(AtomicI32::new(0));
+        let crashy = Crashy {
+            starts: Arc::clone(&starts),
+            pings: Arc::clone(&pings),
+        };
+        let letters = Arc::new(Mutex::new(Vec::new()));
+        let mut mgr = Manager::new();
+        let actor = mgr.manage("crashy", Box::new(crashy.clone()), Default::default());
+        let watcher = mgr.manage("watcher", Box::new(Watcher { letters: Arc::clone(&letters) }), Default::default());
+        mgr.subscribe_dead_letters(watcher);
+        let strategy = SupervisionStrategy::Restart {
+            max_retries: 1,
+            window: Duration::from_secs(60),
+        };
+        let factory = crashy.clone();
+        assert!(mgr.set_supervisor("crashy", strategy, move || Box::new(factory.clone())));
+        assert!(!mgr.set_supervisor("missing", strategy, move || Box::new(crashy.clone())));
+        mgr.init();
+
+        // Restarted once, then stopped on the second failure
+        for msg in [Box::new(Crash) as Box<dyn Message>, Box::new(Ping), Box::new(Crash), Box::new(Ping)] {
+            actor.send(msg, None);
+        }
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while letters.lock().unwrap().len() < 3 {
+            assert!(Instant::now() < deadline, "dead letters never arrived");
+            thread::sleep(Duration::from_millis(1));
+        }
+        mgr.end();
+
+        let letters = letters.lock().unwrap();
+        assert_eq!(letters[0].reason, "handler panicked: boom");
+        assert!(letters[1].message_type.ends_with("::Crash"));
+        assert_eq!(letters[2].reason, "actor stopped");
+        assert_eq!(starts.load(Ordering::SeqCst), 2);
+        assert_eq!(pings.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_on_restart() {
+        use crate::supervision::RestartReason;
+        use crate::{define_message, ActorContext, Message};
+
+        struct Crash;
+        define_message!(Crash);
+
+        #[derive(Clone)]
+        struct Restartable {
+            reasons: Arc<Mutex<Vec<RestartReason>>>,
+        }
+        impl Actor for Restartable {
+            fn on_restart(&mut self, reason: &RestartReason, _ctx: &mut ActorContext) {
+                self.reasons.lock().unwrap().push(reason.clone());
+            }
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if msg.as_any().is::<Crash>() {
+                    panic!("boom");
+                }
+            }
+        }
+
+        let reasons = Arc::new(Mutex::new(Vec::new()));
+        let actor = Restartable { reasons: Arc::clone(&reasons) };
+        let mut mgr = Manager::new();
+        let actor_ref = mgr.manage("restartable", Box::new(actor.clone()), Default::default());
+        let strategy = SupervisionStrategy::Restart { max_retries: 1, window: Duration::from_secs(60) };
+        mgr.set_supervisor("restartable", strategy, move || Box::new(actor.clone()));
+        assert!(!mgr.restart_actor("missing"));
+        mgr.init();
+
+        // The explicit restart does not use up the one restart after a failure
+        assert!(mgr.restart_actor("restartable"));
+        actor_ref.send(Box::new(Crash), None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while reasons.lock().unwrap().len() < 2 {
+            assert!(Instant::now() < deadline, "restarts never happened");
+            thread::sleep(Duration::from_millis(1));
+        }
+        let expected = vec![
+            RestartReason::Explicit,
+            RestartReason::Panic { message: "handler panicked: boom".to_string() },
+        ];
+        assert_eq!(*reasons.lock().unwrap(), expected);
+        assert!(actor_ref.is_alive());
+        mgr.end();
+    }
+
+    #[test]
+    fn test_supervisor_escalates_to_parent() {
+        use crate::{define_message, ActorContext, Message};
+
+        struct Crash;
+        define_message!(Crash);
+
+        struct Child;
+        impl Actor for Child {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if msg.as_any().is::<Crash>() {
+                    panic!("boom");
+                }
+            }
+        }
+
+        struct Parent {
+            ended: Arc<AtomicBool>,
+        }
+        impl Actor for Parent {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                if msg.as_any().is::<Start>() {
+                    let config = ThreadConfig::with_supervision(SupervisionStrategy::Escalate);
+                    ctx.spawn_child("child", Box::new(Child), config).unwrap();
+                }
+            }
+            fn end(&mut self) {
+                self.ended.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let ended = Arc::new(AtomicBool::new(false));
+        let mut mgr = Manager::new();
+        mgr.manage("parent", Box::new(Parent { ended: Arc::clone(&ended) }), Default::default());
+        mgr.init();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let child = loop {
+            assert!(Instant::now() < deadline, "child was never created");
+            match mgr.get_ref("parent/child") {
+                Some(child) => break child,
+                None => thread::sleep(Duration::from_millis(1)),
+            }
+        };
+        child.send(Box::new(Crash), None);
+
+        // The parent's own strategy (Stop) applies to the escalated failure
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !ended.load(Ordering::SeqCst) {
+            assert!(Instant::now() < deadline, "parent never ended");
+            thread::sleep(Duration::from_millis(1));
+        }
+        mgr.end();
+    }
+
+    #[test]
+    fn test_death_watch() {
+        use crate::watch::Terminated;
+        use crate::{define_message, ActorContext, Message};
+
+        struct Crash;
+        define_message!(Crash);
+
+        struct Target;
+        impl Actor for Target {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if msg.as_any().is::<Crash>() {
+                    panic!("boom");
+                }
+            }
+        }
+
+        struct Watcher {
+            targets: Vec<ActorRef>,
+            ignored: ActorRef,
+            ready: Arc<AtomicBool>,
+            seen: Arc<Mutex<Vec<(String, TerminationReason)>>>,
+        }
+        impl Actor for Watcher {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                if msg.as_any().is::<Start>() {
+                    for target in &self.targets {
+                        ctx.watch(target.clone());
+                    }
+                    ctx.watch(self.ignored.clone());
+                    ctx.unwatch(&self.ignored);
+                    self.ready.store(true, Ordering::SeqCst);
+                } else if let Some(terminated) = msg.as_any().downcast_ref::<Terminated>() {
+                    self.seen.lock().unwrap().push((terminated.who.name().to_string(), terminated.reason));
+                }
+            }
+        }
+
+        let ready = Arc::new(AtomicBool::new(false));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut mgr = Manager::new();
+        let calm = mgr.manage("calm", Box::new(Target), Default::default());
+        let crashy = mgr.manage("crashy", Box::new(Target), Default::default());
+        let ignored = mgr.manage("ignored", Box::new(Target), Default::default());
+        let watcher = Watcher {
+            targets: vec![calm.clone(), crashy.clone()],
+            ignored: ignored.clone(),
+            ready: Arc::clone(&ready),
+            seen: Arc::clone(&seen),
+        };
+        mgr.manage("watcher", Box::new(watcher), Default::default());
+        mgr.init();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !ready.load(Ordering::SeqCst) {
+            assert!(Instant::now() < deadline, "actors never became ready");
+            thread::sleep(Duration::from_millis(1));
+        }
+        ignored.send(Box::new(Shutdown), None);
+        calm.send(Box::new(Shutdown), None);
+        crashy.send(Box::new(Crash), None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while seen.lock().unwrap().len() < 2 {
+            assert!(Instant::now() < deadline, "watchers were never told");
+            thread::sleep(Duration::from_millis(1));
+        }
+        mgr.end();
+
+        let mut seen = seen.lock().unwrap().clone();
+        seen.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            seen,
+            vec![
+                ("calm".to_string(), TerminationReason::Normal),
+                ("crashy".to_string(), TerminationReason::Panicked),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stop_self() {
+        use crate::dead_letters::DeadLetter;
+        use crate::watch::Terminated;
+        use crate::{define_message, ActorContext, Message};
+
+        struct Done;
+        define_message!(Done);
+        struct Extra;
+        define_message!(Extra);
+
+        struct OneShot {
+            stopped: Arc<AtomicBool>,
+        }
+        impl Actor for OneShot {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                if msg.as_any().is::<Done>() {
+                    // Queued behind this handler, so it becomes a dead letter
+                    ctx.self_ref().unwrap().send(Box::new(Extra), None);
+                    ctx.stop_self();
+                } else if msg.as_any().is::<Extra>() {
+                    panic!("handled a message after stop_self");
+                }
+            }
+            fn post_stop(&mut self, _ctx: &mut ActorContext) {
+                self.stopped.store(true, Ordering::SeqCst);
+            }
+        }
+
+        struct Watcher {
+            target: ActorRef,
+            ready: Arc<AtomicBool>,
+            terminated: Arc<Mutex<Option<TerminationReason>>>,
+            letters: Arc<Mutex<Vec<DeadLetter>>>,
+        }
+        impl Actor for Watcher {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                if msg.as_any().is::<Start>() {
+                    ctx.watch(self.target.clone());
+                    self.ready.store(true, Ordering::SeqCst);
+                } else if let Some(terminated) = msg.as_any().downcast_ref::<Terminated>() {
+                    *self.terminated.lock().unwrap() = Some(terminated.reason);
+                } else if let Some(letter) = msg.as_any().downcast_ref::<DeadLetter>() {
+                    self.letters.lock().unwrap().push(letter.clone());
+                }
+            }
+        }
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let ready = Arc::new(AtomicBool::new(false));
+        let terminated = Arc::new(Mutex::new(None));
+        let letters = Arc::new(Mutex::new(Vec::new()));
+        let mut mgr = Manager::new();
+        let one_shot = mgr.manage("one_shot", Box::new(OneShot { stopped: Arc::clone(&stopped) }), Default::default());
+        let watcher = Watcher {
+            target: one_shot.clone(),
+            ready: Arc::clone(&ready),
+            terminated: Arc::clone(&terminated),
+            letters: Arc::clone(&letters),
+        };
+        let watcher = mgr.manage("watcher", Box::new(watcher), Default::default());
+        mgr.subscribe_dead_letters(watcher);
+        mgr.init();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !ready.load(Ordering::SeqCst) {
+            assert!(Instant::now() < deadline, "actor never became ready");
+            thread::sleep(Duration::from_millis(1));
+        }
+        one_shot.send(Box::new(Done), None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while terminated.lock().unwrap().is_none() || letters.lock().unwrap().is_empty() {
+            assert!(Instant::now() < deadline, "termination never reported");
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert!(stopped.load(Ordering::SeqCst));
+        assert_eq!(*terminated.lock().unwrap(), Some(TerminationReason::Normal));
+        assert!(mgr.get_ref("one_shot").is_none());
+        assert_eq!(mgr.get_names(), vec!["watcher".to_string()]);
+        mgr.end();
+
+        // Shutdown is not sent to the stopped actor
+        let letters = letters.lock().unwrap();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].target, "one_shot");
+        assert!(letters[0].message_type.ends_with("::Extra"));
+        assert_eq!(letters[0].reason, "actor stopped");
+    }
+
+    #[test]
+    fn test_actor_status() {
+        use crate::{define_message, ActorContext, Message};
+
+        struct Crash;
+        define_message!(Crash);
+
+        struct Target;
+        impl Actor for Target {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if msg.as_any().is::<Crash>() {
+                    panic!("boom");
+                }
+            }
+        }
+
+        let mut mgr = Manager::new();
+        let handle = mgr.get_handle();
+        let steady = mgr.manage("steady", Box::new(Target), Default::default());
+        let crashy = mgr.manage("crashy", Box::new(Target), Default::default());
+        mgr.init();
+
+        assert_eq!(handle.actor_status("steady"), Some(ActorStatus::Running));
+        assert!(steady.is_alive());
+        assert_eq!(mgr.actor_status("unknown"), None);
+
+        steady.send(Box::new(Shutdown), None);
+        crashy.send(Box::new(Crash), None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while steady.is_alive() || crashy.is_alive() {
+            assert!(Instant::now() < deadline, "actors did not stop");
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(mgr.actor_status("steady"), Some(ActorStatus::Stopped));
+        assert_eq!(handle.actor_status("crashy"), Some(ActorStatus::Panicked));
+        mgr.end();
+    }
+
+    #[test]
+    fn test_message_recording_and_replay() {
+        use crate::messages::Reject;
+        use crate::serialization::register_remote_message;
+        use crate::{ActorContext, Message};
+
+        register_remote_message::<Reject>("Reject");
+
+        struct Sink {
+            reasons: Arc<Mutex<Vec<String>>>,
+        }
+        impl Actor for Sink {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if let Some(reject) = msg.as_any().downcast_ref::<Reject>() {
+                    self.reasons.lock().unwrap().push(reject.reason.clone());
+                }
+            }
+        }
+
+        let path = std::env::temp_dir().join(format!("actors-recording-{}.jsonl", std::process::id()));
+        let reasons = Arc::new(Mutex::new(Vec::new()));
+        let mut mgr = Manager::new();
+        let sink = mgr.manage("sink", Box::new(Sink { reasons: Arc::clone(&reasons) }), Default::default());
+        mgr.init();
+
+        let mut recording = mgr.start_message_recording(path.clone()).unwrap();
+        sink.send(Box::new(Reject::new("Ping", "first", "sink")), None);
+        // Not registered for serialization, so not recorded
+        sink.send(Box::new(Start), None);
+        sink.send(Box::new(Reject::new("Ping", "second", "sink")), None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while reasons.lock().unwrap().len() < 2 {
+            assert!(Instant::now() < deadline, "recorded messages never arrived");
+            thread::sleep(Duration::from_millis(1));
+        }
+        recording.stop();
+        assert_eq!(recording.recorded(), 2);
+
+        assert_eq!(mgr.replay_from_file(path.clone(), 100.0).unwrap(), 2);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while reasons.lock().unwrap().len() < 4 {
+            assert!(Instant::now() < deadline, "replayed messages never arrived");
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(*reasons.lock().unwrap(), vec!["first", "second", "first", "second"]);
+        for speed in [0.0, f64::EPSILON, -1.0, f64::NAN] {
+            let err = mgr.replay_from_file(path.clone(), speed).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+        // Due too late to represent at this speed
+        let record = serde_json::json!({"actor": "audit", "message_type": "Reject", "timestamp_us": u64::MAX});
+        std::fs::write(&path, record.to_string()).unwrap();
+        let err = mgr.replay_from_file(path.clone(), 1e-10).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        mgr.end();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_broadcast_all() {
+        use crate::{define_message, ActorContext};
+
+        struct Ping;
+        define_message!(Ping);
+
+        struct Counter {
+            pings: Arc<AtomicI32>,
+        }
+        impl Actor for Counter {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if msg.as_any().is::<Ping>() {
+                    self.pings.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        }
+
+        let pings = Arc::new(AtomicI32::new(0));
+        let mut mgr = Manager::new();
+        let handle = mgr.get_handle();
+        mgr.manage("a", Box::new(Counter { pings: Arc::clone(&pings) }), Default::default());
+        let b = mgr.manage("b", Box::new(Counter { pings: Arc::clone(&pings) }), Default::default());
+        mgr.init();
+
+        assert!(mgr.broadcast_all(|| Box::new(Ping)).is_empty());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while pings.load(Ordering::SeqCst) < 2 {
+            assert!(Instant::now() < deadline, "broadcast never arrived");
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        b.send(Box::new(Shutdown), None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while b.is_alive() {
+            assert!(Instant::now() < deadline, "actor did not stop");
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(handle.broadcast_all(|| Box::new(Ping)), vec!["b".to_string()]);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while pings.load(Ordering::SeqCst) < 3 {
+            assert!(Instant::now() < deadline, "second broadcast never arrived");
+            thread::sleep(Duration::from_millis(1));
+        }
+        mgr.end();
+    }
+
+    #[test]
+    fn test_get_actor() {
+        use crate::ActorContext;
+
+        struct Parent;
+        impl Actor for Parent {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                if msg.as_any().is::<Start>() {
+                    ctx.spawn_child("child", Box::new(DummyActor), ThreadConfig::default()).unwrap();
+                }
+            }
+        }
+        struct DummyActor;
+        impl Actor for DummyActor {}
+
+        let mut mgr = Manager::new();
+        let handle = mgr.get_handle();
+        mgr.manage("parent", Box::new(Parent), Default::default());
+        mgr.init();
+
+        assert_eq!(mgr.get_actor("parent").unwrap().name(), "parent");
+        assert!(mgr.get_actor("missing").is_none());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while handle.get_actor("parent/child").is_none() {
+            assert!(Instant::now() < deadline, "child was never created");
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(mgr.get_actor("parent/child").unwrap().name(), "parent/child");
+        mgr.end();
+    }
+
+    #[test]
+    fn test_get_or_create_actor() {
+        struct DummyActor;
+        impl Actor for DummyActor {}
+
+        let mut mgr = Manager::new();
+        let created = Arc::new(AtomicI32::new(0));
+        let factory = || {
+            created.fetch_add(1, Ordering::SeqCst);
+            Box::new(DummyActor) as Box<dyn Actor>
+        };
+        let first = mgr.get_or_create_actor("a", factory, ThreadConfig::default()).unwrap();
+        let second = mgr.get_or_create_actor("a", factory, ThreadConfig::default()).unwrap();
+        assert_eq!(first.name(), second.name());
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+        assert_eq!(mgr.get_names(), vec!["a".to_string()]);
+
+        mgr.init();
+        first.send(Box::new(Shutdown), None);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while first.is_alive() {
+            assert!(Instant::now() < deadline, "actor did not stop");
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(matches!(
+            mgr.get_or_create_actor("a", factory, ThreadConfig::default()),
+            Err(GetOrCreateError::ActorStopped(name)) if name == "a"
+        ));
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+
+        // Created after init, so started at once
+        struct Starter(Arc<AtomicI32>);
+        impl Actor for Starter {
+            fn init(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        let started = Arc::new(AtomicI32::new(0));
+        let starter = Box::new(Starter(Arc::clone(&started)));
+        let late = mgr.get_or_create_actor("b", || starter, ThreadConfig::default()).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while started.load(Ordering::SeqCst) == 0 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(started.load(Ordering::SeqCst), 1);
+        assert!(late.is_alive());
+        mgr.end();
+    }
+
+    #[test]
+    fn test_get_or_create_actor_from_handles() {
+        struct DummyActor;
+        impl Actor for DummyActor {}
+
+        let mut mgr = Manager::new();
+        let created = Arc::new(AtomicI32::new(0));
+        let mgr_handle = mgr.get_handle();
+        let get_or_create_all = |name: &'static str| -> Vec<ActorRef> {
+            let threads: Vec<_> = (0..8)
+                .map(|_| {
+                    let handle = mgr_handle.clone();
+                    let created = Arc::clone(&created);
+                    thread::spawn(move || {
+                        let factory = || {
+                            created.fetch_add(1, Ordering::SeqCst);
+                            Box::new(DummyActor) as Box<dyn Actor>
+                        };
+                        handle.get_or_create_actor(name, factory, ThreadConfig::default()).unwrap()
+                    })
+                })
+                .collect();
+            threads.into_iter().map(|thread| thread.join().unwrap()).collect()
+        };
+
+        // Before init the actor waits for it, after init it starts at once
+        let before = get_or_create_all("before");
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+        mgr.init();
+        let after = get_or_create_all("after");
+        assert_eq!(created.load(Ordering::SeqCst), 2);
+        assert!(before.iter().chain(&after).all(ActorRef::is_alive));
+        let mut names = mgr.get_names();
+        names.sort();
+        assert_eq!(names, vec!["after".to_string(), "before".to_string()]);
+
+        mgr.end();
+        assert_eq!(mgr.actor_status("before"), Some(ActorStatus::Stopped));
+        assert_eq!(mgr.actor_status("after"), Some(ActorStatus::Stopped));
+    }
+
+    #[test]
+    fn test_pause_and_resume_actor() {
+        use crate::{define_message, ActorContext};
+
+        struct Work;
+        define_message!(Work);
+
+        struct Worker {
+            done: Arc<AtomicI32>,
+        }
+        impl Actor for Worker {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if msg.as_any().is::<Work>() {
+                    self.done.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        }
+
+        let done = Arc::new(AtomicI32::new(0));
+        let mut mgr = Manager::new();
+        let handle = mgr.get_handle();
+        let config = ThreadConfig::builder().mailbox_capacity(2).build().unwrap();
+        let worker = mgr.manage("worker", Box::new(Worker { done: Arc::clone(&done) }), config);
+        mgr.init();
+        handle.wait_for_idle(Duration::from_secs(5)).unwrap();
+
+        assert!(mgr.pause_actor("worker"));
+        assert!(!mgr.pause_actor("missing"));
+        // Messages stay in the mailbox, so it fills up
+        let mut sent = 0;
+        while worker.try_send(Box::new(Work), None).is_ok() {
+            sent += 1;
+            assert!(sent <= 3, "a paused actor kept taking messages");
+        }
+        assert!(sent >= 2);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(done.load(Ordering::SeqCst), 0);
+
+        assert!(handle.resume_actor("worker"));
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while done.load(Ordering::SeqCst) < sent {
+            assert!(Instant::now() < deadline, "resumed actor did not handle its messages");
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        // A paused actor can still be shut down
+        assert!(handle.pause_actor("worker"));
+        worker.send(Box::new(Work), None);
+        mgr.end();
+        assert_eq!(done.load(Ordering::SeqCst), sent);
+        assert_eq!(mgr.actor_status("worker"), Some(ActorStatus::Stopped));
+    }
+
+    #[test]
+    fn test_wait_for_idle() {
+        use crate::{define_message, ActorContext};
+
+        struct Work;
+        define_message!(Work);
+
+        struct SlowWorker {
+            done: Arc<AtomicI32>,
+        }
+        impl Actor for SlowWorker {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if msg.as_any().is::<Work>() {
+                    thread::sleep(Duration::from_millis(5));
+                    self.done.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        }
+
+        let done = Arc::new(AtomicI32::new(0));
+        let mut mgr = Manager::new();
+        let handle = mgr.get_handle();
+        let worker = mgr.manage("worker", Box::new(SlowWorker { done: Arc::clone(&done) }), Default::default());
+        mgr.init();
+
+        for _ in 0..10 {
+            worker.send(Box::new(Work), None);
+        }
+        assert_eq!(handle.wait_for_idle(Duration::from_millis(1)), Err(IdleTimeout));
+        assert_eq!(handle.wait_for_idle(Duration::from_secs(5)), Ok(()));
+        assert_eq!(done.load(Ordering::SeqCst), 10);
+
+        // Messages a paused actor took out to reach the inspection still wait
+        assert!(mgr.pause_actor("worker"));
+        worker.send(Box::new(Work), None);
+        worker.send(Box::new(Work), None);
+        mgr.inspect_actor::<SlowWorker, _>("worker", Duration::from_secs(5), |_| {}).unwrap();
+        assert_eq!(handle.wait_for_idle(Duration::from_millis(20)), Err(IdleTimeout));
+        assert!(mgr.resume_actor("worker"));
+        assert_eq!(handle.wait_for_idle(Duration::MAX), Ok(()));
+        assert_eq!(done.load(Ordering::SeqCst), 12);
+        mgr.end();
+    }
+
+    #[test]
+    fn test_drain_and_shutdown() {
+        use crate::{define_message, ActorContext};
+
+        struct Work;
+        define_message!(Work);
+
+        struct Worker {
+            done: Arc<AtomicI32>,
+            late: Option<ActorRef>,
+        }
+        impl Actor for Worker {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if msg.as_any().is::<Work>() {
+                    thread::sleep(Duration::from_millis(2));
+                    self.done.fetch_add(1, Ordering::SeqCst);
+                    // Sent after the drain started, so rejected
+                    if let Some(late) = &self.late {
+                        late.send(Box::new(Work), None);
+                    }
+                }
+            }
+        }
+
+        let done = Arc::new(AtomicI32::new(0));
+        let sunk = Arc::new(AtomicI32::new(0));
+        let mut mgr = Manager::new_with_dead_letter_store(100);
+        let sink = mgr.manage("sink", Box::new(Worker { done: Arc::clone(&sunk), late: None }), Default::default());
+        let worker = mgr.manage(
+            "worker",
+            Box::new(Worker { done: Arc::clone(&done), late: Some(sink) }),
+            Default::default(),
+        );
+        mgr.init();
+        for _ in 0..20 {
+            worker.send(Box::new(Work), None);
+        }
+
+        mgr.drain_and_shutdown(Duration::from_secs(5));
+        // Everything queued before the drain was handled
+        assert_eq!(done.load(Ordering::SeqCst), 20);
+        assert_eq!(mgr.actor_status("worker"), Some(ActorStatus::Stopped));
+
+        // What the sink got after it started draining (or stopped) was rejected, not lost
+        let letters = mgr.take_dead_letters();
+        assert!(letters.iter().all(|letter| letter.target == "sink"));
+        assert_eq!(sunk.load(Ordering::SeqCst) + letters.len() as i32, 20);
+    }
+
+    #[test]
+    fn test_drain_skips_actors_without_a_thread() {
+        struct Idle;
+        impl Actor for Idle {}
+
+        // Before init nothing runs, so there is nothing to wait for
+        let mut mgr = Manager::new();
+        mgr.manage("idle", Box::new(Idle), Default::default());
+        mgr.drain_and_shutdown(Duration::MAX);
+
+        // A taken actor is not drained, even with no timeout
+        let mut mgr = Manager::new();
+        mgr.manage("taken", Box::new(Idle), Default::default());
+        mgr.manage("running", Box::new(Idle), Default::default());
+        mgr.init();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let _taken = loop {
+            assert!(Instant::now() < deadline, "actor stayed busy");
+            match mgr.take_actor("taken") {
+                Ok(actor) => break actor,
+                Err(TakeError::ActorBusy(_)) => thread::sleep(Duration::from_millis(1)),
+                Err(e) => panic!("{}", e),
+            }
+        };
+        mgr.drain_and_shutdown(Duration::MAX);
+        assert_eq!(mgr.actor_status("running"), Some(ActorStatus::Stopped));
+    }
+
+    #[test]
+    fn test_drain_timeout_routes_unhandled_to_dead_letters() {
+        use crate::{define_message, ActorContext};
+
+        struct Work;
+        define_message!(Work);
+
+        struct SlowWorker;
+        impl Actor for SlowWorker {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if msg.as_any().is::<Work>() {
+                    thread::sleep(Duration::from_millis(20));
+                }
+            }
+        }
+
+        let mut mgr = Manager::new_with_dead_letter_store(100);
+        let worker = mgr.manage("worker", Box::new(SlowWorker), Default::default());
+        mgr.init();
+        for _ in 0..10 {
+            worker.send(Box::new(Work), None);
+        }
+
+        mgr.drain_and_shutdown(Duration::from_millis(30));
+        let letters = mgr.take_dead_letters();
+        assert!(!letters.is_empty());
+        assert!(letters.iter().all(|letter| letter.target == "worker"));
+    }
+
+    #[test]
+    fn test_drain_children_and_stuck_actors() {
+        use crate::{define_message, ActorContext};
+
+        struct Work;
+        define_message!(Work);
+        struct Block;
+        define_message!(Block);
+
+        struct Leaf {
+            handled: Arc<AtomicI32>,
+        }
+        impl Actor for Leaf {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if msg.as_any().is::<Work>() {
+                    self.handled.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        }
+        struct Parent {
+            handled: Arc<AtomicI32>,
+        }
+        impl Actor for Parent {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                if msg.as_any().is::<Start>() {
+                    let handled = Arc::clone(&self.handled);
+                    ctx.create_child("child", Box::new(Leaf { handled })).unwrap();
+                }
+            }
+        }
+        struct Stuck {
+            release: Receiver<()>,
+        }
+        impl Actor for Stuck {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if msg.as_any().is::<Block>() {
+                    let _ = self.release.recv();
+                }
+            }
+        }
+
+        let handled = Arc::new(AtomicI32::new(0));
+        let (release_tx, release_rx) = channel();
+        let mut mgr = Manager::new_with_dead_letter_store(100);
+        mgr.manage("parent", Box::new(Parent { handled: Arc::clone(&handled) }), Default::default());
+        let stuck = mgr.manage("stuck", Box::new(Stuck { release: release_rx }), Default::default());
+        mgr.init();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let child = loop {
+            if let Some(child) = mgr.get_actor("parent/child") {
+                break child;
+            }
+            assert!(Instant::now() < deadline, "child was never created");
+            thread::sleep(Duration::from_millis(1));
+        };
+        stuck.send(Box::new(Block), None);
+
+        // Sent while the stuck actor holds up the shutdown, after the child started draining
+        let late = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            child.send(Box::new(Work), None);
+        });
+        let started = Instant::now();
+        mgr.drain_and_shutdown(Duration::from_millis(300));
+        assert!(started.elapsed() < Duration::from_secs(2), "waited for the stuck actor");
+        late.join().unwrap();
+
+        assert_eq!(handled.load(Ordering::SeqCst), 0);
+        assert!(mgr.take_dead_letters().iter().any(|letter| letter.target == "parent/child"));
+        release_tx.send(()).unwrap();
+    }
+
+    #[test]
+    fn test_manage_many() {
+        struct DummyActor;
+        impl Actor for DummyActor {}
+
+        let mut mgr = Manager::new();
+        let actors: Vec<(String, Box<dyn Actor>, ThreadConfig)> = (0..3)
+            .map(|i| (format!("worker{}", i), Box::new(DummyActor) as Box<dyn Actor>, ThreadConfig::default()))
+            .collect();
+
+        let refs = mgr.manage_many(actors);
+        let names: Vec<&str> = refs.iter().map(|r| r.name()).collect();
+        assert_eq!(names, vec!["worker0", "worker1", "worker2"]);
+        assert!(mgr.get_ref("worker2").is_some());
+    }
+
+    #[test]
+    fn test_actor_metrics() {
+        struct DummyActor;
+        impl Actor for DummyActor {}
+
+        let mut mgr = Manager::new();
+        let handle = mgr.start_metrics_server(0).unwrap();
+        assert!(handle.port() > 0);
+
+        let actor_ref = mgr.manage("worker", Box::new(DummyActor), Default::default());
+        actor_ref.send(Box::new(Start), None);
+        assert_eq!(mgr.metrics.lock().unwrap()[0].1.mailbox_depth(), 1);
+
+        mgr.init();
+        mgr.end();
+
+        // Both Starts and the Shutdown were handled
+        let text = metrics::render_metrics(&mgr.metrics.lock().unwrap());
+        assert!(text.contains("actor_mailbox_depth{actor=\"worker\"} 0\n"));
+        assert!(text.contains("actor_messages_processed_total{actor=\"worker\"} 3\n"));
+    }
+
+    #[test]
+    fn test_all_mailbox_stats() {
+        struct DummyActor;
+        impl Actor for DummyActor {}
+        struct Work;
+        crate::define_message!(Work);
+
+        let mut mgr = Manager::new();
+        let config = ThreadConfig::builder().mailbox_capacity(2).build().unwrap();
+        let actor_ref = mgr.manage("worker", Box::new(DummyActor), config);
+        actor_ref.send(Box::new(Start), None);
+        assert!(actor_ref.try_send(Box::new(Work), None).is_ok());
+        assert!(actor_ref.try_send(Box::new(Work), None).is_err());
+
+        let stats = actor_ref.mailbox_stats();
+        assert_eq!(stats.current_depth, 2);
+        assert_eq!(stats.peak_depth, 2);
+        assert_eq!(stats.dropped_count, 1);
+
+        mgr.init();
+        mgr.end();
+        let stats = mgr.all_mailbox_stats()["worker"];
+        assert_eq!(stats.current_depth, 0);
+        assert_eq!(stats.total_received, 4);
+        assert_eq!(stats.total_processed, 4);
+    }
+
+    #[test]
+    fn test_actor_custom_metrics() {
+        struct PaymentActor;
+        impl Actor for PaymentActor {
+            fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+                ctx.metrics_counter("messages").increment();
+                if msg.as_any().is::<Start>() {
+                    ctx.metrics_counter("starts").add(10);
+                }
+            }
+        }
+
+        let mut mgr = Manager::new();
+        mgr.manage("payments", Box::new(PaymentActor), Default::default());
+        mgr.init();
+        mgr.end();
+
+        // Start and Shutdown
+        let custom = mgr.actor_custom_metrics("payments");
+        assert_eq!(custom["messages"], 2);
+        assert_eq!(custom["starts"], 10);
+        assert!(mgr.actor_custom_metrics("missing").is_empty());
+    }
+
+    #[test]
+    fn test_message_error_handler() {
+        use crate::messages::Timeout;
+
+        struct FailingActor {
+            handled: Arc<AtomicI32>,
+        }
+        crate::handle_messages!(FailingActor,
+            Start => on_start,
+            Timeout => on_timeout
+        );
+        impl FailingActor {
+            fn on_start(&mut self, _msg: &Start, _ctx: &mut ActorContext) -> Result<(), HandlerError> {
+                Err(Box::new(io::Error::other("disk full")))
+            }
+
+            fn on_timeout(&mut self, _msg: &Timeout, _ctx: &mut ActorContext) {
+                self.handled.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let handled = Arc::new(AtomicI32::new(0));
+        let mut mgr = Manager::new();
+        let seen = Arc::clone(&errors);
+        mgr.set_message_error_handler(move |actor, msg, error| {
+            seen.lock().unwrap().push((actor.to_string(), msg.as_any().is::<Start>(), error.to_string()));
+        });
+        let actor = FailingActor { handled: Arc::clone(&handled) };
+        let failing = mgr.manage("failing", Box::new(actor), Default::default());
+        mgr.init();
+        // The actor keeps running after the error
+        failing.send(Box::new(Timeout::new(1)), None);
+        mgr.end();
+
+        assert_eq!(*errors.lock().unwrap(), vec![("failing".to_string(), true, "disk full".to_string())]);
+        assert_eq!(handled.load(Ordering::SeqCst), 1);
+
+        // Without a handler, the error is a dead letter
+        let mut mgr = Manager::new_with_dead_letter_store(10);
+        let actor = FailingActor { handled: Arc::clone(&handled) };
+        mgr.manage("failing", Box::new(actor), Default::default());
+        mgr.init();
+        let mut letters = mgr.take_dead_letters();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while letters.is_empty() {
+            assert!(Instant::now() < deadline, "no dead letter arrived");
+            thread::sleep(Duration::from_millis(1));
+            letters = mgr.take_dead_letters();
+        }
+        mgr.end();
+        assert_eq!(letters[0].target, "failing");
+        assert_eq!(letters[0].message_type, Start.type_name());
+        assert_eq!(letters[0].reason, "disk full");
+    }
+
+    #[test]
+    fn test_manage_pooled_returns_channel() {
+        struct DummyActor;
+        impl Actor for DummyActor {}
+
+        let pool = ActorRefPool::new(1);
+        let mut mgr = Manager::new();
+        mgr.manage_pooled("pooled", Box::new(DummyActor), Default::default(), &pool);
+        assert_eq!(pool.available(), 0);
+        assert!(mgr.get_ref("pooled").is_some());
+
+        mgr.init();
+        mgr.end();
+        assert_eq!(pool.available(), 1);
+    }
+
+    #[test]
+    fn test_thread_config() {
+        let config = ThreadConfig::with_affinity(vec![0, 1]);
+        assert_eq!(config.affinity, vec![0, 1]);
+        assert_eq!(config.priority, 0);
+
+        let config2 = ThreadConfig::with_priority(50, libc::SCHED_FIFO);
+        assert_eq!(config2.priority, 50);
+        assert_eq!(config2.sched_policy, libc::SCHED_FIFO);
+    }
+
+    #[test]
+    fn test_thread_config_builder() {
+        let config = ThreadConfig::builder()
+            .stack_size(4 * 1024 * 1024)
+            .mailbox_capacity(1024)
+            .name_prefix("worker")
+            .thread_priority(5)
+            .build()
+            .unwrap();
+        assert_eq!(config.stack_size, 4 * 1024 * 1024);
+        assert_eq!(config.mailbox_capacity, Some(1024));
+        assert_eq!(config.name_prefix, "worker");
+        assert_eq!(config.thread_priority, 5);
+
+        assert_eq!(ThreadConfig::builder().mailbox_capacity(0).build().err(), Some(ConfigError::ZeroMailboxCapacity));
+        assert_eq!(ThreadConfig::builder().thread_priority(20).build().err(), Some(ConfigError::InvalidNiceValue(20)));
+        assert_eq!(
+            ThreadConfig::builder().thread_priority(5).realtime_priority(50, libc::SCHED_FIFO).build().err(),
+            Some(ConfigError::NiceWithRealtimePolicy)
+        );
+    }
+
+    #[test]
+    fn test_mailbox_capacity() {
+        struct Work;
+        crate::define_message!(Work);
+
+        struct Blocker {
+            started: std::sync::mpsc::Sender<()>,
+            gate: Arc<Mutex<()>>,
+        }
+        impl Actor for Blocker {
+            fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+                if msg.as_any().is::<Work>() {
+                    let _ = self.started.send(());
+                    drop(self.gate.lock().unwrap());
+                }
+            }
+        }
+
+        let gate = Arc::new(Mutex::new(()));
+        let closed = gate.lock().unwrap();
+        let (started_tx, started_rx) = channel();
+        let mut mgr = Manager::new_with_dead_letter_store(16);
+        let config = ThreadConfig::builder().mailbox_capacity(2).name_prefix("worker").build().unwrap();
+        let blocker = Blocker { started: started_tx, gate: Arc::clone(&gate) };
+        let mut blocker = mgr.manage("blocker", Box::new(blocker), config);
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&errors);
+        blocker.set_error_handler(move |e| seen.lock().unwrap().push(e));
+        mgr.init();
+
+        // The first message blocks the actor, the next two fill its mailbox
+        assert!(blocker.try_send(Box::new(Work), None).is_ok());
+        started_rx.recv().unwrap();
+        assert!(blocker.try_send(Box::new(Work), None).is_ok());
+        assert!(blocker.try_send(Box::new(Work), None).is_ok());
+        assert_eq!(blocker.try_send(Box::new(Work), None), Err(SendError::Full));
+        assert_eq!(*errors.lock().unwrap(), vec![ActorError::MailboxFull]);
+
+        drop(closed);
+        mgr.end();
+        let letters = mgr.take_dead_letters();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].reason, "mailbox full");
     }
 
     #[test]
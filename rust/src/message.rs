@@ -44,6 +44,11 @@ pub trait Message: Any + Send + 'static {
     fn message_id(&self) -> i32 {
         0
     }
+
+    /// Rust type name of the message, for diagnostics such as dead letters
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 /// Macro to implement the Message trait for a type.
@@ -31,6 +31,7 @@ define_message!(Start);
 /// Sent to actors when the Manager shuts down.
 ///
 /// Actors should clean up resources and stop processing when they receive this.
+/// The actor's thread exits once the Shutdown handler returns.
 #[derive(Debug, Clone)]
 pub struct Shutdown;
 define_message!(Shutdown);
@@ -0,0 +1,405 @@
+/*
+
+THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
+
+Copyright 2025 Vincent Maciejewski, & M2 Tech
+Contact:
+v@m2te.ch
+mayeski@gmail.com
+https://www.linkedin.com/in/vmayeski/
+http://m2te.ch/
+
+*/
+
+//! Per-actor metrics and the Prometheus endpoint served by
+//! `Manager::start_metrics_server`.
+//!
+//! Exposed metrics, labelled with `actor`:
+//! - `actor_mailbox_depth` (gauge): messages queued but not yet taken by the actor
+//! - `actor_messages_processed_total` (counter): use `rate()` for the message rate
+//! - `actor_processing_seconds` (histogram): time spent in `process_message`
+//! - `actor_custom_total` (counter, also labelled with `metric`): counters
+//!   from `ActorContext::metrics_counter`
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::manager::ActorStatus;
+
+/// Upper bounds of the processing latency buckets, in microseconds
+const LATENCY_BUCKETS_US: [u64; 7] = [10, 100, 1_000, 10_000, 100_000, 1_000_000, 10_000_000];
+
+/// Counters for one actor, updated by its refs and its runtime thread.
+#[derive(Default)]
+pub(crate) struct ActorMetrics {
+    enqueued: AtomicU64,
+    dequeued: AtomicU64,
+    processed: AtomicU64,
+    /// Per-bucket (non-cumulative) counts; the last slot is +Inf
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS_US.len() + 1],
+    latency_sum_us: AtomicU64,
+    /// True while the actor is inside `process_message`
+    processing: AtomicBool,
+    /// The actor's `ActorStatus`, as its discriminant
+    status: AtomicU8,
+    /// Most messages the mailbox may hold (0 = unbounded)
+    capacity: AtomicUsize,
+    /// Deepest the mailbox has been
+    peak_depth: AtomicU64,
+    /// Messages the mailbox refused, being full or closed
+    dropped: AtomicU64,
+    /// Counters created by the actor, by name
+    custom: Mutex<BTreeMap<String, Counter>>,
+}
+
+/// A custom counter of an actor, from `ActorContext::metrics_counter`.
+///
+/// Clones count into the same value, so a counter can be kept in the actor
+/// or handed to threads it starts.
+#[derive(Debug, Clone, Default)]
+pub struct Counter(Arc<AtomicU64>);
+
+impl Counter {
+    /// Add one
+    pub fn increment(&self) {
+        self.add(1);
+    }
+
+    /// Add `n`
+    pub fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Current value
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Snapshot of an actor's mailbox counters, from `ActorRef::mailbox_stats`.
+///
+/// Counts start when the actor is created. Messages sent through refs not
+/// created by the actor's runtime (e.g. `ActorRef::new`) are not counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MailboxStats {
+    /// Messages waiting in the mailbox
+    pub current_depth: usize,
+    /// Messages put in the mailbox
+    pub total_received: u64,
+    /// Messages handled by the actor
+    pub total_processed: u64,
+    /// Deepest the mailbox has been
+    pub peak_depth: usize,
+    /// Messages the mailbox refused because it was full or closed
+    pub dropped_count: u64,
+}
+
+impl ActorMetrics {
+    /// A message was put in the mailbox
+    pub(crate) fn record_enqueued(&self) {
+        let enqueued = self.enqueued.fetch_add(1, Ordering::Relaxed) + 1;
+        self.record_depth(enqueued);
+    }
+
+    /// Update the peak depth for `enqueued` messages so far
+    fn record_depth(&self, enqueued: u64) {
+        let depth = enqueued.saturating_sub(self.dequeued.load(Ordering::Relaxed));
+        self.peak_depth.fetch_max(depth, Ordering::Relaxed);
+    }
+
+    /// Record a message about to be put in the mailbox, unless the mailbox
+    /// is already at capacity.
+    pub(crate) fn try_record_enqueued(&self) -> bool {
+        let capacity = self.capacity.load(Ordering::Relaxed) as u64;
+        if capacity == 0 {
+            self.record_enqueued();
+            return true;
+        }
+        let dequeued = self.dequeued.load(Ordering::Relaxed);
+        let reserved = self.enqueued.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |enqueued| {
+            (enqueued.saturating_sub(dequeued) < capacity).then_some(enqueued + 1)
+        });
+        match reserved {
+            Ok(enqueued) => {
+                self.record_depth(enqueued + 1);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// A message counted by `try_record_enqueued` could not be sent
+    pub(crate) fn record_unsent(&self) {
+        self.enqueued.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// The mailbox refused a message
+    pub(crate) fn record_dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Limit the mailbox to `capacity` messages (None = unbounded)
+    pub(crate) fn set_mailbox_capacity(&self, capacity: Option<usize>) {
+        self.capacity.store(capacity.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    /// A message was taken from the mailbox
+    pub(crate) fn record_dequeued(&self) {
+        self.dequeued.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A message taken from the mailbox was held back by a paused actor, so
+    /// it counts as waiting again until `record_dequeued`
+    pub(crate) fn record_held(&self) {
+        self.dequeued.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// The actor started handling a message
+    pub(crate) fn record_processing(&self) {
+        self.processing.store(true, Ordering::Relaxed);
+    }
+
+    /// A message was handled by the actor in `elapsed`
+    pub(crate) fn record_processed(&self, elapsed: Duration) {
+        self.processing.store(false, Ordering::Relaxed);
+        let us = elapsed.as_micros() as u64;
+        let bucket = LATENCY_BUCKETS_US
+            .iter()
+            .position(|&bound| us <= bound)
+            .unwrap_or(LATENCY_BUCKETS_US.len());
+        self.latency_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.latency_sum_us.fetch_add(us, Ordering::Relaxed);
+        self.processed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Whether the actor is handling a message right now
+    pub(crate) fn is_processing(&self) -> bool {
+        self.processing.load(Ordering::Relaxed)
+    }
+
+    /// Record a change in the actor's lifecycle
+    pub(crate) fn set_status(&self, status: ActorStatus) {
+        self.status.store(status as u8, Ordering::Relaxed);
+    }
+
+    /// The actor's lifecycle state
+    pub(crate) fn status(&self) -> ActorStatus {
+        match self.status.load(Ordering::Relaxed) {
+            0 => ActorStatus::Running,
+            1 => ActorStatus::Stopping,
+            2 => ActorStatus::Stopped,
+            _ => ActorStatus::Panicked,
+        }
+    }
+
+    /// Messages waiting in the mailbox
+    pub(crate) fn mailbox_depth(&self) -> u64 {
+        // Messages sent through refs created outside the runtime are not counted
+        self.enqueued
+            .load(Ordering::Relaxed)
+            .saturating_sub(self.dequeued.load(Ordering::Relaxed))
+    }
+
+    /// The custom counter `name`, created at zero on first use
+    pub(crate) fn counter(&self, name: &str) -> Counter {
+        self.custom.lock().unwrap().entry(name.to_string()).or_default().clone()
+    }
+
+    /// Current values of the custom counters
+    pub(crate) fn custom_metrics(&self) -> HashMap<String, u64> {
+        self.custom
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, counter)| (name.clone(), counter.get()))
+            .collect()
+    }
+
+    /// Snapshot of the mailbox counters
+    pub(crate) fn mailbox_stats(&self) -> MailboxStats {
+        MailboxStats {
+            current_depth: self.mailbox_depth() as usize,
+            total_received: self.enqueued.load(Ordering::Relaxed),
+            total_processed: self.processed.load(Ordering::Relaxed),
+            peak_depth: self.peak_depth.load(Ordering::Relaxed) as usize,
+            dropped_count: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Metrics of every actor registered with a Manager, by name.
+pub(crate) type MetricsRegistry = Arc<Mutex<Vec<(String, Arc<ActorMetrics>)>>>;
+
+/// Render all actors' metrics in the Prometheus text exposition format.
+pub(crate) fn render_metrics(actors: &[(String, Arc<ActorMetrics>)]) -> String {
+    let mut out = String::new();
+
+    out.push_str("# HELP actor_mailbox_depth Messages waiting in the actor's mailbox.\n");
+    out.push_str("# TYPE actor_mailbox_depth gauge\n");
+    for (name, metrics) in actors {
+        let _ = writeln!(out, "actor_mailbox_depth{{actor=\"{}\"}} {}", escape_label(name), metrics.mailbox_depth());
+    }
+
+    out.push_str("# HELP actor_messages_processed_total Messages handled by the actor.\n");
+    out.push_str("# TYPE actor_messages_processed_total counter\n");
+    for (name, metrics) in actors {
+        let _ = writeln!(
+            out,
+            "actor_messages_processed_total{{actor=\"{}\"}} {}",
+            escape_label(name),
+            metrics.processed.load(Ordering::Relaxed)
+        );
+    }
+
+    out.push_str("# HELP actor_processing_seconds Time spent handling a message.\n");
+    out.push_str("# TYPE actor_processing_seconds histogram\n");
+    for (name, metrics) in actors {
+        let name = escape_label(name);
+        let mut cumulative = 0;
+        for (i, bucket) in metrics.latency_buckets.iter().enumerate() {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let le = match LATENCY_BUCKETS_US.get(i) {
+                Some(&us) => (us as f64 / 1e6).to_string(),
+                None => "+Inf".to_string(),
+            };
+            let _ = writeln!(out, "actor_processing_seconds_bucket{{actor=\"{}\",le=\"{}\"}} {}", name, le, cumulative);
+        }
+        let sum = metrics.latency_sum_us.load(Ordering::Relaxed) as f64 / 1e6;
+        let _ = writeln!(out, "actor_processing_seconds_sum{{actor=\"{}\"}} {}", name, sum);
+        let _ = writeln!(out, "actor_processing_seconds_count{{actor=\"{}\"}} {}", name, cumulative);
+    }
+
+    out.push_str("# HELP actor_custom_total Custom counters of the actor.\n");
+    out.push_str("# TYPE actor_custom_total counter\n");
+    for (name, metrics) in actors {
+        for (metric, counter) in metrics.custom.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "actor_custom_total{{actor=\"{}\",metric=\"{}\"}} {}",
+                escape_label(name),
+                escape_label(metric),
+                counter.get()
+            );
+        }
+    }
+
+    out
+}
+
+/// Escape a Prometheus label value
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Handle to a running metrics HTTP server.
+///
+/// The server stops when `stop()` is called or the handle is dropped.
+pub struct MetricsServerHandle {
+    server: Arc<tiny_http::Server>,
+    port: u16,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MetricsServerHandle {
+    /// Port the server is listening on
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Stop the server and wait for its thread to exit.
+    pub fn stop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MetricsServerHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Serve `GET /metrics` on `port` (all interfaces) from a background thread.
+pub(crate) fn start_server(port: u16, registry: MetricsRegistry) -> io::Result<MetricsServerHandle> {
+    let server = tiny_http::Server::http(("0.0.0.0", port)).map_err(io::Error::other)?;
+    let port = server.server_addr().to_ip().map_or(port, |addr| addr.port());
+    let server = Arc::new(server);
+
+    let thread_server = Arc::clone(&server);
+    let thread = std::thread::spawn(move || {
+        // Ends when the handle calls unblock()
+        for request in thread_server.incoming_requests() {
+            let response = if *request.method() == tiny_http::Method::Get && request.url() == "/metrics" {
+                let body = render_metrics(&registry.lock().unwrap());
+                let content_type = tiny_http::Header::from_bytes("Content-Type", "text/plain; version=0.0.4")
+                    .expect("valid header");
+                tiny_http::Response::from_string(body).with_header(content_type)
+            } else {
+                tiny_http::Response::from_string("Not Found").with_status_code(404)
+            };
+            let _ = request.respond(response);
+        }
+    });
+
+    Ok(MetricsServerHandle {
+        server,
+        port,
+        thread: Some(thread),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write as _};
+    use std::net::TcpStream;
+
+    #[test]
+    fn test_render_metrics() {
+        let metrics = Arc::new(ActorMetrics::default());
+        metrics.record_enqueued();
+        metrics.record_enqueued();
+        metrics.record_dequeued();
+        metrics.record_processed(Duration::from_micros(50));
+        metrics.record_processed(Duration::from_secs(20));
+        metrics.counter("cache_misses").add(3);
+
+        let text = render_metrics(&[("worker \"1\"".to_string(), metrics)]);
+        assert!(text.contains("actor_mailbox_depth{actor=\"worker \\\"1\\\"\"} 1\n"));
+        assert!(text.contains("actor_messages_processed_total{actor=\"worker \\\"1\\\"\"} 2\n"));
+        assert!(text.contains("le=\"0.00001\"} 0\n"));
+        assert!(text.contains("le=\"0.0001\"} 1\n"));
+        assert!(text.contains("le=\"10\"} 1\n"));
+        assert!(text.contains("le=\"+Inf\"} 2\n"));
+        assert!(text.contains("actor_processing_seconds_count{actor=\"worker \\\"1\\\"\"} 2\n"));
+        assert!(text.contains("actor_custom_total{actor=\"worker \\\"1\\\"\",metric=\"cache_misses\"} 3\n"));
+    }
+
+    #[test]
+    fn test_metrics_server() {
+        let registry: MetricsRegistry = Arc::new(Mutex::new(vec![("a".to_string(), Arc::default())]));
+        let mut handle = start_server(0, registry).unwrap();
+
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(("127.0.0.1", handle.port())).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+
+        let response = get("/metrics");
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("actor_mailbox_depth{actor=\"a\"} 0"));
+        assert!(get("/other").starts_with("HTTP/1.1 404"));
+
+        handle.stop();
+    }
+}
@@ -0,0 +1,164 @@
+/*
+
+THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
+
+Copyright 2025 Vincent Maciejewski, & M2 Tech
+Contact:
+v@m2te.ch
+mayeski@gmail.com
+https://www.linkedin.com/in/vmayeski/
+http://m2te.ch/
+
+*/
+
+//! Per-actor rate limiting of remote messages (see
+//! `ZmqReceiver::set_per_actor_rate_limit`).
+//!
+//! Each (actor, sender endpoint) pair has its own token bucket, so one
+//! flooding sender does not slow down the others. A message arriving with
+//! no token left is held until a token frees up, behind the ones already
+//! held, up to the limit's burst size.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+/// Rate limit of one actor
+#[derive(Debug, Clone, Copy)]
+struct RateLimit {
+    max_per_second: f64,
+    /// Messages each sender may have held at once
+    burst_size: usize,
+}
+
+/// Token bucket of one sender to one actor, with its held messages
+struct Bucket<T> {
+    tokens: f64,
+    refilled: Instant,
+    held: VecDeque<T>,
+}
+
+impl<T> Bucket<T> {
+    fn refill(&mut self, limit: RateLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        // Up to one second's worth of messages can pass without waiting
+        self.tokens = (self.tokens + elapsed * limit.max_per_second).min(limit.max_per_second.max(1.0));
+        self.refilled = now;
+    }
+}
+
+/// What to do with a message passed to `RateLimiter::admit`
+pub(crate) enum Admission<T> {
+    /// Deliver it now
+    Deliver(T),
+    /// It is held until `RateLimiter::release` returns it
+    Held,
+    /// The sender's buffer is full: it is not delivered
+    Overflow(T),
+}
+
+/// Token buckets of the rate-limited actors.
+pub(crate) struct RateLimiter<T> {
+    limits: HashMap<String, RateLimit>,
+    /// (actor name, sender endpoint) -> bucket
+    buckets: HashMap<(String, String), Bucket<T>>,
+}
+
+impl<T> RateLimiter<T> {
+    pub(crate) fn new() -> Self {
+        RateLimiter {
+            limits: HashMap::new(),
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Limit each sender to `max_per_second` messages to `actor_name`,
+    /// holding up to `burst_size` more.
+    pub(crate) fn set_limit(&mut self, actor_name: &str, max_per_second: f64, burst_size: usize) {
+        let limit = RateLimit { max_per_second, burst_size };
+        self.limits.insert(actor_name.to_string(), limit);
+    }
+
+    /// Decide whether `item`, sent by `sender_endpoint` to `actor_name`, is
+    /// delivered now.
+    pub(crate) fn admit(&mut self, actor_name: &str, sender_endpoint: &str, item: T, now: Instant) -> Admission<T> {
+        let Some(&limit) = self.limits.get(actor_name) else {
+            return Admission::Deliver(item);
+        };
+        let bucket = self
+            .buckets
+            .entry((actor_name.to_string(), sender_endpoint.to_string()))
+            .or_insert_with(|| Bucket {
+                tokens: limit.max_per_second.max(1.0),
+                refilled: now,
+                held: VecDeque::new(),
+            });
+        bucket.refill(limit, now);
+
+        // Held messages go first, to keep each sender's messages in order
+        if bucket.held.is_empty() && bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Admission::Deliver(item)
+        } else if bucket.held.len() < limit.burst_size {
+            bucket.held.push_back(item);
+            Admission::Held
+        } else {
+            Admission::Overflow(item)
+        }
+    }
+
+    /// Take the held messages that may be delivered by `now`, oldest first
+    /// for each sender.
+    pub(crate) fn release(&mut self, now: Instant) -> Vec<T> {
+        let mut released = Vec::new();
+        let limits = &self.limits;
+        self.buckets.retain(|(actor_name, _), bucket| {
+            let Some(&limit) = limits.get(actor_name) else {
+                return false;
+            };
+            bucket.refill(limit, now);
+            while bucket.tokens >= 1.0 {
+                let Some(item) = bucket.held.pop_front() else {
+                    break;
+                };
+                bucket.tokens -= 1.0;
+                released.push(item);
+            }
+            // A full, idle bucket behaves like a new one: drop it
+            !bucket.held.is_empty() || bucket.tokens < limit.max_per_second.max(1.0)
+        });
+        released
+    }
+
+    /// Take every held message, oldest first for each sender, without
+    /// waiting for tokens.
+    pub(crate) fn take_all(&mut self) -> Vec<T> {
+        self.buckets.drain().flat_map(|(_, bucket)| bucket.held).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_rate_limiter_per_sender() {
+        let mut limiter = RateLimiter::new();
+        limiter.set_limit("pong", 2.0, 1);
+        let start = Instant::now();
+
+        assert!(matches!(limiter.admit("pong", "a", 1, start), Admission::Deliver(1)));
+        assert!(matches!(limiter.admit("pong", "a", 2, start), Admission::Deliver(2)));
+        assert!(matches!(limiter.admit("pong", "a", 3, start), Admission::Held));
+        assert!(matches!(limiter.admit("pong", "a", 4, start), Admission::Overflow(4)));
+        // Other senders and other actors have their own budget
+        assert!(matches!(limiter.admit("pong", "b", 5, start), Admission::Deliver(5)));
+        assert!(matches!(limiter.admit("ping", "a", 6, start), Admission::Deliver(6)));
+
+        assert!(limiter.release(start + Duration::from_millis(100)).is_empty());
+        assert_eq!(limiter.release(start + Duration::from_millis(600)), vec![3]);
+        // The held message went first; the next one waits for another token
+        assert!(matches!(limiter.admit("pong", "a", 7, start + Duration::from_millis(700)), Admission::Held));
+        assert_eq!(limiter.release(start + Duration::from_millis(1300)), vec![7]);
+    }
+}
@@ -0,0 +1,178 @@
+/*
+
+THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
+
+Copyright 2025 Vincent Maciejewski, & M2 Tech
+Contact:
+v@m2te.ch
+mayeski@gmail.com
+https://www.linkedin.com/in/vmayeski/
+http://m2te.ch/
+
+*/
+
+//! Recording of the messages handled by a Manager's actors, for
+//! `Manager::replay_from_file`.
+//!
+//! A recording is a JSON-lines file with one record per message:
+//! `{"actor": "pong", "message_type": "Ping", "message": {...}, "timestamp_us": 1500}`,
+//! where `timestamp_us` counts from the start of the recording. Messages are
+//! serialized with the remote message registry, so only types registered
+//! with `register_remote_message` are recorded; others are skipped.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::actor::ActorRef;
+use crate::serialization::{get_type_name, serialize_message, try_deserialize_message};
+use crate::Message;
+
+struct Recording {
+    file: BufWriter<File>,
+    started: Instant,
+    /// Messages written, shared with the recording's handle
+    recorded: Arc<AtomicU64>,
+}
+
+#[derive(Default)]
+struct RecorderState {
+    /// Checked before taking the lock, so actors pay little when not recording
+    active: AtomicBool,
+    recording: Mutex<Option<Recording>>,
+}
+
+/// A Manager's message recorder, shared with its actor runtimes.
+#[derive(Clone, Default)]
+pub(crate) struct MessageRecorder {
+    state: Arc<RecorderState>,
+}
+
+impl MessageRecorder {
+    /// Record messages to a new file at `path`, replacing any recording in progress.
+    pub(crate) fn start(&self, path: &Path) -> io::Result<RecordingHandle> {
+        let file = BufWriter::new(File::create(path)?);
+        let recorded = Arc::new(AtomicU64::new(0));
+        *self.state.recording.lock().unwrap() = Some(Recording {
+            file,
+            started: Instant::now(),
+            recorded: Arc::clone(&recorded),
+        });
+        self.state.active.store(true, Ordering::Release);
+        Ok(RecordingHandle {
+            recorder: self.clone(),
+            recorded,
+        })
+    }
+
+    /// Record that `actor` is about to handle `msg`.
+    pub(crate) fn record(&self, actor: &str, msg: &dyn Message) {
+        if !self.state.active.load(Ordering::Acquire) {
+            return;
+        }
+        let Some(msg_type) = get_type_name(msg) else {
+            return;
+        };
+        let message = serialize_message(msg, &msg_type);
+
+        let mut recording = self.state.recording.lock().unwrap();
+        if let Some(recording) = recording.as_mut() {
+            let record = serde_json::json!({
+                "actor": actor,
+                "message_type": msg_type,
+                "message": message,
+                "timestamp_us": recording.started.elapsed().as_micros() as u64,
+            });
+            if writeln!(recording.file, "{}", record).is_ok() {
+                recording.recorded.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Stop the recording counting into `recorded`, unless another has replaced it.
+    fn stop(&self, recorded: &Arc<AtomicU64>) {
+        let mut current = self.state.recording.lock().unwrap();
+        if current.as_ref().is_some_and(|r| Arc::ptr_eq(&r.recorded, recorded)) {
+            self.state.active.store(false, Ordering::Release);
+            if let Some(mut recording) = current.take() {
+                let _ = recording.file.flush();
+            }
+        }
+    }
+}
+
+/// Handle to a recording started by `Manager::start_message_recording`.
+///
+/// Recording stops when `stop()` is called or the handle is dropped.
+pub struct RecordingHandle {
+    recorder: MessageRecorder,
+    recorded: Arc<AtomicU64>,
+}
+
+impl RecordingHandle {
+    /// Number of messages recorded so far
+    pub fn recorded(&self) -> u64 {
+        self.recorded.load(Ordering::Relaxed)
+    }
+
+    /// Stop recording and flush the file.
+    pub fn stop(&mut self) {
+        self.recorder.stop(&self.recorded);
+    }
+}
+
+impl Drop for RecordingHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Send the messages recorded at `path` to the actors `lookup` finds, keeping
+/// their timing at `speed` times real time.
+///
+/// Returns the number of messages sent; records for unknown actors or
+/// unregistered message types are skipped, and messages refused by a full
+/// mailbox go to the dead letter queue. Fails with `InvalidInput` if `speed`
+/// is NaN or not above `f64::EPSILON`, and with `InvalidData` if a record
+/// would be due too late to represent at that speed.
+pub(crate) fn replay<F>(path: &Path, speed: f64, lookup: F) -> io::Result<usize>
+where
+    F: Fn(&str) -> Option<ActorRef>,
+{
+    if speed.is_nan() || speed <= f64::EPSILON {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "speed must be greater than f64::EPSILON"));
+    }
+
+    let started = Instant::now();
+    let mut sent = 0;
+    for line in BufReader::new(File::open(path)?).lines() {
+        let record: serde_json::Value = serde_json::from_str(&line?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let (Some(actor), Some(msg_type), Some(timestamp_us)) = (
+            record["actor"].as_str(),
+            record["message_type"].as_str(),
+            record["timestamp_us"].as_u64(),
+        ) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "malformed recording"));
+        };
+
+        let due = Duration::try_from_secs_f64(Duration::from_micros(timestamp_us).as_secs_f64() / speed)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "recording too long for this speed"))?;
+        if let Some(wait) = due.checked_sub(started.elapsed()) {
+            thread::sleep(wait);
+        }
+
+        let (Some(actor_ref), Ok(msg)) = (lookup(actor), try_deserialize_message(msg_type, record["message"].clone()))
+        else {
+            continue;
+        };
+        if actor_ref.try_send(msg, None).is_ok() {
+            sent += 1;
+        }
+    }
+    Ok(sent)
+}
@@ -8,13 +8,43 @@ Copyright 2025 Vincent Maciejewski, & M2 Tech
 
 //! Registry client for communicating with GlobalRegistry via ZMQ.
 
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::future::{poll_fn, Future};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::task::Poll;
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use serde_json::json;
 use tokio::runtime::Runtime;
-use zeromq::{ReqSocket, Socket, SocketRecv, SocketSend};
+use tokio::sync::{mpsc, oneshot};
+use uuid::Uuid;
+use zeromq::{DealerSocket, ReqSocket, Socket, SocketRecv, SocketSend, SubSocket, ZmqMessage};
+
+/// Interval between heartbeats sent to the GlobalRegistry.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Number of recent heartbeat round trips kept for `heartbeat_stats`.
+const HEARTBEAT_RTT_WINDOW: usize = 10;
+
+/// Source of `request_id`s used to match registry replies to requests.
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// How long dropping a client waits for `deregister_all` to be acknowledged.
+const DROP_DEREGISTER_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// PUB topic the GlobalRegistry publishes manager lifecycle events on.
+const MANAGER_EVENT_TOPIC: &str = "ManagerEvent";
+
+/// Prefix of the per-actor PUB topics for actor availability events.
+const ACTOR_EVENT_TOPIC_PREFIX: &str = "ActorEvent:";
+
+/// PUB topic the GlobalRegistry fans manager announcements out on.
+const ANNOUNCE_TOPIC: &str = "Announce";
 
 /// Error types for registry operations.
 #[derive(Debug, Clone)]
@@ -47,6 +77,633 @@ impl std::fmt::Display for RegistryError {
 
 impl std::error::Error for RegistryError {}
 
+/// Error from `RegistryClient::export_registry_snapshot` and
+/// `RegistryClient::import_registry_snapshot`.
+#[derive(Debug)]
+pub enum ExportError {
+    /// The registry could not be reached or gave an unexpected reply
+    Registry(RegistryError),
+    /// The snapshot file could not be read or written
+    Io(std::io::Error),
+    /// The snapshot file is not valid JSON, or the registry rejected its contents
+    InvalidSnapshot(String),
+}
+
+impl std::fmt::Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::Registry(e) => write!(f, "{}", e),
+            ExportError::Io(e) => write!(f, "Snapshot file error: {}", e),
+            ExportError::InvalidSnapshot(reason) => write!(f, "Invalid registry snapshot: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<RegistryError> for ExportError {
+    fn from(e: RegistryError) -> Self {
+        ExportError::Registry(e)
+    }
+}
+
+impl From<std::io::Error> for ExportError {
+    fn from(e: std::io::Error) -> Self {
+        ExportError::Io(e)
+    }
+}
+
+/// An actor known to the GlobalRegistry, as returned by `RegistryClient::list_actors`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActorInfo {
+    pub actor_name: String,
+    pub endpoint: String,
+    pub manager_id: String,
+    /// Whether the actor's manager is sending heartbeats
+    pub online: bool,
+}
+
+/// A manager joining or leaving the registry, from `RegistryClient::subscribe_manager_events`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManagerLifecycleEvent {
+    /// The registry heard from this manager for the first time
+    Joined { manager_id: String, timestamp_ms: u64 },
+    /// The manager left; `graceful` is false when it missed its heartbeats
+    Left {
+        manager_id: String,
+        timestamp_ms: u64,
+        graceful: bool,
+    },
+}
+
+/// A change in an actor's availability, from `RegistryClient::subscribe`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActorEvent {
+    /// The actor was registered and can be reached at `endpoint`
+    Online { endpoint: String },
+    /// The actor's manager stopped sending heartbeats
+    Offline,
+    /// The actor was unregistered (or its manager left gracefully)
+    Unregistered,
+}
+
+/// Outcome of `RegistryClient::deregister_if_owner`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeregisterResult {
+    /// The actor was removed, or was not registered
+    Deregistered,
+    /// Another manager has registered the actor since; it was kept
+    NotOwner { current_manager: String },
+}
+
+/// Handle for a registry event subscription.
+///
+/// The subscription ends when the handle is dropped or `unsubscribe` is called.
+pub struct SubscriptionHandle {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SubscriptionHandle {
+    /// Stop receiving events.
+    pub fn unsubscribe(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.thread.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for SubscriptionHandle {
+    fn drop(&mut self) {
+        self.unsubscribe();
+    }
+}
+
+/// Heartbeat round-trip statistics, from `RegistryClient::heartbeat_stats`.
+///
+/// RTT figures cover the last 10 acknowledged heartbeats (zero before the first).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeartbeatStats {
+    /// Round-trip time of the most recent acknowledged heartbeat
+    pub last_rtt: Duration,
+    /// Average round-trip time over the window
+    pub avg_rtt: Duration,
+    /// Largest round-trip time in the window
+    pub max_rtt: Duration,
+    /// Heartbeats sent but not acknowledged within twice the heartbeat interval
+    pub missed_count: u64,
+}
+
+/// Rolling record of heartbeat round trips, shared with the heartbeat thread.
+#[derive(Default)]
+struct HeartbeatTracker {
+    rtts: VecDeque<Duration>,
+    missed_count: u64,
+}
+
+impl HeartbeatTracker {
+    fn record_rtt(&mut self, rtt: Duration) {
+        self.rtts.push_back(rtt);
+        while self.rtts.len() > HEARTBEAT_RTT_WINDOW {
+            self.rtts.pop_front();
+        }
+    }
+
+    fn record_missed(&mut self) {
+        self.missed_count += 1;
+    }
+
+    fn stats(&self) -> HeartbeatStats {
+        let total: Duration = self.rtts.iter().sum();
+        HeartbeatStats {
+            last_rtt: self.rtts.back().copied().unwrap_or_default(),
+            avg_rtt: if self.rtts.is_empty() {
+                Duration::ZERO
+            } else {
+                total / self.rtts.len() as u32
+            },
+            max_rtt: self.rtts.iter().max().copied().unwrap_or_default(),
+            missed_count: self.missed_count,
+        }
+    }
+}
+
+/// Exponential back-off policy for connecting to the registry.
+///
+/// The delay before retry `n` (starting at 0) is `base_delay * 2^n`,
+/// capped at `max_delay`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of retries after the first failed attempt (default: 5)
+    pub max_retries: u32,
+    /// Delay before the first retry (default: 100ms)
+    pub base_delay: Duration,
+    /// Upper bound for any single delay (default: 5s)
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a retry policy
+    pub fn new(max_retries: u32, base_delay: Duration, max_delay: Duration) -> Self {
+        RetryPolicy {
+            max_retries,
+            base_delay,
+            max_delay,
+        }
+    }
+
+    /// Policy that never retries
+    pub fn no_retry() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            ..Default::default()
+        }
+    }
+
+    /// Delay to wait before retry number `retry` (0-based).
+    pub fn delay_for(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// State of a RegistryClient's circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests go to the registry normally
+    Closed,
+    /// Too many consecutive failures; requests fail immediately
+    Open,
+    /// Reset timeout elapsed; a single probe request is allowed through
+    HalfOpen,
+}
+
+/// Circuit breaker settings for a RegistryClient.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive connection failures before the breaker opens (default: 5)
+    pub failure_threshold: u32,
+    /// How long the breaker stays open before allowing a probe (default: 10s)
+    pub reset_timeout: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        CircuitBreakerConfig {
+            failure_threshold: 5,
+            reset_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Tracks consecutive registry failures and fails fast while the registry is down.
+struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    probe_in_flight: bool,
+}
+
+impl CircuitBreaker {
+    fn new(config: CircuitBreakerConfig) -> Self {
+        CircuitBreaker {
+            config,
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            probe_in_flight: false,
+        }
+    }
+
+    /// Current state, moving Open to HalfOpen once the reset timeout has elapsed.
+    fn state(&mut self) -> CircuitState {
+        if self.state == CircuitState::Open {
+            let elapsed = self.opened_at.map(|t| t.elapsed()).unwrap_or_default();
+            if elapsed >= self.config.reset_timeout {
+                self.state = CircuitState::HalfOpen;
+                self.probe_in_flight = false;
+            }
+        }
+        self.state
+    }
+
+    /// Whether a request may be sent now.
+    fn allow_request(&mut self) -> bool {
+        match self.state() {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => {
+                if self.probe_in_flight {
+                    false
+                } else {
+                    self.probe_in_flight = true;
+                    true
+                }
+            }
+        }
+    }
+
+    fn record_success(&mut self) {
+        self.state = CircuitState::Closed;
+        self.consecutive_failures = 0;
+        self.opened_at = None;
+        self.probe_in_flight = false;
+    }
+
+    fn record_failure(&mut self) {
+        self.consecutive_failures += 1;
+        if self.state == CircuitState::HalfOpen
+            || self.consecutive_failures >= self.config.failure_threshold
+        {
+            self.state = CircuitState::Open;
+            self.opened_at = Some(Instant::now());
+            self.probe_in_flight = false;
+        }
+    }
+}
+
+/// How `RegistryClient::lookup_with_policy` picks among instances of an actor.
+///
+/// Instances are the registrations of one name from different datacenters,
+/// and its weighted registrations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoadBalancePolicy {
+    /// Same as `lookup`: online first, then same datacenter
+    #[default]
+    Default,
+    /// The online instance with the most remaining capacity
+    /// (`max_capacity - current_load`); instances without a capacity come last
+    LeastLoaded,
+    /// An online instance at random, in proportion to its priority (see
+    /// `RegistryClient::register_with_priority`); unweighted instances count 1
+    Weighted,
+}
+
+/// Which registered version of an actor `RegistryClient::lookup_versioned` finds.
+///
+/// Among the versions that match, online ones win, then the newest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionConstraint {
+    /// This version or a newer one
+    AtLeast(u32),
+    /// Exactly this version
+    Exact(u32),
+    /// The newest version
+    Latest,
+}
+
+impl VersionConstraint {
+    /// The constraint as sent in a `LookupActor` request
+    fn to_json(self) -> serde_json::Value {
+        match self {
+            VersionConstraint::AtLeast(version) => json!({"constraint": "AtLeast", "version": version}),
+            VersionConstraint::Exact(version) => json!({"constraint": "Exact", "version": version}),
+            VersionConstraint::Latest => json!({"constraint": "Latest"}),
+        }
+    }
+}
+
+/// Options for `RegistryClient::wait_for_actor_with`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaitOptions {
+    /// Time between lookups (default: 100ms)
+    pub poll_interval: Duration,
+    /// Give up after this long (default: 10s)
+    pub timeout: Duration,
+    /// Return as soon as the actor is registered, even if its manager is offline (default: false)
+    pub accept_offline: bool,
+}
+
+impl Default for WaitOptions {
+    fn default() -> Self {
+        WaitOptions {
+            poll_interval: Duration::from_millis(100),
+            timeout: Duration::from_secs(10),
+            accept_offline: false,
+        }
+    }
+}
+
+impl WaitOptions {
+    /// Set the time between lookups
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Set how long to wait before giving up (`Duration::MAX` never gives up)
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Accept an actor whose manager is offline
+    pub fn with_accept_offline(mut self, accept: bool) -> Self {
+        self.accept_offline = accept;
+        self
+    }
+}
+
+/// Configuration for a RegistryClient.
+#[derive(Debug, Clone, Default)]
+pub struct RegistryClientConfig {
+    /// How long a successful `lookup` result is reused without asking the
+    /// registry again (None = caching disabled, the default)
+    pub cache_ttl: Option<Duration>,
+    /// Back-off used when connecting to the registry (requests and heartbeats)
+    pub retry_policy: RetryPolicy,
+    /// When to stop contacting an unreachable registry and fail fast
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// PUB endpoint of the registry's manager events
+    /// (None = registry endpoint with the port incremented by one, the default)
+    pub events_endpoint: Option<String>,
+    /// Prefix isolating this application's actors in a shared registry.
+    /// Actor names are sent as `"<namespace>/<name>"` (empty = no prefix, the default)
+    pub namespace: String,
+    /// Datacenter this manager runs in, e.g. `"us-east-1"` (None = not geo-aware, the default).
+    /// Registrations are annotated with it and lookups prefer actors in it
+    pub datacenter: Option<String>,
+}
+
+impl RegistryClientConfig {
+    /// Enable the local lookup cache with the given time-to-live
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Set the connection retry policy
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Set the endpoint manager lifecycle events are published on
+    pub fn with_events_endpoint(mut self, endpoint: &str) -> Self {
+        self.events_endpoint = Some(endpoint.to_string());
+        self
+    }
+
+    /// Set the namespace actor names are registered and looked up in
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.namespace = namespace.to_string();
+        self
+    }
+
+    /// Set the circuit breaker thresholds
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = breaker;
+        self
+    }
+
+    /// Set the datacenter registrations are annotated with and lookups prefer
+    pub fn with_datacenter(mut self, datacenter: &str) -> Self {
+        self.datacenter = Some(datacenter.to_string());
+        self
+    }
+}
+
+/// Wire format of registry protocol messages.
+///
+/// The registry speaks JSON by default (`JsonSerializer`). Implement this to
+/// talk to a registry using another encoding, e.g. msgpack, and pass it to
+/// `RegistryClient::new_with_serializer`. Used for requests, replies,
+/// heartbeats and published events.
+pub trait RegistrySerializer: Send + Sync {
+    /// Encode a message for the wire
+    fn serialize(&self, value: &serde_json::Value) -> Vec<u8>;
+    /// Decode a message from the wire; return `Value::Null` for undecodable bytes
+    fn deserialize(&self, bytes: &[u8]) -> serde_json::Value;
+}
+
+/// The default registry wire format, JSON with `serde_json`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonSerializer;
+
+impl RegistrySerializer for JsonSerializer {
+    fn serialize(&self, value: &serde_json::Value) -> Vec<u8> {
+        value.to_string().into_bytes()
+    }
+
+    fn deserialize(&self, bytes: &[u8]) -> serde_json::Value {
+        serde_json::from_slice(bytes).unwrap_or(serde_json::Value::Null)
+    }
+}
+
+/// Connect a socket to the registry, retrying with exponential back-off.
+async fn connect_with_retry<S: Socket>(endpoint: &str, policy: &RetryPolicy) -> Result<S, RegistryError> {
+    let mut retry = 0;
+    loop {
+        let mut socket = S::new();
+        if socket.connect(endpoint).await.is_ok() {
+            // Small delay to let connection establish
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            return Ok(socket);
+        }
+
+        if retry >= policy.max_retries {
+            return Err(RegistryError::ConnectionError(format!(
+                "exhausted {} retries",
+                policy.max_retries
+            )));
+        }
+        tokio::time::sleep(policy.delay_for(retry)).await;
+        retry += 1;
+    }
+}
+
+/// Channel a request's reply (or failure) is delivered on.
+type ReplySender = oneshot::Sender<Result<serde_json::Value, RegistryError>>;
+
+/// A request queued for the registry connection task.
+struct PendingRequest {
+    request_id: u64,
+    data: Vec<u8>,
+    reply: ReplySender,
+}
+
+/// Shared DEALER connection to the registry.
+///
+/// A background task owns the socket; requests are queued to it and replies
+/// are matched back to their caller by `request_id`, so several threads can
+/// have requests in flight at once.
+struct RegistryConnection {
+    requests: mpsc::UnboundedSender<PendingRequest>,
+}
+
+/// Body of the connection task: send queued requests and route replies.
+///
+/// Ends when every RegistryClient using the connection is gone or the socket fails.
+async fn run_connection(
+    mut socket: DealerSocket,
+    mut requests: mpsc::UnboundedReceiver<PendingRequest>,
+    serializer: Arc<dyn RegistrySerializer>,
+) {
+    // Requests sent and not answered yet, oldest first
+    let mut waiting: VecDeque<(u64, ReplySender)> = VecDeque::new();
+
+    loop {
+        tokio::select! {
+            request = requests.recv() => {
+                let request = match request {
+                    Some(request) => request,
+                    None => break,
+                };
+                // Empty delimiter frame, as a REQ socket would send
+                let mut frames = ZmqMessage::from(Vec::<u8>::new());
+                frames.push_back(request.data.into());
+                match socket.send(frames).await {
+                    Ok(()) => {
+                        // Callers that timed out dropped their receiver: forget them
+                        waiting.retain(|(_, reply)| !reply.is_closed());
+                        waiting.push_back((request.request_id, request.reply));
+                    }
+                    Err(e) => {
+                        let _ = request.reply.send(Err(RegistryError::ConnectionError(format!("Send failed: {}", e))));
+                        break;
+                    }
+                }
+            }
+            reply = socket.recv() => {
+                let reply = match reply {
+                    Ok(reply) => reply,
+                    Err(_) => break,
+                };
+                let data = reply.iter().last().map(|b| b.as_ref()).unwrap_or(&[]);
+                let value = serializer.deserialize(data);
+                waiting.retain(|(_, reply)| !reply.is_closed());
+                // Replies with an unknown request_id are from timed-out callers. A reply
+                // without one (e.g. an error for an undecodable request) is answered
+                // in order, so it is for the oldest request
+                let index = match value.get("request_id").and_then(|v| v.as_u64()) {
+                    Some(id) => waiting.iter().position(|(request_id, _)| *request_id == id),
+                    None => (!waiting.is_empty()).then_some(0),
+                };
+                if let Some((_, tx)) = index.and_then(|index| waiting.remove(index)) {
+                    let _ = tx.send(Ok(value));
+                }
+            }
+        }
+    }
+
+    for (_, tx) in waiting {
+        let _ = tx.send(Err(RegistryError::ConnectionError("Connection closed".to_string())));
+    }
+}
+
+/// Requests a hedged client may send to several nodes: lookups, which are
+/// safe to repeat. Writes only go to the primary.
+const HEDGED_REQUESTS: [&str; 3] = ["LookupActor", "LookupMany", "LookupGroup"];
+
+/// The other registry nodes of a hedged client (see `RegistryClient::new_hedged`).
+struct Hedge {
+    /// Endpoint and shared connection of each node after the primary
+    nodes: Vec<(String, Arc<Mutex<Option<RegistryConnection>>>)>,
+    hedge_after: Duration,
+    /// Requests that were also sent to the other nodes
+    count: AtomicU64,
+}
+
+/// Wait for the first successful reply among `replies`.
+///
+/// Fails with the last error if every reply fails.
+async fn first_reply(
+    mut replies: Vec<oneshot::Receiver<Result<serde_json::Value, RegistryError>>>,
+) -> Result<serde_json::Value, RegistryError> {
+    let mut last_error = RegistryError::ConnectionError("Connection closed".to_string());
+    poll_fn(|cx| {
+        let mut i = 0;
+        while i < replies.len() {
+            match Pin::new(&mut replies[i]).poll(cx) {
+                Poll::Ready(Ok(Ok(reply))) => return Poll::Ready(Ok(reply)),
+                Poll::Ready(Ok(Err(e))) => {
+                    last_error = e;
+                    replies.swap_remove(i);
+                }
+                Poll::Ready(Err(_)) => {
+                    replies.swap_remove(i);
+                }
+                Poll::Pending => i += 1,
+            }
+        }
+        if replies.is_empty() {
+            Poll::Ready(Err(last_error.clone()))
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// Queue a request on a connection, returning where its reply will arrive.
+fn queue_request(
+    requests: &mpsc::UnboundedSender<PendingRequest>,
+    request_id: u64,
+    data: Vec<u8>,
+) -> Result<oneshot::Receiver<Result<serde_json::Value, RegistryError>>, RegistryError> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    requests
+        .send(PendingRequest {
+            request_id,
+            data,
+            reply: reply_tx,
+        })
+        .map_err(|_| RegistryError::ConnectionError("Connection closed".to_string()))?;
+    Ok(reply_rx)
+}
+
 /// Client for communicating with the GlobalRegistry via ZMQ.
 ///
 /// The RegistryClient:
@@ -55,7 +712,9 @@ impl std::error::Error for RegistryError {}
 /// - Handles registration of local actors
 ///
 /// # Example
-/// ```ignore
+/// ```no_run
+/// # use actors::{RegistryClient, RegistryError};
+/// # fn main() -> Result<(), RegistryError> {
 /// let client = RegistryClient::new("MyManager", "tcp://localhost:5555");
 /// client.start_heartbeat();
 ///
@@ -66,14 +725,31 @@ impl std::error::Error for RegistryError {}
 /// let endpoint = client.lookup("OtherActor")?;
 ///
 /// client.stop_heartbeat();
+/// # Ok(())
+/// # }
 /// ```
 pub struct RegistryClient {
     manager_id: String,
     registry_endpoint: String,
-    socket: Arc<Mutex<Option<ReqSocket>>>,
+    connection: Arc<Mutex<Option<RegistryConnection>>>,
     runtime: Arc<Runtime>,
     heartbeat_handle: Mutex<Option<JoinHandle<()>>>,
+    heartbeat_tracker: Arc<Mutex<HeartbeatTracker>>,
+    /// Sent with every heartbeat (see `set_heartbeat_metadata`)
+    heartbeat_metadata: Arc<Mutex<HashMap<String, String>>>,
     running: Arc<Mutex<bool>>,
+    config: RegistryClientConfig,
+    /// actor_name -> (endpoint, time resolved)
+    cache: Arc<Mutex<HashMap<String, (String, Instant)>>>,
+    breaker: Arc<Mutex<CircuitBreaker>>,
+    /// Added to every request sent through this client (see `with_correlation_id`)
+    correlation_id: Option<Uuid>,
+    /// Glob patterns of names this client refuses to register
+    name_blacklist: Arc<Mutex<Vec<String>>>,
+    /// Other registry nodes, for a client from `new_hedged`
+    hedge: Option<Arc<Hedge>>,
+    /// Wire format of registry messages
+    serializer: Arc<dyn RegistrySerializer>,
 }
 
 impl RegistryClient {
@@ -83,313 +759,2647 @@ impl RegistryClient {
     /// * `manager_id` - Unique identifier for this manager
     /// * `registry_endpoint` - ZMQ endpoint of the GlobalRegistry (e.g., "tcp://localhost:5555")
     pub fn new(manager_id: &str, registry_endpoint: &str) -> Self {
+        Self::with_config(manager_id, registry_endpoint, RegistryClientConfig::default())
+    }
+
+    /// Create a registry client that prefers actors in its own datacenter.
+    ///
+    /// Actors it registers are annotated with `datacenter`, so the same name
+    /// can be registered once per datacenter. `lookup` returns an online
+    /// instance in `datacenter` if there is one and falls back to any online
+    /// instance otherwise.
+    pub fn new_geo_aware(manager_id: &str, registry_endpoint: &str, datacenter: &str) -> Self {
+        let config = RegistryClientConfig::default().with_datacenter(datacenter);
+        Self::with_config(manager_id, registry_endpoint, config)
+    }
+
+    /// Create a new registry client with the given configuration.
+    ///
+    /// # Arguments
+    /// * `manager_id` - Unique identifier for this manager
+    /// * `registry_endpoint` - ZMQ endpoint of the GlobalRegistry
+    /// * `config` - Client options (lookup caching, ...)
+    pub fn with_config(manager_id: &str, registry_endpoint: &str, config: RegistryClientConfig) -> Self {
         let runtime = Arc::new(Runtime::new().expect("Failed to create runtime"));
+        let breaker = Arc::new(Mutex::new(CircuitBreaker::new(config.circuit_breaker.clone())));
 
         RegistryClient {
             manager_id: manager_id.to_string(),
             registry_endpoint: registry_endpoint.to_string(),
-            socket: Arc::new(Mutex::new(None)),
+            connection: Arc::new(Mutex::new(None)),
             runtime,
             heartbeat_handle: Mutex::new(None),
+            heartbeat_tracker: Arc::new(Mutex::new(HeartbeatTracker::default())),
+            heartbeat_metadata: Arc::new(Mutex::new(HashMap::new())),
             running: Arc::new(Mutex::new(false)),
+            config,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+            breaker,
+            correlation_id: None,
+            name_blacklist: Arc::new(Mutex::new(Vec::new())),
+            hedge: None,
+            serializer: Arc::new(JsonSerializer),
         }
     }
 
-    /// Connect the ZMQ socket (lazy initialization).
-    fn ensure_connected(&self) -> Result<(), RegistryError> {
-        let mut socket_guard = self.socket.lock().unwrap();
-        if socket_guard.is_none() {
-            let endpoint = self.registry_endpoint.clone();
-            let socket = self.runtime.block_on(async {
-                let mut socket = ReqSocket::new();
-                socket.connect(&endpoint).await.map_err(|e| {
-                    RegistryError::ConnectionError(format!("Failed to connect: {}", e))
-                })?;
-                // Small delay to let connection establish
-                tokio::time::sleep(Duration::from_millis(50)).await;
-                Ok::<ReqSocket, RegistryError>(socket)
-            })?;
-            *socket_guard = Some(socket);
-        }
-        Ok(())
+    /// Create a registry client that encodes registry messages with `serializer`
+    /// instead of JSON.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{RegistryClient, RegistrySerializer};
+    /// # struct MsgpackSerializer;
+    /// # impl RegistrySerializer for MsgpackSerializer {
+    /// #     fn serialize(&self, value: &serde_json::Value) -> Vec<u8> { rmp_serde::to_vec(value).unwrap() }
+    /// #     fn deserialize(&self, bytes: &[u8]) -> serde_json::Value {
+    /// #         rmp_serde::from_slice(bytes).unwrap_or(serde_json::Value::Null)
+    /// #     }
+    /// # }
+    /// let client = RegistryClient::new_with_serializer("MyManager", "tcp://localhost:5555", Box::new(MsgpackSerializer));
+    /// ```
+    pub fn new_with_serializer(
+        manager_id: &str,
+        registry_endpoint: &str,
+        serializer: Box<dyn RegistrySerializer>,
+    ) -> Self {
+        let mut client = Self::new(manager_id, registry_endpoint);
+        client.serializer = Arc::from(serializer);
+        client
     }
 
-    /// Send a request and receive a reply.
-    fn send_recv(&self, msg: serde_json::Value) -> Result<serde_json::Value, RegistryError> {
-        self.ensure_connected()?;
-
-        let mut socket_guard = self.socket.lock().unwrap();
-        let socket = socket_guard.as_mut().unwrap();
+    /// Create a client for a replicated registry that hedges slow requests.
+    ///
+    /// Each request goes to the first of `endpoints`. If it has not answered
+    /// within `hedge_after`, the request is also sent to all the others and
+    /// the first reply wins; later replies are ignored. `hedge_count` tells
+    /// how often that happened.
+    ///
+    /// # Panics
+    /// Panics if `endpoints` is empty.
+    pub fn new_hedged(manager_id: &str, endpoints: Vec<String>, hedge_after: Duration) -> Self {
+        let mut endpoints = endpoints.into_iter();
+        let primary = endpoints.next().expect("new_hedged needs at least one endpoint");
+        let mut client = Self::new(manager_id, &primary);
+        client.hedge = Some(Arc::new(Hedge {
+            nodes: endpoints.map(|endpoint| (endpoint, Arc::new(Mutex::new(None)))).collect(),
+            hedge_after,
+            count: AtomicU64::new(0),
+        }));
+        client
+    }
 
-        self.runtime.block_on(async {
-            let data = msg.to_string().into_bytes();
-            socket.send(data.into()).await.map_err(|e| {
-                RegistryError::ConnectionError(format!("Send failed: {}", e))
-            })?;
+    /// How many requests were also sent to the other nodes of a hedged client.
+    ///
+    /// Always 0 for a client not created with `new_hedged`.
+    pub fn hedge_count(&self) -> u64 {
+        self.hedge.as_ref().map_or(0, |hedge| hedge.count.load(Ordering::Relaxed))
+    }
 
-            let reply = socket.recv().await.map_err(|e| {
-                RegistryError::ConnectionError(format!("Recv failed: {}", e))
-            })?;
+    /// Get a client that tags every request with `id`.
+    ///
+    /// The returned client shares this client's connection, lookup cache and
+    /// circuit breaker, but not its heartbeat. The registry echoes the ID back
+    /// in each reply.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{RegistryClient, RegistryError, Uuid};
+    /// # fn example(client: &RegistryClient, correlation_id: Uuid) -> Result<(), RegistryError> {
+    /// let traced = client.with_correlation_id(correlation_id);
+    /// let endpoint = traced.lookup("OtherActor")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_correlation_id(&self, id: Uuid) -> RegistryClient {
+        RegistryClient {
+            manager_id: self.manager_id.clone(),
+            registry_endpoint: self.registry_endpoint.clone(),
+            connection: Arc::clone(&self.connection),
+            runtime: Arc::clone(&self.runtime),
+            heartbeat_handle: Mutex::new(None),
+            heartbeat_tracker: Arc::new(Mutex::new(HeartbeatTracker::default())),
+            heartbeat_metadata: Arc::clone(&self.heartbeat_metadata),
+            running: Arc::new(Mutex::new(false)),
+            config: self.config.clone(),
+            cache: Arc::clone(&self.cache),
+            breaker: Arc::clone(&self.breaker),
+            correlation_id: Some(id),
+            name_blacklist: Arc::clone(&self.name_blacklist),
+            hedge: self.hedge.clone(),
+            serializer: Arc::clone(&self.serializer),
+        }
+    }
 
-            let reply_data = reply.get(0).map(|b| b.as_ref()).unwrap_or(&[]);
-            let reply_str = String::from_utf8(reply_data.to_vec()).map_err(|e| {
-                RegistryError::ConnectionError(format!("Invalid UTF-8: {}", e))
-            })?;
+    /// Name of an actor as stored in the registry (with the namespace prefix, if any).
+    fn qualify(&self, actor_name: &str) -> String {
+        if self.config.namespace.is_empty() {
+            actor_name.to_string()
+        } else {
+            format!("{}/{}", self.config.namespace, actor_name)
+        }
+    }
 
-            serde_json::from_str(&reply_str).map_err(|e| {
-                RegistryError::ConnectionError(format!("Invalid JSON: {}", e))
-            })
-        })
+    /// Datacenter sent with registrations and lookups (empty when not geo-aware).
+    fn datacenter(&self) -> &str {
+        self.config.datacenter.as_deref().unwrap_or("")
     }
 
-    /// Start the heartbeat background thread.
+    /// Remove this client's namespace prefix from a registry name.
     ///
-    /// Sends Heartbeat messages every 2 seconds to keep actors marked as online.
-    pub fn start_heartbeat(&self) {
-        let mut running = self.running.lock().unwrap();
-        if *running {
-            return; // Already running
+    /// Names outside the namespace are returned unchanged.
+    pub fn strip_namespace<'a>(&self, full_name: &'a str) -> &'a str {
+        if self.config.namespace.is_empty() {
+            return full_name;
         }
-        *running = true;
-        drop(running);
+        full_name
+            .strip_prefix(self.config.namespace.as_str())
+            .and_then(|rest| rest.strip_prefix('/'))
+            .unwrap_or(full_name)
+    }
 
-        let manager_id = self.manager_id.clone();
-        let registry_endpoint = self.registry_endpoint.clone();
-        let running_flag = Arc::clone(&self.running);
+    /// Whether a registry name belongs to this client's namespace.
+    fn in_namespace(&self, full_name: &str) -> bool {
+        self.config.namespace.is_empty() || self.strip_namespace(full_name) != full_name
+    }
 
-        let handle = thread::spawn(move || {
-            let rt = Runtime::new().expect("Failed to create heartbeat runtime");
+    /// Get the correlation ID added to requests, if any.
+    pub fn correlation_id(&self) -> Option<Uuid> {
+        self.correlation_id
+    }
 
-            rt.block_on(async {
-                // Create dedicated socket for heartbeats
-                let mut socket = ReqSocket::new();
-                if socket.connect(&registry_endpoint).await.is_err() {
-                    return;
-                }
-                tokio::time::sleep(Duration::from_millis(50)).await;
+    /// Get the client configuration.
+    pub fn config(&self) -> &RegistryClientConfig {
+        &self.config
+    }
 
-                while *running_flag.lock().unwrap() {
-                    // Send heartbeat
-                    let timestamp_ms = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis() as u64;
-
-                    let msg = json!({
-                        "message_type": "Heartbeat",
-                        "manager_id": manager_id,
-                        "timestamp_ms": timestamp_ms
-                    });
+    /// Get the current circuit breaker state.
+    pub fn breaker_state(&self) -> CircuitState {
+        self.breaker.lock().unwrap().state()
+    }
 
-                    let data = msg.to_string().into_bytes();
-                    if socket.send(data.into()).await.is_ok() {
-                        let _ = socket.recv().await; // Ignore reply
-                    }
+    /// Send a request and receive a reply, going through the circuit breaker.
+    ///
+    /// Connection failures count towards opening the breaker; while it is open
+    /// this fails immediately without contacting the registry.
+    fn send_recv(&self, msg: serde_json::Value) -> Result<serde_json::Value, RegistryError> {
+        self.send_recv_with_timeout(msg, None)
+    }
 
-                    // Sleep for 2 seconds
-                    tokio::time::sleep(Duration::from_secs(2)).await;
-                }
-            });
-        });
+    /// Like `send_recv`, but gives up with `RegistryError::Timeout` after `timeout`.
+    fn send_recv_with_timeout(
+        &self,
+        mut msg: serde_json::Value,
+        timeout: Option<Duration>,
+    ) -> Result<serde_json::Value, RegistryError> {
+        if let Some(id) = self.correlation_id {
+            msg["correlation_id"] = json!(id.to_string());
+        }
 
-        *self.heartbeat_handle.lock().unwrap() = Some(handle);
+        if !self.breaker.lock().unwrap().allow_request() {
+            return Err(RegistryError::ConnectionError("circuit open".to_string()));
+        }
+
+        let result = self.send_recv_inner(msg, timeout);
+
+        match result {
+            Ok(_) => self.breaker.lock().unwrap().record_success(),
+            Err(_) => self.breaker.lock().unwrap().record_failure(),
+        }
+        result
     }
 
-    /// Stop the heartbeat background thread.
-    pub fn stop_heartbeat(&self) {
-        *self.running.lock().unwrap() = false;
+    /// Run `fut` on the client runtime, failing with `RegistryError::Timeout` after `deadline`.
+    fn block_on_until<T>(
+        &self,
+        fut: impl Future<Output = Result<T, RegistryError>>,
+        deadline: Option<Instant>,
+    ) -> Result<T, RegistryError> {
+        match deadline {
+            None => self.runtime.block_on(fut),
+            Some(deadline) => self.runtime.block_on(async {
+                let limit = deadline.saturating_duration_since(Instant::now());
+                tokio::time::timeout(limit, fut).await.unwrap_or_else(|_| {
+                    Err(RegistryError::Timeout(format!("no reply within {:?}", limit)))
+                })
+            }),
+        }
+    }
 
-        if let Some(handle) = self.heartbeat_handle.lock().unwrap().take() {
-            let _ = handle.join();
+    /// Get the request queue of the shared connection, connecting if needed.
+    ///
+    /// Failed connects are retried according to the configured `RetryPolicy`.
+    fn connection(&self, deadline: Option<Instant>) -> Result<mpsc::UnboundedSender<PendingRequest>, RegistryError> {
+        self.connection_to(&self.connection, &self.registry_endpoint, deadline)
+    }
+
+    /// Get the request queue of the connection in `slot` to `endpoint`, connecting if needed.
+    fn connection_to(
+        &self,
+        slot: &Mutex<Option<RegistryConnection>>,
+        endpoint: &str,
+        deadline: Option<Instant>,
+    ) -> Result<mpsc::UnboundedSender<PendingRequest>, RegistryError> {
+        let mut connection = slot.lock().unwrap();
+
+        // Reconnect if the connection task has stopped
+        if let Some(conn) = connection.as_ref() {
+            if !conn.requests.is_closed() {
+                return Ok(conn.requests.clone());
+            }
         }
+
+        let socket: DealerSocket = self.block_on_until(
+            connect_with_retry(endpoint, &self.config.retry_policy),
+            deadline,
+        )?;
+        Ok(self.start_connection(&mut connection, socket))
     }
 
-    /// Register an actor with the GlobalRegistry.
+    /// Start the connection task for `socket` and keep its request queue in `connection`.
+    fn start_connection(
+        &self,
+        connection: &mut Option<RegistryConnection>,
+        socket: DealerSocket,
+    ) -> mpsc::UnboundedSender<PendingRequest> {
+        let (requests, requests_rx) = mpsc::unbounded_channel();
+        self.runtime.spawn(run_connection(socket, requests_rx, Arc::clone(&self.serializer)));
+
+        *connection = Some(RegistryConnection {
+            requests: requests.clone(),
+        });
+        requests
+    }
+
+    /// Get the request queues of the other nodes of `hedge`, connecting to
+    /// the nodes not connected yet concurrently.
     ///
-    /// # Arguments
-    /// * `actor_name` - Unique name for the actor
-    /// * `endpoint` - ZMQ endpoint where the actor can be reached
+    /// Nodes that cannot be reached by `deadline` are left out.
+    fn hedge_connections(
+        &self,
+        hedge: &Hedge,
+        deadline: Option<Instant>,
+    ) -> Vec<mpsc::UnboundedSender<PendingRequest>> {
+        let open_requests = |slot: &Mutex<Option<RegistryConnection>>| {
+            let connection = slot.lock().unwrap();
+            connection.as_ref().map(|conn| conn.requests.clone()).filter(|requests| !requests.is_closed())
+        };
+        let connects: Vec<_> = hedge
+            .nodes
+            .iter()
+            .map(|(endpoint, slot)| {
+                let connect = open_requests(slot).is_none().then(|| {
+                    let endpoint = endpoint.clone();
+                    let policy = self.config.retry_policy.clone();
+                    self.runtime.spawn(async move { connect_with_retry::<DealerSocket>(&endpoint, &policy).await })
+                });
+                (slot, connect)
+            })
+            .collect();
+
+        connects
+            .into_iter()
+            .filter_map(|(slot, connect)| {
+                let Some(connect) = connect else {
+                    return open_requests(slot);
+                };
+                let socket = self
+                    .block_on_until(
+                        async {
+                            connect.await.unwrap_or_else(|_| {
+                                Err(RegistryError::ConnectionError("Connect task failed".to_string()))
+                            })
+                        },
+                        deadline,
+                    )
+                    .ok()?;
+                let mut connection = slot.lock().unwrap();
+                // Another request may have connected meanwhile
+                match connection.as_ref().filter(|conn| !conn.requests.is_closed()) {
+                    Some(conn) => Some(conn.requests.clone()),
+                    None => Some(self.start_connection(&mut connection, socket)),
+                }
+            })
+            .collect()
+    }
+
+    /// Send a request and wait for the reply with the same `request_id`.
     ///
-    /// # Returns
-    /// * `Ok(())` if registration succeeded
-    /// * `Err(RegistryError)` if registration failed
-    pub fn register(&self, actor_name: &str, endpoint: &str) -> Result<(), RegistryError> {
+    /// Callers on other threads can have their own requests in flight meanwhile.
+    /// A hedged client sends lookups to its other nodes as well if the
+    /// primary is slow to answer.
+    fn send_recv_inner(
+        &self,
+        mut msg: serde_json::Value,
+        timeout: Option<Duration>,
+    ) -> Result<serde_json::Value, RegistryError> {
+        // None if the timeout is too long to represent: wait forever
+        let deadline = timeout.and_then(|limit| Instant::now().checked_add(limit));
+        let requests = self.connection(deadline)?;
+
+        let request_id = NEXT_REQUEST_ID.fetch_add(1, Ordering::SeqCst);
+        msg["request_id"] = json!(request_id);
+        let data = self.serializer.serialize(&msg);
+        let mut reply_rx = queue_request(&requests, request_id, data.clone())?;
+
+        let message_type = msg["message_type"].as_str().unwrap_or("");
+        let hedge = self.hedge.as_ref().filter(|_| HEDGED_REQUESTS.contains(&message_type));
+        // Give the primary `hedge_after` to answer before asking the other nodes
+        let hedge_at = hedge.and_then(|hedge| Instant::now().checked_add(hedge.hedge_after));
+        let (Some(hedge), Some(hedge_at)) = (hedge, hedge_at) else {
+            return self.block_on_until(first_reply(vec![reply_rx]), deadline);
+        };
+        let primary_deadline = deadline.map_or(hedge_at, |deadline| deadline.min(hedge_at));
+        let primary = self.block_on_until(
+            async {
+                (&mut reply_rx)
+                    .await
+                    .unwrap_or_else(|_| Err(RegistryError::ConnectionError("Connection closed".to_string())))
+            },
+            Some(primary_deadline),
+        );
+        match primary {
+            Err(RegistryError::Timeout(_)) if primary_deadline == hedge_at => {}
+            result => return result,
+        }
+
+        hedge.count.fetch_add(1, Ordering::Relaxed);
+        let mut replies = vec![reply_rx];
+        for requests in self.hedge_connections(hedge, deadline) {
+            if let Ok(reply) = queue_request(&requests, request_id, data.clone()) {
+                replies.push(reply);
+            }
+        }
+        self.block_on_until(first_reply(replies), deadline)
+    }
+
+    /// Check that the registry is reachable, returning the round-trip time.
+    pub fn ping_registry(&self) -> Result<Duration, RegistryError> {
+        self.ping_registry_with_timeout(None)
+    }
+
+    /// Returns true if the registry answers a ping within 500 ms.
+    pub fn is_registry_alive(&self) -> bool {
+        self.ping_registry_with_timeout(Some(Duration::from_millis(500))).is_ok()
+    }
+
+    fn ping_registry_with_timeout(&self, timeout: Option<Duration>) -> Result<Duration, RegistryError> {
         let msg = json!({
-            "message_type": "RegisterActor",
-            "manager_id": self.manager_id,
-            "actor_name": actor_name,
-            "actor_endpoint": endpoint
+            "message_type": "Ping"
         });
 
-        let reply = self.send_recv(msg)?;
+        let sent_at = Instant::now();
+        let reply = self.send_recv_with_timeout(msg, timeout)?;
+        let rtt = sent_at.elapsed();
 
         match reply.get("message_type").and_then(|v| v.as_str()) {
-            Some("RegistrationOk") => Ok(()),
-            Some("RegistrationFailed") => {
-                let reason = reply.get("reason")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-                Err(RegistryError::RegistrationFailed {
-                    actor_name: actor_name.to_string(),
-                    reason: reason.to_string(),
-                })
-            }
+            Some("Pong") => Ok(rtt),
             _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
         }
     }
 
-    /// Lookup an actor by name.
+    /// Start the heartbeat background thread.
     ///
-    /// # Arguments
-    /// * `actor_name` - Name of the actor to find
+    /// Sends Heartbeat messages every 2 seconds to keep actors marked as online.
+    /// Round-trip times of the acknowledgements are available from `heartbeat_stats`.
+    pub fn start_heartbeat(&self) {
+        let mut running = self.running.lock().unwrap();
+        if *running {
+            return; // Already running
+        }
+        *running = true;
+        drop(running);
+
+        let manager_id = self.manager_id.clone();
+        let registry_endpoint = self.registry_endpoint.clone();
+        let running_flag = Arc::clone(&self.running);
+        let retry_policy = self.config.retry_policy.clone();
+        let tracker = Arc::clone(&self.heartbeat_tracker);
+        let metadata = Arc::clone(&self.heartbeat_metadata);
+        let serializer = Arc::clone(&self.serializer);
+
+        let handle = thread::spawn(move || {
+            let rt = Runtime::new().expect("Failed to create heartbeat runtime");
+
+            rt.block_on(async {
+                // Create dedicated socket for heartbeats
+                let mut socket: ReqSocket = match connect_with_retry(&registry_endpoint, &retry_policy).await {
+                    Ok(socket) => socket,
+                    Err(_) => return,
+                };
+
+                while *running_flag.lock().unwrap() {
+                    // Send heartbeat
+                    let msg = heartbeat_message(&manager_id, &metadata.lock().unwrap());
+                    let data = serializer.serialize(&msg);
+                    let sent_at = Instant::now();
+                    if socket.send(data.into()).await.is_ok() {
+                        match tokio::time::timeout(HEARTBEAT_INTERVAL * 2, socket.recv()).await {
+                            Ok(Ok(_)) => tracker.lock().unwrap().record_rtt(sent_at.elapsed()),
+                            _ => {
+                                tracker.lock().unwrap().record_missed();
+                                // The REQ socket is stuck waiting for a reply; start over
+                                socket = match connect_with_retry(&registry_endpoint, &retry_policy).await {
+                                    Ok(socket) => socket,
+                                    Err(_) => return,
+                                };
+                            }
+                        }
+                    }
+
+                    tokio::time::sleep(HEARTBEAT_INTERVAL).await;
+                }
+            });
+        });
+
+        *self.heartbeat_handle.lock().unwrap() = Some(handle);
+    }
+
+    /// Tell the registry this manager is shutting down.
     ///
-    /// # Returns
-    /// * `Ok(endpoint)` if actor found and online
-    /// * `Err(RegistryError::NotFound)` if actor not registered
-    /// * `Err(RegistryError::Offline)` if actor's manager missed heartbeats
-    pub fn lookup(&self, actor_name: &str) -> Result<String, RegistryError> {
+    /// The registry removes the manager's actors and publishes a graceful
+    /// `Left` event. Call `stop_heartbeat` first so the manager does not rejoin.
+    pub fn leave(&self) -> Result<(), RegistryError> {
         let msg = json!({
-            "message_type": "LookupActor",
-            "actor_name": actor_name
+            "message_type": "ManagerLeave",
+            "manager_id": self.manager_id
         });
 
         let reply = self.send_recv(msg)?;
 
         match reply.get("message_type").and_then(|v| v.as_str()) {
-            Some("LookupResult") => {
-                let endpoint = reply.get("endpoint").and_then(|v| v.as_str());
-                let online = reply.get("online").and_then(|v| v.as_bool()).unwrap_or(false);
-
-                match endpoint {
-                    Some(ep) => {
-                        if online {
-                            Ok(ep.to_string())
-                        } else {
-                            Err(RegistryError::Offline(actor_name.to_string()))
-                        }
-                    }
-                    None => Err(RegistryError::NotFound(actor_name.to_string())),
-                }
-            }
+            Some("ManagerLeft") => Ok(()),
             _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
         }
     }
 
-    /// Lookup an actor, returning the endpoint even if offline.
+    /// Unregister every actor of this manager in one request.
     ///
-    /// Use this when you want to attempt communication with a potentially
-    /// recovering actor.
-    pub fn lookup_allow_offline(&self, actor_name: &str) -> Result<(String, bool), RegistryError> {
+    /// Unlike `leave`, the manager itself stays known to the registry.
+    /// Clears the local lookup cache.
+    ///
+    /// # Returns
+    /// The number of actors the registry removed.
+    pub fn deregister_all(&self) -> Result<usize, RegistryError> {
+        self.deregister_all_with_timeout(None)
+    }
+
+    fn deregister_all_with_timeout(&self, timeout: Option<Duration>) -> Result<usize, RegistryError> {
+        self.cache.lock().unwrap().clear();
+
         let msg = json!({
-            "message_type": "LookupActor",
-            "actor_name": actor_name
+            "message_type": "DeregisterManager",
+            "manager_id": self.manager_id
         });
 
-        let reply = self.send_recv(msg)?;
+        let reply = self.send_recv_with_timeout(msg, timeout)?;
 
         match reply.get("message_type").and_then(|v| v.as_str()) {
-            Some("LookupResult") => {
-                let endpoint = reply.get("endpoint").and_then(|v| v.as_str());
-                let online = reply.get("online").and_then(|v| v.as_bool()).unwrap_or(false);
-
-                match endpoint {
-                    Some(ep) => Ok((ep.to_string(), online)),
-                    None => Err(RegistryError::NotFound(actor_name.to_string())),
-                }
-            }
+            Some("ManagerDeregistered") => Ok(reply.get("count").and_then(|v| v.as_u64()).unwrap_or(0) as usize),
             _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
         }
     }
-}
 
-impl Drop for RegistryClient {
-    fn drop(&mut self) {
-        self.stop_heartbeat();
+    /// Endpoint the registry publishes manager lifecycle events on.
+    fn events_endpoint(&self) -> String {
+        if let Some(endpoint) = &self.config.events_endpoint {
+            return endpoint.clone();
+        }
+        match self.registry_endpoint.rsplit_once(':') {
+            Some((host, port)) => match port.parse::<u16>() {
+                Ok(port) => format!("{}:{}", host, port + 1),
+                Err(_) => self.registry_endpoint.clone(),
+            },
+            None => self.registry_endpoint.clone(),
+        }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    #[test]
-    fn test_registry_error_display_not_found() {
-        let err = RegistryError::NotFound("pong".to_string());
-        assert_eq!(format!("{}", err), "Actor not found: pong");
+    /// Call `f` for every manager that joins or leaves the registry.
+    ///
+    /// Events are received on a background thread from the registry's
+    /// `ManagerEvent` PUB topic. Only events published after the
+    /// subscription is established are seen.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{ManagerLifecycleEvent, RegistryClient};
+    /// # fn example(client: &RegistryClient) {
+    /// let _sub = client.subscribe_manager_events(|event| match event {
+    ///     ManagerLifecycleEvent::Joined { manager_id, .. } => println!("{} joined", manager_id),
+    ///     ManagerLifecycleEvent::Left { manager_id, graceful, .. } => println!("{} left ({})", manager_id, graceful),
+    /// });
+    /// # }
+    /// ```
+    pub fn subscribe_manager_events<F>(&self, f: F) -> SubscriptionHandle
+    where
+        F: Fn(ManagerLifecycleEvent) + Send + 'static,
+    {
+        self.subscribe_events(MANAGER_EVENT_TOPIC.to_string(), move |value| {
+            if let Some(event) = parse_manager_event(value) {
+                f(event);
+            }
+        })
     }
 
-    #[test]
-    fn test_registry_error_display_offline() {
-        let err = RegistryError::Offline("pong".to_string());
-        assert_eq!(format!("{}", err), "Actor offline: pong");
+    /// Tell the other managers where this manager's actors are.
+    ///
+    /// Sends an `Announce` with the `(actor_name, endpoint)` pairs to the
+    /// registry, which fans it out to every manager subscribed with
+    /// `subscribe_announcements`. Meant to be called once the actors are
+    /// registered at startup, so peers do not need a lookup per actor. The
+    /// registry drops pairs this manager has not registered.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{RegistryClient, RegistryError};
+    /// # fn example(client: &RegistryClient) -> Result<(), RegistryError> {
+    /// client.register("pong", "tcp://host1:5001")?;
+    /// client.announce(&[("pong", "tcp://host1:5001")])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn announce(&self, actors: &[(&str, &str)]) -> Result<(), RegistryError> {
+        let actors: Vec<(String, &str)> = actors
+            .iter()
+            .map(|&(name, endpoint)| (self.qualify(name), endpoint))
+            .collect();
+        let msg = json!({
+            "message_type": "Announce",
+            "manager_id": self.manager_id,
+            "actors": actors
+        });
+        match self.send_recv(msg)?.get("message_type").and_then(|v| v.as_str()) {
+            Some("Announced") => Ok(()),
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+        }
     }
 
-    #[test]
-    fn test_registry_error_display_registration_failed() {
-        let err = RegistryError::RegistrationFailed {
-            actor_name: "pong".to_string(),
-            reason: "Name already registered".to_string(),
-        };
-        assert_eq!(
-            format!("{}", err),
-            "Registration failed for 'pong': Name already registered"
-        );
+    /// Fill the local lookup cache from other managers' announcements.
+    ///
+    /// Each actor announced with `announce` is cached as if `lookup` had
+    /// resolved it, so the first lookup does not go to the registry. Has no
+    /// effect unless `cache_ttl` is configured. Actors outside this client's
+    /// namespace and this manager's own announcements are ignored.
+    pub fn subscribe_announcements(&self) -> SubscriptionHandle {
+        self.subscribe_events(ANNOUNCE_TOPIC.to_string(), self.announcement_handler())
     }
 
-    #[test]
-    fn test_registry_error_display_connection_error() {
-        let err = RegistryError::ConnectionError("Connection refused".to_string());
-        assert_eq!(format!("{}", err), "Registry connection error: Connection refused");
+    /// Caches the actors of an announcement published on the `Announce` topic.
+    fn announcement_handler(&self) -> impl Fn(&serde_json::Value) + Send + 'static {
+        let cache = Arc::clone(&self.cache);
+        let caching = self.config.cache_ttl.is_some();
+        let manager_id = self.manager_id.clone();
+        let prefix = self.qualify("");
+        move |value| {
+            let Some((announcer, actors)) = parse_announcement(value) else {
+                return;
+            };
+            if !caching || announcer == manager_id {
+                return;
+            }
+            let now = Instant::now();
+            let mut cache = cache.lock().unwrap();
+            for (name, endpoint) in actors {
+                // Cached under the name `lookup` is called with
+                if let Some(name) = name.strip_prefix(prefix.as_str()) {
+                    cache.insert(name.to_string(), (endpoint, now));
+                }
+            }
+        }
     }
 
-    #[test]
-    fn test_registry_error_display_timeout() {
-        let err = RegistryError::Timeout("Operation timed out".to_string());
-        assert_eq!(format!("{}", err), "Registry timeout: Operation timed out");
+    /// Call `callback` whenever `actor_name` comes online, goes offline or is unregistered.
+    ///
+    /// Events are received on a background thread from the registry's
+    /// per-actor PUB topic, so no polling is involved. Only events published
+    /// after the subscription is established are seen; use `lookup` for the
+    /// current state.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{ActorEvent, RegistryClient};
+    /// # fn example(client: &RegistryClient) {
+    /// let _sub = client.subscribe("pong", |event| match event {
+    ///     ActorEvent::Online { endpoint } => println!("pong is at {}", endpoint),
+    ///     ActorEvent::Offline | ActorEvent::Unregistered => println!("pong is gone"),
+    /// });
+    /// # }
+    /// ```
+    pub fn subscribe<F>(&self, actor_name: &str, callback: F) -> SubscriptionHandle
+    where
+        F: Fn(ActorEvent) + Send + 'static,
+    {
+        let topic = format!("{}{}", ACTOR_EVENT_TOPIC_PREFIX, self.qualify(actor_name));
+        self.subscribe_events(topic, move |value| {
+            if let Some(event) = parse_actor_event(value) {
+                callback(event);
+            }
+        })
     }
 
-    #[test]
-    fn test_register_message_format() {
+    /// Like `subscribe`, but registers the subscription with the registry and
+    /// first calls `callback` with the actor's current state.
+    ///
+    /// The registry keeps a subscriber list per actor and forgets this
+    /// manager's subscriptions when the manager leaves or times out. The
+    /// current state is `Online` or `Offline`; nothing is delivered first if
+    /// the actor is not registered or the registry cannot be reached. An event
+    /// published while the subscription is being set up may be missed.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{ActorEvent, RegistryClient};
+    /// # fn example(client: &RegistryClient) {
+    /// let _sub = client.subscribe_actor("pong", |event| match event {
+    ///     ActorEvent::Online { endpoint } => println!("pong is at {}", endpoint),
+    ///     ActorEvent::Offline | ActorEvent::Unregistered => println!("pong is gone"),
+    /// });
+    /// # }
+    /// ```
+    pub fn subscribe_actor<F>(&self, actor_name: &str, callback: F) -> SubscriptionHandle
+    where
+        F: Fn(ActorEvent) + Send + 'static,
+    {
         let msg = json!({
-            "message_type": "RegisterActor",
-            "manager_id": "mgr1",
-            "actor_name": "pong",
-            "actor_endpoint": "tcp://localhost:5001"
+            "message_type": "SubscribeActor",
+            "actor_name": self.qualify(actor_name),
+            "manager_id": self.manager_id,
+            "datacenter": self.datacenter()
         });
-
-        assert_eq!(msg["message_type"], "RegisterActor");
-        assert_eq!(msg["manager_id"], "mgr1");
-        assert_eq!(msg["actor_name"], "pong");
-        assert_eq!(msg["actor_endpoint"], "tcp://localhost:5001");
+        if let Some(event) = self.send_recv(msg).ok().as_ref().and_then(initial_actor_event) {
+            callback(event);
+        }
+        self.subscribe(actor_name, callback)
     }
 
-    #[test]
-    fn test_lookup_message_format() {
+    /// Remove this manager from the registry's subscriber list for `actor_name`.
+    ///
+    /// Events keep arriving until the `SubscriptionHandle` is stopped or dropped.
+    pub fn unsubscribe_actor(&self, actor_name: &str) -> Result<(), RegistryError> {
         let msg = json!({
-            "message_type": "LookupActor",
-            "actor_name": "pong"
+            "message_type": "UnsubscribeActor",
+            "actor_name": self.qualify(actor_name),
+            "manager_id": self.manager_id
         });
-
-        assert_eq!(msg["message_type"], "LookupActor");
-        assert_eq!(msg["actor_name"], "pong");
+        match self.send_recv(msg)?.get("message_type").and_then(|v| v.as_str()) {
+            Some("RegistrationOk") => Ok(()),
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+        }
     }
 
-    #[test]
-    fn test_heartbeat_message_format() {
-        let timestamp_ms = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
+    /// Run `f` on a background thread for each event published on exactly `topic`.
+    fn subscribe_events<F>(&self, topic: String, f: F) -> SubscriptionHandle
+    where
+        F: Fn(&serde_json::Value) + Send + 'static,
+    {
+        let events_endpoint = self.events_endpoint();
+        let retry_policy = self.config.retry_policy.clone();
+        let running = Arc::new(AtomicBool::new(true));
+        let running_flag = Arc::clone(&running);
+        let serializer = Arc::clone(&self.serializer);
 
-        let msg = json!({
-            "message_type": "Heartbeat",
-            "manager_id": "mgr1",
-            "timestamp_ms": timestamp_ms
+        let handle = thread::spawn(move || {
+            let rt = Runtime::new().expect("Failed to create event subscriber runtime");
+
+            rt.block_on(async {
+                let mut socket = SubSocket::new();
+                let mut retry = 0;
+                while socket.connect(&events_endpoint).await.is_err() {
+                    if retry >= retry_policy.max_retries || !running_flag.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    tokio::time::sleep(retry_policy.delay_for(retry)).await;
+                    retry += 1;
+                }
+                if socket.subscribe(&topic).await.is_err() {
+                    return;
+                }
+
+                while running_flag.load(Ordering::SeqCst) {
+                    match tokio::time::timeout(Duration::from_millis(100), socket.recv()).await {
+                        Ok(Ok(msg)) => {
+                            // [topic, event]; SUB filtering is by prefix, so check the whole topic
+                            if msg.get(0).map(|t| t.as_ref()) != Some(topic.as_bytes()) {
+                                continue;
+                            }
+                            let data = msg.iter().last().map(|b| b.as_ref()).unwrap_or(&[]);
+                            let value = serializer.deserialize(data);
+                            if !value.is_null() {
+                                f(&value);
+                            }
+                        }
+                        Ok(Err(_)) => break,
+                        Err(_) => continue,
+                    }
+                }
+            });
         });
 
-        assert_eq!(msg["message_type"], "Heartbeat");
-        assert_eq!(msg["manager_id"], "mgr1");
-        assert!(msg["timestamp_ms"].as_u64().unwrap() > 0);
+        SubscriptionHandle {
+            running,
+            thread: Some(handle),
+        }
+    }
+
+    /// Get round-trip statistics for heartbeats sent by this client.
+    pub fn heartbeat_stats(&self) -> HeartbeatStats {
+        self.heartbeat_tracker.lock().unwrap().stats()
+    }
+
+    /// Tag every heartbeat from now on with `metadata`, replacing any set
+    /// before.
+    ///
+    /// The registry keeps each manager's latest metadata, so it can filter
+    /// managers by e.g. `tenant_id` or `region`. An empty map clears the
+    /// metadata the registry has for this manager.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use actors::RegistryClient;
+    /// # fn example(client: &RegistryClient) {
+    /// client.set_heartbeat_metadata(HashMap::from([
+    ///     ("tenant_id".to_string(), "acme".to_string()),
+    ///     ("region".to_string(), "eu-west".to_string()),
+    /// ]));
+    /// client.start_heartbeat();
+    /// # }
+    /// ```
+    pub fn set_heartbeat_metadata(&self, metadata: HashMap<String, String>) {
+        *self.heartbeat_metadata.lock().unwrap() = metadata;
+    }
+
+    /// Stop the heartbeat background thread.
+    pub fn stop_heartbeat(&self) {
+        *self.running.lock().unwrap() = false;
+
+        if let Some(handle) = self.heartbeat_handle.lock().unwrap().take() {
+            let _ = handle.join();
+        }
+    }
+
+    /// Register an actor with the GlobalRegistry.
+    ///
+    /// # Arguments
+    /// * `actor_name` - Unique name for the actor
+    /// * `endpoint` - ZMQ endpoint where the actor can be reached
+    ///
+    /// # Returns
+    /// * `Ok(())` if registration succeeded
+    /// * `Err(RegistryError)` if registration failed
+    pub fn register(&self, actor_name: &str, endpoint: &str) -> Result<(), RegistryError> {
+        self.register_actor(actor_name, endpoint, None, None, None)
+    }
+
+    /// Register one version of an actor.
+    ///
+    /// Several versions of the same name can be registered at once, e.g. by
+    /// the old and new managers during a rolling upgrade. Versioned actors
+    /// are found with `lookup_versioned`, not `lookup`.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{RegistryClient, RegistryError, VersionConstraint};
+    /// # fn example(client: &RegistryClient) -> Result<(), RegistryError> {
+    /// client.register_versioned("pricer", 2, "tcp://host2:5001")?;
+    /// let (endpoint, version) = client.lookup_versioned("pricer", VersionConstraint::AtLeast(2))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn register_versioned(&self, actor_name: &str, version: u32, endpoint: &str) -> Result<(), RegistryError> {
+        self.register_actor(actor_name, endpoint, None, Some(version), None)
+    }
+
+    /// Unregister one version of an actor registered with `register_versioned`.
+    pub fn unregister_versioned(&self, actor_name: &str, version: u32) -> Result<(), RegistryError> {
+        let msg = json!({
+            "message_type": "UnregisterActor",
+            "actor_name": self.qualify(actor_name),
+            "datacenter": self.datacenter(),
+            "version": version
+        });
+
+        let reply = self.send_recv(msg)?;
+
+        match reply.get("message_type").and_then(|v| v.as_str()) {
+            Some("RegistrationOk") => Ok(()),
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+        }
+    }
+
+    /// Register an actor that can handle up to `max_capacity` units of load.
+    ///
+    /// Every endpoint registered this way is a separate instance of the name,
+    /// even within one datacenter. Report its load with `update_capacity`;
+    /// `lookup_with_policy` with `LoadBalancePolicy::LeastLoaded` then
+    /// prefers the least loaded instance. Remove an instance with
+    /// `unregister_instance`.
+    pub fn register_with_capacity(
+        &self,
+        actor_name: &str,
+        endpoint: &str,
+        max_capacity: u32,
+    ) -> Result<(), RegistryError> {
+        self.register_actor(actor_name, endpoint, Some(max_capacity), None, None)
+    }
+
+    /// Register one weighted instance of an actor, e.g. for a blue-green deployment.
+    ///
+    /// Every endpoint registered this way is a separate instance of the name.
+    /// `lookup_with_load_balance` picks an online instance at random, in
+    /// proportion to its priority: the instance with priority 90 gets nine
+    /// times the lookups of one with priority 10. Priority 0 makes an instance
+    /// standby only, returned when no other instance is online. Registering
+    /// the same endpoint again from this manager changes its priority.
+    /// Remove an instance with `unregister_instance`.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{RegistryClient, RegistryError};
+    /// # fn example(blue_client: &RegistryClient, green_client: &RegistryClient) -> Result<(), RegistryError> {
+    /// // Green starts with a tenth of the traffic
+    /// blue_client.register_with_priority("pricer", "tcp://blue:5001", 90)?;
+    /// green_client.register_with_priority("pricer", "tcp://green:5001", 10)?;
+    /// // Later, move everything to green, keeping blue as a standby
+    /// blue_client.register_with_priority("pricer", "tcp://blue:5001", 0)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn register_with_priority(&self, actor_name: &str, endpoint: &str, priority: u8) -> Result<(), RegistryError> {
+        self.register_actor(actor_name, endpoint, None, None, Some(priority))
+    }
+
+    /// Unregister the instance of an actor registered at `endpoint` with
+    /// `register_with_priority` or `register_with_capacity`.
+    pub fn unregister_instance(&self, actor_name: &str, endpoint: &str) -> Result<(), RegistryError> {
+        self.invalidate_cache(actor_name);
+
+        let msg = json!({
+            "message_type": "UnregisterActor",
+            "actor_name": self.qualify(actor_name),
+            "datacenter": self.datacenter(),
+            "actor_endpoint": endpoint
+        });
+
+        let reply = self.send_recv(msg)?;
+
+        match reply.get("message_type").and_then(|v| v.as_str()) {
+            Some("RegistrationOk") => Ok(()),
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+        }
+    }
+
+    /// Refuse to register names matching `pattern`.
+    ///
+    /// `pattern` is a glob: `*` matches any run of characters and `?` any one
+    /// character. `register`, `register_with_capacity`,
+    /// `register_group_member` and the `commit` of a transaction that
+    /// registers a matching name fail with `RegistrationFailed` ("Blacklisted
+    /// name"), without contacting the registry. Patterns match the name
+    /// before the namespace prefix is added.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::RegistryClient;
+    /// # fn example(client: &RegistryClient) {
+    /// client.add_name_blacklist("test_*");
+    /// assert!(client.register("test_actor", "tcp://localhost:5001").is_err());
+    /// # }
+    /// ```
+    pub fn add_name_blacklist(&self, pattern: &str) {
+        self.name_blacklist.lock().unwrap().push(pattern.to_string());
+    }
+
+    /// Fail if `actor_name` matches a blacklisted pattern.
+    fn check_blacklist(&self, actor_name: &str) -> Result<(), RegistryError> {
+        let blacklist = self.name_blacklist.lock().unwrap();
+        if blacklist.iter().any(|pattern| glob_match(pattern, actor_name)) {
+            return Err(RegistryError::RegistrationFailed {
+                actor_name: actor_name.to_string(),
+                reason: "Blacklisted name".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn register_actor(
+        &self,
+        actor_name: &str,
+        endpoint: &str,
+        max_capacity: Option<u32>,
+        version: Option<u32>,
+        priority: Option<u8>,
+    ) -> Result<(), RegistryError> {
+        self.check_blacklist(actor_name)?;
+
+        let msg = json!({
+            "message_type": "RegisterActor",
+            "manager_id": self.manager_id,
+            "actor_name": self.qualify(actor_name),
+            "actor_endpoint": endpoint,
+            "datacenter": self.datacenter(),
+            "max_capacity": max_capacity,
+            "version": version,
+            "priority": priority
+        });
+
+        let reply = self.send_recv(msg)?;
+
+        match reply.get("message_type").and_then(|v| v.as_str()) {
+            Some("RegistrationOk") => Ok(()),
+            Some("RegistrationFailed") => {
+                let reason = reply.get("reason")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unknown");
+                Err(RegistryError::RegistrationFailed {
+                    actor_name: actor_name.to_string(),
+                    reason: reason.to_string(),
+                })
+            }
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+        }
+    }
+
+    /// Unregister an actor from the GlobalRegistry.
+    ///
+    /// Also evicts the actor from the local lookup cache.
+    pub fn unregister(&self, actor_name: &str) -> Result<(), RegistryError> {
+        self.invalidate_cache(actor_name);
+
+        let msg = json!({
+            "message_type": "UnregisterActor",
+            "actor_name": self.qualify(actor_name),
+            "datacenter": self.datacenter()
+        });
+
+        let reply = self.send_recv(msg)?;
+
+        match reply.get("message_type").and_then(|v| v.as_str()) {
+            Some("RegistrationOk") => Ok(()),
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+        }
+    }
+
+    /// Unregister an actor only if this manager registered it.
+    ///
+    /// Use this instead of `unregister` when another manager may have taken
+    /// over the name, e.g. after a failover: the registry checks the owning
+    /// manager and keeps a registration made by another one.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{DeregisterResult, RegistryClient, RegistryError};
+    /// # fn example(client: &RegistryClient) -> Result<(), RegistryError> {
+    /// match client.deregister_if_owner("pong")? {
+    ///     DeregisterResult::Deregistered => println!("pong unregistered"),
+    ///     DeregisterResult::NotOwner { current_manager } => println!("pong now belongs to {}", current_manager),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn deregister_if_owner(&self, actor_name: &str) -> Result<DeregisterResult, RegistryError> {
+        self.invalidate_cache(actor_name);
+
+        let msg = json!({
+            "message_type": "UnregisterActor",
+            "actor_name": self.qualify(actor_name),
+            "datacenter": self.datacenter(),
+            "manager_id": self.manager_id
+        });
+
+        deregister_result(&self.send_recv(msg)?)
+    }
+
+    /// Lookup an actor by name.
+    ///
+    /// If `cache_ttl` is configured, an endpoint resolved less than `cache_ttl`
+    /// ago is returned without contacting the registry.
+    ///
+    /// # Arguments
+    /// * `actor_name` - Name of the actor to find
+    ///
+    /// # Returns
+    /// * `Ok(endpoint)` if actor found and online
+    /// * `Err(RegistryError::NotFound)` if actor not registered
+    /// * `Err(RegistryError::Offline)` if actor's manager missed heartbeats
+    pub fn lookup(&self, actor_name: &str) -> Result<String, RegistryError> {
+        if let Some(endpoint) = self.cached_endpoint(actor_name) {
+            return Ok(endpoint);
+        }
+
+        let msg = json!({
+            "message_type": "LookupActor",
+            "actor_name": self.qualify(actor_name),
+            "datacenter": self.datacenter()
+        });
+
+        let reply = self.send_recv(msg)?;
+
+        let result = match reply.get("message_type").and_then(|v| v.as_str()) {
+            Some("LookupResult") => lookup_result(actor_name, &reply),
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+        };
+
+        if let Ok(ref endpoint) = result {
+            self.cache_endpoint(actor_name, endpoint);
+        }
+        result
+    }
+
+    /// Report the current load of an actor registered with `register_with_capacity`.
+    ///
+    /// Call periodically; the registry ranks instances by `max_capacity - current_load`.
+    /// Only the instances registered by this client's manager are updated.
+    pub fn update_capacity(&self, actor_name: &str, current_load: u32) -> Result<(), RegistryError> {
+        let msg = json!({
+            "message_type": "UpdateCapacity",
+            "actor_name": self.qualify(actor_name),
+            "current_load": current_load,
+            "datacenter": self.datacenter(),
+            "manager_id": self.manager_id
+        });
+
+        let reply = self.send_recv(msg)?;
+
+        match reply.get("message_type").and_then(|v| v.as_str()) {
+            Some("RegistrationOk") => Ok(()),
+            Some("RegistrationFailed") => {
+                let reason = reply.get("reason")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unknown");
+                Err(RegistryError::RegistrationFailed {
+                    actor_name: actor_name.to_string(),
+                    reason: reason.to_string(),
+                })
+            }
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+        }
+    }
+
+    /// Lookup an actor, choosing among its instances with `policy`.
+    ///
+    /// `LoadBalancePolicy::Default` is the same as `lookup`. Other policies
+    /// always ask the registry, since load changes faster than the cache expires.
+    pub fn lookup_with_policy(
+        &self,
+        actor_name: &str,
+        policy: LoadBalancePolicy,
+    ) -> Result<String, RegistryError> {
+        if policy == LoadBalancePolicy::Default {
+            return self.lookup(actor_name);
+        }
+
+        let policy = match policy {
+            LoadBalancePolicy::Weighted => "Weighted",
+            _ => "LeastLoaded",
+        };
+        let msg = json!({
+            "message_type": "LookupActor",
+            "actor_name": self.qualify(actor_name),
+            "datacenter": self.datacenter(),
+            "policy": policy
+        });
+
+        let reply = self.send_recv(msg)?;
+
+        match reply.get("message_type").and_then(|v| v.as_str()) {
+            Some("LookupResult") => lookup_result(actor_name, &reply),
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+        }
+    }
+
+    /// Lookup an actor registered with `register_with_priority`, picking among
+    /// its online instances at random by priority.
+    ///
+    /// Same as `lookup_with_policy(actor_name, LoadBalancePolicy::Weighted)`.
+    /// Call it per message (or per batch) so traffic follows the priorities.
+    pub fn lookup_with_load_balance(&self, actor_name: &str) -> Result<String, RegistryError> {
+        self.lookup_with_policy(actor_name, LoadBalancePolicy::Weighted)
+    }
+
+    /// Lookup a version of an actor registered with `register_versioned`.
+    ///
+    /// Returns the endpoint and the version found. Always asks the registry;
+    /// the lookup cache is bypassed.
+    pub fn lookup_versioned(
+        &self,
+        actor_name: &str,
+        constraint: VersionConstraint,
+    ) -> Result<(String, u32), RegistryError> {
+        let msg = json!({
+            "message_type": "LookupActor",
+            "actor_name": self.qualify(actor_name),
+            "datacenter": self.datacenter(),
+            "version": constraint.to_json()
+        });
+
+        let reply = self.send_recv(msg)?;
+
+        match reply.get("message_type").and_then(|v| v.as_str()) {
+            Some("LookupResult") => {
+                let endpoint = lookup_result(actor_name, &reply)?;
+                match reply.get("version").and_then(|v| v.as_u64()) {
+                    Some(version) => Ok((endpoint, version as u32)),
+                    None => Err(RegistryError::NotFound(actor_name.to_string())),
+                }
+            }
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+        }
+    }
+
+    /// Remove an actor from the local lookup cache.
+    ///
+    /// The next `lookup` for this name goes to the registry.
+    pub fn invalidate_cache(&self, actor_name: &str) {
+        self.cache.lock().unwrap().remove(actor_name);
+    }
+
+    /// Get a cached endpoint if caching is enabled and the entry is still fresh.
+    fn cached_endpoint(&self, actor_name: &str) -> Option<String> {
+        let ttl = self.config.cache_ttl?;
+        let cache = self.cache.lock().unwrap();
+        match cache.get(actor_name) {
+            Some((endpoint, resolved_at)) if resolved_at.elapsed() < ttl => Some(endpoint.clone()),
+            _ => None,
+        }
+    }
+
+    /// Remember a resolved endpoint (no-op when caching is disabled).
+    fn cache_endpoint(&self, actor_name: &str, endpoint: &str) {
+        if self.config.cache_ttl.is_some() {
+            self.cache
+                .lock()
+                .unwrap()
+                .insert(actor_name.to_string(), (endpoint.to_string(), Instant::now()));
+        }
+    }
+
+    /// Lookup several actors in a single round trip.
+    ///
+    /// Results are returned in the same order as `actor_names`, each paired with
+    /// the name it belongs to. If the registry does not understand `LookupMany`
+    /// this falls back to one `lookup` call per name.
+    pub fn lookup_many(&self, actor_names: &[&str]) -> Vec<(String, Result<String, RegistryError>)> {
+        let qualified: Vec<String> = actor_names.iter().map(|name| self.qualify(name)).collect();
+        let msg = json!({
+            "message_type": "LookupMany",
+            "actor_names": qualified
+        });
+
+        match self.send_recv(msg) {
+            Ok(reply) => match parse_lookup_many_reply(actor_names, &qualified, &reply) {
+                Some(results) => results,
+                // Older registry: resolve one name at a time
+                None => actor_names
+                    .iter()
+                    .map(|name| (name.to_string(), self.lookup(name)))
+                    .collect(),
+            },
+            Err(e) => actor_names
+                .iter()
+                .map(|name| (name.to_string(), Err(e.clone())))
+                .collect(),
+        }
+    }
+
+    /// Start a transaction that registers and deregisters several actors atomically.
+    ///
+    /// Operations are staged locally and sent to the registry on `commit`.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{RegistryClient, RegistryError};
+    /// # fn example(client: &RegistryClient) -> Result<(), RegistryError> {
+    /// let mut txn = client.begin_transaction();
+    /// txn.register("A", "tcp://localhost:5001");
+    /// txn.register("B", "tcp://localhost:5001");
+    /// txn.commit()?;  // both registered, or neither
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn begin_transaction(&self) -> RegistryTransaction<'_> {
+        RegistryTransaction {
+            client: self,
+            operations: Vec::new(),
+            blacklisted: None,
+        }
+    }
+
+    /// Wait until an actor is registered and online, returning its endpoint.
+    ///
+    /// Calls `lookup` every `poll_interval` until it succeeds. Returns
+    /// `RegistryError::Timeout` if the actor is not available within `timeout`.
+    pub fn wait_for_actor(
+        &self,
+        actor_name: &str,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<String, RegistryError> {
+        let options = WaitOptions::default()
+            .with_poll_interval(poll_interval)
+            .with_timeout(timeout);
+        self.wait_for_actor_with(actor_name, &options)
+    }
+
+    /// Wait until an actor is available, as configured by `options`.
+    ///
+    /// A timeout too long to represent, such as `Duration::MAX`, waits forever.
+    pub fn wait_for_actor_with(&self, actor_name: &str, options: &WaitOptions) -> Result<String, RegistryError> {
+        // None if the timeout is too long to represent: wait forever
+        let deadline = Instant::now().checked_add(options.timeout);
+
+        loop {
+            let result = if options.accept_offline {
+                self.lookup_allow_offline(actor_name).map(|(endpoint, _)| endpoint)
+            } else {
+                self.lookup(actor_name)
+            };
+
+            let last_error = match result {
+                Ok(endpoint) => return Ok(endpoint),
+                Err(e) => e,
+            };
+
+            let now = Instant::now();
+            if deadline.is_some_and(|deadline| now >= deadline) {
+                return Err(RegistryError::Timeout(format!(
+                    "'{}' not available after {:?} ({})",
+                    actor_name, options.timeout, last_error
+                )));
+            }
+            let remaining = deadline.map_or(Duration::MAX, |deadline| deadline - now);
+            thread::sleep(options.poll_interval.min(remaining));
+        }
+    }
+
+    /// List every actor registered with the GlobalRegistry.
+    pub fn list_actors(&self) -> Result<Vec<ActorInfo>, RegistryError> {
+        let msg = json!({
+            "message_type": "ListActors"
+        });
+
+        let reply = self.send_recv(msg)?;
+
+        match reply.get("message_type").and_then(|v| v.as_str()) {
+            Some("ActorList") => Ok(parse_actor_list(&reply)
+                .into_iter()
+                .filter(|actor| self.in_namespace(&actor.actor_name))
+                .collect()),
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+        }
+    }
+
+    /// Write the registry's full state (actors, managers, endpoints and
+    /// groups) to a JSON file, for disaster recovery.
+    ///
+    /// The snapshot covers every namespace, not just this client's.
+    pub fn export_registry_snapshot(&self, path: PathBuf) -> Result<(), ExportError> {
+        let msg = json!({
+            "message_type": "ExportState"
+        });
+
+        let reply = self.send_recv(msg)?;
+
+        match reply.get("message_type").and_then(|v| v.as_str()) {
+            Some("RegistryState") => {
+                let state = reply.get("state").cloned().unwrap_or_default();
+                let text = serde_json::to_string_pretty(&state)
+                    .map_err(|e| ExportError::InvalidSnapshot(e.to_string()))?;
+                fs::write(path, text)?;
+                Ok(())
+            }
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string()).into()),
+        }
+    }
+
+    /// Replace the registry's state with a snapshot written by
+    /// `export_registry_snapshot`.
+    ///
+    /// Managers in the snapshot are treated as online until they miss their
+    /// heartbeats. Clears the local lookup cache.
+    pub fn import_registry_snapshot(&self, path: PathBuf) -> Result<(), ExportError> {
+        let text = fs::read_to_string(path)?;
+        let state: serde_json::Value =
+            serde_json::from_str(&text).map_err(|e| ExportError::InvalidSnapshot(e.to_string()))?;
+        self.cache.lock().unwrap().clear();
+
+        let msg = json!({
+            "message_type": "ImportState",
+            "state": state
+        });
+
+        let reply = self.send_recv(msg)?;
+
+        match reply.get("message_type").and_then(|v| v.as_str()) {
+            Some("StateImported") => Ok(()),
+            Some("ImportFailed") => {
+                let reason = reply.get("reason")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unknown");
+                Err(ExportError::InvalidSnapshot(reason.to_string()))
+            }
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string()).into()),
+        }
+    }
+
+    /// Add an actor to a registry-maintained group.
+    ///
+    /// The actor is registered at `endpoint` if it is not registered yet.
+    /// An actor can belong to several groups; it leaves all of them when it
+    /// is unregistered.
+    pub fn register_group_member(
+        &self,
+        group_name: &str,
+        member_actor_name: &str,
+        endpoint: &str,
+    ) -> Result<(), RegistryError> {
+        self.check_blacklist(member_actor_name)?;
+        self.invalidate_cache(member_actor_name);
+
+        let msg = json!({
+            "message_type": "RegisterGroupMember",
+            "manager_id": self.manager_id,
+            "group_name": self.qualify(group_name),
+            "actor_name": self.qualify(member_actor_name),
+            "actor_endpoint": endpoint
+        });
+
+        let reply = self.send_recv(msg)?;
+
+        match reply.get("message_type").and_then(|v| v.as_str()) {
+            Some("RegistrationOk") => Ok(()),
+            Some("RegistrationFailed") => {
+                let reason = reply.get("reason")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unknown");
+                Err(RegistryError::RegistrationFailed {
+                    actor_name: member_actor_name.to_string(),
+                    reason: reason.to_string(),
+                })
+            }
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+        }
+    }
+
+    /// List the online members of a group, sorted by name.
+    ///
+    /// An unknown group has no members. Pick a member per message to spread
+    /// load across the group.
+    pub fn lookup_group(&self, group_name: &str) -> Result<Vec<ActorInfo>, RegistryError> {
+        let msg = json!({
+            "message_type": "LookupGroup",
+            "group_name": self.qualify(group_name)
+        });
+
+        let reply = self.send_recv(msg)?;
+
+        match reply.get("message_type").and_then(|v| v.as_str()) {
+            Some("GroupMembers") => Ok(parse_actor_list(&reply)
+                .into_iter()
+                .filter(|actor| actor.online)
+                .collect()),
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+        }
+    }
+
+    /// List the registered actors whose manager is online.
+    pub fn list_online_actors(&self) -> Result<Vec<ActorInfo>, RegistryError> {
+        Ok(self
+            .list_actors()?
+            .into_iter()
+            .filter(|actor| actor.online)
+            .collect())
+    }
+
+    /// Lookup an actor, returning the endpoint even if offline.
+    ///
+    /// Use this when you want to attempt communication with a potentially
+    /// recovering actor. Always asks the registry; the lookup cache is bypassed.
+    pub fn lookup_allow_offline(&self, actor_name: &str) -> Result<(String, bool), RegistryError> {
+        let msg = json!({
+            "message_type": "LookupActor",
+            "actor_name": self.qualify(actor_name),
+            "datacenter": self.datacenter()
+        });
+
+        let reply = self.send_recv(msg)?;
+
+        match reply.get("message_type").and_then(|v| v.as_str()) {
+            Some("LookupResult") => {
+                let endpoint = reply.get("endpoint").and_then(|v| v.as_str());
+                let online = reply.get("online").and_then(|v| v.as_bool()).unwrap_or(false);
+
+                match endpoint {
+                    Some(ep) => Ok((ep.to_string(), online)),
+                    None => Err(RegistryError::NotFound(actor_name.to_string())),
+                }
+            }
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+        }
+    }
+}
+
+/// A set of registry operations applied atomically.
+///
+/// Created by `RegistryClient::begin_transaction`. On `commit` the registry
+/// first prepares the transaction (validates every operation and reserves the
+/// actor names) and then commits it, so either all operations take effect or
+/// none do. Dropping the transaction without committing discards it.
+pub struct RegistryTransaction<'a> {
+    client: &'a RegistryClient,
+    operations: Vec<serde_json::Value>,
+    /// First staged registration of a blacklisted name; fails the commit
+    blacklisted: Option<RegistryError>,
+}
+
+impl RegistryTransaction<'_> {
+    /// Stage registration of an actor.
+    ///
+    /// A blacklisted name (see `RegistryClient::add_name_blacklist`) makes
+    /// `commit` fail.
+    pub fn register(&mut self, actor_name: &str, endpoint: &str) -> &mut Self {
+        if self.blacklisted.is_none() {
+            self.blacklisted = self.client.check_blacklist(actor_name).err();
+        }
+        // The same fields as RegistryClient::register
+        self.operations.push(json!({
+            "op": "register",
+            "actor_name": self.client.qualify(actor_name),
+            "actor_endpoint": endpoint,
+            "datacenter": self.client.datacenter(),
+            "max_capacity": null,
+            "version": null,
+            "priority": null
+        }));
+        self
+    }
+
+    /// Stage removal of an actor.
+    pub fn deregister(&mut self, actor_name: &str) -> &mut Self {
+        self.operations.push(json!({
+            "op": "deregister",
+            "actor_name": self.client.qualify(actor_name),
+            "datacenter": self.client.datacenter()
+        }));
+        self
+    }
+
+    /// Number of staged operations.
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Check if no operations are staged.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Apply all staged operations atomically.
+    ///
+    /// # Returns
+    /// * `Ok(())` if every operation was applied
+    /// * `Err(RegistryError::RegistrationFailed)` if any operation was rejected,
+    ///   or registers a blacklisted name (nothing was applied)
+    pub fn commit(self) -> Result<(), RegistryError> {
+        if let Some(err) = self.blacklisted {
+            return Err(err);
+        }
+        if self.operations.is_empty() {
+            return Ok(());
+        }
+
+        for op in &self.operations {
+            if let Some(name) = op["actor_name"].as_str() {
+                self.client.invalidate_cache(name);
+            }
+        }
+
+        // Phase one: prepare
+        let reply = self.client.send_recv(json!({
+            "message_type": "BeginTxn",
+            "manager_id": self.client.manager_id,
+            "operations": self.operations
+        }))?;
+
+        let txn_id = match reply.get("message_type").and_then(|v| v.as_str()) {
+            Some("TxnPrepared") => reply
+                .get("txn_id")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| RegistryError::ConnectionError("Missing txn_id".to_string()))?
+                .to_string(),
+            Some("TxnFailed") => return Err(txn_failed(self.client, &reply)),
+            _ => return Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+        };
+
+        // Phase two: commit
+        let reply = match self.client.send_recv(json!({
+            "message_type": "CommitTxn",
+            "txn_id": txn_id
+        })) {
+            Ok(reply) => reply,
+            Err(e) => {
+                // Best effort: release the reservation (the registry also expires it)
+                let _ = self.client.send_recv(json!({
+                    "message_type": "AbortTxn",
+                    "txn_id": txn_id
+                }));
+                return Err(e);
+            }
+        };
+
+        match reply.get("message_type").and_then(|v| v.as_str()) {
+            Some("TxnCommitted") => Ok(()),
+            Some("TxnFailed") => Err(txn_failed(self.client, &reply)),
+            _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+        }
+    }
+
+    /// Discard all staged operations without contacting the registry.
+    pub fn rollback(self) {}
+}
+
+/// Build a `Heartbeat` message with `metadata`, sent even when empty so the
+/// registry drops what it had.
+fn heartbeat_message(manager_id: &str, metadata: &HashMap<String, String>) -> serde_json::Value {
+    let timestamp_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+
+    let mut msg = json!({
+        "message_type": "Heartbeat",
+        "manager_id": manager_id,
+        "timestamp_ms": timestamp_ms
+    });
+    msg["metadata"] = json!(metadata);
+    msg
+}
+
+/// Convert a `TxnFailed` reply into a RegistryError.
+fn txn_failed(client: &RegistryClient, reply: &serde_json::Value) -> RegistryError {
+    let actor_name = reply.get("actor_name").and_then(|v| v.as_str()).unwrap_or("");
+    RegistryError::RegistrationFailed {
+        actor_name: client.strip_namespace(actor_name).to_string(),
+        reason: reply
+            .get("reason")
+            .and_then(|v| v.as_str())
+            .unwrap_or("Unknown")
+            .to_string(),
+    }
+}
+
+/// Match `name` against a glob `pattern` (`*` is any run, `?` any one character).
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name position it was tried at
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            // Let the last `*` absorb one more character
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Parse the reply to a conditional `UnregisterActor`.
+fn deregister_result(reply: &serde_json::Value) -> Result<DeregisterResult, RegistryError> {
+    match reply.get("message_type").and_then(|v| v.as_str()) {
+        Some("RegistrationOk") => Ok(DeregisterResult::Deregistered),
+        Some("NotOwner") => Ok(DeregisterResult::NotOwner {
+            current_manager: reply["current_manager"].as_str().unwrap_or_default().to_string(),
+        }),
+        _ => Err(RegistryError::ConnectionError("Unexpected response".to_string())),
+    }
+}
+
+/// Interpret a single lookup entry (`endpoint` + `online` fields).
+fn lookup_result(actor_name: &str, entry: &serde_json::Value) -> Result<String, RegistryError> {
+    let endpoint = entry.get("endpoint").and_then(|v| v.as_str());
+    let online = entry.get("online").and_then(|v| v.as_bool()).unwrap_or(false);
+
+    match endpoint {
+        Some(ep) => {
+            if online {
+                Ok(ep.to_string())
+            } else {
+                Err(RegistryError::Offline(actor_name.to_string()))
+            }
+        }
+        None => Err(RegistryError::NotFound(actor_name.to_string())),
+    }
+}
+
+/// Parse a `LookupMany` reply.
+///
+/// `registry_names` are the names as sent (namespace-qualified), in the same
+/// order as `actor_names`. Returns None if the reply has no `results` array
+/// (the registry did not recognise the request). Names missing from the
+/// results are reported as NotFound.
+fn parse_lookup_many_reply(
+    actor_names: &[&str],
+    registry_names: &[String],
+    reply: &serde_json::Value,
+) -> Option<Vec<(String, Result<String, RegistryError>)>> {
+    let entries = reply.get("results")?.as_array()?;
+
+    let results = actor_names
+        .iter()
+        .zip(registry_names)
+        .map(|(name, registry_name)| {
+            let entry = entries
+                .iter()
+                .find(|e| e.get("actor_name").and_then(|v| v.as_str()) == Some(registry_name.as_str()));
+            let result = match entry {
+                Some(e) => lookup_result(name, e),
+                None => Err(RegistryError::NotFound(name.to_string())),
+            };
+            (name.to_string(), result)
+        })
+        .collect();
+
+    Some(results)
+}
+
+/// Parse a manager event published on the `ManagerEvent` topic.
+fn parse_manager_event(value: &serde_json::Value) -> Option<ManagerLifecycleEvent> {
+    let manager_id = value.get("manager_id")?.as_str()?.to_string();
+    let timestamp_ms = value.get("timestamp_ms").and_then(|v| v.as_u64()).unwrap_or(0);
+
+    match value.get("event")?.as_str()? {
+        "Joined" => Some(ManagerLifecycleEvent::Joined {
+            manager_id,
+            timestamp_ms,
+        }),
+        "Left" => Some(ManagerLifecycleEvent::Left {
+            manager_id,
+            timestamp_ms,
+            graceful: value.get("graceful").and_then(|v| v.as_bool()).unwrap_or(false),
+        }),
+        _ => None,
+    }
+}
+
+/// Parse an announcement published on the `Announce` topic into the
+/// announcing manager and its `(actor_name, endpoint)` pairs.
+fn parse_announcement(value: &serde_json::Value) -> Option<(String, Vec<(String, String)>)> {
+    let manager_id = value.get("manager_id")?.as_str()?.to_string();
+    let actors = value
+        .get("actors")?
+        .as_array()?
+        .iter()
+        .filter_map(|pair| {
+            let name = pair.get(0)?.as_str()?;
+            let endpoint = pair.get(1)?.as_str()?;
+            Some((name.to_string(), endpoint.to_string()))
+        })
+        .collect();
+    Some((manager_id, actors))
+}
+
+/// Parse an actor availability event published by the registry.
+fn parse_actor_event(value: &serde_json::Value) -> Option<ActorEvent> {
+    match value.get("event")?.as_str()? {
+        "Online" => Some(ActorEvent::Online {
+            endpoint: value.get("endpoint")?.as_str()?.to_string(),
+        }),
+        "Offline" => Some(ActorEvent::Offline),
+        "Unregistered" => Some(ActorEvent::Unregistered),
+        _ => None,
+    }
+}
+
+/// The current state of an actor from the `LookupResult` reply to `SubscribeActor`.
+fn initial_actor_event(reply: &serde_json::Value) -> Option<ActorEvent> {
+    if reply.get("message_type")?.as_str()? != "LookupResult" {
+        return None;
+    }
+    let endpoint = reply.get("endpoint")?.as_str()?.to_string();
+    if reply.get("online").and_then(|v| v.as_bool()).unwrap_or(false) {
+        Some(ActorEvent::Online { endpoint })
+    } else {
+        Some(ActorEvent::Offline)
+    }
+}
+
+/// Parse the `actors` array of an `ActorList` reply, skipping malformed entries.
+fn parse_actor_list(reply: &serde_json::Value) -> Vec<ActorInfo> {
+    let entries = match reply.get("actors").and_then(|v| v.as_array()) {
+        Some(entries) => entries,
+        None => return Vec::new(),
+    };
+
+    entries
+        .iter()
+        .filter_map(|e| {
+            Some(ActorInfo {
+                actor_name: e.get("actor_name")?.as_str()?.to_string(),
+                endpoint: e.get("endpoint")?.as_str()?.to_string(),
+                manager_id: e.get("manager_id")?.as_str()?.to_string(),
+                online: e.get("online").and_then(|v| v.as_bool()).unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+impl Drop for RegistryClient {
+    fn drop(&mut self) {
+        // A client still heartbeating belongs to a live manager: remove its
+        // actors so no stale entries linger after a clean shutdown
+        if *self.running.lock().unwrap() {
+            let _ = self.deregister_all_with_timeout(Some(DROP_DEREGISTER_TIMEOUT));
+        }
+        self.stop_heartbeat();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_error_display_not_found() {
+        let err = RegistryError::NotFound("pong".to_string());
+        assert_eq!(format!("{}", err), "Actor not found: pong");
+    }
+
+    #[test]
+    fn test_registry_error_display_offline() {
+        let err = RegistryError::Offline("pong".to_string());
+        assert_eq!(format!("{}", err), "Actor offline: pong");
+    }
+
+    #[test]
+    fn test_registry_error_display_registration_failed() {
+        let err = RegistryError::RegistrationFailed {
+            actor_name: "pong".to_string(),
+            reason: "Name already registered".to_string(),
+        };
+        assert_eq!(
+            format!("{}", err),
+            "Registration failed for 'pong': Name already registered"
+        );
+    }
+
+    #[test]
+    fn test_registry_error_display_connection_error() {
+        let err = RegistryError::ConnectionError("Connection refused".to_string());
+        assert_eq!(format!("{}", err), "Registry connection error: Connection refused");
+    }
+
+    #[test]
+    fn test_registry_error_display_timeout() {
+        let err = RegistryError::Timeout("Operation timed out".to_string());
+        assert_eq!(format!("{}", err), "Registry timeout: Operation timed out");
+    }
+
+    #[test]
+    fn test_register_message_format() {
+        let msg = json!({
+            "message_type": "RegisterActor",
+            "manager_id": "mgr1",
+            "actor_name": "pong",
+            "actor_endpoint": "tcp://localhost:5001"
+        });
+
+        assert_eq!(msg["message_type"], "RegisterActor");
+        assert_eq!(msg["manager_id"], "mgr1");
+        assert_eq!(msg["actor_name"], "pong");
+        assert_eq!(msg["actor_endpoint"], "tcp://localhost:5001");
+    }
+
+    #[test]
+    fn test_lookup_message_format() {
+        let msg = json!({
+            "message_type": "LookupActor",
+            "actor_name": "pong"
+        });
+
+        assert_eq!(msg["message_type"], "LookupActor");
+        assert_eq!(msg["actor_name"], "pong");
+    }
+
+    #[test]
+    fn test_parse_lookup_many_reply() {
+        let reply = json!({
+            "message_type": "LookupManyResult",
+            "results": [
+                {"actor_name": "pong", "endpoint": "tcp://localhost:5001", "online": true},
+                {"actor_name": "ping", "endpoint": "tcp://localhost:5002", "online": false},
+                {"actor_name": "gone", "endpoint": null, "online": false}
+            ]
+        });
+
+        let names = ["pong", "ping", "gone", "missing"];
+        let registry_names: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+        let results = parse_lookup_many_reply(&names, &registry_names, &reply).unwrap();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].0, "pong");
+        assert_eq!(results[0].1.as_ref().unwrap(), "tcp://localhost:5001");
+        assert!(matches!(results[1].1, Err(RegistryError::Offline(_))));
+        assert!(matches!(results[2].1, Err(RegistryError::NotFound(_))));
+        assert!(matches!(results[3].1, Err(RegistryError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_parse_lookup_many_reply_unrecognised() {
+        let reply = json!({"error": "Unknown message type: LookupMany"});
+        assert!(parse_lookup_many_reply(&["pong"], &["pong".to_string()], &reply).is_none());
+    }
+
+    #[test]
+    fn test_heartbeat_message_format() {
+        let msg = heartbeat_message("mgr1", &HashMap::new());
+
+        assert_eq!(msg["message_type"], "Heartbeat");
+        assert_eq!(msg["manager_id"], "mgr1");
+        assert!(msg["timestamp_ms"].as_u64().unwrap() > 0);
+        assert_eq!(msg["metadata"], json!({}));
+
+        let metadata = HashMap::from([("tenant_id".to_string(), "acme".to_string())]);
+        let msg = heartbeat_message("mgr1", &metadata);
+        assert_eq!(msg["metadata"], json!({"tenant_id": "acme"}));
+    }
+
+    #[test]
+    fn test_lookup_cache() {
+        let config = RegistryClientConfig::default().with_cache_ttl(Duration::from_secs(60));
+        let client = RegistryClient::with_config("test_manager", "tcp://localhost:5555", config);
+
+        client.cache_endpoint("pong", "tcp://localhost:5001");
+        assert_eq!(client.lookup("pong").unwrap(), "tcp://localhost:5001");
+
+        client.invalidate_cache("pong");
+        assert!(client.cached_endpoint("pong").is_none());
+    }
+
+    #[test]
+    fn test_lookup_cache_expired_and_disabled() {
+        let config = RegistryClientConfig::default().with_cache_ttl(Duration::from_millis(0));
+        let client = RegistryClient::with_config("test_manager", "tcp://localhost:5555", config);
+        client.cache_endpoint("pong", "tcp://localhost:5001");
+        assert!(client.cached_endpoint("pong").is_none());
+
+        // Caching is off by default
+        let client = RegistryClient::new("test_manager", "tcp://localhost:5555");
+        client.cache_endpoint("pong", "tcp://localhost:5001");
+        assert!(client.cached_endpoint("pong").is_none());
+    }
+
+    #[test]
+    fn test_transaction_staging() {
+        let client = RegistryClient::new("test_manager", "tcp://localhost:5555");
+        let mut txn = client.begin_transaction();
+        assert!(txn.is_empty());
+
+        txn.register("a", "tcp://localhost:5001").deregister("b");
+        assert_eq!(txn.len(), 2);
+        assert_eq!(txn.operations[0]["op"], "register");
+        assert_eq!(txn.operations[0]["actor_endpoint"], "tcp://localhost:5001");
+        assert_eq!(txn.operations[1]["op"], "deregister");
+        assert_eq!(txn.operations[1]["actor_name"], "b");
+
+        txn.rollback();
+    }
+
+    #[test]
+    fn test_namespace() {
+        let plain = RegistryClient::new("mgr1", "tcp://localhost:5555");
+        assert_eq!(plain.qualify("pong"), "pong");
+        assert_eq!(plain.strip_namespace("app/pong"), "app/pong");
+        assert!(plain.in_namespace("other/pong"));
+
+        let config = RegistryClientConfig::default().with_namespace("app");
+        let client = RegistryClient::with_config("mgr1", "tcp://localhost:5555", config);
+        assert_eq!(client.qualify("pong"), "app/pong");
+        assert_eq!(client.strip_namespace("app/pong"), "pong");
+        assert_eq!(client.strip_namespace("application/pong"), "application/pong");
+        assert!(client.in_namespace("app/pong"));
+        assert!(!client.in_namespace("other/pong"));
+
+        let mut txn = client.begin_transaction();
+        txn.register("a", "tcp://localhost:5001");
+        assert_eq!(txn.operations[0]["actor_name"], "app/a");
+        assert_eq!(txn.operations[0]["datacenter"], "");
+
+        let reply = json!({"message_type": "TxnFailed", "actor_name": "app/a", "reason": "taken"});
+        assert!(matches!(
+            txn_failed(&client, &reply),
+            RegistryError::RegistrationFailed { actor_name, .. } if actor_name == "a"
+        ));
+    }
+
+    #[test]
+    fn test_txn_failed_error() {
+        let reply = json!({
+            "message_type": "TxnFailed",
+            "actor_name": "b",
+            "reason": "Name already registered"
+        });
+        let client = RegistryClient::new("test_manager", "tcp://localhost:5555");
+        let err = txn_failed(&client, &reply);
+        assert_eq!(format!("{}", err), "Registration failed for 'b': Name already registered");
+    }
+
+    #[test]
+    fn test_heartbeat_tracker_window() {
+        let mut tracker = HeartbeatTracker::default();
+        assert_eq!(tracker.stats(), HeartbeatStats::default());
+
+        for ms in 1..=12 {
+            tracker.record_rtt(Duration::from_millis(ms));
+        }
+        tracker.record_missed();
+
+        // Only the last 10 (3..=12 ms) count
+        let stats = tracker.stats();
+        assert_eq!(stats.last_rtt, Duration::from_millis(12));
+        assert_eq!(stats.max_rtt, Duration::from_millis(12));
+        assert_eq!(stats.avg_rtt, Duration::from_micros(7500));
+        assert_eq!(stats.missed_count, 1);
+    }
+
+    #[test]
+    fn test_parse_manager_event() {
+        let joined = json!({"event": "Joined", "manager_id": "mgr1", "timestamp_ms": 1000});
+        assert_eq!(
+            parse_manager_event(&joined),
+            Some(ManagerLifecycleEvent::Joined {
+                manager_id: "mgr1".to_string(),
+                timestamp_ms: 1000,
+            })
+        );
+
+        let left = json!({"event": "Left", "manager_id": "mgr2", "timestamp_ms": 2000, "graceful": true});
+        assert_eq!(
+            parse_manager_event(&left),
+            Some(ManagerLifecycleEvent::Left {
+                manager_id: "mgr2".to_string(),
+                timestamp_ms: 2000,
+                graceful: true,
+            })
+        );
+
+        assert_eq!(parse_manager_event(&json!({"event": "Moved", "manager_id": "mgr1"})), None);
+    }
+
+    #[test]
+    fn test_announcement_fills_cache() {
+        let config = RegistryClientConfig::default()
+            .with_cache_ttl(Duration::from_secs(60))
+            .with_namespace("prod");
+        let client = RegistryClient::with_config("mgr1", "tcp://localhost:5555", config);
+        let handler = client.announcement_handler();
+
+        handler(&json!({
+            "manager_id": "mgr2",
+            "actors": [["prod/pong", "tcp://host2:5001"], ["staging/pong", "tcp://host3:5001"]],
+            "timestamp_ms": 1000
+        }));
+        assert_eq!(client.cached_endpoint("pong"), Some("tcp://host2:5001".to_string()));
+        assert!(client.cached_endpoint("staging/pong").is_none());
+
+        // This manager's own announcement is ignored
+        handler(&json!({"manager_id": "mgr1", "actors": [["prod/ping", "tcp://host1:5001"]]}));
+        assert!(client.cached_endpoint("ping").is_none());
+        assert_eq!(parse_announcement(&json!({"actors": []})), None);
+    }
+
+    #[test]
+    fn test_deregister_result() {
+        let ok = json!({"message_type": "RegistrationOk", "actor_name": "pong"});
+        assert_eq!(deregister_result(&ok).unwrap(), DeregisterResult::Deregistered);
+
+        let not_owner = json!({"message_type": "NotOwner", "actor_name": "pong", "current_manager": "mgr2"});
+        assert_eq!(
+            deregister_result(&not_owner).unwrap(),
+            DeregisterResult::NotOwner { current_manager: "mgr2".to_string() }
+        );
+
+        assert!(deregister_result(&json!({"message_type": "Error"})).is_err());
+    }
+
+    #[test]
+    fn test_initial_actor_event() {
+        let online = json!({"message_type": "LookupResult", "actor_name": "pong", "endpoint": "tcp://host:5001", "online": true});
+        assert_eq!(
+            initial_actor_event(&online),
+            Some(ActorEvent::Online { endpoint: "tcp://host:5001".to_string() })
+        );
+
+        let offline = json!({"message_type": "LookupResult", "actor_name": "pong", "endpoint": "tcp://host:5001", "online": false});
+        assert_eq!(initial_actor_event(&offline), Some(ActorEvent::Offline));
+
+        let missing = json!({"message_type": "LookupResult", "actor_name": "pong", "endpoint": null, "online": false});
+        assert_eq!(initial_actor_event(&missing), None);
+        assert_eq!(initial_actor_event(&json!({"message_type": "Error"})), None);
+    }
+
+    #[test]
+    fn test_parse_actor_event() {
+        assert_eq!(
+            parse_actor_event(&json!({"event": "Online", "actor_name": "pong", "endpoint": "tcp://h:1"})),
+            Some(ActorEvent::Online {
+                endpoint: "tcp://h:1".to_string()
+            })
+        );
+        assert_eq!(parse_actor_event(&json!({"event": "Offline"})), Some(ActorEvent::Offline));
+        assert_eq!(parse_actor_event(&json!({"event": "Unregistered"})), Some(ActorEvent::Unregistered));
+        assert_eq!(parse_actor_event(&json!({"event": "Online"})), None);
+    }
+
+    #[test]
+    fn test_subscribe_filters_exact_actor() {
+        use zeromq::PubSocket;
+
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let mut publisher = PubSocket::new();
+        let endpoint = rt.block_on(publisher.bind("tcp://127.0.0.1:0")).unwrap().to_string();
+
+        let config = RegistryClientConfig::default().with_events_endpoint(&endpoint);
+        let client = RegistryClient::with_config("mgr1", "tcp://127.0.0.1:1", config);
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        let _sub = client.subscribe("pong", move |event| {
+            let _ = tx.lock().unwrap().send(event);
+        });
+
+        // Publish until the subscriber has joined; "pong2" shares the topic prefix
+        let event = rt.block_on(async {
+            for _ in 0..50 {
+                for (actor, endpoint) in [("pong2", "tcp://wrong:1"), ("pong", "tcp://right:1")] {
+                    let mut frames = ZmqMessage::from(format!("{}{}", ACTOR_EVENT_TOPIC_PREFIX, actor).into_bytes());
+                    let payload = json!({"event": "Online", "actor_name": actor, "endpoint": endpoint});
+                    frames.push_back(payload.to_string().into_bytes().into());
+                    publisher.send(frames).await.unwrap();
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                if let Ok(event) = rx.try_recv() {
+                    return Some(event);
+                }
+            }
+            None
+        });
+
+        assert_eq!(
+            event,
+            Some(ActorEvent::Online {
+                endpoint: "tcp://right:1".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_events_endpoint() {
+        let client = RegistryClient::new("mgr1", "tcp://localhost:5555");
+        assert_eq!(client.events_endpoint(), "tcp://localhost:5556");
+
+        let config = RegistryClientConfig::default().with_events_endpoint("tcp://events:7000");
+        let client = RegistryClient::with_config("mgr1", "tcp://localhost:5555", config);
+        assert_eq!(client.events_endpoint(), "tcp://events:7000");
+    }
+
+    #[test]
+    fn test_replies_matched_by_request_id() {
+        use zeromq::RouterSocket;
+
+        // Mock registry that answers two lookups in reverse order
+        let (endpoint_tx, endpoint_rx) = std::sync::mpsc::channel();
+        let server = std::thread::spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+            rt.block_on(async {
+                let mut socket = RouterSocket::new();
+                let endpoint = socket.bind("tcp://127.0.0.1:0").await.unwrap();
+                endpoint_tx.send(endpoint.to_string()).unwrap();
+
+                let mut requests = Vec::new();
+                for _ in 0..2 {
+                    requests.push(socket.recv().await.unwrap());
+                }
+                for request in requests.into_iter().rev() {
+                    let msg: serde_json::Value =
+                        serde_json::from_slice(request.iter().last().unwrap()).unwrap();
+                    let reply = json!({
+                        "message_type": "LookupResult",
+                        "request_id": msg["request_id"],
+                        "endpoint": format!("tcp://{}", msg["actor_name"].as_str().unwrap()),
+                        "online": true
+                    });
+                    let mut frames = ZmqMessage::from(request.get(0).unwrap().clone());
+                    frames.push_back(Vec::<u8>::new().into());
+                    frames.push_back(reply.to_string().into_bytes().into());
+                    socket.send(frames).await.unwrap();
+                }
+            });
+        });
+
+        let client = Arc::new(RegistryClient::new("mgr1", &endpoint_rx.recv().unwrap()));
+        let lookups: Vec<_> = ["alpha", "beta"]
+            .into_iter()
+            .map(|name| {
+                let client = Arc::clone(&client);
+                std::thread::spawn(move || client.lookup(name))
+            })
+            .collect();
+
+        assert_eq!(lookups.into_iter().map(|h| h.join().unwrap().unwrap()).collect::<Vec<_>>(), vec![
+            "tcp://alpha".to_string(),
+            "tcp://beta".to_string()
+        ]);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_reply_without_request_id() {
+        // Answered in order, so a reply without an id is for the oldest request
+        let endpoint = mock_registry(|_| json!({"message_type": "Pong", "request_id": null}));
+        let client = RegistryClient::new("mgr1", &endpoint);
+        assert!(client.ping_registry().is_ok());
+        assert!(client.ping_registry().is_ok());
+    }
+
+    /// Start a mock registry that answers every request with `handler`'s reply.
+    ///
+    /// The `request_id` is echoed automatically, unless the reply has one.
+    /// Returns the endpoint; the server thread runs until the test process exits.
+    fn mock_registry<F>(handler: F) -> String
+    where
+        F: Fn(&serde_json::Value) -> serde_json::Value + Send + 'static,
+    {
+        use zeromq::RouterSocket;
+
+        let (endpoint_tx, endpoint_rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+            rt.block_on(async {
+                let mut socket = RouterSocket::new();
+                let endpoint = socket.bind("tcp://127.0.0.1:0").await.unwrap();
+                endpoint_tx.send(endpoint.to_string()).unwrap();
+
+                while let Ok(request) = socket.recv().await {
+                    let msg: serde_json::Value = serde_json::from_slice(request.iter().last().unwrap()).unwrap();
+                    let mut reply = handler(&msg);
+                    if reply.get("request_id").is_none() {
+                        reply["request_id"] = msg["request_id"].clone();
+                    }
+
+                    // [peer identity, empty delimiter, reply]
+                    let mut frames = ZmqMessage::from(request.get(0).unwrap().clone());
+                    frames.push_back(Vec::<u8>::new().into());
+                    frames.push_back(reply.to_string().into_bytes().into());
+                    if socket.send(frames).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        });
+        endpoint_rx.recv().unwrap()
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("test_*", "test_actor"));
+        assert!(glob_match("test_*", "test_"));
+        assert!(glob_match("*_tmp", "worker_tmp"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(glob_match("*a*b*", "xxaxxbxx"));
+        assert!(!glob_match("test_*", "prod_actor"));
+        assert!(!glob_match("a?c", "ac"));
+        assert!(!glob_match("exact", "exactly"));
+    }
+
+    #[test]
+    fn test_name_blacklist_skips_registry() {
+        let registered = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&registered);
+        let endpoint = mock_registry(move |msg| {
+            seen.lock().unwrap().push(msg["actor_name"].as_str().unwrap().to_string());
+            json!({"message_type": "RegistrationOk"})
+        });
+
+        let client = RegistryClient::new("mgr1", &endpoint);
+        client.add_name_blacklist("test_*");
+        // Clients with a correlation ID share the blacklist
+        let traced = client.with_correlation_id(Uuid::new_v4());
+
+        match traced.register("test_actor", "tcp://h:1") {
+            Err(RegistryError::RegistrationFailed { actor_name, reason }) => {
+                assert_eq!(actor_name, "test_actor");
+                assert_eq!(reason, "Blacklisted name");
+            }
+            other => panic!("expected RegistrationFailed, got {:?}", other),
+        }
+        assert!(client.register_group_member("workers", "test_worker", "tcp://h:2").is_err());
+        client.register("prod_actor", "tcp://h:3").unwrap();
+
+        // A transaction staging a blacklisted name fails as a whole
+        let mut txn = client.begin_transaction();
+        txn.register("prod_other", "tcp://h:4").register("test_other", "tcp://h:4");
+        match txn.commit() {
+            Err(RegistryError::RegistrationFailed { actor_name, .. }) => assert_eq!(actor_name, "test_other"),
+            other => panic!("expected RegistrationFailed, got {:?}", other),
+        }
+
+        assert_eq!(*registered.lock().unwrap(), vec!["prod_actor".to_string()]);
+    }
+
+    #[test]
+    fn test_custom_serializer() {
+        struct CountingSerializer {
+            calls: Arc<AtomicU64>,
+        }
+        impl RegistrySerializer for CountingSerializer {
+            fn serialize(&self, value: &serde_json::Value) -> Vec<u8> {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                JsonSerializer.serialize(value)
+            }
+            fn deserialize(&self, bytes: &[u8]) -> serde_json::Value {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                JsonSerializer.deserialize(bytes)
+            }
+        }
+
+        let endpoint = mock_registry(|_| {
+            json!({"message_type": "LookupResult", "endpoint": "tcp://h:1", "online": true})
+        });
+        let calls = Arc::new(AtomicU64::new(0));
+        let serializer = CountingSerializer { calls: Arc::clone(&calls) };
+        let client = RegistryClient::new_with_serializer("mgr1", &endpoint, Box::new(serializer));
+
+        assert_eq!(client.lookup("pong").unwrap(), "tcp://h:1");
+        // One request encoded, one reply decoded
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(JsonSerializer.deserialize(b"not json").is_null());
+    }
+
+    #[test]
+    fn test_hedged_lookup() {
+        let primary = mock_registry(|msg| {
+            if msg["actor_name"] == "slow" {
+                std::thread::sleep(Duration::from_millis(500));
+            }
+            match msg["message_type"].as_str() {
+                Some("RegisterActor") => json!({"message_type": "RegistrationOk"}),
+                _ => json!({"message_type": "LookupResult", "endpoint": "tcp://primary:1", "online": true}),
+            }
+        });
+        let secondary_writes = Arc::new(AtomicU64::new(0));
+        let writes = Arc::clone(&secondary_writes);
+        let secondary = mock_registry(move |msg| {
+            if msg["message_type"] != "LookupActor" {
+                writes.fetch_add(1, Ordering::SeqCst);
+            }
+            json!({"message_type": "LookupResult", "endpoint": "tcp://secondary:1", "online": true})
+        });
+
+        let client = RegistryClient::new_hedged("mgr1", vec![primary, secondary], Duration::from_millis(100));
+
+        // A prompt primary answers alone
+        assert_eq!(client.lookup("fast").unwrap(), "tcp://primary:1");
+        assert_eq!(client.hedge_count(), 0);
+
+        // A slow primary is hedged and the other node's reply wins
+        assert_eq!(client.lookup("slow").unwrap(), "tcp://secondary:1");
+        assert_eq!(client.hedge_count(), 1);
+
+        // Writes are never hedged, however slow the primary is
+        client.register("slow", "tcp://h:1").unwrap();
+        assert_eq!(client.hedge_count(), 1);
+        assert_eq!(secondary_writes.load(Ordering::SeqCst), 0);
+        assert_eq!(RegistryClient::new("mgr1", "tcp://127.0.0.1:1").hedge_count(), 0);
+    }
+
+    #[test]
+    fn test_lookup_group() {
+        let endpoint = mock_registry(|msg| {
+            assert_eq!(msg["group_name"], "prod/payments");
+            json!({
+                "message_type": "GroupMembers",
+                "actors": [
+                    {"actor_name": "prod/p1", "endpoint": "tcp://h:1", "manager_id": "mgr1", "online": true},
+                    {"actor_name": "prod/p2", "endpoint": "tcp://h:2", "manager_id": "mgr2", "online": false}
+                ]
+            })
+        });
+
+        let config = RegistryClientConfig::default().with_namespace("prod");
+        let client = RegistryClient::with_config("mgr1", &endpoint, config);
+        let members = client.lookup_group("payments").unwrap();
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].endpoint, "tcp://h:1");
+    }
+
+    #[test]
+    fn test_geo_aware_lookup_sends_datacenter() {
+        let endpoint = mock_registry(|msg| {
+            assert_eq!(msg["datacenter"], "eu-west-1");
+            json!({
+                "message_type": "LookupResult",
+                "actor_name": msg["actor_name"],
+                "endpoint": "tcp://eu:5001",
+                "online": true,
+                "datacenter": "eu-west-1"
+            })
+        });
+
+        let client = RegistryClient::new_geo_aware("mgr1", &endpoint, "eu-west-1");
+        assert_eq!(client.lookup("pong").unwrap(), "tcp://eu:5001");
+
+        let plain = RegistryClient::new("mgr2", "tcp://127.0.0.1:1");
+        assert_eq!(plain.datacenter(), "");
+    }
+
+    #[test]
+    fn test_capacity_and_least_loaded_lookup() {
+        let endpoint = mock_registry(|msg| match msg["message_type"].as_str() {
+            Some("RegisterActor") => {
+                assert_eq!(msg["max_capacity"], 10);
+                json!({"message_type": "RegistrationOk"})
+            }
+            Some("UpdateCapacity") => {
+                assert_eq!(msg["current_load"], 4);
+                assert_eq!(msg["manager_id"], "mgr1");
+                json!({"message_type": "RegistrationOk"})
+            }
+            _ => {
+                assert_eq!(msg["policy"], "LeastLoaded");
+                json!({"message_type": "LookupResult", "endpoint": "tcp://h:1", "online": true})
+            }
+        });
+
+        let config = RegistryClientConfig::default().with_cache_ttl(Duration::from_secs(60));
+        let client = RegistryClient::with_config("mgr1", &endpoint, config);
+        client.register_with_capacity("worker", "tcp://h:1", 10).unwrap();
+        client.update_capacity("worker", 4).unwrap();
+        assert_eq!(client.lookup_with_policy("worker", LoadBalancePolicy::LeastLoaded).unwrap(), "tcp://h:1");
+
+        // Not cached: the next LeastLoaded lookup asks again
+        assert!(client.cached_endpoint("worker").is_none());
+    }
+
+    #[test]
+    fn test_priority_and_weighted_lookup() {
+        let endpoint = mock_registry(|msg| match msg["message_type"].as_str() {
+            Some("RegisterActor") => {
+                assert_eq!(msg["priority"], 10);
+                json!({"message_type": "RegistrationOk"})
+            }
+            Some("UnregisterActor") => {
+                assert_eq!(msg["actor_endpoint"], "tcp://green:1");
+                json!({"message_type": "RegistrationOk"})
+            }
+            _ => {
+                assert_eq!(msg["policy"], "Weighted");
+                json!({"message_type": "LookupResult", "endpoint": "tcp://green:1", "online": true})
+            }
+        });
+
+        let client = RegistryClient::new("mgr1", &endpoint);
+        client.register_with_priority("pricer", "tcp://green:1", 10).unwrap();
+        assert_eq!(client.lookup_with_load_balance("pricer").unwrap(), "tcp://green:1");
+        assert!(client.cached_endpoint("pricer").is_none());
+        client.unregister_instance("pricer", "tcp://green:1").unwrap();
+    }
+
+    #[test]
+    fn test_versioned_register_and_lookup() {
+        let endpoint = mock_registry(|msg| match msg["message_type"].as_str() {
+            Some("RegisterActor") => {
+                assert_eq!(msg["version"], 2);
+                json!({"message_type": "RegistrationOk"})
+            }
+            _ => match msg["version"]["constraint"].as_str() {
+                Some("AtLeast") => {
+                    assert_eq!(msg["version"]["version"], 1);
+                    json!({"message_type": "LookupResult", "endpoint": "tcp://h:2", "online": true, "version": 2})
+                }
+                _ => json!({"message_type": "LookupResult", "endpoint": null, "online": false}),
+            },
+        });
+
+        let client = RegistryClient::new("mgr1", &endpoint);
+        client.register_versioned("pricer", 2, "tcp://h:2").unwrap();
+        assert_eq!(
+            client.lookup_versioned("pricer", VersionConstraint::AtLeast(1)).unwrap(),
+            ("tcp://h:2".to_string(), 2)
+        );
+        assert!(matches!(
+            client.lookup_versioned("pricer", VersionConstraint::Exact(3)),
+            Err(RegistryError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_registry_snapshot_round_trip() {
+        let state = json!({"version": 1, "actors": [{"actor_name": "pong", "endpoint": "tcp://h:1"}]});
+        let exported = state.clone();
+        let endpoint = mock_registry(move |msg| match msg["message_type"].as_str() {
+            Some("ExportState") => json!({"message_type": "RegistryState", "state": exported}),
+            Some("ImportState") if msg["state"]["version"] == 1 => {
+                assert_eq!(msg["state"]["actors"][0]["actor_name"], "pong");
+                json!({"message_type": "StateImported", "count": 1})
+            }
+            _ => json!({"message_type": "ImportFailed", "reason": "Missing version"}),
+        });
+
+        let dir = std::env::temp_dir().join(format!("actors-snapshot-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("registry.json");
+
+        let client = RegistryClient::new("mgr1", &endpoint);
+        client.export_registry_snapshot(path.clone()).unwrap();
+        let written: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written, state);
+        client.import_registry_snapshot(path.clone()).unwrap();
+
+        // Rejected contents, unparseable files and missing files are errors
+        fs::write(&path, "{}").unwrap();
+        assert!(matches!(client.import_registry_snapshot(path.clone()), Err(ExportError::InvalidSnapshot(_))));
+        fs::write(&path, "not json").unwrap();
+        assert!(matches!(client.import_registry_snapshot(path.clone()), Err(ExportError::InvalidSnapshot(_))));
+        assert!(matches!(client.import_registry_snapshot(dir.join("missing.json")), Err(ExportError::Io(_))));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_deregister_all_on_drop() {
+        let (seen_tx, seen_rx) = std::sync::mpsc::channel();
+        let endpoint = mock_registry(move |msg| {
+            let message_type = msg["message_type"].as_str().unwrap_or("").to_string();
+            let reply = match message_type.as_str() {
+                "DeregisterManager" => json!({"message_type": "ManagerDeregistered", "count": 2}),
+                _ => json!({"message_type": "HeartbeatAck"}),
+            };
+            let _ = seen_tx.send(message_type);
+            reply
+        });
+
+        let client = RegistryClient::new("mgr1", &endpoint);
+        assert_eq!(client.deregister_all().unwrap(), 2);
+
+        // Not heartbeating: dropping sends nothing
+        let idle = client.with_correlation_id(Uuid::new_v4());
+        drop(idle);
+
+        client.start_heartbeat();
+        drop(client);
+
+        let deregisters = seen_rx.try_iter().filter(|t| t == "DeregisterManager").count();
+        assert_eq!(deregisters, 2);
+    }
+
+    #[test]
+    fn test_is_registry_alive_without_registry() {
+        let client = RegistryClient::new("mgr1", "tcp://127.0.0.1:1");
+
+        let start = Instant::now();
+        assert!(!client.is_registry_alive());
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy::new(10, Duration::from_millis(100), Duration::from_millis(1000));
+        assert_eq!(policy.delay_for(0), Duration::from_millis(100));
+        assert_eq!(policy.delay_for(1), Duration::from_millis(200));
+        assert_eq!(policy.delay_for(2), Duration::from_millis(400));
+        assert_eq!(policy.delay_for(4), Duration::from_millis(1000));
+        assert_eq!(policy.delay_for(40), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_parse_actor_list() {
+        let reply = json!({
+            "message_type": "ActorList",
+            "actors": [
+                {"actor_name": "pong", "endpoint": "tcp://localhost:5001", "manager_id": "mgr1", "online": true},
+                {"actor_name": "ping", "endpoint": "tcp://localhost:5002", "manager_id": "mgr2", "online": false},
+                {"actor_name": "broken"}
+            ]
+        });
+
+        let actors = parse_actor_list(&reply);
+        assert_eq!(actors.len(), 2);
+        assert_eq!(
+            actors[0],
+            ActorInfo {
+                actor_name: "pong".to_string(),
+                endpoint: "tcp://localhost:5001".to_string(),
+                manager_id: "mgr1".to_string(),
+                online: true,
+            }
+        );
+        assert!(!actors[1].online);
+        assert!(parse_actor_list(&json!({"message_type": "ActorList"})).is_empty());
+    }
+
+    #[test]
+    fn test_circuit_breaker_transitions() {
+        let mut breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            reset_timeout: Duration::from_millis(20),
+        });
+        assert!(breaker.allow_request());
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allow_request());
+
+        // After the reset timeout exactly one probe goes through
+        thread::sleep(Duration::from_millis(30));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.allow_request());
+        assert!(!breaker.allow_request());
+
+        // Failed probe reopens, successful probe closes
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        thread::sleep(Duration::from_millis(30));
+        assert!(breaker.allow_request());
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_with_correlation_id_shares_state() {
+        let client = RegistryClient::with_config(
+            "mgr1",
+            "tcp://localhost:5555",
+            RegistryClientConfig::default().with_cache_ttl(Duration::from_secs(60)),
+        );
+        let id = Uuid::from_u128(0x1234);
+        let traced = client.with_correlation_id(id);
+
+        assert_eq!(client.correlation_id(), None);
+        assert_eq!(traced.correlation_id(), Some(id));
+
+        traced.cache_endpoint("pong", "tcp://localhost:5001");
+        assert_eq!(client.cached_endpoint("pong"), Some("tcp://localhost:5001".to_string()));
+    }
+
+    #[test]
+    fn test_wait_options() {
+        let options = WaitOptions::default();
+        assert_eq!(options.poll_interval, Duration::from_millis(100));
+        assert!(!options.accept_offline);
+
+        let options = WaitOptions::default()
+            .with_poll_interval(Duration::from_millis(10))
+            .with_timeout(Duration::from_secs(1))
+            .with_accept_offline(true);
+        assert_eq!(options.poll_interval, Duration::from_millis(10));
+        assert_eq!(options.timeout, Duration::from_secs(1));
+        assert!(options.accept_offline);
+    }
+
+    #[test]
+    fn test_wait_for_actor_times_out() {
+        // With the breaker open every poll fails fast without touching the network
+        let config = RegistryClientConfig::default().with_circuit_breaker(CircuitBreakerConfig {
+            failure_threshold: 1,
+            reset_timeout: Duration::from_secs(60),
+        });
+        let client = RegistryClient::with_config("mgr1", "tcp://localhost:5555", config);
+        client.breaker.lock().unwrap().record_failure();
+
+        let start = Instant::now();
+        let result = client.wait_for_actor("pong", Duration::from_millis(10), Duration::from_millis(50));
+        assert!(matches!(result, Err(RegistryError::Timeout(_))));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_wait_for_actor_without_deadline() {
+        use std::sync::atomic::AtomicUsize;
+
+        // Not found for the first lookups, then online
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let endpoint = mock_registry(move |_| {
+            if lookups.fetch_add(1, Ordering::SeqCst) < 2 {
+                json!({"message_type": "Unknown"})
+            } else {
+                json!({"message_type": "LookupResult", "endpoint": "tcp://pong:5001", "online": true})
+            }
+        });
+        let client = RegistryClient::new("mgr1", &endpoint);
+        let options = WaitOptions::default()
+            .with_poll_interval(Duration::from_millis(1))
+            .with_timeout(Duration::MAX);
+        assert_eq!(client.wait_for_actor_with("pong", &options).unwrap(), "tcp://pong:5001");
     }
 
     #[test]
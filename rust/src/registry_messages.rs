@@ -16,21 +16,77 @@ use crate::define_message;
 ///
 /// Sent during Manager::manage() to register actor name -> endpoint mapping.
 /// GlobalRegistry replies with RegistrationOk or RegistrationFailed.
+/// Create it with `new` and the `with_*` methods.
+#[non_exhaustive]
 pub struct RegisterActor {
     pub manager_id: String,
     pub actor_name: String,
     pub actor_endpoint: String,  // ZMQ endpoint for reaching this actor
+    pub datacenter: String,      // empty unless registered by a geo-aware manager
+    pub max_capacity: Option<u32>,  // for LeastLoaded lookups (None = not load-tracked)
 }
 define_message!(RegisterActor);
 
+impl RegisterActor {
+    pub fn new(manager_id: String, actor_name: String, actor_endpoint: String) -> Self {
+        RegisterActor {
+            manager_id,
+            actor_name,
+            actor_endpoint,
+            datacenter: String::new(),
+            max_capacity: None,
+        }
+    }
+
+    /// Register the actor in `datacenter` (see `RegistryClient::new_geo_aware`)
+    pub fn with_datacenter(mut self, datacenter: &str) -> Self {
+        self.datacenter = datacenter.to_string();
+        self
+    }
+
+    /// Track the actor's load for LeastLoaded lookups
+    pub fn with_max_capacity(mut self, max_capacity: u32) -> Self {
+        self.max_capacity = Some(max_capacity);
+        self
+    }
+}
+
+/// UpdateCapacity - Report an actor's current load
+///
+/// Sent periodically for actors registered with a max_capacity, and applied
+/// to the instances `manager_id` registered. GlobalRegistry replies with
+/// RegistrationOk, or RegistrationFailed if the actor is not registered.
+pub struct UpdateCapacity {
+    pub actor_name: String,
+    pub current_load: u32,
+    pub datacenter: String,
+    pub manager_id: String,
+}
+define_message!(UpdateCapacity);
+
 /// UnregisterActor - Remove an actor from the registry
 ///
 /// Sent when an actor is stopped or Manager shuts down.
+/// Create it with `new` and the `with_*` methods.
+#[non_exhaustive]
 pub struct UnregisterActor {
     pub actor_name: String,
+    pub datacenter: String,  // as given at registration
 }
 define_message!(UnregisterActor);
 
+impl UnregisterActor {
+    pub fn new(actor_name: String) -> Self {
+        UnregisterActor { actor_name, datacenter: String::new() }
+    }
+
+    /// Unregister the actor's registration in `datacenter`
+    pub fn with_datacenter(mut self, datacenter: &str) -> Self {
+        self.datacenter = datacenter.to_string();
+        self
+    }
+}
+
 /// RegistrationOk - Confirms successful actor registration
 pub struct RegistrationOk {
     pub actor_name: String,
@@ -50,23 +106,61 @@ define_message!(RegistrationFailed);
 ///
 /// Manager sends this when local lookup fails.
 /// GlobalRegistry replies with LookupResult via standard reply() mechanism.
+/// Create it with `new` and the `with_*` methods.
+#[non_exhaustive]
 pub struct LookupActor {
     pub actor_name: String,
+    pub datacenter: String,  // preferred datacenter (empty = no preference)
+    pub policy: String,      // "LeastLoaded" or empty for the default
 }
 define_message!(LookupActor);
 
+impl LookupActor {
+    pub fn new(actor_name: String) -> Self {
+        LookupActor { actor_name, datacenter: String::new(), policy: String::new() }
+    }
+
+    /// Prefer an instance in `datacenter`
+    pub fn with_datacenter(mut self, datacenter: &str) -> Self {
+        self.datacenter = datacenter.to_string();
+        self
+    }
+
+    /// Pick the instance with a lookup policy, e.g. "LeastLoaded"
+    pub fn with_policy(mut self, policy: &str) -> Self {
+        self.policy = policy.to_string();
+        self
+    }
+}
+
 /// LookupResult - Response to LookupActor
 ///
 /// Contains the endpoint if found, and online status.
 /// If endpoint is None, the actor was not found.
 /// If online is false, the actor's Manager has missed heartbeats.
+/// datacenter is empty unless the actor was registered by a geo-aware manager.
+/// Create it with `new` and the `with_*` methods.
+#[non_exhaustive]
 pub struct LookupResult {
     pub actor_name: String,
     pub endpoint: Option<String>,  // ZMQ endpoint for reaching this actor
     pub online: bool,
+    pub datacenter: String,
 }
 define_message!(LookupResult);
 
+impl LookupResult {
+    pub fn new(actor_name: String, endpoint: Option<String>, online: bool) -> Self {
+        LookupResult { actor_name, endpoint, online, datacenter: String::new() }
+    }
+
+    /// Report the datacenter the actor was registered in
+    pub fn with_datacenter(mut self, datacenter: &str) -> Self {
+        self.datacenter = datacenter.to_string();
+        self
+    }
+}
+
 /// Heartbeat - Manager health check
 ///
 /// Managers send this every 2 seconds.
@@ -91,3 +185,14 @@ impl Heartbeat {
 /// HeartbeatAck - Acknowledgement of heartbeat
 pub struct HeartbeatAck;
 define_message!(HeartbeatAck);
+
+/// Announce - A starting manager's actors, for peer discovery
+///
+/// GlobalRegistry replies with Announced and publishes the announcement on
+/// its `Announce` PUB topic, so subscribed managers can fill their lookup
+/// caches without a lookup per actor.
+pub struct Announce {
+    pub manager_id: String,
+    pub actors: Vec<(String, String)>,  // (actor_name, endpoint) pairs
+}
+define_message!(Announce);
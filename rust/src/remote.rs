@@ -20,24 +20,437 @@ http://m2te.ch/
 //!
 //! Uses the `zeromq` crate (pure Rust) for wire-compatible ZMQ messaging.
 
-use std::collections::HashMap;
-use std::sync::mpsc::{channel, Sender};
-use std::sync::{Arc, Mutex};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
 use tokio::runtime::Runtime;
-use zeromq::{PullSocket, PushSocket, Socket, SocketRecv, SocketSend};
+use uuid::Uuid;
+use zeromq::{PubSocket, PullSocket, PushSocket, Socket, SocketRecv, SocketSend, SubSocket, ZmqMessage};
 
-use crate::actor::ActorRef;
+use crate::actor::{ActorRef, Envelope, SendError, TypedActorRef};
+use crate::batch;
+use crate::codec::{self, to_hex, Decoded, SerializationBackend};
+use crate::compression::{compress, decompress, CompressionAlgorithm};
+use crate::dead_letters::DeadLetter;
 use crate::messages::Reject;
+use crate::rate_limit::{Admission, RateLimiter};
+use crate::registry::RetryPolicy;
+use crate::replay::{ReplayLog, SharedReplayLog};
 use crate::serialization::{get_type_name, serialize_message, try_deserialize_message};
 use crate::Message;
 
+/// Control frame sent by a ZmqReceiver when a tracked message was delivered.
+const CONTROL_ACK: &str = "Ack";
+
+/// Control frame sent by a ZmqReceiver when a tracked message can never be delivered.
+const CONTROL_NACK: &str = "Nack";
+
+/// Default number of idempotency keys a ZmqReceiver remembers for duplicate detection.
+const DEDUP_CAPACITY: usize = 10_000;
+
+/// Upper bound for the delay before retrying a failed send.
+const MAX_SEND_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How long a draining ZmqReceiver waits for another frame before deciding
+/// nothing is left to drain.
+const DRAIN_IDLE: Duration = Duration::from_millis(50);
+
+/// Longest wait of `ZmqReceiverHandle::stop_drain`; longer timeouts are clamped to it.
+const MAX_DRAIN_TIMEOUT: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
+/// `(endpoint, data)` captured by a test-recorder ZmqSender.
+type RecordedSend = (String, Vec<u8>);
+
+/// Subscription change forwarded to a running ZmqReceiver's SUB socket.
+enum TopicCommand {
+    Subscribe(String),
+    Unsubscribe(String),
+}
+
 /// Internal request for async remote sends.
 /// Sent to the dedicated sender thread.
 struct SendRequest {
     endpoint: String,
     data: Vec<u8>,
+    /// Set when the message must be retransmitted until acknowledged
+    delivery_id: Option<u64>,
+}
+
+/// Commands processed by the dedicated sender thread.
+enum SenderCommand {
+    /// Transmit a message
+    Send(SendRequest),
+    /// Transmit raw frames as one multipart message (from `multiplex` or a ZmqStreamWriter)
+    SendFrames { endpoint: String, frames: Vec<Vec<u8>> },
+    /// The receiver delivered the message with this delivery id
+    Ack(u64),
+    /// The receiver rejected the message with this delivery id
+    Nack(u64),
+    /// Transmit a message before `expires_at` (None: no limit) and report the outcome
+    SendTimeout {
+        request: SendRequest,
+        deadline: Duration,
+        expires_at: Option<Instant>,
+        reply: Sender<Result<(), SendError>>,
+        /// Where the task transmitting the message reports back
+        done: Sender<SenderCommand>,
+    },
+    /// A `SendTimeout` missed its deadline while transmitting on its own task
+    SendTimedOut {
+        request: SendRequest,
+        deadline: Duration,
+        reply: Sender<Result<(), SendError>>,
+    },
+    /// A `SendTimeout` task is done with its endpoint's socket
+    ReturnSocket { endpoint: String, socket: PushSocket },
+    /// Transmit a message to several endpoints at once and report the outcome
+    Broadcast {
+        endpoints: Vec<String>,
+        data: Vec<u8>,
+        reply: Sender<BroadcastResult>,
+        /// Where the task transmitting the message returns the sockets
+        done: Sender<SenderCommand>,
+    },
+}
+
+/// Outcome of `ZmqSender::broadcast`, by endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BroadcastResult {
+    /// Endpoints the message was handed to
+    pub succeeded: Vec<String>,
+    /// Endpoints that failed, with why
+    pub failed: Vec<(String, SendError)>,
+}
+
+/// An unacknowledged message held in the retry buffer.
+struct PendingSend {
+    endpoint: String,
+    data: Vec<u8>,
+    sent_at: Instant,
+}
+
+/// Delivery ids of tracked sends still waiting for their Ack or Nack
+/// (see `ZmqSenderConfig::with_max_in_flight`).
+#[derive(Default)]
+struct InFlight {
+    ids: Mutex<HashSet<u64>>,
+    /// Signalled whenever an id is removed
+    released: Condvar,
+}
+
+impl InFlight {
+    /// Add `id`, first waiting while `limit` sends are in flight if a limit is given.
+    fn acquire(&self, id: u64, limit: Option<usize>) {
+        let mut ids = self.ids.lock().unwrap();
+        if let Some(limit) = limit {
+            while ids.len() >= limit {
+                ids = self.released.wait(ids).unwrap();
+            }
+        }
+        ids.insert(id);
+    }
+
+    fn release(&self, id: u64) {
+        if self.ids.lock().unwrap().remove(&id) {
+            self.released.notify_all();
+        }
+    }
+}
+
+/// A message whose transmission failed, waiting to be tried again.
+struct RetrySend {
+    endpoint: String,
+    data: Vec<u8>,
+    /// Transmissions tried so far
+    attempts: u32,
+    due: Instant,
+    /// Back-off of a send that timed out (see `OnTimeout::Retry`); other
+    /// sends follow `ZmqSenderConfig::with_retry`
+    policy: Option<RetryPolicy>,
+}
+
+/// Failed untracked sends and the back-off state of their endpoints
+/// (see `ZmqSenderConfig::with_retry`).
+#[derive(Default)]
+struct SendRetries {
+    /// Endpoint -> consecutive failed transmissions to it
+    failures: HashMap<String, u32>,
+    waiting: Vec<RetrySend>,
+}
+
+impl SendRetries {
+    /// Reset the back-off of `endpoint` after a successful transmission.
+    fn on_success(&mut self, endpoint: &str) {
+        self.failures.remove(endpoint);
+    }
+
+    /// Schedule another attempt at `send`, or give it back once it has
+    /// used all of its attempts.
+    fn on_failure(&mut self, mut send: RetrySend, config: &ZmqSenderConfig) -> Option<RetrySend> {
+        let failures = self.failures.entry(send.endpoint.clone()).or_insert(0);
+        *failures += 1;
+        let delay = match &send.policy {
+            Some(policy) if send.attempts <= policy.max_retries => policy.delay_for(send.attempts - 1),
+            None if send.attempts < config.max_send_attempts => config.retry_backoff(*failures),
+            _ => return Some(send),
+        };
+        send.due = Instant::now() + delay;
+        self.waiting.push(send);
+        None
+    }
+
+    /// When the next retry is due
+    fn next_due(&self) -> Option<Instant> {
+        self.waiting.iter().map(|send| send.due).min()
+    }
+
+    /// Remove and return the retries that are due at `now`.
+    fn take_due(&mut self, now: Instant) -> Vec<RetrySend> {
+        let (due, waiting) = std::mem::take(&mut self.waiting)
+            .into_iter()
+            .partition(|send| send.due <= now);
+        self.waiting = waiting;
+        due
+    }
+}
+
+/// Delivery guarantee for messages sent through a ZmqSender.
+///
+/// `AtLeastOnce` and `ExactlyOnce` require the receiving process to be a Rust
+/// `ZmqReceiver`, which sends the Ack/Nack control frames back to the sender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SendSemantics {
+    /// Fire-and-forget. A message may be lost but is never sent twice.
+    #[default]
+    AtMostOnce,
+    /// Retransmit until the receiver acknowledges the message.
+    /// The target actor may see the same message more than once.
+    AtLeastOnce,
+    /// Retransmit until acknowledged and tag every message with an idempotency
+    /// key so the receiver can drop duplicates.
+    ExactlyOnce,
+}
+
+/// What happens to a message `ZmqSender::send_timeout` could not send in time.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum OnTimeout {
+    /// Discard it
+    #[default]
+    Drop,
+    /// Send it again in the background, backing off as `RetryPolicy` says;
+    /// it goes to the dead letter actor once the retries are used up
+    Retry(RetryPolicy),
+    /// Send it to the dead letter actor (see `ZmqSender::set_dead_letter_actor`)
+    DeadLetter,
+}
+
+/// Configuration for a ZmqSender.
+#[derive(Debug, Clone)]
+pub struct ZmqSenderConfig {
+    /// Delivery guarantee (default: AtMostOnce)
+    pub send_semantics: SendSemantics,
+    /// How long to wait for an Ack before retransmitting (default: 500ms)
+    pub ack_timeout: Duration,
+    /// Maximum number of unacknowledged messages kept for retransmission.
+    /// When full, the oldest pending message is no longer retransmitted and
+    /// goes to the dead letter actor (default: 10000)
+    pub retry_buffer_capacity: usize,
+    /// Transmission attempts for an `AtMostOnce` message before it goes to
+    /// the dead letter actor (default: 1, no retry)
+    pub max_send_attempts: u32,
+    /// Delay before retrying after the first failed send to an endpoint (default: 100ms)
+    pub initial_backoff: Duration,
+    /// Factor the delay grows by with each further failure (default: 2.0)
+    pub backoff_factor: f64,
+    /// How long `ZmqSender::broadcast` waits for each endpoint (default: 1s)
+    pub broadcast_timeout: Duration,
+    /// Compression of sent messages (default: None)
+    pub compression: CompressionAlgorithm,
+    /// Maximum number of unacknowledged tracked messages before `send_to`
+    /// blocks (default: None, unlimited)
+    pub max_in_flight: Option<usize>,
+    /// Tag every message with a random idempotency key (default: false)
+    pub idempotency_keys: bool,
+    /// What happens to a message `send_timeout` could not send in time (default: Drop)
+    pub on_timeout: OnTimeout,
+    /// Serialization of sent messages (default: Json)
+    pub serialization: SerializationBackend,
+}
+
+impl Default for ZmqSenderConfig {
+    fn default() -> Self {
+        ZmqSenderConfig {
+            send_semantics: SendSemantics::AtMostOnce,
+            ack_timeout: Duration::from_millis(500),
+            retry_buffer_capacity: 10_000,
+            max_send_attempts: 1,
+            initial_backoff: Duration::from_millis(100),
+            backoff_factor: 2.0,
+            broadcast_timeout: Duration::from_secs(1),
+            compression: CompressionAlgorithm::None,
+            max_in_flight: None,
+            idempotency_keys: false,
+            on_timeout: OnTimeout::Drop,
+            serialization: SerializationBackend::Json,
+        }
+    }
+}
+
+impl ZmqSenderConfig {
+    /// Set the delivery guarantee
+    pub fn with_send_semantics(mut self, semantics: SendSemantics) -> Self {
+        self.send_semantics = semantics;
+        self
+    }
+
+    /// Set how long to wait for an Ack before retransmitting
+    pub fn with_ack_timeout(mut self, timeout: Duration) -> Self {
+        self.ack_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of unacknowledged messages kept for retransmission
+    pub fn with_retry_buffer_capacity(mut self, capacity: usize) -> Self {
+        self.retry_buffer_capacity = capacity;
+        self
+    }
+
+    /// Set how long `ZmqSender::broadcast` waits for each endpoint
+    pub fn with_broadcast_timeout(mut self, timeout: Duration) -> Self {
+        self.broadcast_timeout = timeout;
+        self
+    }
+
+    /// Limit how many tracked messages may wait for their Ack at once.
+    ///
+    /// Once `n` are unacknowledged, `send_to` blocks until an Ack or Nack
+    /// arrives, so a slow receiver is not flooded. Only applies to
+    /// `AtLeastOnce` and `ExactlyOnce` sends; untracked messages are never
+    /// acknowledged. The limit is capped at `retry_buffer_capacity`, since
+    /// messages evicted from the retry buffer may never be acknowledged.
+    pub fn with_max_in_flight(mut self, n: usize) -> Self {
+        self.max_in_flight = Some(n);
+        self
+    }
+
+    /// Set what happens to a message `ZmqSender::send_timeout` could not send in time
+    pub fn with_on_timeout(mut self, on_timeout: OnTimeout) -> Self {
+        self.on_timeout = on_timeout;
+        self
+    }
+
+    /// Tag every message with a UUID v4 `idempotency_key`, so receivers
+    /// with deduplication (see `ZmqReceiverConfig::with_dedup`) drop copies
+    /// of it, e.g. when it was retried after a send that did reach them.
+    ///
+    /// `ExactlyOnce` sends always carry a key of their own.
+    pub fn with_idempotency_keys(mut self, enabled: bool) -> Self {
+        self.idempotency_keys = enabled;
+        self
+    }
+
+    /// Compress every message sent, e.g. for large payloads.
+    ///
+    /// Receivers decompress transparently. Peers in other languages do not
+    /// understand compressed messages, so only enable it between Rust
+    /// processes.
+    pub fn with_compression(mut self, algorithm: CompressionAlgorithm) -> Self {
+        self.compression = algorithm;
+        self
+    }
+
+    /// Serialize messages registered with `register_remote_message` with
+    /// `backend` instead of JSON.
+    ///
+    /// MessagePack and Bincode messages are smaller and faster to parse.
+    /// Receivers decode every backend, whatever their own configuration.
+    /// Peers in other languages only read JSON messages, and Bincode
+    /// cannot encode types using `#[serde(flatten)]` or untagged enums.
+    pub fn with_serialization(mut self, backend: SerializationBackend) -> Self {
+        self.serialization = backend;
+        self
+    }
+
+    /// Retry failed sends with exponential back-off.
+    ///
+    /// A message that cannot be transmitted (e.g. its endpoint cannot be
+    /// resolved, or the connection drops) is tried up to `max_attempts` times in all, waiting
+    /// `initial_backoff`, then `factor` times longer after each further
+    /// failure to the same endpoint. A successful send resets the endpoint's
+    /// delay. After the last attempt the message goes to the dead letter
+    /// actor (see `ZmqSender::set_dead_letter_actor`).
+    ///
+    /// Only applies to `AtMostOnce` sends; tracked sends are retransmitted
+    /// until acknowledged instead.
+    pub fn with_retry(mut self, max_attempts: u32, initial_backoff: Duration, factor: f64) -> Self {
+        self.max_send_attempts = max_attempts;
+        self.initial_backoff = initial_backoff;
+        self.backoff_factor = factor;
+        self
+    }
+
+    /// Delay before the next attempt after `failures` consecutive failed
+    /// sends to an endpoint, capped at `MAX_SEND_BACKOFF`.
+    fn retry_backoff(&self, failures: u32) -> Duration {
+        let exponent = failures.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let secs = self.initial_backoff.as_secs_f64() * self.backoff_factor.powi(exponent);
+        Duration::try_from_secs_f64(secs).map_or(MAX_SEND_BACKOFF, |delay| delay.min(MAX_SEND_BACKOFF))
+    }
+}
+
+/// Configuration for a ZmqReceiver (see `ZmqReceiver::with_config`).
+#[derive(Debug, Clone)]
+pub struct ZmqReceiverConfig {
+    /// Number of idempotency keys remembered to drop duplicates (default: 10000)
+    pub dedup_capacity: usize,
+    /// How long a key is remembered (default: None, until `dedup_capacity`
+    /// newer keys were seen)
+    pub dedup_window: Option<Duration>,
+    /// Backend tried first for a message with an unknown codec tag (default: Json)
+    pub serialization: SerializationBackend,
+}
+
+impl Default for ZmqReceiverConfig {
+    fn default() -> Self {
+        ZmqReceiverConfig {
+            dedup_capacity: DEDUP_CAPACITY,
+            dedup_window: None,
+            serialization: SerializationBackend::Json,
+        }
+    }
+}
+
+impl ZmqReceiverConfig {
+    /// Drop messages whose `idempotency_key` was seen within `window`.
+    ///
+    /// At most `capacity` keys are remembered; the oldest are forgotten
+    /// first. Senders add keys with `ZmqSenderConfig::with_idempotency_keys`
+    /// (or `SendSemantics::ExactlyOnce`). Dropped duplicates are counted in
+    /// `ZmqReceiver::duplicates_dropped`.
+    pub fn with_dedup(mut self, window: Duration, capacity: usize) -> Self {
+        self.dedup_window = Some(window);
+        self.dedup_capacity = capacity;
+        self
+    }
+
+    /// Decode messages whose codec tag is unknown, e.g. from a newer
+    /// sender, with `backend` first, then with each other backend in turn.
+    ///
+    /// Messages with a known tag are always decoded with the codec it names.
+    pub fn with_serialization(mut self, backend: SerializationBackend) -> Self {
+        self.serialization = backend;
+        self
+    }
+
+    fn dedup(&self) -> DedupWindow {
+        DedupWindow::new(self.dedup_capacity, self.dedup_window)
+    }
 }
 
 /// Reference to an actor in a remote process.
@@ -76,6 +489,9 @@ impl RemoteActorRef {
     }
 
     /// Send a message to this remote actor
+    ///
+    /// The sender's name and endpoint travel with the message, so the remote
+    /// actor can answer with `ctx.reply` (see `ZmqSender::send_to`).
     pub fn send(&self, msg: Box<dyn Message>, sender: Option<ActorRef>) {
         self.zmq_sender.send_to(&self.endpoint, &self.name, msg, sender);
     }
@@ -84,6 +500,11 @@ impl RemoteActorRef {
     pub fn into_actor_ref(self) -> ActorRef {
         ActorRef::Remote(self)
     }
+
+    /// Restrict this reference to messages of type `M`.
+    pub fn into_typed<M: Message>(self) -> TypedActorRef<M> {
+        self.into_actor_ref().into_typed()
+    }
 }
 
 /// Sends messages to remote processes via ZMQ PUSH sockets.
@@ -96,8 +517,25 @@ impl RemoteActorRef {
 ///   // Sends are now async - returns immediately
 ///   zmq_sender.send_to("tcp://localhost:5001", "pong", msg, sender);
 pub struct ZmqSender {
-    send_tx: Sender<SendRequest>,
+    send_tx: Sender<SenderCommand>,
     local_endpoint: String,
+    config: ZmqSenderConfig,
+    /// Source of delivery ids for acknowledged sends
+    next_delivery_id: AtomicU64,
+    /// Tracked sends awaiting their Ack
+    in_flight: InFlight,
+    /// Distinguishes this sender's idempotency keys from those of earlier runs
+    instance_id: String,
+    /// Set for test recorders: sends are stored here instead of transmitted
+    recorder: Option<Mutex<Vec<RecordedSend>>>,
+    /// Receiver actor name -> key its messages are signed with (see `sign_for`)
+    signing_keys: Mutex<HashMap<String, Vec<u8>>>,
+    /// Receives messages that could not be sent, shared with the sender thread
+    dead_letter_actor: Arc<Mutex<Option<ActorRef>>>,
+    /// Starts as `config.compression`; see `set_compression`
+    compression: Mutex<CompressionAlgorithm>,
+    /// Sends that missed their `send_timeout` deadline
+    send_timeouts: AtomicU64,
 }
 
 impl ZmqSender {
@@ -108,39 +546,139 @@ impl ZmqSender {
     /// # Arguments
     /// * `local_endpoint` - This process's endpoint for reply routing
     pub fn new(local_endpoint: &str) -> Self {
-        let (send_tx, send_rx) = channel::<SendRequest>();
+        Self::with_config(local_endpoint, ZmqSenderConfig::default())
+    }
+
+    /// Create a new ZmqSender with the given configuration.
+    ///
+    /// # Arguments
+    /// * `local_endpoint` - This process's endpoint for reply routing and Acks
+    /// * `config` - Delivery semantics and retry settings
+    pub fn with_config(local_endpoint: &str, config: ZmqSenderConfig) -> Self {
+        let (send_tx, send_rx) = channel::<SenderCommand>();
+        let dead_letter_actor = Arc::new(Mutex::new(None));
 
         // Spawn dedicated sender thread
+        let thread_config = config.clone();
+        let thread_dead_letter_actor = Arc::clone(&dead_letter_actor);
         thread::spawn(move || {
             let rt = Runtime::new().expect("Failed to create sender runtime");
-            let mut sockets: HashMap<String, PushSocket> = HashMap::new();
+            rt.block_on(run_sender_loop(send_rx, thread_config, thread_dead_letter_actor));
+        });
 
-            rt.block_on(async {
-                while let Ok(req) = send_rx.recv() {
-                    // Get or create socket for this endpoint
-                    let needs_connect = !sockets.contains_key(&req.endpoint);
-
-                    if needs_connect {
-                        let mut socket = PushSocket::new();
-                        let connect_endpoint = req.endpoint.replace("tcp://*:", "tcp://localhost:");
-                        if socket.connect(&connect_endpoint).await.is_ok() {
-                            // Small delay to let connection establish
-                            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
-                            sockets.insert(req.endpoint.clone(), socket);
-                        }
-                    }
+        let mut sender = Self::with_channel(local_endpoint, config, send_tx, None);
+        sender.dead_letter_actor = dead_letter_actor;
+        sender
+    }
 
-                    // Send the message
-                    if let Some(socket) = sockets.get_mut(&req.endpoint) {
-                        let _ = socket.send(req.data.into()).await;
-                    }
-                }
-            });
-        });
+    /// Create a ZmqSender for unit tests that records sends instead of transmitting them.
+    ///
+    /// No sender thread is started and no sockets are opened. Inspect what
+    /// would have been sent with `recorded_sends()`.
+    pub fn new_test_recorder(local_endpoint: &str) -> Self {
+        // Nothing reads the channel; commands other than sends are dropped
+        let (send_tx, _) = channel::<SenderCommand>();
+        Self::with_channel(
+            local_endpoint,
+            ZmqSenderConfig::default(),
+            send_tx,
+            Some(Mutex::new(Vec::new())),
+        )
+    }
+
+    fn with_channel(
+        local_endpoint: &str,
+        config: ZmqSenderConfig,
+        send_tx: Sender<SenderCommand>,
+        recorder: Option<Mutex<Vec<RecordedSend>>>,
+    ) -> Self {
+        let instance_id = format!(
+            "{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        );
 
         ZmqSender {
             send_tx,
             local_endpoint: local_endpoint.to_string(),
+            compression: Mutex::new(config.compression),
+            config,
+            next_delivery_id: AtomicU64::new(1),
+            in_flight: InFlight::default(),
+            instance_id,
+            recorder,
+            signing_keys: Mutex::new(HashMap::new()),
+            dead_letter_actor: Arc::new(Mutex::new(None)),
+            send_timeouts: AtomicU64::new(0),
+        }
+    }
+
+    /// Send messages that could not be transmitted to `actor_ref`, as
+    /// `DeadLetter`s naming the target actor.
+    ///
+    /// Pass a Manager's dead letter queue (`mgr.get_ref(DEAD_LETTERS_NAME)`)
+    /// to have them logged and forwarded to its watchers. Without a dead
+    /// letter actor such messages are dropped.
+    pub fn set_dead_letter_actor(&self, actor_ref: ActorRef) {
+        *self.dead_letter_actor.lock().unwrap() = Some(actor_ref);
+    }
+
+    /// Change how messages sent from now on are compressed.
+    pub fn set_compression(&self, algorithm: CompressionAlgorithm) {
+        *self.compression.lock().unwrap() = algorithm;
+    }
+
+    /// How messages are currently compressed
+    pub fn compression(&self) -> CompressionAlgorithm {
+        *self.compression.lock().unwrap()
+    }
+
+    /// Sign every message sent to actors named `actor_name` with `secret_key`,
+    /// for receivers that call `ZmqReceiver::require_auth_for`.
+    pub fn sign_for(&self, actor_name: &str, secret_key: &[u8]) {
+        self.signing_keys
+            .lock()
+            .unwrap()
+            .insert(actor_name.to_string(), secret_key.to_vec());
+    }
+
+    /// Get the `(endpoint, data)` pairs sent so far by a test recorder.
+    ///
+    /// Frames of a multipart message are recorded as one entry each.
+    /// Always empty for a sender created with `new` or `with_config`.
+    pub fn recorded_sends(&self) -> Vec<RecordedSend> {
+        match &self.recorder {
+            Some(recorder) => recorder.lock().unwrap().clone(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Hand a send to the sender thread, or record it in test mode.
+    fn queue(&self, request: SendRequest) {
+        match &self.recorder {
+            Some(recorder) => recorder.lock().unwrap().push((request.endpoint, request.data)),
+            None => {
+                let _ = self.send_tx.send(SenderCommand::Send(request));
+            }
+        }
+    }
+
+    /// Hand a multipart message to the sender thread, or record its frames in test mode.
+    fn queue_frames(&self, endpoint: &str, frames: Vec<Vec<u8>>) {
+        match &self.recorder {
+            Some(recorder) => {
+                let mut recorder = recorder.lock().unwrap();
+                recorder.extend(frames.into_iter().map(|frame| (endpoint.to_string(), frame)));
+            }
+            None => {
+                let _ = self.send_tx.send(SenderCommand::SendFrames {
+                    endpoint: endpoint.to_string(),
+                    frames,
+                });
+            }
         }
     }
 
@@ -149,6 +687,116 @@ impl ZmqSender {
         &self.local_endpoint
     }
 
+    /// Get the sender configuration.
+    pub fn config(&self) -> &ZmqSenderConfig {
+        &self.config
+    }
+
+    /// Number of tracked messages sent and not yet acknowledged.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.ids.lock().unwrap().len()
+    }
+
+    /// Called by the local ZmqReceiver when an Ack control frame arrives.
+    pub(crate) fn on_ack(&self, delivery_id: u64) {
+        self.in_flight.release(delivery_id);
+        let _ = self.send_tx.send(SenderCommand::Ack(delivery_id));
+    }
+
+    /// Called by the local ZmqReceiver when a Nack control frame arrives.
+    ///
+    /// The message can never be delivered, so it is removed from the retry buffer.
+    pub(crate) fn on_nack(&self, delivery_id: u64) {
+        self.in_flight.release(delivery_id);
+        let _ = self.send_tx.send(SenderCommand::Nack(delivery_id));
+    }
+
+    /// Queue pre-encoded bytes to an endpoint (used for control frames).
+    pub(crate) fn send_raw(&self, endpoint: &str, data: Vec<u8>) {
+        self.queue(SendRequest {
+            endpoint: endpoint.to_string(),
+            data,
+            delivery_id: None,
+        });
+    }
+
+    /// Send pre-encoded bytes to every endpoint in `endpoints` at once, and
+    /// wait for the outcome.
+    ///
+    /// Each endpoint is sent to concurrently, and is failed with
+    /// `SendError::Timeout` if it takes longer than the configured
+    /// `broadcast_timeout`, or `SendError::Unreachable` if it cannot be
+    /// connected or the send fails. Other sends through this sender go on
+    /// meanwhile. The broadcast is not all-or-nothing: the result
+    /// lists which endpoints got the message and which did not. A success
+    /// means the message was handed to ZMQ, not that an actor handled it.
+    /// Broadcasts are not retried.
+    ///
+    /// Blocks the calling thread, so do not call it from an async task.
+    ///
+    /// # Example
+    /// ```
+    /// # fn example(zmq_sender: &actors::ZmqSender, data: Vec<u8>) {
+    /// let result = zmq_sender.broadcast(&["tcp://host1:5001", "tcp://host2:5001"], data);
+    /// for (endpoint, error) in &result.failed {
+    ///     eprintln!("{} missed the update: {}", endpoint, error);
+    /// }
+    /// # }
+    /// ```
+    pub fn broadcast(&self, endpoints: &[&str], data: Vec<u8>) -> BroadcastResult {
+        let endpoints: Vec<String> = endpoints.iter().map(|e| e.to_string()).collect();
+        if let Some(recorder) = &self.recorder {
+            let mut recorder = recorder.lock().unwrap();
+            recorder.extend(endpoints.iter().map(|endpoint| (endpoint.clone(), data.clone())));
+            return BroadcastResult { succeeded: endpoints, failed: Vec::new() };
+        }
+
+        let (reply, result) = channel();
+        let done = self.send_tx.clone();
+        let command = SenderCommand::Broadcast { endpoints: endpoints.clone(), data, reply, done };
+        match self.send_tx.send(command).ok().and_then(|_| result.recv().ok()) {
+            Some(result) => result,
+            // The sender thread is gone
+            None => BroadcastResult {
+                succeeded: Vec::new(),
+                failed: endpoints.into_iter().map(|e| (e, SendError::Unreachable)).collect(),
+            },
+        }
+    }
+
+    /// Stream a large binary payload to an endpoint, one frame per write.
+    ///
+    /// Each non-empty `write()` is handed to the sender thread right away, so
+    /// only the chunk being written is held in memory. `flush()` (or dropping
+    /// the writer) ends the stream with an empty frame. The frames are raw
+    /// bytes, not an actor envelope: the peer must be a plain ZMQ PULL socket,
+    /// not a ZmqReceiver.
+    ///
+    /// The `zeromq` crate has no incremental `send_more`, so each frame
+    /// travels as its own message rather than as part of one multipart
+    /// message. They go out in order on the endpoint's socket; the peer reads
+    /// messages until the empty one.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::io::Write;
+    /// # fn example(zmq_sender: &actors::ZmqSender, frame: &[u8]) -> std::io::Result<()> {
+    /// let mut writer = zmq_sender.streaming_write("tcp://localhost:6000");
+    /// for chunk in frame.chunks(64 * 1024) {
+    ///     writer.write_all(chunk)?;
+    /// }
+    /// writer.flush()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn streaming_write(&self, endpoint: &str) -> ZmqStreamWriter<'_> {
+        ZmqStreamWriter {
+            sender: self,
+            endpoint: endpoint.to_string(),
+            open: false,
+        }
+    }
+
     /// Create a remote actor reference
     pub fn remote_ref(self: &Arc<Self>, name: &str, endpoint: &str) -> RemoteActorRef {
         RemoteActorRef::new(name, endpoint, Arc::clone(self))
@@ -159,11 +807,20 @@ impl ZmqSender {
     /// The message is serialized on the caller's thread, then queued
     /// to the dedicated sender thread for actual ZMQ transmission.
     ///
+    /// Replies are routed automatically: the envelope carries `sender_actor`
+    /// and `sender_endpoint` (the reply-to address, this sender's
+    /// `local_endpoint` for local senders). The receiving `ZmqReceiver` turns
+    /// them into a `RemoteActorRef`, which is what `ctx.reply` sends to.
+    /// C++ senders have no endpoint and cannot be replied to remotely.
+    ///
     /// # Arguments
     /// * `endpoint` - Remote process's ZMQ endpoint
     /// * `actor_name` - Name of the target actor
     /// * `msg` - Message to send
     /// * `sender` - Optional sender for reply routing
+    ///
+    /// Blocks while `ZmqSenderConfig::max_in_flight` tracked messages are
+    /// waiting for their Ack.
     pub fn send_to(
         &self,
         endpoint: &str,
@@ -171,39 +828,246 @@ impl ZmqSender {
         msg: Box<dyn Message>,
         sender: Option<ActorRef>,
     ) {
+        self.send_message(endpoint, actor_name, msg, sender, true);
+    }
+
+    /// Serialize and queue a message, waiting for room in flight if
+    /// `wait_in_flight` is set.
+    fn send_message(
+        &self,
+        endpoint: &str,
+        actor_name: &str,
+        msg: Box<dyn Message>,
+        sender: Option<ActorRef>,
+        wait_in_flight: bool,
+    ) {
+        let mut data = self.envelope(actor_name, msg.as_ref(), sender.as_ref());
+
+        // Tracked sends carry a delivery id so the receiver can Ack them
+        let delivery_id = match self.config.send_semantics {
+            SendSemantics::AtMostOnce => None,
+            SendSemantics::AtLeastOnce | SendSemantics::ExactlyOnce => {
+                let id = self.next_delivery_id.fetch_add(1, Ordering::SeqCst);
+                data["delivery_id"] = serde_json::json!(id);
+                data["ack_endpoint"] = serde_json::json!(self.local_endpoint);
+                if self.config.send_semantics == SendSemantics::ExactlyOnce {
+                    data["idempotency_key"] = serde_json::json!(format!("{}:{}", self.instance_id, id));
+                }
+                let limit = self.config.max_in_flight.map(|n| n.min(self.config.retry_buffer_capacity));
+                self.in_flight.acquire(id, limit.filter(|_| wait_in_flight));
+                Some(id)
+            }
+        };
+
+        let data_bytes = compress(self.compression(), codec::encode(&data, msg.as_ref(), self.config.serialization));
+
+        // Queue to sender thread (non-blocking!)
+        self.queue(SendRequest {
+            endpoint: endpoint.to_string(),
+            data: data_bytes,
+            delivery_id,
+        });
+    }
+
+    /// JSON envelope of a message to `actor_name`, signed if `sign_for` was
+    /// called for it, without delivery tracking. A message with a binary
+    /// codec of its own is left out unless signed (see `codec::encode`).
+    fn envelope(&self, actor_name: &str, msg: &dyn Message, sender: Option<&ActorRef>) -> serde_json::Value {
         // Get message type name (must be registered)
-        let msg_type = get_message_type_name(msg.as_ref());
-        let msg_json = serialize_message(msg.as_ref(), &msg_type);
+        let msg_type = get_message_type_name(msg);
+        let signing_key = self.signing_keys.lock().unwrap().get(actor_name).cloned();
+        // Encoded from `msg` when sent, so only the signature needs it serialized
+        let msg_json = if signing_key.is_none() && codec::has_binary_codec(&msg_type) {
+            serde_json::Value::Null
+        } else {
+            serialize_message(msg, &msg_type)
+        };
 
         // Determine sender info for reply routing
-        let (sender_actor, sender_endpoint) = match &sender {
+        let (sender_actor, sender_endpoint) = match sender {
             Some(ActorRef::Local(r)) => (Some(r.name().to_string()), Some(self.local_endpoint.clone())),
             Some(ActorRef::Remote(r)) => (Some(r.name().to_string()), Some(r.endpoint().to_string())),
             Some(ActorRef::Cpp(r)) => (Some(r.name().to_string()), None),  // Cpp actors don't have endpoints
             None => (None, None),
         };
 
-        let data = serde_json::json!({
+        let mut data = serde_json::json!({
             "sender_actor": sender_actor,
             "sender_endpoint": sender_endpoint,
             "receiver": actor_name,
             "message_type": msg_type,
             "message": msg_json
         });
+        if let Some(key) = signing_key {
+            let mac = hmac_sha256::HMAC::mac(signed_content(actor_name, &msg_type, &data["message"]), key);
+            data["signature"] = serde_json::json!(to_hex(&mac));
+        }
+        if self.config.idempotency_keys {
+            data["idempotency_key"] = serde_json::json!(Uuid::new_v4().to_string());
+        }
+        data
+    }
+
+    /// Send a message to a remote actor, waiting until it is transmitted or
+    /// `deadline` has passed.
+    ///
+    /// Unlike `send_to`, which queues the message and returns at once, this
+    /// reports whether the message was handed to ZMQ in time, e.g. when the
+    /// remote is slow or unreachable. `deadline` counts from this call,
+    /// including any wait behind other queued sends. On
+    /// `Err(SendError::Timeout)` the message is handled as
+    /// `ZmqSenderConfig::on_timeout` says and counted in `send_timeouts()`.
+    ///
+    /// The message is transmitted on its own task, so a slow endpoint does
+    /// not hold up other sends, and is not ordered with them. It is not
+    /// tracked, whatever the `SendSemantics`. Blocks the calling thread, so
+    /// do not call it from an async task.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::time::Duration;
+    /// # use actors::{Message, OnTimeout, ZmqSender, ZmqSenderConfig};
+    /// # fn example(msg: Box<dyn Message>) {
+    /// let config = ZmqSenderConfig::default().with_on_timeout(OnTimeout::DeadLetter);
+    /// let zmq_sender = ZmqSender::with_config("tcp://localhost:5002", config);
+    /// if let Err(e) = zmq_sender.send_timeout("tcp://host:5001", "pong", msg, None, Duration::from_millis(100)) {
+    ///     eprintln!("pong is slow: {}", e);
+    /// }
+    /// # }
+    /// ```
+    pub fn send_timeout(
+        &self,
+        endpoint: &str,
+        actor_name: &str,
+        msg: Box<dyn Message>,
+        sender: Option<ActorRef>,
+        deadline: Duration,
+    ) -> Result<(), SendError> {
+        let data = self.envelope(actor_name, msg.as_ref(), sender.as_ref());
+        let request = SendRequest {
+            endpoint: endpoint.to_string(),
+            data: compress(self.compression(), codec::encode(&data, msg.as_ref(), self.config.serialization)),
+            delivery_id: None,
+        };
+        if self.recorder.is_some() {
+            self.queue(request);
+            return Ok(());
+        }
 
-        let data_bytes = data.to_string().into_bytes();
+        // None if the deadline is too long to represent: wait forever
+        let expires_at = Instant::now().checked_add(deadline);
+        let (reply, result) = channel();
+        let done = self.send_tx.clone();
+        let command = SenderCommand::SendTimeout { request, deadline, expires_at, reply, done };
+        let result = match self.send_tx.send(command) {
+            Ok(()) => match expires_at {
+                Some(expires_at) => result.recv_timeout(expires_at.saturating_duration_since(Instant::now())),
+                None => result.recv().map_err(RecvTimeoutError::from),
+            },
+            Err(_) => Err(RecvTimeoutError::Disconnected),
+        };
+        let result = match result {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => Err(SendError::Timeout),
+            // The sender thread is gone
+            Err(RecvTimeoutError::Disconnected) => Err(SendError::Disconnected),
+        };
+        if result == Err(SendError::Timeout) {
+            self.send_timeouts.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
 
-        // Queue to sender thread (non-blocking!)
-        let _ = self.send_tx.send(SendRequest {
+    /// Send several messages to remote actors at `endpoint` in one ZMQ frame.
+    ///
+    /// For high-frequency senders, packing many small messages into one
+    /// frame saves a ZMQ send per message. Each message gets its own
+    /// envelope, as with `send_to`, and the receiving `ZmqReceiver` routes
+    /// each one to its actor in order. Batches are sent like `send_to` but
+    /// are not tracked, whatever the `SendSemantics`, and a batch that
+    /// cannot be sent is retried or dead-lettered as a whole.
+    ///
+    /// Returns the number of messages queued, or `SendError::Disconnected`
+    /// if the sender thread is gone.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{define_message, Message, SendError, ZmqSender};
+    /// # struct Tick { price: f64 }
+    /// # define_message!(Tick);
+    /// # impl From<&f64> for Tick { fn from(price: &f64) -> Self { Tick { price: *price } } }
+    /// # fn example(zmq_sender: &ZmqSender, prices: &[f64]) -> Result<(), SendError> {
+    /// let ticks = prices.iter().map(|p| ("pricer", Box::new(Tick::from(p)) as Box<dyn Message>)).collect();
+    /// zmq_sender.send_batch("tcp://host:5001", ticks, None)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn send_batch(
+        &self,
+        endpoint: &str,
+        messages: Vec<(&str, Box<dyn Message>)>,
+        sender: Option<ActorRef>,
+    ) -> Result<usize, SendError> {
+        if messages.is_empty() {
+            return Ok(0);
+        }
+        let envelopes: Vec<Vec<u8>> = messages
+            .iter()
+            .map(|(actor_name, msg)| {
+                let data = self.envelope(actor_name, msg.as_ref(), sender.as_ref());
+                codec::encode(&data, msg.as_ref(), self.config.serialization)
+            })
+            .collect();
+        let request = SendRequest {
             endpoint: endpoint.to_string(),
-            data: data_bytes,
-        });
+            data: compress(self.compression(), batch::pack(&envelopes)),
+            delivery_id: None,
+        };
+        if self.recorder.is_some() {
+            self.queue(request);
+        } else {
+            self.send_tx
+                .send(SenderCommand::Send(request))
+                .map_err(|_| SendError::Disconnected)?;
+        }
+        Ok(envelopes.len())
+    }
+
+    /// Send raw bytes to `actor_name` at `endpoint` on one of its channels.
+    ///
+    /// Channels are logical sub-channels of an actor over the sender's one
+    /// socket per endpoint, e.g. one per kind of data an actor streams to
+    /// another. The message is three frames: `channel_id` as a big-endian
+    /// u32, the actor name and `data`. The receiving `ZmqReceiver` delivers
+    /// a `ChannelData` to the actor registered for the channel with
+    /// `register_channel`, or else to the actor registered as `actor_name`.
+    /// `data` is not an envelope: it is not compressed, signed or tracked.
+    ///
+    /// # Example
+    /// ```
+    /// # const QUOTES_CHANNEL: u32 = 1;
+    /// # const TRADES_CHANNEL: u32 = 2;
+    /// # fn example(zmq_sender: &actors::ZmqSender, quote_bytes: Vec<u8>, trade_bytes: Vec<u8>) {
+    /// zmq_sender.multiplex("tcp://host:5001", "renderer", QUOTES_CHANNEL, quote_bytes);
+    /// zmq_sender.multiplex("tcp://host:5001", "renderer", TRADES_CHANNEL, trade_bytes);
+    /// # }
+    /// ```
+    pub fn multiplex(&self, endpoint: &str, actor_name: &str, channel_id: u32, data: Vec<u8>) {
+        let frames = vec![channel_id.to_be_bytes().to_vec(), actor_name.as_bytes().to_vec(), data];
+        self.queue_frames(endpoint, frames);
+    }
+
+    /// Number of `send_timeout` calls whose message was not sent in time.
+    pub fn send_timeouts(&self) -> u64 {
+        self.send_timeouts.load(Ordering::Relaxed)
     }
 
     /// Send a message to a remote actor (async version for use within tokio runtime).
     ///
     /// This is used internally by ZmqReceiver when it needs to send Reject messages
-    /// back to the sender. Now just queues to the sender thread like send_to().
+    /// back to the sender. Now just queues to the sender thread like send_to(),
+    /// but never waits for `max_in_flight`: the receiver must keep handling
+    /// the Acks that make room.
     pub async fn send_to_async(
         &self,
         endpoint: &str,
@@ -211,244 +1075,2943 @@ impl ZmqSender {
         msg: Box<dyn Message>,
         sender: Option<ActorRef>,
     ) {
-        // Just delegate to send_to - it's already non-blocking
-        self.send_to(endpoint, actor_name, msg, sender);
+        self.send_message(endpoint, actor_name, msg, sender, false);
     }
 }
 
-/// Get the message type name for serialization.
-/// This requires the message to be registered with register_remote_message.
-fn get_message_type_name(msg: &dyn Message) -> String {
-    get_type_name(msg)
-        .unwrap_or_else(|| {
-            panic!("Message type not registered for remote serialization. Call register_remote_message::<YourType>(\"YourType\") first.")
-        })
+/// `std::io::Write` adapter returned by `ZmqSender::streaming_write`.
+///
+/// Each non-empty write is sent as one frame; `flush` ends the stream.
+pub struct ZmqStreamWriter<'a> {
+    sender: &'a ZmqSender,
+    endpoint: String,
+    /// Frames were sent since the stream was last ended
+    open: bool,
 }
 
-/// Registry of local actors for the ZmqReceiver.
-/// Thread-safe container for looking up ActorRefs by name.
-#[derive(Clone)]
-pub struct ActorRegistry {
-    actors: Arc<Mutex<HashMap<String, ActorRef>>>,
-}
+impl io::Write for ZmqStreamWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !buf.is_empty() {
+            self.sender.queue_frames(&self.endpoint, vec![buf.to_vec()]);
+            self.open = true;
+        }
+        Ok(buf.len())
+    }
 
-impl ActorRegistry {
-    /// Create a new empty registry.
-    pub fn new() -> Self {
-        ActorRegistry {
-            actors: Arc::new(Mutex::new(HashMap::new())),
+    fn flush(&mut self) -> io::Result<()> {
+        if self.open {
+            self.sender.queue_frames(&self.endpoint, vec![Vec::new()]);
+            self.open = false;
         }
+        Ok(())
     }
+}
 
-    /// Register a local actor.
-    pub fn register(&self, name: &str, actor_ref: ActorRef) {
-        self.actors.lock().unwrap().insert(name.to_string(), actor_ref);
+impl Drop for ZmqStreamWriter<'_> {
+    fn drop(&mut self) {
+        let _ = io::Write::flush(self);
     }
+}
 
-    /// Look up an actor by name.
-    pub fn get(&self, name: &str) -> Option<ActorRef> {
-        self.actors.lock().unwrap().get(name).cloned()
+/// Several ZmqSenders used in turn, for high-frequency remote sends.
+///
+/// Each sender has its own thread and its own socket per remote endpoint,
+/// so every endpoint gets `pool_size` sockets, connected on first use and
+/// reconnected after a failed send. Sends are fire-and-forget
+/// (`SendSemantics::AtMostOnce`): Acks go to a ZmqReceiver's own ZmqSender,
+/// not to the pool. Messages sent through different sockets may arrive out
+/// of order.
+///
+/// # Example
+/// ```
+/// # use std::sync::Arc;
+/// # use actors::{define_message, ZmqSenderPool};
+/// # struct Ping { count: i32 }
+/// # define_message!(Ping);
+/// # fn example() {
+/// let pool = Arc::new(ZmqSenderPool::new("tcp://localhost:5002", 4));
+/// pool.send("tcp://localhost:5001", "pong", Box::new(Ping { count: 1 }));
+/// # }
+/// ```
+pub struct ZmqSenderPool {
+    senders: Vec<ZmqSender>,
+    /// Index of the sender for the next message
+    next: AtomicUsize,
+}
+
+impl ZmqSenderPool {
+    /// Create a pool of `pool_size` senders (at least one).
+    ///
+    /// # Arguments
+    /// * `local_endpoint` - This process's endpoint for reply routing
+    /// * `pool_size` - Sockets per remote endpoint
+    pub fn new(local_endpoint: &str, pool_size: usize) -> Self {
+        ZmqSenderPool {
+            senders: (0..pool_size.max(1)).map(|_| ZmqSender::new(local_endpoint)).collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Number of senders, i.e. sockets per remote endpoint
+    pub fn pool_size(&self) -> usize {
+        self.senders.len()
+    }
+
+    /// Send a message to `actor_name` at `remote_endpoint` through the next
+    /// sender in turn (see `ZmqSender::send_to`).
+    pub fn send(&self, remote_endpoint: &str, actor_name: &str, msg: Box<dyn Message>) {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.senders.len();
+        self.senders[index].send_to(remote_endpoint, actor_name, msg, None);
     }
 }
 
-impl Default for ActorRegistry {
-    fn default() -> Self {
-        Self::new()
+/// Fire-and-forget sender for pipeline stages: spreads messages over the
+/// `ZmqPullReceiver`s of the next stage, in turn.
+///
+/// Messages carry no reply address, so the receiving actors cannot reply
+/// and rejections are not reported back. Each endpoint gets its own PUSH
+/// socket on a dedicated thread, connected on first use and reconnected
+/// after a failed send (see `ZmqSender`).
+///
+/// # Example
+/// ```
+/// # use actors::{define_message, ZmqPushSender};
+/// # struct Number { value: i32 }
+/// # define_message!(Number);
+/// # fn example() {
+/// // Two workers in the next stage
+/// let push = ZmqPushSender::new(&["tcp://worker1:5101", "tcp://worker2:5101"]);
+/// for n in 0..10 {
+///     push.send("square", Box::new(Number { value: n }));
+/// }
+/// # }
+/// ```
+pub struct ZmqPushSender {
+    sender: ZmqSender,
+    endpoints: Vec<String>,
+    /// Index of the endpoint for the next message
+    next: AtomicUsize,
+}
+
+impl ZmqPushSender {
+    /// Create a sender pushing to `endpoints` in turn.
+    ///
+    /// # Panics
+    /// If `endpoints` is empty.
+    pub fn new(endpoints: &[&str]) -> Self {
+        // No replies or Acks come back, so there is no local endpoint
+        Self::with_sender(ZmqSender::new(""), endpoints)
+    }
+
+    fn with_sender(sender: ZmqSender, endpoints: &[&str]) -> Self {
+        assert!(!endpoints.is_empty(), "ZmqPushSender needs at least one endpoint");
+        ZmqPushSender {
+            sender,
+            endpoints: endpoints.iter().map(|e| e.to_string()).collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Endpoints messages are pushed to
+    pub fn endpoints(&self) -> &[String] {
+        &self.endpoints
+    }
+
+    /// Send a message to `actor_name` at the next endpoint in turn.
+    pub fn send(&self, actor_name: &str, msg: Box<dyn Message>) {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.endpoints.len();
+        self.sender.send_to(&self.endpoints[index], actor_name, msg, None);
     }
 }
 
-/// Receives messages from remote processes and routes to local actors.
+/// Topic of the messages for `actor_name` on a `ZmqPublisher`.
 ///
-/// Binds to a ZMQ PULL socket and forwards incoming messages to local actors.
-pub struct ZmqReceiver {
+/// The trailing NUL keeps ZMQ's prefix matching from delivering messages
+/// for "pong2" to a subscriber of "pong".
+fn actor_topic(actor_name: &str) -> String {
+    format!("{}\0", actor_name)
+}
+
+/// Publishes messages on a ZMQ PUB socket, each under the topic of its
+/// target actor.
+///
+/// Receivers created with `ZmqReceiver::new_sub` subscribe to the actors
+/// registered with them, so the publisher only sends them messages they
+/// can deliver. Each message is two frames, `[topic, envelope]`. Like any
+/// PUB socket, nothing is acknowledged, and messages published before a
+/// receiver has connected and subscribed are lost.
+///
+/// # Example
+/// ```
+/// # use std::sync::Arc;
+/// # use actors::{define_message, ActorRef, ZmqPublisher, ZmqReceiver, ZmqSender};
+/// # struct Ping { count: i32 }
+/// # define_message!(Ping);
+/// # fn example(zmq_sender: Arc<ZmqSender>, pong_ref: ActorRef) {
+/// let publisher = ZmqPublisher::new("tcp://0.0.0.0:5600", Arc::clone(&zmq_sender));
+///
+/// // In another process
+/// let receiver = ZmqReceiver::new_sub("tcp://server:5600", zmq_sender);
+/// receiver.register("pong", pong_ref);
+///
+/// publisher.publish("pong", Box::new(Ping { count: 1 }), None);
+/// # }
+/// ```
+pub struct ZmqPublisher {
     bind_endpoint: String,
     zmq_sender: Arc<ZmqSender>,
-    registry: ActorRegistry,
-    running: Arc<Mutex<bool>>,
+    /// `(topic, envelope)` pairs for the publisher thread
+    publish_tx: Sender<(String, Vec<u8>)>,
 }
 
-impl ZmqReceiver {
-    /// Create a new ZmqReceiver.
+impl ZmqPublisher {
+    /// Bind a PUB socket to `bind_endpoint`, served by its own thread.
     ///
-    /// # Arguments
-    /// * `bind_endpoint` - Endpoint to bind to (e.g., "tcp://0.0.0.0:5001")
-    /// * `zmq_sender` - ZmqSender for creating reply ActorRefs
+    /// Messages are encoded by `zmq_sender`, so they carry its reply
+    /// address, signatures and compression.
     pub fn new(bind_endpoint: &str, zmq_sender: Arc<ZmqSender>) -> Self {
-        ZmqReceiver {
+        let (publish_tx, publish_rx) = channel::<(String, Vec<u8>)>();
+        // Convert tcp://*: to tcp://0.0.0.0: for pure-Rust zeromq
+        let normalized_endpoint = bind_endpoint.replace("tcp://*:", "tcp://0.0.0.0:");
+
+        thread::spawn(move || {
+            let rt = Runtime::new().expect("Failed to create publisher runtime");
+
+            rt.block_on(async {
+                let mut socket = PubSocket::new();
+                socket.bind(&normalized_endpoint).await.expect("Failed to bind socket");
+
+                // Runs until the ZmqPublisher is dropped
+                while let Ok((topic, data)) = publish_rx.recv() {
+                    let mut msg = ZmqMessage::from(topic.into_bytes());
+                    msg.push_back(data.into());
+                    let _ = socket.send(msg).await;
+                }
+            });
+        });
+
+        ZmqPublisher {
             bind_endpoint: bind_endpoint.to_string(),
             zmq_sender,
-            registry: ActorRegistry::new(),
-            running: Arc::new(Mutex::new(true)),
+            publish_tx,
         }
     }
 
-    /// Register a local actor to receive messages.
-    pub fn register(&self, name: &str, actor_ref: ActorRef) {
-        self.registry.register(name, actor_ref);
+    /// Endpoint the publisher binds to
+    pub fn bind_endpoint(&self) -> &str {
+        &self.bind_endpoint
     }
 
-    /// Get the registry (for sharing with other components).
-    pub fn registry(&self) -> &ActorRegistry {
-        &self.registry
+    /// Publish a message to every subscribed receiver that has `actor_name`
+    /// registered.
+    pub fn publish(&self, actor_name: &str, msg: Box<dyn Message>, sender: Option<ActorRef>) {
+        let data = self.zmq_sender.envelope(actor_name, msg.as_ref(), sender.as_ref());
+        let data = codec::encode(&data, msg.as_ref(), self.zmq_sender.config.serialization);
+        let data = compress(self.zmq_sender.compression(), data);
+        let _ = self.publish_tx.send((actor_topic(actor_name), data));
     }
+}
 
-    /// Start the receiver in a new thread.
-    ///
-    /// Returns a handle that can be used to stop the receiver.
-    pub fn start(&self) -> ZmqReceiverHandle {
-        let bind_endpoint = self.bind_endpoint.clone();
-        let zmq_sender = Arc::clone(&self.zmq_sender);
-        let registry = self.registry.clone();
-        let running = Arc::clone(&self.running);
+/// Body of the dedicated sender thread.
+///
+/// Transmits queued messages and, for tracked sends, keeps them in a retry
+/// buffer until an Ack or Nack arrives. Unacknowledged messages are
+/// retransmitted every `ack_timeout`. Untracked messages that fail to
+/// transmit are retried with back-off if `max_send_attempts` allows.
+async fn run_sender_loop(
+    send_rx: Receiver<SenderCommand>,
+    config: ZmqSenderConfig,
+    dead_letter_actor: Arc<Mutex<Option<ActorRef>>>,
+) {
+    let mut sockets: HashMap<String, PushSocket> = HashMap::new();
+    // Delivery ids are monotonic, so the first entry is always the oldest
+    let mut pending: BTreeMap<u64, PendingSend> = BTreeMap::new();
+    let mut retries = SendRetries::default();
 
-        let handle = thread::spawn(move || {
-            // Create a new tokio runtime for this thread
-            let rt = Runtime::new().expect("Failed to create receiver runtime");
+    loop {
+        let mut wake_at = retries.next_due();
+        // An ack_timeout too large for an Instant never retransmits
+        if let Some(ack_due) = Instant::now().checked_add(config.ack_timeout).filter(|_| !pending.is_empty()) {
+            wake_at = Some(wake_at.map_or(ack_due, |due| due.min(ack_due)));
+        }
+        let command = match wake_at {
+            None => match send_rx.recv() {
+                Ok(command) => Some(command),
+                Err(_) => break,
+            },
+            Some(wake_at) => match send_rx.recv_timeout(wake_at.saturating_duration_since(Instant::now())) {
+                Ok(command) => Some(command),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            },
+        };
 
-            rt.block_on(async {
-                let mut socket = PullSocket::new();
+        match command {
+            Some(SenderCommand::Send(req)) => {
+                let sent = transmit(&mut sockets, &req.endpoint, req.data.clone().into()).await;
+                match req.delivery_id {
+                    Some(id) => {
+                        pending.insert(id, PendingSend {
+                            endpoint: req.endpoint,
+                            data: req.data,
+                            sent_at: Instant::now(),
+                        });
+                        while pending.len() > config.retry_buffer_capacity {
+                            let Some((_, evicted)) = pending.pop_first() else { break };
+                            let reason = format!("Retry buffer full, no Ack from {}", evicted.endpoint);
+                            send_dead_letter(&dead_letter_actor, &evicted.data, &reason);
+                        }
+                    }
+                    None => {
+                        let send = RetrySend {
+                            endpoint: req.endpoint,
+                            data: req.data,
+                            attempts: 1,
+                            due: Instant::now(),
+                            policy: None,
+                        };
+                        on_transmitted(&mut retries, send, sent, &config, &dead_letter_actor);
+                    }
+                }
+            }
+            Some(SenderCommand::SendFrames { endpoint, frames }) => {
+                let mut frames = frames.into_iter().map(Into::into);
+                if let Some(first) = frames.next() {
+                    let mut message = ZmqMessage::from(first);
+                    for frame in frames {
+                        message.push_back(frame);
+                    }
+                    transmit(&mut sockets, &endpoint, message).await;
+                }
+            }
+            Some(SenderCommand::Ack(id)) | Some(SenderCommand::Nack(id)) => {
+                pending.remove(&id);
+            }
+            Some(SenderCommand::SendTimeout { request, deadline, expires_at, reply, done }) => {
+                if expires_at.is_some_and(|expires_at| Instant::now() >= expires_at) {
+                    // Waited too long behind other commands
+                    on_send_timeout(&mut retries, request, deadline, &config, &dead_letter_actor);
+                    let _ = reply.send(Err(SendError::Timeout));
+                } else {
+                    let socket = sockets.remove_entry(&request.endpoint).into_iter().collect();
+                    tokio::spawn(send_within(request, deadline, expires_at, socket, reply, done));
+                }
+            }
+            Some(SenderCommand::SendTimedOut { request, deadline, reply }) => {
+                on_send_timeout(&mut retries, request, deadline, &config, &dead_letter_actor);
+                let _ = reply.send(Err(SendError::Timeout));
+            }
+            Some(SenderCommand::ReturnSocket { endpoint, socket }) => {
+                // Unless a send meanwhile connected a new one
+                sockets.entry(endpoint).or_insert(socket);
+            }
+            Some(SenderCommand::Broadcast { endpoints, data, reply, done }) => {
+                // On its own task, so other sends go on meanwhile
+                let taken = endpoints.iter().filter_map(|endpoint| sockets.remove_entry(endpoint)).collect();
+                tokio::spawn(broadcast(taken, endpoints, data, config.broadcast_timeout, reply, done));
+            }
+            None => {}
+        }
 
-                // Convert tcp://*: to tcp://0.0.0.0: for pure-Rust zeromq
-                let normalized_endpoint = bind_endpoint.replace("tcp://*:", "tcp://0.0.0.0:");
-                socket.bind(&normalized_endpoint).await.expect("Failed to bind socket");
+        // Retransmit anything that has waited too long for its Ack
+        for entry in pending.values_mut() {
+            if entry.sent_at.elapsed() >= config.ack_timeout {
+                transmit(&mut sockets, &entry.endpoint, entry.data.clone().into()).await;
+                entry.sent_at = Instant::now();
+            }
+        }
 
-                loop {
-                    // Check running flag
-                    if !*running.lock().unwrap() {
-                        break;
-                    }
+        for mut send in retries.take_due(Instant::now()) {
+            let sent = transmit(&mut sockets, &send.endpoint, send.data.clone().into()).await;
+            send.attempts += 1;
+            on_transmitted(&mut retries, send, sent, &config, &dead_letter_actor);
+        }
+    }
+}
 
-                    // Use tokio timeout to periodically check running flag
-                    let recv_result = tokio::time::timeout(
-                        tokio::time::Duration::from_millis(100),
-                        socket.recv()
-                    ).await;
+/// Record the outcome of transmitting an untracked send, scheduling a retry
+/// or routing it to the dead letter actor if it failed.
+fn on_transmitted(
+    retries: &mut SendRetries,
+    send: RetrySend,
+    sent: bool,
+    config: &ZmqSenderConfig,
+    dead_letter_actor: &Mutex<Option<ActorRef>>,
+) {
+    if sent {
+        retries.on_success(&send.endpoint);
+        return;
+    }
+    let Some(send) = retries.on_failure(send, config) else {
+        return;
+    };
+    let reason = format!("Send to {} failed after {} attempts", send.endpoint, send.attempts);
+    send_dead_letter(dead_letter_actor, &send.data, &reason);
+}
 
-                    match recv_result {
-                        Ok(Ok(msg)) => {
-                            let data = msg.get(0).map(|b| b.as_ref()).unwrap_or(&[]);
-                            if let Ok(json_str) = String::from_utf8(data.to_vec()) {
-                                if let Ok(envelope) = serde_json::from_str::<serde_json::Value>(&json_str) {
-                                    Self::handle_remote_message_async(&envelope, &zmq_sender, &registry).await;
-                                }
-                            }
-                        }
-                        Ok(Err(_)) => {
-                            // Socket error, exit
-                            break;
-                        }
-                        Err(_) => {
-                            // Timeout, continue to check running flag
-                            continue;
-                        }
-                    }
-                }
-            });
-        });
+/// Transmit a `send_timeout` message with the socket of its endpoint, if
+/// connected, and report back to the sender loop through `done`.
+async fn send_within(
+    request: SendRequest,
+    deadline: Duration,
+    expires_at: Option<Instant>,
+    mut socket: HashMap<String, PushSocket>,
+    reply: Sender<Result<(), SendError>>,
+    done: Sender<SenderCommand>,
+) {
+    let message = request.data.clone().into();
+    let sending = transmit(&mut socket, &request.endpoint, message);
+    let sent = match expires_at {
+        Some(expires_at) => tokio::time::timeout_at(expires_at.into(), sending).await,
+        None => Ok(sending.await),
+    };
+    match sent {
+        Ok(sent) => {
+            let _ = reply.send(if sent { Ok(()) } else { Err(SendError::Disconnected) });
+            if let Some((endpoint, socket)) = socket.into_iter().next() {
+                let _ = done.send(SenderCommand::ReturnSocket { endpoint, socket });
+            }
+        }
+        // A socket that timed out mid-send is dropped and reconnected next time
+        Err(_) => {
+            let _ = done.send(SenderCommand::SendTimedOut { request, deadline, reply });
+        }
+    }
+}
 
-        ZmqReceiverHandle {
-            running: Arc::clone(&self.running),
-            thread: Some(handle),
+/// Handle a `send_timeout` message that missed its deadline as
+/// `config.on_timeout` says.
+fn on_send_timeout(
+    retries: &mut SendRetries,
+    request: SendRequest,
+    deadline: Duration,
+    config: &ZmqSenderConfig,
+    dead_letter_actor: &Mutex<Option<ActorRef>>,
+) {
+    match &config.on_timeout {
+        OnTimeout::Drop => {}
+        OnTimeout::Retry(policy) => {
+            let send = RetrySend {
+                endpoint: request.endpoint,
+                data: request.data,
+                attempts: 1,
+                due: Instant::now(),
+                policy: Some(policy.clone()),
+            };
+            on_transmitted(retries, send, false, config, dead_letter_actor);
+        }
+        OnTimeout::DeadLetter => {
+            let reason = format!("Send to {} timed out after {:?}", request.endpoint, deadline);
+            send_dead_letter(dead_letter_actor, &request.data, &reason);
         }
     }
+}
 
-    async fn handle_remote_message_async(
-        data: &serde_json::Value,
-        zmq_sender: &Arc<ZmqSender>,
-        registry: &ActorRegistry,
-    ) {
-        let receiver_name = data["receiver"].as_str().unwrap_or("");
-        let msg_type = data["message_type"].as_str().unwrap_or("");
-        let msg_data = &data["message"];
+/// Send an unsent envelope, or each envelope of an unsent batch, to the dead
+/// letter actor, if one is set.
+fn send_dead_letter(dead_letter_actor: &Mutex<Option<ActorRef>>, data: &[u8], reason: &str) {
+    if let Some(actor) = dead_letter_actor.lock().unwrap().as_ref() {
+        // Frames of this sender only carry codec tags it knows
+        let mut envelopes: Vec<_> = decode_envelopes(data, SerializationBackend::Json)
+            .into_iter()
+            .map(|(envelope, _)| envelope)
+            .collect();
+        if envelopes.is_empty() {
+            envelopes.push(serde_json::Value::Null);
+        }
+        for envelope in envelopes {
+            let letter = DeadLetter::new(
+                envelope["receiver"].as_str().unwrap_or(""),
+                envelope["message_type"].as_str().unwrap_or(""),
+                reason,
+            );
+            actor.send(Box::new(letter), None);
+        }
+    }
+}
 
-        // Get sender info for replies (needed for both success and reject)
-        let sender_actor = data["sender_actor"].as_str();
-        let sender_endpoint = data["sender_endpoint"].as_str();
+/// Send `data` to each endpoint on its own task, giving each `timeout`, then
+/// report the outcome to `reply` and give the sockets back to the sender loop.
+async fn broadcast(
+    mut sockets: HashMap<String, PushSocket>,
+    endpoints: Vec<String>,
+    data: Vec<u8>,
+    timeout: Duration,
+    reply: Sender<BroadcastResult>,
+    done: Sender<SenderCommand>,
+) {
+    let tasks: Vec<_> = endpoints
+        .into_iter()
+        .map(|endpoint| {
+            // Each task owns its endpoint's socket while it sends
+            let mut socket: HashMap<String, PushSocket> = sockets.remove_entry(&endpoint).into_iter().collect();
+            let message: ZmqMessage = data.clone().into();
+            let task_endpoint = endpoint.clone();
+            let task = tokio::spawn(async move {
+                let sent = tokio::time::timeout(timeout, transmit(&mut socket, &task_endpoint, message)).await;
+                (socket, sent)
+            });
+            (endpoint, task)
+        })
+        .collect();
 
-        // Look up local actor
-        let local_ref = match registry.get(receiver_name) {
-            Some(r) => r,
-            None => {
-                // Actor not found - send reject back to sender
-                if let (Some(actor), Some(endpoint)) = (sender_actor, sender_endpoint) {
-                    let reject = Reject::new(
-                        msg_type,
-                        &format!("Actor '{}' not found", receiver_name),
-                        receiver_name,
-                    );
-                    zmq_sender.send_to_async(endpoint, actor, Box::new(reject), None).await;
+    let mut result = BroadcastResult::default();
+    for (endpoint, task) in tasks {
+        match task.await {
+            Ok((socket, Ok(true))) => {
+                for (endpoint, socket) in socket {
+                    let _ = done.send(SenderCommand::ReturnSocket { endpoint, socket });
                 }
-                return;
+                result.succeeded.push(endpoint);
             }
-        };
+            Ok((_, Ok(false))) => result.failed.push((endpoint, SendError::Unreachable)),
+            // A socket that timed out mid-send is dropped and reconnected next time
+            Ok((_, Err(_))) => result.failed.push((endpoint, SendError::Timeout)),
+            Err(_) => result.failed.push((endpoint, SendError::Unreachable)),
+        }
+    }
+    let _ = reply.send(result);
+}
 
-        // Try to deserialize the message
-        match try_deserialize_message(msg_type, msg_data.clone()) {
-            Ok(msg) => {
-                // Success - create sender ref and deliver to local actor
-                let sender_ref = if let (Some(actor), Some(endpoint)) = (sender_actor, sender_endpoint) {
-                    Some(ActorRef::Remote(RemoteActorRef::new(
-                        actor,
-                        endpoint,
-                        Arc::clone(zmq_sender),
-                    )))
-                } else {
-                    None
-                };
-                local_ref.send(msg, sender_ref);
-            }
-            Err(reason) => {
-                // Deserialization failed - send reject back to sender
-                if let (Some(actor), Some(endpoint)) = (sender_actor, sender_endpoint) {
-                    let reject = Reject::new(msg_type, &reason, receiver_name);
-                    zmq_sender.send_to_async(endpoint, actor, Box::new(reject), None).await;
+/// Send a message to an endpoint, connecting a PUSH socket on first use.
+///
+/// Returns false if the endpoint could not be connected or the send failed.
+async fn transmit(sockets: &mut HashMap<String, PushSocket>, endpoint: &str, message: ZmqMessage) -> bool {
+    // Get or create socket for this endpoint
+    if !sockets.contains_key(endpoint) {
+        let mut socket = PushSocket::new();
+        let connect_endpoint = endpoint.replace("tcp://*:", "tcp://localhost:");
+        if socket.connect(&connect_endpoint).await.is_ok() {
+            // Small delay to let connection establish
+            tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            sockets.insert(endpoint.to_string(), socket);
+        }
+    }
+
+    // Send the message
+    let Some(socket) = sockets.get_mut(endpoint) else {
+        return false;
+    };
+    if socket.send(message).await.is_err() {
+        // Reconnect on the next send
+        sockets.remove(endpoint);
+        return false;
+    }
+    true
+}
+
+/// What an envelope's HMAC-SHA256 `signature` covers: receiver, message type
+/// and message, one per line.
+///
+/// The message is signed as compact JSON with sorted keys, so senders in
+/// other languages can produce the same signature.
+fn signed_content(receiver: &str, msg_type: &str, message: &serde_json::Value) -> String {
+    format!("{}\n{}\n{}", receiver, msg_type, message)
+}
+
+fn from_hex(hex: &str) -> Option<[u8; 32]> {
+    codec::from_hex(hex)?.try_into().ok()
+}
+
+/// Get the message type name for serialization.
+/// This requires the message to be registered with register_remote_message.
+fn get_message_type_name(msg: &dyn Message) -> String {
+    get_type_name(msg)
+        .unwrap_or_else(|| {
+            panic!("Message type not registered for remote serialization. Call register_remote_message::<YourType>(\"YourType\") first.")
+        })
+}
+
+/// Bounded set of recently seen idempotency keys.
+///
+/// Used by the ZmqReceiver to drop duplicate deliveries. The oldest key is
+/// forgotten once `capacity` keys are held, or once it is older than
+/// `window` if one is set.
+struct DedupWindow {
+    seen: HashSet<String>,
+    /// Keys in the order they were first seen, with when
+    order: VecDeque<(String, Instant)>,
+    capacity: usize,
+    window: Option<Duration>,
+}
+
+impl DedupWindow {
+    fn new(capacity: usize, window: Option<Duration>) -> Self {
+        DedupWindow {
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+            capacity,
+            window,
+        }
+    }
+
+    /// Check whether a key has been seen before (within the window).
+    fn contains(&mut self, key: &str) -> bool {
+        self.expire(Instant::now());
+        self.seen.contains(key)
+    }
+
+    /// Remember a key, evicting the oldest one if the window is full.
+    fn insert(&mut self, key: &str) {
+        let now = Instant::now();
+        self.expire(now);
+        if self.seen.insert(key.to_string()) {
+            self.order.push_back((key.to_string(), now));
+            while self.order.len() > self.capacity {
+                if let Some((old, _)) = self.order.pop_front() {
+                    self.seen.remove(&old);
                 }
             }
         }
     }
+
+    /// Forget the keys seen longer than `window` ago.
+    fn expire(&mut self, now: Instant) {
+        let Some(window) = self.window else {
+            return;
+        };
+        while let Some((key, seen_at)) = self.order.front() {
+            if now.saturating_duration_since(*seen_at) < window {
+                break;
+            }
+            self.seen.remove(key);
+            self.order.pop_front();
+        }
+    }
 }
 
-/// Handle for controlling a running ZmqReceiver.
-pub struct ZmqReceiverHandle {
-    running: Arc<Mutex<bool>>,
-    thread: Option<thread::JoinHandle<()>>,
+/// Why a ZmqReceiver did not deliver a `RemoteDeadLetter`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadLetterReason {
+    /// No actor of that name is registered
+    UnknownActor,
+    /// The sender exceeded the actor's rate limit and burst size
+    /// (see `ZmqReceiver::set_per_actor_rate_limit`)
+    RateLimited,
+    /// The actor requires signed messages and this one was not signed
+    /// with its key (see `ZmqReceiver::require_auth_for`)
+    Unauthenticated,
+    /// The actor's mailbox was full
+    MailboxFull,
+    /// The actor has stopped
+    ActorStopped,
 }
 
-impl ZmqReceiverHandle {
-    /// Stop the receiver.
-    pub fn stop(&mut self) {
-        *self.running.lock().unwrap() = false;
-        if let Some(handle) = self.thread.take() {
-            let _ = handle.join();
+impl From<SendError> for DeadLetterReason {
+    fn from(err: SendError) -> Self {
+        match err {
+            SendError::Full => DeadLetterReason::MailboxFull,
+            _ => DeadLetterReason::ActorStopped,
         }
     }
 }
 
-impl Drop for ZmqReceiverHandle {
-    fn drop(&mut self) {
-        self.stop();
-    }
+/// A remote message the ZmqReceiver did not deliver, e.g. one addressed to
+/// an actor it does not know or over the actor's rate limit.
+///
+/// Sent to the actor given to `ZmqReceiver::set_dead_letter_actor`.
+#[derive(Debug, Clone)]
+pub struct RemoteDeadLetter {
+    /// The `receiver` named in the envelope
+    pub original_target: String,
+    /// The whole envelope, as JSON (for `ChannelData`, the data)
+    pub payload: Vec<u8>,
+    /// The sender's `sender_endpoint`, if it gave one
+    pub source_endpoint: Option<String>,
+    /// Why it was not delivered
+    pub reason: DeadLetterReason,
 }
+crate::define_message!(RemoteDeadLetter);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Bytes sent to an actor on one of its channels with `ZmqSender::multiplex`.
+#[derive(Debug, Clone)]
+pub struct ChannelData {
+    /// The channel it was sent on
+    pub channel_id: u32,
+    pub data: Vec<u8>,
+}
+crate::define_message!(ChannelData);
 
-    #[test]
-    fn test_remote_actor_ref_creation() {
-        let sender = Arc::new(ZmqSender::new("tcp://0.0.0.0:5555"));
-        let remote_ref = RemoteActorRef::new("test_actor", "tcp://localhost:5556", sender);
+/// A rate-limited message waiting for its sender's next token
+struct HeldMessage {
+    target: ActorRef,
+    msg: Box<dyn Message>,
+    sender: Option<ActorRef>,
+    /// Sent to the dead letter actor if the message is not delivered (only
+    /// kept while there is a dead letter actor)
+    undelivered: Option<RemoteDeadLetter>,
+}
 
-        assert_eq!(remote_ref.name(), "test_actor");
-        assert_eq!(remote_ref.endpoint(), "tcp://localhost:5556");
-    }
+/// Registry of local actors for the ZmqReceiver.
+/// Thread-safe container for looking up ActorRefs by name.
+#[derive(Clone)]
+pub struct ActorRegistry {
+    actors: Arc<Mutex<HashMap<String, ActorRef>>>,
+    /// Receives messages for names not in `actors`
+    dead_letter_actor: Arc<Mutex<Option<ActorRef>>>,
+    /// Actor name -> key its messages must be signed with
+    auth_keys: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+    /// Captures messages for names not in `actors` (see `ZmqReceiver::new_test_probe`)
+    probe: Arc<Mutex<Option<ActorRef>>>,
+    /// Per-actor rate limits and the messages they hold
+    rate_limiter: Arc<Mutex<RateLimiter<HeldMessage>>>,
+    /// Messages dropped as duplicates of an already delivered one
+    duplicates: Arc<AtomicU64>,
+    /// (actor name, channel id) -> actor receiving that channel's data
+    channels: Arc<Mutex<HashMap<(String, u32), ActorRef>>>,
+}
 
-    #[test]
+impl ActorRegistry {
+    /// Create a new empty registry.
+    pub fn new() -> Self {
+        ActorRegistry {
+            actors: Arc::new(Mutex::new(HashMap::new())),
+            dead_letter_actor: Arc::new(Mutex::new(None)),
+            auth_keys: Arc::new(Mutex::new(HashMap::new())),
+            probe: Arc::new(Mutex::new(None)),
+            rate_limiter: Arc::new(Mutex::new(RateLimiter::new())),
+            duplicates: Arc::new(AtomicU64::new(0)),
+            channels: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn dead_letter_actor(&self) -> Option<ActorRef> {
+        self.dead_letter_actor.lock().unwrap().clone()
+    }
+
+    /// The dead letter for the envelope `data`, if there is a dead letter actor to send it to.
+    fn envelope_letter(&self, data: &serde_json::Value, reason: DeadLetterReason) -> Option<RemoteDeadLetter> {
+        self.dead_letter_actor.lock().unwrap().is_some().then(|| RemoteDeadLetter {
+            original_target: data["receiver"].as_str().unwrap_or("").to_string(),
+            payload: data.to_string().into_bytes(),
+            source_endpoint: data["sender_endpoint"].as_str().map(str::to_string),
+            reason,
+        })
+    }
+
+    /// Send the undelivered envelope `data` to the dead letter actor, if one is set.
+    fn dead_letter(&self, data: &serde_json::Value, reason: DeadLetterReason) {
+        if let Some(letter) = self.envelope_letter(data, reason) {
+            self.send_dead_letter(letter);
+        }
+    }
+
+    /// Send `letter` to the dead letter actor, if one is set.
+    fn send_dead_letter(&self, letter: RemoteDeadLetter) {
+        if let Some(dead_letter_actor) = self.dead_letter_actor() {
+            // Never block the receiver on a full dead letter actor
+            let _ = dead_letter_actor.try_send(Box::new(letter), None);
+        }
+    }
+
+    /// Deliver the rate-limited messages whose senders have tokens again.
+    fn release_held(&self) {
+        let released = self.rate_limiter.lock().unwrap().release(Instant::now());
+        self.deliver_held(released);
+    }
+
+    /// Deliver every rate-limited message now, tokens or not (the receiver is stopping).
+    fn flush_held(&self) {
+        let held = self.rate_limiter.lock().unwrap().take_all();
+        self.deliver_held(held);
+    }
+
+    /// Deliver `released`, dead-lettering what a full mailbox does not take.
+    fn deliver_held(&self, released: Vec<HeldMessage>) {
+        for held in released {
+            // Already acknowledged, so the sender will not retry it
+            if let (Err(err), Some(letter)) = (held.target.try_send(held.msg, held.sender), held.undelivered) {
+                self.send_dead_letter(RemoteDeadLetter { reason: err.into(), ..letter });
+            }
+        }
+    }
+
+    /// Check the envelope's signature if its receiver requires one.
+    fn is_authentic(&self, envelope: &serde_json::Value) -> bool {
+        let receiver = envelope["receiver"].as_str().unwrap_or("");
+        let auth_keys = self.auth_keys.lock().unwrap();
+        let Some(key) = auth_keys.get(receiver) else {
+            return true;
+        };
+        let Some(signature) = envelope["signature"].as_str().and_then(from_hex) else {
+            return false;
+        };
+        let msg_type = envelope["message_type"].as_str().unwrap_or("");
+        hmac_sha256::HMAC::verify(signed_content(receiver, msg_type, &envelope["message"]), key, &signature)
+    }
+
+    /// Register a local actor.
+    pub fn register(&self, name: &str, actor_ref: ActorRef) {
+        self.actors.lock().unwrap().insert(name.to_string(), actor_ref);
+    }
+
+    /// Look up an actor by name.
+    pub fn get(&self, name: &str) -> Option<ActorRef> {
+        self.actors.lock().unwrap().get(name).cloned()
+    }
+
+    /// The actor `name` resolves to when routing: a registered actor, else the test probe.
+    fn route_target(&self, name: &str) -> Option<ActorRef> {
+        self.get(name).or_else(|| self.probe.lock().unwrap().clone())
+    }
+
+    /// Route one channel of `name` to `actor_ref` instead of the actor registered as `name`.
+    pub fn register_channel(&self, name: &str, channel_id: u32, actor_ref: ActorRef) {
+        self.channels.lock().unwrap().insert((name.to_string(), channel_id), actor_ref);
+    }
+
+    /// Send the undelivered `data` for a channel of `name` to the dead letter actor, if one is set.
+    fn dead_letter_channel(&self, name: &str, data: Vec<u8>, reason: DeadLetterReason) {
+        self.send_dead_letter(RemoteDeadLetter {
+            original_target: name.to_string(),
+            payload: data,
+            source_endpoint: None,
+            reason,
+        });
+    }
+
+    /// Deliver data received on a channel of `name` (see `ZmqSender::multiplex`).
+    ///
+    /// Channel data carries no signature, so it is refused for actors that
+    /// require signed messages. It is rate limited like messages from one
+    /// more sender. Returns true if the target's mailbox accepted it (or it
+    /// is held).
+    fn route_channel(&self, name: &str, channel_id: u32, data: Vec<u8>) -> bool {
+        if self.auth_keys.lock().unwrap().contains_key(name) {
+            self.dead_letter_channel(name, data, DeadLetterReason::Unauthenticated);
+            return false;
+        }
+        let channel_target = self.channels.lock().unwrap().get(&(name.to_string(), channel_id)).cloned();
+        let Some(target) = channel_target.or_else(|| self.route_target(name)) else {
+            self.dead_letter_channel(name, data, DeadLetterReason::UnknownActor);
+            return false;
+        };
+
+        // The mailbox drops what it does not accept, so keep a copy for the dead letter
+        let undelivered = self.dead_letter_actor.lock().unwrap().is_some().then(|| RemoteDeadLetter {
+            original_target: name.to_string(),
+            payload: data.clone(),
+            source_endpoint: None,
+            reason: DeadLetterReason::RateLimited,
+        });
+        let msg = Box::new(ChannelData { channel_id, data });
+        let held = HeldMessage { target, msg, sender: None, undelivered };
+        let admission = self.rate_limiter.lock().unwrap().admit(name, "", held, Instant::now());
+        match admission {
+            Admission::Deliver(held) => match held.target.try_send(held.msg, None) {
+                Ok(()) => true,
+                Err(err) => {
+                    if let Some(letter) = held.undelivered {
+                        self.send_dead_letter(RemoteDeadLetter { reason: err.into(), ..letter });
+                    }
+                    false
+                }
+            },
+            Admission::Held => true,
+            Admission::Overflow(held) => {
+                if let Some(letter) = held.undelivered {
+                    self.send_dead_letter(letter);
+                }
+                false
+            }
+        }
+    }
+}
+
+impl Default for ActorRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Receives messages from remote processes and routes to local actors.
+///
+/// Binds to a ZMQ PULL socket and forwards incoming messages to local actors.
+/// Optionally also connects a single SUB socket to a publisher; topics on it
+/// are managed with `subscribe_topic` / `unsubscribe_topic`. A receiver
+/// created with `new_sub` only has the SUB socket, subscribed to the actors
+/// registered with it (see `ZmqPublisher`).
+///
+/// Routed messages go through the target actor's `Actor::peek` like local
+/// sends, so an actor can reject or defer remote messages as well.
+pub struct ZmqReceiver {
+    bind_endpoints: Vec<String>,
+    zmq_sender: Arc<ZmqSender>,
+    registry: ActorRegistry,
+    running: Arc<Mutex<bool>>,
+    /// Publisher endpoint the SUB socket connects to (None = no SUB socket)
+    subscribe_endpoint: Option<String>,
+    /// Topics currently subscribed on the SUB socket
+    topics: Arc<Mutex<HashSet<String>>>,
+    /// Registering an actor subscribes to its `ZmqPublisher` topic (`new_sub`)
+    subscribe_registered: bool,
+    /// Forwards subscription changes to the running SUB socket
+    topic_tx: Mutex<Option<Sender<TopicCommand>>>,
+    /// Test injectors never bind a socket
+    test_injector: bool,
+    /// Duplicate detection for frames passed to `inject`
+    injected_dedup: Mutex<DedupWindow>,
+    /// Write-ahead log of received envelopes (see `enable_replay_log`)
+    replay_log: Mutex<Option<SharedReplayLog>>,
+    config: ZmqReceiverConfig,
+}
+
+impl ZmqReceiver {
+    /// Create a new ZmqReceiver.
+    ///
+    /// # Arguments
+    /// * `bind_endpoint` - Endpoint to bind to (e.g., "tcp://0.0.0.0:5001")
+    /// * `zmq_sender` - ZmqSender for creating reply ActorRefs
+    pub fn new(bind_endpoint: &str, zmq_sender: Arc<ZmqSender>) -> Self {
+        Self::new_multi(&[bind_endpoint], zmq_sender)
+    }
+
+    /// Create a ZmqReceiver for the messages of a `ZmqPublisher`.
+    ///
+    /// It binds no PULL socket: it only connects a SUB socket to
+    /// `publisher_endpoint`. Each actor registered with it is subscribed to,
+    /// so the publisher filters out messages for other actors before they
+    /// reach the network.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use actors::{ActorRef, ZmqReceiver, ZmqSender};
+    /// # fn example(zmq_sender: Arc<ZmqSender>, pong_ref: ActorRef) {
+    /// let receiver = ZmqReceiver::new_sub("tcp://server:5600", zmq_sender);
+    /// receiver.register("pong", pong_ref); // subscribes to "pong"
+    /// let handle = receiver.start();
+    /// # }
+    /// ```
+    pub fn new_sub(publisher_endpoint: &str, zmq_sender: Arc<ZmqSender>) -> Self {
+        let mut receiver = Self::new_multi(&[], zmq_sender).with_subscriber(publisher_endpoint);
+        receiver.subscribe_registered = true;
+        receiver
+    }
+
+    /// Create a ZmqReceiver that listens on several endpoints at once,
+    /// e.g. one per network interface.
+    ///
+    /// Messages from every endpoint are routed to the same registered actors.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use actors::{ZmqReceiver, ZmqSender};
+    /// # fn example(zmq_sender: Arc<ZmqSender>) {
+    /// let receiver = ZmqReceiver::new_multi(&["tcp://10.0.0.5:5001", "ipc:///tmp/actors.sock"], zmq_sender);
+    /// # }
+    /// ```
+    pub fn new_multi(bind_endpoints: &[&str], zmq_sender: Arc<ZmqSender>) -> Self {
+        ZmqReceiver {
+            bind_endpoints: bind_endpoints.iter().map(|e| e.to_string()).collect(),
+            zmq_sender,
+            registry: ActorRegistry::new(),
+            running: Arc::new(Mutex::new(true)),
+            subscribe_endpoint: None,
+            topics: Arc::new(Mutex::new(HashSet::new())),
+            subscribe_registered: false,
+            topic_tx: Mutex::new(None),
+            test_injector: false,
+            injected_dedup: Mutex::new(ZmqReceiverConfig::default().dedup()),
+            replay_log: Mutex::new(None),
+            config: ZmqReceiverConfig::default(),
+        }
+    }
+
+    /// Use `config` instead of the default configuration.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use std::time::Duration;
+    /// # use actors::{ZmqReceiver, ZmqReceiverConfig, ZmqSender};
+    /// # fn example(zmq_sender: Arc<ZmqSender>) {
+    /// let config = ZmqReceiverConfig::default().with_dedup(Duration::from_secs(60), 100_000);
+    /// let receiver = ZmqReceiver::new("tcp://0.0.0.0:5001", zmq_sender).with_config(config);
+    /// # }
+    /// ```
+    pub fn with_config(mut self, config: ZmqReceiverConfig) -> Self {
+        self.injected_dedup = Mutex::new(config.dedup());
+        self.config = config;
+        self
+    }
+
+    /// Number of messages dropped as duplicates of an already delivered one
+    /// (see `ZmqReceiverConfig::with_dedup`).
+    pub fn duplicates_dropped(&self) -> u64 {
+        self.registry.duplicates.load(Ordering::Relaxed)
+    }
+
+    /// Log every received message to `dir` before routing it.
+    ///
+    /// A message is marked acked once the target actor's mailbox accepts it;
+    /// the returned handle re-injects messages that never were. The log keeps
+    /// at most `max_size_mb` on disk, discarding the oldest entries. Call
+    /// before `start()`.
+    pub fn enable_replay_log(&self, dir: PathBuf, max_size_mb: u64) -> io::Result<ReplayHandle> {
+        let log = Arc::new(Mutex::new(ReplayLog::open(&dir, max_size_mb)?));
+        *self.replay_log.lock().unwrap() = Some(Arc::clone(&log));
+
+        Ok(ReplayHandle {
+            log,
+            zmq_sender: Arc::clone(&self.zmq_sender),
+            registry: self.registry.clone(),
+            dedup: Mutex::new(self.config.dedup()),
+        })
+    }
+
+    /// Create a ZmqReceiver for unit tests that only routes frames passed to `inject`.
+    ///
+    /// No socket is bound; `start()` returns a handle without starting a thread.
+    pub fn new_test_injector(zmq_sender: Arc<ZmqSender>) -> Self {
+        let mut receiver = Self::new("", zmq_sender);
+        receiver.test_injector = true;
+        receiver
+    }
+
+    /// Create a ZmqReceiver for integration tests that captures the messages
+    /// it receives in the returned `TestProbe`.
+    ///
+    /// Messages for a registered actor are still delivered to it; all others
+    /// go to the probe instead of being rejected. Messages arrive from the
+    /// socket once `start()` is called, or from `inject`.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use std::time::Duration;
+    /// # use actors::{define_message, ProbeError, ZmqReceiver, ZmqSender};
+    /// # struct Ping { count: i32 }
+    /// # define_message!(Ping);
+    /// # fn example(zmq_sender: Arc<ZmqSender>) -> Result<(), ProbeError> {
+    /// let (receiver, probe) = ZmqReceiver::new_test_probe("tcp://0.0.0.0:5001", zmq_sender);
+    /// let _handle = receiver.start();
+    ///
+    /// // ... code under test sends a Ping to any actor at tcp://localhost:5001 ...
+    /// let ping = probe.expect_message::<Ping>(Duration::from_secs(1))?;
+    /// assert_eq!(ping.count, 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_test_probe(bind_endpoint: &str, zmq_sender: Arc<ZmqSender>) -> (Self, TestProbe) {
+        let receiver = Self::new(bind_endpoint, zmq_sender);
+        let (tx, rx) = channel();
+        *receiver.registry.probe.lock().unwrap() = Some(ActorRef::new(tx, "test_probe".to_string()));
+        (receiver, TestProbe { rx })
+    }
+
+    /// Route a raw frame to local actors as if it had arrived on the socket.
+    ///
+    /// Runs synchronously on the caller's thread. Frames that are not valid
+    /// JSON envelopes are ignored, like on the socket.
+    pub fn inject(&self, frame: &[u8]) {
+        self.registry.release_held();
+        let envelopes = decode_envelopes(frame, self.config.serialization);
+        if envelopes.is_empty() {
+            return;
+        }
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("Failed to create injector runtime");
+        let mut dedup = self.injected_dedup.lock().unwrap();
+        let replay_log = self.replay_log.lock().unwrap().clone();
+        for (envelope, decoded) in envelopes {
+            rt.block_on(Self::route_envelope(
+                &envelope,
+                decoded,
+                &self.zmq_sender,
+                &self.registry,
+                &mut dedup,
+                replay_log.as_ref(),
+            ));
+        }
+    }
+
+    /// Also receive published messages by connecting a SUB socket to `publisher_endpoint`.
+    ///
+    /// Each published message is `[topic, envelope]` (or a single envelope frame)
+    /// and is routed to a local actor by the envelope's `receiver`, like PULL messages.
+    pub fn with_subscriber(mut self, publisher_endpoint: &str) -> Self {
+        self.subscribe_endpoint = Some(publisher_endpoint.to_string());
+        self
+    }
+
+    /// Subscribe the SUB socket to `topic` (ZMQ prefix match).
+    ///
+    /// May be called before or after `start()`.
+    pub fn subscribe_topic(&self, topic: &str) {
+        if self.topics.lock().unwrap().insert(topic.to_string()) {
+            if let Some(tx) = self.topic_tx.lock().unwrap().as_ref() {
+                let _ = tx.send(TopicCommand::Subscribe(topic.to_string()));
+            }
+        }
+    }
+
+    /// Unsubscribe the SUB socket from `topic`.
+    pub fn unsubscribe_topic(&self, topic: &str) {
+        if self.topics.lock().unwrap().remove(topic) {
+            if let Some(tx) = self.topic_tx.lock().unwrap().as_ref() {
+                let _ = tx.send(TopicCommand::Unsubscribe(topic.to_string()));
+            }
+        }
+    }
+
+    /// Topics currently subscribed.
+    pub fn topics(&self) -> Vec<String> {
+        let mut topics: Vec<String> = self.topics.lock().unwrap().iter().cloned().collect();
+        topics.sort();
+        topics
+    }
+
+    /// Deliver the data sent to `actor_name` on `channel_id` to `actor_ref`.
+    ///
+    /// Data on channels of `actor_name` that are not registered goes to the
+    /// actor registered as `actor_name`, as a `ChannelData` like here.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{ActorRef, ZmqReceiver};
+    /// # const TRADES_CHANNEL: u32 = 2;
+    /// # fn example(receiver: &ZmqReceiver, renderer_ref: ActorRef, trade_log_ref: ActorRef) {
+    /// receiver.register("renderer", renderer_ref);
+    /// receiver.register_channel("renderer", TRADES_CHANNEL, trade_log_ref);
+    /// # }
+    /// ```
+    pub fn register_channel(&self, actor_name: &str, channel_id: u32, actor_ref: ActorRef) {
+        self.registry.register_channel(actor_name, channel_id, actor_ref);
+    }
+
+    /// Register a local actor to receive messages.
+    pub fn register(&self, name: &str, actor_ref: ActorRef) {
+        self.registry.register(name, actor_ref);
+        if self.subscribe_registered {
+            self.subscribe_topic(&actor_topic(name));
+        }
+    }
+
+    /// Send every message for an unknown actor name, or otherwise not
+    /// delivered (see `DeadLetterReason`), to `actor_ref`, as a
+    /// `RemoteDeadLetter`, e.g. to audit protocol mismatches.
+    ///
+    /// The sender still gets its Reject (and Nack, for tracked sends).
+    /// May be called before or after `start()`.
+    pub fn set_dead_letter_actor(&self, actor_ref: ActorRef) {
+        *self.registry.dead_letter_actor.lock().unwrap() = Some(actor_ref);
+    }
+
+    /// Limit each sender endpoint to `max_per_second` messages to `actor_name`.
+    ///
+    /// Messages over the limit are held and delivered in order as the rate
+    /// allows, up to one second's worth per sender (see
+    /// `set_per_actor_rate_limit_with_burst`). Beyond that, the sender gets
+    /// a Reject (and Nack, for tracked sends) and the message goes to the
+    /// dead letter actor as `DeadLetterReason::RateLimited`. Held messages
+    /// are acknowledged when they are held; one the actor's mailbox does not
+    /// accept when it is released goes to the dead letter actor. May be
+    /// called before or after `start()`.
+    pub fn set_per_actor_rate_limit(&self, actor_name: &str, max_per_second: f64) {
+        self.set_per_actor_rate_limit_with_burst(actor_name, max_per_second, max_per_second.ceil() as usize);
+    }
+
+    /// Like `set_per_actor_rate_limit`, holding up to `burst_size` messages per sender.
+    pub fn set_per_actor_rate_limit_with_burst(&self, actor_name: &str, max_per_second: f64, burst_size: usize) {
+        self.registry
+            .rate_limiter
+            .lock()
+            .unwrap()
+            .set_limit(actor_name, max_per_second, burst_size);
+    }
+
+    /// Only deliver messages to `actor_name` that are signed with `secret_key`
+    /// (see `ZmqSender::sign_for`).
+    ///
+    /// Unsigned or wrongly signed messages are rejected like those for an
+    /// unknown actor: the sender gets a Reject (and Nack, for tracked sends)
+    /// and the message goes to the dead letter actor as
+    /// `DeadLetterReason::Unauthenticated`. So does any data sent to the
+    /// actor's channels, which cannot be signed. Messages to other actors are
+    /// not checked. May be called before or
+    /// after `start()`.
+    pub fn require_auth_for(&self, actor_name: &str, secret_key: &[u8]) {
+        self.registry
+            .auth_keys
+            .lock()
+            .unwrap()
+            .insert(actor_name.to_string(), secret_key.to_vec());
+    }
+
+    /// Endpoints the receiver binds to.
+    pub fn bind_endpoints(&self) -> &[String] {
+        &self.bind_endpoints
+    }
+
+    /// Get the registry (for sharing with other components).
+    pub fn registry(&self) -> &ActorRegistry {
+        &self.registry
+    }
+
+    /// Start the receiver in a new thread.
+    ///
+    /// Returns a handle that can be used to stop the receiver.
+    pub fn start(&self) -> ZmqReceiverHandle {
+        if self.test_injector {
+            return ZmqReceiverHandle {
+                running: Arc::clone(&self.running),
+                drain_until: Arc::new(Mutex::new(None)),
+                thread: None,
+                sub_thread: None,
+            };
+        }
+
+        let bind_endpoints = self.bind_endpoints.clone();
+        let zmq_sender = Arc::clone(&self.zmq_sender);
+        let registry = self.registry.clone();
+        let running = Arc::clone(&self.running);
+        let replay_log = self.replay_log.lock().unwrap().clone();
+        let mut dedup = self.config.dedup();
+        let serialization = self.config.serialization;
+        let drain_until: Arc<Mutex<Option<Instant>>> = Arc::new(Mutex::new(None));
+        let drain_deadline = Arc::clone(&drain_until);
+
+        // Receivers created with `new_sub` have nothing to bind
+        let handle = (!bind_endpoints.is_empty()).then(|| thread::spawn(move || {
+            // Create a new tokio runtime for this thread
+            let rt = Runtime::new().expect("Failed to create receiver runtime");
+
+            rt.block_on(async {
+                let mut socket = PullSocket::new();
+                let mut drained = 0;
+
+                // One PULL socket serves all endpoints
+                for bind_endpoint in &bind_endpoints {
+                    // Convert tcp://*: to tcp://0.0.0.0: for pure-Rust zeromq
+                    let normalized_endpoint = bind_endpoint.replace("tcp://*:", "tcp://0.0.0.0:");
+                    socket.bind(&normalized_endpoint).await.expect("Failed to bind socket");
+                }
+
+                loop {
+                    // Check running flag
+                    if !*running.lock().unwrap() {
+                        break;
+                    }
+                    registry.release_held();
+
+                    let deadline = *drain_deadline.lock().unwrap();
+                    if let Some(deadline) = deadline {
+                        // Stop accepting connections; connected peers' frames still arrive
+                        socket.unbind_all().await;
+                        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+                            match tokio::time::timeout(remaining.min(DRAIN_IDLE), socket.recv()).await {
+                                Ok(Ok(msg)) => {
+                                    drained += Self::route_frame(
+                                        &msg, &zmq_sender, &registry, &mut dedup, replay_log.as_ref(), serialization
+                                    ).await;
+                                }
+                                // Socket error, or nothing left to drain
+                                _ => break,
+                            }
+                        }
+                        registry.flush_held();
+                        break;
+                    }
+
+                    // Use tokio timeout to periodically check running flag
+                    let recv_result = tokio::time::timeout(
+                        tokio::time::Duration::from_millis(100),
+                        socket.recv()
+                    ).await;
+
+                    match recv_result {
+                        Ok(Ok(msg)) => {
+                            Self::route_frame(
+                                &msg, &zmq_sender, &registry, &mut dedup, replay_log.as_ref(), serialization
+                            ).await;
+                        }
+                        Ok(Err(_)) => {
+                            // Socket error, exit
+                            break;
+                        }
+                        Err(_) => {
+                            // Timeout, continue to check running flag
+                            continue;
+                        }
+                    }
+                }
+                drained
+            })
+        }));
+
+        let sub_thread = self
+            .subscribe_endpoint
+            .clone()
+            .map(|endpoint| self.start_subscriber(endpoint, Arc::clone(&drain_until)));
+
+        ZmqReceiverHandle {
+            running: Arc::clone(&self.running),
+            drain_until,
+            thread: handle,
+            sub_thread,
+        }
+    }
+
+    /// Run the SUB socket in its own thread, applying topic changes as they arrive.
+    ///
+    /// Like the PULL thread, it returns the number of messages delivered
+    /// while draining.
+    fn start_subscriber(
+        &self,
+        publisher_endpoint: String,
+        drain_until: Arc<Mutex<Option<Instant>>>,
+    ) -> thread::JoinHandle<usize> {
+        let zmq_sender = Arc::clone(&self.zmq_sender);
+        let registry = self.registry.clone();
+        let running = Arc::clone(&self.running);
+        let topics = Arc::clone(&self.topics);
+        let replay_log = self.replay_log.lock().unwrap().clone();
+        let mut dedup = self.config.dedup();
+        let serialization = self.config.serialization;
+
+        let (topic_tx, topic_rx) = channel();
+        *self.topic_tx.lock().unwrap() = Some(topic_tx);
+
+        thread::spawn(move || {
+            let rt = Runtime::new().expect("Failed to create subscriber runtime");
+
+            rt.block_on(async {
+                let mut socket = SubSocket::new();
+                let mut drained = 0;
+
+                let connect_endpoint = publisher_endpoint.replace("tcp://*:", "tcp://localhost:");
+                if socket.connect(&connect_endpoint).await.is_err() {
+                    return drained;
+                }
+                let initial: Vec<String> = topics.lock().unwrap().iter().cloned().collect();
+                for topic in initial {
+                    let _ = socket.subscribe(&topic).await;
+                }
+
+                loop {
+                    if !*running.lock().unwrap() {
+                        break;
+                    }
+                    registry.release_held();
+
+                    while let Ok(cmd) = topic_rx.try_recv() {
+                        let _ = match cmd {
+                            TopicCommand::Subscribe(topic) => socket.subscribe(&topic).await,
+                            TopicCommand::Unsubscribe(topic) => socket.unsubscribe(&topic).await,
+                        };
+                    }
+
+                    let deadline = *drain_until.lock().unwrap();
+                    if let Some(deadline) = deadline {
+                        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+                            match tokio::time::timeout(remaining.min(DRAIN_IDLE), socket.recv()).await {
+                                Ok(Ok(msg)) => {
+                                    drained += Self::route_published(
+                                        &msg, &zmq_sender, &registry, &mut dedup, replay_log.as_ref(), serialization
+                                    ).await;
+                                }
+                                // Socket error, or nothing left to drain
+                                _ => break,
+                            }
+                        }
+                        registry.flush_held();
+                        break;
+                    }
+
+                    let recv_result = tokio::time::timeout(
+                        tokio::time::Duration::from_millis(100),
+                        socket.recv()
+                    ).await;
+
+                    match recv_result {
+                        Ok(Ok(msg)) => {
+                            Self::route_published(
+                                &msg, &zmq_sender, &registry, &mut dedup, replay_log.as_ref(), serialization
+                            ).await;
+                        }
+                        Ok(Err(_)) => break,
+                        Err(_) => continue,
+                    }
+                }
+                drained
+            })
+        })
+    }
+
+    /// Route a message received on the SUB socket.
+    ///
+    /// Returns 1 if it was a valid envelope accepted by its actor's mailbox, else 0.
+    async fn route_published(
+        msg: &ZmqMessage,
+        zmq_sender: &Arc<ZmqSender>,
+        registry: &ActorRegistry,
+        dedup: &mut DedupWindow,
+        replay_log: Option<&SharedReplayLog>,
+        serialization: SerializationBackend,
+    ) -> usize {
+        // Envelope is the last frame; the first one is the topic
+        let data = msg.iter().last().map(|b| b.as_ref()).unwrap_or(&[]);
+        let Some((envelope, decoded)) = decode_envelope(data, serialization) else {
+            return 0;
+        };
+        usize::from(Self::route_envelope(&envelope, decoded, zmq_sender, registry, dedup, replay_log).await)
+    }
+
+    /// Route a message received on the PULL socket: one envelope, each
+    /// envelope of a batch, or data sent on a channel.
+    ///
+    /// Returns how many envelopes (or channel data) were valid and accepted
+    /// by their actor's mailbox.
+    async fn route_frame(
+        msg: &ZmqMessage,
+        zmq_sender: &Arc<ZmqSender>,
+        registry: &ActorRegistry,
+        dedup: &mut DedupWindow,
+        replay_log: Option<&SharedReplayLog>,
+        serialization: SerializationBackend,
+    ) -> usize {
+        if let Some((actor_name, channel_id, data)) = demultiplex(msg) {
+            return usize::from(registry.route_channel(&actor_name, channel_id, data));
+        }
+        let data = msg.get(0).map(|b| b.as_ref()).unwrap_or(&[]);
+        let mut delivered = 0;
+        for (envelope, decoded) in decode_envelopes(data, serialization) {
+            if Self::route_envelope(&envelope, decoded, zmq_sender, registry, dedup, replay_log).await {
+                delivered += 1;
+            }
+        }
+        delivered
+    }
+
+    /// Route an envelope, recording it in the replay log first if one is enabled.
+    /// `decoded` is its message, if a binary codec carried it.
+    ///
+    /// Returns true if its actor's mailbox accepted it.
+    async fn route_envelope(
+        envelope: &serde_json::Value,
+        decoded: Option<Box<dyn Message>>,
+        zmq_sender: &Arc<ZmqSender>,
+        registry: &ActorRegistry,
+        dedup: &mut DedupWindow,
+        replay_log: Option<&SharedReplayLog>,
+    ) -> bool {
+        // Ack/Nack control frames are not messages and are never replayed
+        let seq = match replay_log {
+            Some(log) if envelope.get("control").is_none() => log.lock().unwrap().append(envelope).ok(),
+            _ => None,
+        };
+
+        let routed = Self::handle_remote_message_async(envelope, decoded, zmq_sender, registry, dedup).await;
+
+        if let (Some(seq), Some(log)) = (seq, replay_log) {
+            // A full mailbox leaves the entry unacked, for `replay_unacked`
+            let _ = match routed {
+                Routed::Delivered => log.lock().unwrap().ack(seq),
+                Routed::MailboxFull => log.lock().unwrap().mark_full(seq),
+                Routed::Failed => Ok(()),
+            };
+        }
+        routed == Routed::Delivered
+    }
+
+    /// Deliver an envelope to its local actor, with `decoded` as its message
+    /// if given, else the message deserialized from the envelope.
+    async fn handle_remote_message_async(
+        data: &serde_json::Value,
+        decoded: Option<Box<dyn Message>>,
+        zmq_sender: &Arc<ZmqSender>,
+        registry: &ActorRegistry,
+        dedup: &mut DedupWindow,
+    ) -> Routed {
+        // Ack/Nack control frames are for our own ZmqSender
+        if let (Some(control), Some(id)) = (data["control"].as_str(), data["delivery_id"].as_u64()) {
+            match control {
+                CONTROL_ACK => zmq_sender.on_ack(id),
+                CONTROL_NACK => zmq_sender.on_nack(id),
+                _ => {}
+            }
+            return Routed::Failed;
+        }
+
+        // Tracked sends expect an Ack or Nack back
+        let delivery_id = data["delivery_id"].as_u64();
+        let ack_endpoint = data["ack_endpoint"].as_str();
+        let idempotency_key = data["idempotency_key"].as_str();
+
+        // Already delivered: re-Ack (the first Ack may have been lost) and drop
+        if let Some(key) = idempotency_key {
+            if dedup.contains(key) {
+                registry.duplicates.fetch_add(1, Ordering::Relaxed);
+                Self::send_ack(zmq_sender, delivery_id, ack_endpoint, None);
+                return Routed::Delivered;
+            }
+        }
+
+        let receiver_name = data["receiver"].as_str().unwrap_or("");
+        let msg_type = data["message_type"].as_str().unwrap_or("");
+        let msg_data = &data["message"];
+
+        // Get sender info for replies (needed for both success and reject)
+        let sender_actor = data["sender_actor"].as_str();
+        let sender_endpoint = data["sender_endpoint"].as_str();
+
+        // Look up local actor
+        let local_ref = match registry.route_target(receiver_name) {
+            Some(r) => r,
+            None => {
+                let reason = format!("Actor '{}' not found", receiver_name);
+                Self::send_ack(zmq_sender, delivery_id, ack_endpoint, Some(&reason));
+
+                // Actor not found - send reject back to sender
+                if let (Some(actor), Some(endpoint)) = (sender_actor, sender_endpoint) {
+                    let reject = Reject::new(msg_type, &reason, receiver_name);
+                    zmq_sender.send_to_async(endpoint, actor, Box::new(reject), None).await;
+                }
+                registry.dead_letter(data, DeadLetterReason::UnknownActor);
+                return Routed::Failed;
+            }
+        };
+
+        if !registry.is_authentic(data) {
+            let reason = format!("Invalid signature for actor '{}'", receiver_name);
+            Self::send_ack(zmq_sender, delivery_id, ack_endpoint, Some(&reason));
+            if let (Some(actor), Some(endpoint)) = (sender_actor, sender_endpoint) {
+                let reject = Reject::new(msg_type, &reason, receiver_name);
+                zmq_sender.send_to_async(endpoint, actor, Box::new(reject), None).await;
+            }
+            registry.dead_letter(data, DeadLetterReason::Unauthenticated);
+            return Routed::Failed;
+        }
+
+        // Try to deserialize the message, unless the codec already did
+        let msg = match decoded {
+            Some(msg) => Ok(msg),
+            None => try_deserialize_message(msg_type, msg_data.clone()),
+        };
+        match msg {
+            Ok(msg) => {
+                // Success - create sender ref and deliver to local actor
+                let sender_ref = if let (Some(actor), Some(endpoint)) = (sender_actor, sender_endpoint) {
+                    Some(ActorRef::Remote(RemoteActorRef::new(
+                        actor,
+                        endpoint,
+                        Arc::clone(zmq_sender),
+                    )))
+                } else {
+                    None
+                };
+                let undelivered = registry.envelope_letter(data, DeadLetterReason::RateLimited);
+                let held = HeldMessage { target: local_ref, msg, sender: sender_ref, undelivered };
+                let admission = registry.rate_limiter.lock().unwrap().admit(
+                    receiver_name,
+                    sender_endpoint.unwrap_or(""),
+                    held,
+                    Instant::now(),
+                );
+                match admission {
+                    Admission::Deliver(held) => match held.target.try_send(held.msg, held.sender) {
+                        Ok(()) => {}
+                        // Left unacked so the sender retries
+                        Err(SendError::Full) => return Routed::MailboxFull,
+                        Err(_) => return Routed::Failed,
+                    },
+                    // Acked now, delivered by `release_held` once the sender has a token
+                    Admission::Held => {}
+                    Admission::Overflow(_) => {
+                        let reason = format!("Rate limit exceeded for actor '{}'", receiver_name);
+                        Self::send_ack(zmq_sender, delivery_id, ack_endpoint, Some(&reason));
+                        if let (Some(actor), Some(endpoint)) = (sender_actor, sender_endpoint) {
+                            let reject = Reject::new(msg_type, &reason, receiver_name);
+                            zmq_sender.send_to_async(endpoint, actor, Box::new(reject), None).await;
+                        }
+                        registry.dead_letter(data, DeadLetterReason::RateLimited);
+                        return Routed::Failed;
+                    }
+                }
+
+                if let Some(key) = idempotency_key {
+                    dedup.insert(key);
+                }
+                Self::send_ack(zmq_sender, delivery_id, ack_endpoint, None);
+                Routed::Delivered
+            }
+            Err(reason) => {
+                Self::send_ack(zmq_sender, delivery_id, ack_endpoint, Some(&reason));
+
+                // Deserialization failed - send reject back to sender
+                if let (Some(actor), Some(endpoint)) = (sender_actor, sender_endpoint) {
+                    let reject = Reject::new(msg_type, &reason, receiver_name);
+                    zmq_sender.send_to_async(endpoint, actor, Box::new(reject), None).await;
+                }
+                Routed::Failed
+            }
+        }
+    }
+
+    /// Send an Ack (or a Nack when `nack_reason` is set) for a tracked message.
+    ///
+    /// Does nothing for untracked (`AtMostOnce`) messages.
+    fn send_ack(
+        zmq_sender: &ZmqSender,
+        delivery_id: Option<u64>,
+        ack_endpoint: Option<&str>,
+        nack_reason: Option<&str>,
+    ) {
+        if let (Some(id), Some(endpoint)) = (delivery_id, ack_endpoint) {
+            let frame = match nack_reason {
+                None => serde_json::json!({
+                    "control": CONTROL_ACK,
+                    "delivery_id": id
+                }),
+                Some(reason) => serde_json::json!({
+                    "control": CONTROL_NACK,
+                    "delivery_id": id,
+                    "reason": reason
+                }),
+            };
+            zmq_sender.send_raw(endpoint, frame.to_string().into_bytes());
+        }
+    }
+}
+
+/// What became of a received envelope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Routed {
+    /// Accepted by its actor's mailbox, now or before
+    Delivered,
+    /// Turned away by its actor's full mailbox
+    MailboxFull,
+    /// Not delivered for any other reason
+    Failed,
+}
+
+/// Re-injects logged messages that no actor mailbox accepted.
+///
+/// Returned by `ZmqReceiver::enable_replay_log`.
+pub struct ReplayHandle {
+    log: SharedReplayLog,
+    zmq_sender: Arc<ZmqSender>,
+    registry: ActorRegistry,
+    dedup: Mutex<DedupWindow>,
+}
+
+impl ReplayHandle {
+    /// Route again every unacked message received at or after `from`,
+    /// including those turned away by a full mailbox.
+    ///
+    /// Messages accepted this time are marked acked; those whose mailbox is
+    /// still full stay in the log. Returns how many were delivered.
+    pub fn replay_unacked(&self, from: SystemTime) -> io::Result<usize> {
+        let entries = self.log.lock().unwrap().unacked_since(from)?;
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("Failed to create replay runtime");
+        let mut dedup = self.dedup.lock().unwrap();
+
+        let mut delivered = 0;
+        for entry in entries {
+            let routed = rt.block_on(ZmqReceiver::handle_remote_message_async(
+                &entry.envelope,
+                None,
+                &self.zmq_sender,
+                &self.registry,
+                &mut dedup,
+            ));
+            match routed {
+                Routed::Delivered => {
+                    self.log.lock().unwrap().ack(entry.seq)?;
+                    delivered += 1;
+                }
+                Routed::MailboxFull => self.log.lock().unwrap().mark_full(entry.seq)?,
+                Routed::Failed => {}
+            }
+        }
+        Ok(delivered)
+    }
+}
+
+/// Parse a received frame into its JSON envelope, decompressing it if needed.
+fn decode_envelope(frame: &[u8], backend: SerializationBackend) -> Option<Decoded> {
+    parse_envelope(&decompress(frame)?, backend)
+}
+
+/// Parse an envelope: bare JSON, or a codec frame for messages encoded with
+/// a binary codec (see `SerializationBackend`), whose message is decoded too.
+fn parse_envelope(data: &[u8], backend: SerializationBackend) -> Option<Decoded> {
+    codec::decode(data, backend).or_else(|| Some((serde_json::from_slice(data).ok()?, None)))
+}
+
+/// Split a message sent with `ZmqSender::multiplex` into its actor name,
+/// channel id and data.
+///
+/// Returns None for anything else, such as a single-frame envelope.
+fn demultiplex(msg: &ZmqMessage) -> Option<(String, u32, Vec<u8>)> {
+    if msg.len() != 3 {
+        return None;
+    }
+    let channel_id = u32::from_be_bytes(msg.get(0)?.as_ref().try_into().ok()?);
+    let actor_name = std::str::from_utf8(msg.get(1)?).ok()?.to_string();
+    Some((actor_name, channel_id, msg.get(2)?.to_vec()))
+}
+
+/// Parse a received frame into its JSON envelopes: the frame's one envelope,
+/// or each valid envelope of a batch (see `ZmqSender::send_batch`).
+fn decode_envelopes(frame: &[u8], backend: SerializationBackend) -> Vec<Decoded> {
+    let Some(data) = decompress(frame) else {
+        return Vec::new();
+    };
+    match batch::unpack(&data) {
+        Some(envelopes) => envelopes.into_iter().filter_map(|data| parse_envelope(data, backend)).collect(),
+        None => parse_envelope(&data, backend).into_iter().collect(),
+    }
+}
+
+/// The receiving end of a pipeline stage fed by `ZmqPushSender`s.
+///
+/// Every `ZmqReceiver` binds a PULL socket and routes messages by the actor
+/// name in their envelope, so this is the same type under the pipeline name.
+pub type ZmqPullReceiver = ZmqReceiver;
+
+/// Handle for controlling a running ZmqReceiver.
+pub struct ZmqReceiverHandle {
+    running: Arc<Mutex<bool>>,
+    /// Set by `stop_drain`: the receive loop drains until then and exits
+    drain_until: Arc<Mutex<Option<Instant>>>,
+    /// Both return the number of messages delivered while draining
+    thread: Option<thread::JoinHandle<usize>>,
+    sub_thread: Option<thread::JoinHandle<usize>>,
+}
+
+impl ZmqReceiverHandle {
+    /// Stop the receiver after delivering the messages it already received.
+    ///
+    /// New connections are refused, but frames from connected peers, and
+    /// published messages on the SUB socket, are still delivered until none
+    /// arrive for a moment or `timeout` elapses, whichever comes first.
+    /// Messages held back by a rate limit are then delivered without waiting
+    /// for their sender's tokens. Returns the number of messages delivered
+    /// while draining.
+    ///
+    /// # Example
+    /// ```
+    /// # use std::time::Duration;
+    /// # fn example(receiver_handle: &mut actors::ZmqReceiverHandle, mgr: &mut actors::Manager) {
+    /// let drained = receiver_handle.stop_drain(Duration::from_secs(2));
+    /// println!("delivered {} messages while draining", drained);
+    /// mgr.end();
+    /// # }
+    /// ```
+    pub fn stop_drain(&mut self, timeout: Duration) -> usize {
+        let now = Instant::now();
+        // Draining ends once the sockets are idle, so a timeout too long to represent is as good as forever
+        let deadline = now.checked_add(timeout).unwrap_or(now + MAX_DRAIN_TIMEOUT);
+        *self.drain_until.lock().unwrap() = Some(deadline);
+        let drained = [self.thread.take(), self.sub_thread.take()]
+            .into_iter()
+            .flatten()
+            .map(|handle| handle.join().unwrap_or(0))
+            .sum();
+        self.stop();
+        drained
+    }
+
+    /// Stop the receiver.
+    pub fn stop(&mut self) {
+        *self.running.lock().unwrap() = false;
+        if let Some(handle) = self.thread.take() {
+            let _ = handle.join();
+        }
+        if let Some(handle) = self.sub_thread.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for ZmqReceiverHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Error returned by `TestProbe::expect_message`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeError {
+    /// No message arrived in time
+    Timeout {
+        /// Type name of the expected message
+        expected: &'static str,
+        waited: Duration,
+    },
+    /// The next message was of another type
+    WrongType {
+        /// Type name of the expected message
+        expected: &'static str,
+        /// Type name of the message received
+        received: &'static str,
+    },
+}
+
+impl std::fmt::Display for ProbeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProbeError::Timeout { expected, waited } => {
+                write!(f, "Expected a {} but no message arrived within {:?}", expected, waited)
+            }
+            ProbeError::WrongType { expected, received } => {
+                write!(f, "Expected a {} but received a {}", expected, received)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProbeError {}
+
+/// Messages captured by a receiver created with `ZmqReceiver::new_test_probe`,
+/// in the order they arrived.
+pub struct TestProbe {
+    rx: Receiver<Envelope>,
+}
+
+impl TestProbe {
+    /// Wait up to `timeout` for the next message and return it as a `T`.
+    ///
+    /// The message is consumed even when it is not a `T`.
+    pub fn expect_message<T: Message>(&self, timeout: Duration) -> Result<T, ProbeError> {
+        let expected = std::any::type_name::<T>();
+        let envelope = self
+            .rx
+            .recv_timeout(timeout)
+            .map_err(|_| ProbeError::Timeout { expected, waited: timeout })?;
+        let received = envelope.msg.type_name();
+        let msg: Box<dyn std::any::Any> = envelope.msg;
+        msg.downcast::<T>()
+            .map(|msg| *msg)
+            .map_err(|_| ProbeError::WrongType { expected, received })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_actor_ref_creation() {
+        let sender = Arc::new(ZmqSender::new("tcp://0.0.0.0:5555"));
+        let remote_ref = RemoteActorRef::new("test_actor", "tcp://localhost:5556", sender);
+
+        assert_eq!(remote_ref.name(), "test_actor");
+        assert_eq!(remote_ref.endpoint(), "tcp://localhost:5556");
+    }
+
+    #[test]
     fn test_zmq_sender_creation() {
         let sender = ZmqSender::new("tcp://0.0.0.0:5557");
         assert_eq!(sender.local_endpoint(), "tcp://0.0.0.0:5557");
     }
 
+    #[test]
+    fn test_zmq_sender_config() {
+        let config = ZmqSenderConfig::default();
+        assert_eq!(config.send_semantics, SendSemantics::AtMostOnce);
+
+        let config = ZmqSenderConfig::default()
+            .with_send_semantics(SendSemantics::ExactlyOnce)
+            .with_ack_timeout(Duration::from_millis(50))
+            .with_retry_buffer_capacity(8);
+        assert_eq!(config.send_semantics, SendSemantics::ExactlyOnce);
+        assert_eq!(config.ack_timeout, Duration::from_millis(50));
+        assert_eq!(config.retry_buffer_capacity, 8);
+
+        let sender = ZmqSender::with_config("tcp://0.0.0.0:5558", config);
+        assert_eq!(sender.config().send_semantics, SendSemantics::ExactlyOnce);
+    }
+
+    #[test]
+    fn test_max_in_flight() {
+        let config = ZmqSenderConfig::default()
+            .with_send_semantics(SendSemantics::AtLeastOnce)
+            .with_max_in_flight(2);
+        let (send_tx, _) = channel::<SenderCommand>();
+        let recorder = Some(Mutex::new(Vec::new()));
+        let sender = Arc::new(ZmqSender::with_channel("tcp://localhost:5001", config, send_tx, recorder));
+        let send = |sender: &ZmqSender| {
+            sender.send_to("tcp://localhost:5002", "pong", Box::new(Reject::new("Ping", "", "")), None)
+        };
+
+        send(&sender);
+        send(&sender);
+        assert_eq!(sender.in_flight(), 2);
+
+        // A third send waits for an Ack
+        let blocked = Arc::clone(&sender);
+        let third = thread::spawn(move || send(&blocked));
+        thread::sleep(Duration::from_millis(100));
+        assert!(!third.is_finished());
+        assert_eq!(sender.recorded_sends().len(), 2);
+
+        sender.on_ack(1);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !third.is_finished() {
+            assert!(Instant::now() < deadline, "an Ack did not release the blocked send");
+            thread::sleep(Duration::from_millis(1));
+        }
+        third.join().unwrap();
+        assert_eq!(sender.recorded_sends().len(), 3);
+        assert_eq!(sender.in_flight(), 2);
+
+        sender.on_nack(2);
+        sender.on_ack(3);
+        assert_eq!(sender.in_flight(), 0);
+    }
+
+    /// A local endpoint on a port that was free a moment ago, for tests that
+    /// need to know a receiver's endpoint before it binds.
+    fn free_endpoint() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("tcp://{}", listener.local_addr().unwrap())
+    }
+
+    #[test]
+    fn test_at_least_once_retransmits_until_acked() {
+        use crate::actor::ActorRuntime;
+        use crate::serialization::register_remote_message;
+
+        struct Sink;
+        impl crate::Actor for Sink {}
+
+        register_remote_message::<Reject>("Reject");
+        let endpoint_a = &free_endpoint();
+        let endpoint_b = &free_endpoint();
+        let config = ZmqSenderConfig::default()
+            .with_send_semantics(SendSemantics::AtLeastOnce)
+            .with_ack_timeout(Duration::from_millis(20));
+        // A's receiver takes the Acks for A's sender
+        let sender_a = Arc::new(ZmqSender::with_config(endpoint_a, config));
+        let _receiver_a = ZmqReceiver::new(endpoint_a, Arc::clone(&sender_a)).start();
+        let sender_b = Arc::new(ZmqSender::new(endpoint_b));
+        let receiver_b = ZmqReceiver::new(endpoint_b, sender_b);
+
+        // A full mailbox leaves the message unacked
+        let pong = ActorRuntime::new("pong".to_string(), Box::new(Sink));
+        pong.metrics.set_mailbox_capacity(Some(1));
+        pong.get_ref().try_send(Box::new(Reject::new("Filler", "", "pong")), None).unwrap();
+        receiver_b.register("pong", pong.get_ref());
+        let _receiver_b = receiver_b.start();
+
+        sender_a.send_to(endpoint_b, "pong", Box::new(Reject::new("Ping", "retried", "pong")), None);
+        thread::sleep(Duration::from_millis(200));
+        assert_eq!(sender_a.in_flight(), 1);
+
+        // Once there is room, a retransmission is delivered and acked
+        pong.receiver.recv().unwrap();
+        pong.metrics.record_dequeued();
+        let envelope = pong.receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(envelope.msg.as_any().downcast_ref::<Reject>().unwrap().reason, "retried");
+        pong.metrics.record_dequeued();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while sender_a.in_flight() > 0 {
+            assert!(Instant::now() < deadline, "delivered message was never acked");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let config = ZmqSenderConfig::default().with_retry(4, Duration::from_millis(10), 3.0);
+        assert_eq!(config.max_send_attempts, 4);
+        assert_eq!(config.retry_backoff(1), Duration::from_millis(10));
+        assert_eq!(config.retry_backoff(3), Duration::from_millis(90));
+        assert_eq!(config.retry_backoff(100), MAX_SEND_BACKOFF);
+
+        let mut retries = SendRetries::default();
+        let send = |attempts, policy| RetrySend {
+            endpoint: "tcp://h:1".to_string(),
+            data: Vec::new(),
+            attempts,
+            due: Instant::now(),
+            policy,
+        };
+        assert!(retries.on_failure(send(1, None), &config).is_none());
+        assert!(retries.on_failure(send(4, None), &config).is_some());
+        assert_eq!(retries.failures["tcp://h:1"], 2);
+        retries.on_success("tcp://h:1");
+        assert!(retries.failures.is_empty());
+
+        // Timed-out sends follow their own policy: 2 retries after the first attempt
+        let policy = RetryPolicy::new(2, Duration::from_secs(10), Duration::from_secs(60));
+        let before = Instant::now();
+        assert!(retries.on_failure(send(2, Some(policy.clone())), &config).is_none());
+        assert!(retries.waiting[1].due >= before + Duration::from_secs(20));
+        assert!(retries.on_failure(send(3, Some(policy)), &config).is_some());
+    }
+
+    #[test]
+    fn test_send_timeout() {
+        use crate::actor::Envelope;
+        use crate::serialization::register_remote_message;
+
+        register_remote_message::<Reject>("Reject");
+        let config = ZmqSenderConfig::default().with_on_timeout(OnTimeout::DeadLetter);
+        let sender = ZmqSender::with_config("tcp://127.0.0.1:5580", config);
+        let (tx, rx) = channel::<Envelope>();
+        sender.set_dead_letter_actor(ActorRef::new(tx, "audit".to_string()));
+
+        // Nothing listens on the TCP port, so connecting never completes
+        let started = Instant::now();
+        let msg = Box::new(Reject::new("Ping", "", ""));
+        let result = sender.send_timeout("tcp://127.0.0.1:5581", "pong", msg, None, Duration::from_millis(200));
+        assert_eq!(result, Err(SendError::Timeout));
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(sender.send_timeouts(), 1);
+
+        let envelope = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        let letter = envelope.msg.as_any().downcast_ref::<DeadLetter>().unwrap();
+        assert_eq!(letter.target, "pong");
+        assert!(letter.reason.contains("timed out"));
+
+        // Each send waits on its own endpoint, not behind the others
+        let sender = Arc::new(sender);
+        let started = Instant::now();
+        let sends: Vec<_> = ["tcp://127.0.0.1:5587", "tcp://127.0.0.1:5588"]
+            .into_iter()
+            .map(|endpoint| {
+                let sender = Arc::clone(&sender);
+                thread::spawn(move || {
+                    let msg = Box::new(Reject::new("Ping", "", ""));
+                    sender.send_timeout(endpoint, "pong", msg, None, Duration::from_millis(500))
+                })
+            })
+            .collect();
+        for send in sends {
+            assert_eq!(send.join().unwrap(), Err(SendError::Timeout));
+        }
+        assert!(started.elapsed() < Duration::from_millis(900));
+        assert_eq!(sender.send_timeouts(), 3);
+
+        let recorder = ZmqSender::new_test_recorder("tcp://localhost:5580");
+        let msg = Box::new(Reject::new("Ping", "", ""));
+        assert_eq!(recorder.send_timeout("tcp://h:1", "pong", msg, None, Duration::ZERO), Ok(()));
+        assert_eq!(recorder.recorded_sends().len(), 1);
+    }
+
+    #[test]
+    fn test_failed_send_goes_to_dead_letter_actor() {
+        use crate::serialization::register_remote_message;
+        use std::sync::mpsc::channel;
+
+        register_remote_message::<Reject>("Reject");
+
+        let config = ZmqSenderConfig::default().with_retry(3, Duration::from_millis(5), 2.0);
+        let sender = ZmqSender::with_config("tcp://127.0.0.1:5570", config);
+        let (tx, rx) = channel();
+        sender.set_dead_letter_actor(ActorRef::new(tx, "audit".to_string()));
+
+        // The socket file does not exist, so every attempt fails
+        let reject = Reject::new("Ping", "test", "pong");
+        sender.send_to("ipc:///nonexistent/actors.sock", "pong", Box::new(reject), None);
+
+        let envelope = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let letter = envelope.msg.as_any().downcast_ref::<DeadLetter>().unwrap();
+        assert_eq!(letter.target, "pong");
+        assert_eq!(letter.message_type, "Reject");
+        assert_eq!(letter.reason, "Send to ipc:///nonexistent/actors.sock failed after 3 attempts");
+    }
+
+    #[test]
+    fn test_retry_buffer_eviction_goes_to_dead_letter_actor() {
+        use crate::serialization::register_remote_message;
+        use std::sync::mpsc::channel;
+
+        register_remote_message::<Reject>("Reject");
+
+        let config = ZmqSenderConfig::default()
+            .with_send_semantics(SendSemantics::AtLeastOnce)
+            .with_retry_buffer_capacity(1);
+        let sender = ZmqSender::with_config("tcp://127.0.0.1:5570", config);
+        let (tx, rx) = channel();
+        sender.set_dead_letter_actor(ActorRef::new(tx, "audit".to_string()));
+
+        // Nothing acks, so the second send evicts the first
+        let endpoint = "ipc:///nonexistent/actors.sock";
+        sender.send_to(endpoint, "first", Box::new(Reject::new("Ping", "", "first")), None);
+        sender.send_to(endpoint, "second", Box::new(Reject::new("Ping", "", "second")), None);
+
+        let envelope = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let letter = envelope.msg.as_any().downcast_ref::<DeadLetter>().unwrap();
+        assert_eq!(letter.target, "first");
+        assert_eq!(letter.reason, format!("Retry buffer full, no Ack from {}", endpoint));
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn test_broadcast() {
+        let recorder = ZmqSender::new_test_recorder("tcp://localhost:5001");
+        let result = recorder.broadcast(&["tcp://host1:5001", "tcp://host2:5001"], b"update".to_vec());
+        assert_eq!(result.succeeded, vec!["tcp://host1:5001", "tcp://host2:5001"]);
+        assert!(result.failed.is_empty());
+        assert_eq!(recorder.recorded_sends().len(), 2);
+
+        // Nothing listens on the TCP port, so connecting never completes
+        let config = ZmqSenderConfig::default().with_broadcast_timeout(Duration::from_millis(200));
+        let sender = ZmqSender::with_config("tcp://127.0.0.1:5571", config);
+        let started = Instant::now();
+        let result = sender.broadcast(&["ipc:///nonexistent/actors.sock", "tcp://127.0.0.1:5572"], b"update".to_vec());
+        assert!(result.succeeded.is_empty());
+        assert_eq!(
+            result.failed,
+            vec![
+                ("ipc:///nonexistent/actors.sock".to_string(), SendError::Unreachable),
+                ("tcp://127.0.0.1:5572".to_string(), SendError::Timeout),
+            ]
+        );
+        // The endpoints were tried concurrently
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // Other sends go on while a broadcast waits for a slow endpoint
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let mut pull = PullSocket::new();
+        let endpoint = rt.block_on(pull.bind("tcp://127.0.0.1:0")).unwrap().to_string();
+        let config = ZmqSenderConfig::default().with_broadcast_timeout(Duration::from_secs(1));
+        let sender = ZmqSender::with_config("tcp://127.0.0.1:5571", config);
+        thread::scope(|scope| {
+            let broadcast = scope.spawn(|| sender.broadcast(&["tcp://127.0.0.1:5572"], b"update".to_vec()));
+            thread::sleep(Duration::from_millis(50));
+            sender.multiplex(&endpoint, "sink", 1, b"data".to_vec());
+            let received = rt.block_on(async { tokio::time::timeout(Duration::from_millis(500), pull.recv()).await });
+            assert!(received.unwrap().is_ok());
+            assert!(!broadcast.is_finished());
+        });
+    }
+
+    #[test]
+    fn test_sender_pool_round_robin() {
+        use crate::serialization::register_remote_message;
+
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Arc<ZmqSenderPool>>();
+
+        register_remote_message::<Reject>("Reject");
+        let pool = ZmqSenderPool {
+            senders: (0..3).map(|_| ZmqSender::new_test_recorder("tcp://localhost:5001")).collect(),
+            next: AtomicUsize::new(0),
+        };
+        for _ in 0..6 {
+            pool.send("tcp://localhost:5002", "pong", Box::new(Reject::new("Ping", "busy", "pong")));
+        }
+        for sender in &pool.senders {
+            assert_eq!(sender.recorded_sends().len(), 2);
+        }
+        assert_eq!(ZmqSenderPool::new("tcp://localhost:5001", 0).pool_size(), 1);
+    }
+
+    #[test]
+    fn test_push_sender_round_robin() {
+        use crate::serialization::register_remote_message;
+
+        register_remote_message::<Reject>("Reject");
+        let recorder = ZmqSender::new_test_recorder("");
+        let push = ZmqPushSender::with_sender(recorder, &["tcp://stage2a:5101", "tcp://stage2b:5101"]);
+
+        for _ in 0..3 {
+            push.send("square", Box::new(Reject::new("Ping", "test", "pong")));
+        }
+
+        let sends = push.sender.recorded_sends();
+        let endpoints: Vec<&str> = sends.iter().map(|(endpoint, _)| endpoint.as_str()).collect();
+        assert_eq!(endpoints, ["tcp://stage2a:5101", "tcp://stage2b:5101", "tcp://stage2a:5101"]);
+        let envelope: serde_json::Value = serde_json::from_slice(&sends[0].1).unwrap();
+        assert_eq!(envelope["receiver"], "square");
+        assert!(envelope["sender_endpoint"].is_null());
+    }
+
+    #[test]
+    fn test_dedup_window_evicts_oldest() {
+        let mut dedup = DedupWindow::new(2, None);
+        dedup.insert("a");
+        dedup.insert("b");
+        assert!(dedup.contains("a"));
+
+        dedup.insert("c");
+        assert!(!dedup.contains("a"));
+        assert!(dedup.contains("b"));
+        assert!(dedup.contains("c"));
+    }
+
+    #[test]
+    fn test_dedup_idempotency_keys() {
+        use crate::actor::Envelope;
+        use crate::serialization::register_remote_message;
+
+        register_remote_message::<Reject>("Reject");
+        let config = ZmqSenderConfig::default().with_idempotency_keys(true);
+        let (send_tx, _) = channel::<SenderCommand>();
+        let recorder = Some(Mutex::new(Vec::new()));
+        let sender = Arc::new(ZmqSender::with_channel("tcp://localhost:5579", config, send_tx, recorder));
+        for _ in 0..2 {
+            sender.send_to("tcp://localhost:5579", "sink", Box::new(Reject::new("Ping", "", "")), None);
+        }
+        let sends = sender.recorded_sends();
+        let key = |frame: &[u8]| serde_json::from_slice::<serde_json::Value>(frame).unwrap()["idempotency_key"].clone();
+        assert!(key(&sends[0].1).is_string());
+        assert_ne!(key(&sends[0].1), key(&sends[1].1));
+
+        let config = ZmqReceiverConfig::default().with_dedup(Duration::from_millis(100), 10);
+        let receiver = ZmqReceiver::new_test_injector(sender).with_config(config);
+        let (tx, rx) = channel::<Envelope>();
+        receiver.register("sink", ActorRef::new(tx, "sink".to_string()));
+
+        for frame in [&sends[0].1, &sends[0].1, &sends[1].1] {
+            receiver.inject(frame);
+        }
+        assert_eq!(rx.try_iter().count(), 2);
+        assert_eq!(receiver.duplicates_dropped(), 1);
+
+        // Keys are forgotten once the window has passed
+        thread::sleep(Duration::from_millis(150));
+        receiver.inject(&sends[0].1);
+        assert_eq!(rx.try_iter().count(), 1);
+        assert_eq!(receiver.duplicates_dropped(), 1);
+    }
+
+    #[test]
+    fn test_receiver_topics() {
+        let sender = Arc::new(ZmqSender::new("tcp://0.0.0.0:5559"));
+        let receiver = ZmqReceiver::new("tcp://0.0.0.0:5559", sender)
+            .with_subscriber("tcp://localhost:5560");
+
+        receiver.subscribe_topic("prices");
+        receiver.subscribe_topic("orders");
+        receiver.subscribe_topic("prices");
+        assert_eq!(receiver.topics(), vec!["orders".to_string(), "prices".to_string()]);
+
+        receiver.unsubscribe_topic("prices");
+        receiver.unsubscribe_topic("unknown");
+        assert_eq!(receiver.topics(), vec!["orders".to_string()]);
+    }
+
+    #[test]
+    fn test_receiver_binds_multiple_endpoints() {
+        use crate::serialization::register_remote_message;
+        use std::sync::mpsc::channel;
+
+        register_remote_message::<Reject>("Reject");
+
+        let endpoints = ["tcp://127.0.0.1:5573", "tcp://127.0.0.1:5574"];
+        let sender = Arc::new(ZmqSender::new(endpoints[0]));
+        let receiver = ZmqReceiver::new_multi(&endpoints, Arc::clone(&sender));
+        assert_eq!(receiver.bind_endpoints(), endpoints);
+
+        let (tx, rx) = channel();
+        receiver.register("pong", ActorRef::new(tx, "pong".to_string()));
+        let _handle = receiver.start();
+
+        for endpoint in endpoints {
+            sender.send_to(endpoint, "pong", Box::new(Reject::new("Ping", endpoint, "pong")), None);
+        }
+        let mut reasons: Vec<String> = (0..2)
+            .map(|_| {
+                let envelope = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+                envelope.msg.as_any().downcast_ref::<Reject>().unwrap().reason.clone()
+            })
+            .collect();
+        reasons.sort();
+        assert_eq!(reasons, endpoints);
+    }
+
+    #[test]
+    fn test_stop_drain() {
+        use crate::serialization::register_remote_message;
+        use std::sync::mpsc::channel;
+
+        register_remote_message::<Reject>("Reject");
+
+        let endpoint = "tcp://127.0.0.1:5577";
+        let sender = Arc::new(ZmqSender::new(endpoint));
+        let receiver = ZmqReceiver::new(endpoint, Arc::clone(&sender));
+        let (tx, rx) = channel();
+        receiver.register("pong", ActorRef::new(tx, "pong".to_string()));
+        // Holds all but the first message until a token frees up
+        receiver.set_per_actor_rate_limit_with_burst("pong", 0.1, 100);
+        let mut handle = receiver.start();
+
+        // Wait until the sender is connected
+        sender.send_to(endpoint, "pong", Box::new(Reject::new("Ping", "first", "pong")), None);
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        for _ in 0..20 {
+            sender.send_to(endpoint, "pong", Box::new(Reject::new("Ping", "more", "pong")), None);
+        }
+        let started = Instant::now();
+        let drained = handle.stop_drain(Duration::MAX);
+
+        // Nothing in flight or held is lost, and draining ends once the socket is idle
+        assert!(drained <= 20);
+        assert_eq!(rx.try_iter().count(), 20);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_stop_drain_subscriber() {
+        use crate::serialization::register_remote_message;
+        use std::sync::mpsc::channel;
+
+        register_remote_message::<Reject>("Reject");
+        let sender = Arc::new(ZmqSender::new(""));
+        let publisher = ZmqPublisher::new("tcp://127.0.0.1:5591", Arc::clone(&sender));
+        let receiver = ZmqReceiver::new_sub("tcp://127.0.0.1:5591", sender);
+        let (tx, rx) = channel();
+        receiver.register("pong", ActorRef::new(tx, "pong".to_string()));
+        let mut handle = receiver.start();
+
+        // Messages published before the subscription reaches the publisher are lost
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            publisher.publish("pong", Box::new(Reject::new("Ping", "first", "")), None);
+            if rx.recv_timeout(Duration::from_millis(50)).is_ok() {
+                break;
+            }
+            assert!(Instant::now() < deadline, "no published message arrived");
+        }
+        thread::sleep(Duration::from_millis(100));
+        rx.try_iter().for_each(drop);
+
+        for _ in 0..20 {
+            publisher.publish("pong", Box::new(Reject::new("Ping", "more", "")), None);
+        }
+        let drained = handle.stop_drain(Duration::from_secs(5));
+
+        assert!(drained <= 20);
+        assert_eq!(rx.try_iter().count(), 20);
+    }
+
+    #[test]
+    fn test_streaming_write() {
+        use std::io::Write;
+
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let mut pull = PullSocket::new();
+        let endpoint = rt.block_on(pull.bind("tcp://127.0.0.1:0")).unwrap().to_string();
+
+        let sender = ZmqSender::new("tcp://127.0.0.1:0");
+        let mut writer = sender.streaming_write(&endpoint);
+        let mut recv = || {
+            let message = rt
+                .block_on(async { tokio::time::timeout(Duration::from_secs(5), pull.recv()).await })
+                .unwrap()
+                .unwrap();
+            message.iter().map(|f| f.to_vec()).collect::<Vec<_>>()
+        };
+        // Sent as soon as it is written
+        writer.write_all(b"frame-1").unwrap();
+        assert_eq!(recv(), vec![b"frame-1".to_vec()]);
+        writer.write_all(b"").unwrap();
+        writer.write_all(b"frame-2").unwrap();
+        writer.flush().unwrap();
+        writer.flush().unwrap();
+        assert_eq!(recv(), vec![b"frame-2".to_vec()]);
+        assert_eq!(recv(), vec![Vec::<u8>::new()]);
+
+        // Dropping the writer ends the stream
+        let recorder = ZmqSender::new_test_recorder("tcp://127.0.0.1:0");
+        recorder.streaming_write("tcp://peer:1").write_all(b"tail").unwrap();
+        let peer = "tcp://peer:1".to_string();
+        assert_eq!(recorder.recorded_sends(), vec![(peer.clone(), b"tail".to_vec()), (peer, Vec::new())]);
+    }
+
+    #[test]
+    fn test_reply_routed_to_sender_endpoint() {
+        use crate::actor::{ActorContext, Envelope};
+        use crate::serialization::register_remote_message;
+        use std::sync::mpsc::channel;
+
+        register_remote_message::<Reject>("Reject");
+
+        // Process A: "ping" sends to the remote "pong"
+        let sender_a = Arc::new(ZmqSender::new_test_recorder("tcp://a:1"));
+        let (ping_tx, _ping_rx) = channel::<Envelope>();
+        let ping = ActorRef::new(ping_tx, "ping".to_string());
+        sender_a.remote_ref("pong", "tcp://b:1").send(Box::new(Reject::new("Ping", "", "")), Some(ping));
+
+        let sends = sender_a.recorded_sends();
+        let frame: serde_json::Value = serde_json::from_slice(&sends[0].1).unwrap();
+        assert_eq!(frame["sender_actor"], "ping");
+        assert_eq!(frame["sender_endpoint"], "tcp://a:1");
+
+        // Process B: "pong" replies without knowing where ping lives
+        let sender_b = Arc::new(ZmqSender::new_test_recorder("tcp://b:1"));
+        let receiver_b = ZmqReceiver::new_test_injector(Arc::clone(&sender_b));
+        let (pong_tx, pong_rx) = channel::<Envelope>();
+        receiver_b.register("pong", ActorRef::new(pong_tx, "pong".to_string()));
+        receiver_b.inject(&sends[0].1);
+
+        let envelope = pong_rx.try_recv().unwrap();
+        let mut ctx = ActorContext::new();
+        ctx.prepare_for_envelope(envelope.sender, None);
+        ctx.reply(Box::new(Reject::new("Pong", "", "")));
+
+        let replies = sender_b.recorded_sends();
+        assert_eq!(replies[0].0, "tcp://a:1");
+        let reply: serde_json::Value = serde_json::from_slice(&replies[0].1).unwrap();
+        assert_eq!(reply["receiver"], "ping");
+    }
+
+    #[test]
+    fn test_recorder_and_injector() {
+        use crate::actor::Envelope;
+        use crate::serialization::register_remote_message;
+        use std::sync::mpsc::channel;
+
+        register_remote_message::<Reject>("Reject");
+        let sender = Arc::new(ZmqSender::new_test_recorder("tcp://localhost:5561"));
+        let receiver = ZmqReceiver::new_test_injector(Arc::clone(&sender));
+        let (tx, rx) = channel::<Envelope>();
+        receiver.register("sink", ActorRef::new(tx, "sink".to_string()));
+
+        // Unknown actor: Reject goes back to the sender, and is recorded
+        let frame = serde_json::json!({
+            "sender_actor": "client",
+            "sender_endpoint": "tcp://localhost:5562",
+            "receiver": "missing",
+            "message_type": "Ping",
+            "message": {}
+        });
+        receiver.inject(frame.to_string().as_bytes());
+        receiver.inject(b"not json");
+
+        let sends = sender.recorded_sends();
+        assert_eq!(sends.len(), 1);
+        assert_eq!(sends[0].0, "tcp://localhost:5562");
+        let reply: serde_json::Value = serde_json::from_slice(&sends[0].1).unwrap();
+        assert_eq!(reply["receiver"], "client");
+        assert_eq!(reply["message_type"], "Reject");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_compressed_sends() {
+        use crate::actor::Envelope;
+        use crate::serialization::register_remote_message;
+        use std::sync::mpsc::channel;
+
+        register_remote_message::<Reject>("Reject");
+        let sender = Arc::new(ZmqSender::new_test_recorder("tcp://localhost:5561"));
+        let receiver = ZmqReceiver::new_test_injector(Arc::clone(&sender));
+        let (tx, rx) = channel::<Envelope>();
+        receiver.register("sink", ActorRef::new(tx, "sink".to_string()));
+
+        let reason = "busy ".repeat(1000);
+        for algorithm in [CompressionAlgorithm::Zstd(3), CompressionAlgorithm::Lz4, CompressionAlgorithm::None] {
+            sender.set_compression(algorithm);
+            assert_eq!(sender.compression(), algorithm);
+            sender.send_to("tcp://localhost:5561", "sink", Box::new(Reject::new("Ping", &reason, "pong")), None);
+        }
+
+        let sends = sender.recorded_sends();
+        assert!(sends[0].1.len() < reason.len() / 10);
+        assert!(sends[1].1.len() < reason.len() / 10);
+        assert!(serde_json::from_slice::<serde_json::Value>(&sends[2].1).is_ok());
+        for (_, frame) in &sends {
+            receiver.inject(frame);
+            let envelope = rx.try_recv().unwrap();
+            assert_eq!(envelope.msg.as_any().downcast_ref::<Reject>().unwrap().reason, reason);
+        }
+    }
+
+    #[test]
+    fn test_send_batch() {
+        use crate::actor::Envelope;
+        use crate::serialization::register_remote_message;
+        use std::sync::mpsc::channel;
+
+        register_remote_message::<Reject>("Reject");
+        let sender = Arc::new(ZmqSender::new_test_recorder("tcp://localhost:5582"));
+        sender.set_compression(CompressionAlgorithm::Lz4);
+        let receiver = ZmqReceiver::new_test_injector(Arc::clone(&sender));
+        let (tx, rx) = channel::<Envelope>();
+        receiver.register("sink", ActorRef::new(tx, "sink".to_string()));
+
+        let messages: Vec<(&str, Box<dyn Message>)> = (0..3)
+            .map(|i| ("sink", Box::new(Reject::new("Ping", &i.to_string(), "pong")) as Box<dyn Message>))
+            .chain([("missing", Box::new(Reject::new("Ping", "lost", "pong")) as Box<dyn Message>)])
+            .collect();
+        assert_eq!(sender.send_batch("tcp://localhost:5582", messages, None), Ok(4));
+        assert_eq!(sender.send_batch("tcp://localhost:5582", Vec::new(), None), Ok(0));
+
+        // One frame, routed message by message in order
+        let sends = sender.recorded_sends();
+        assert_eq!(sends.len(), 1);
+        receiver.inject(&sends[0].1);
+        let reasons: Vec<String> = rx
+            .try_iter()
+            .map(|e| e.msg.as_any().downcast_ref::<Reject>().unwrap().reason.clone())
+            .collect();
+        assert_eq!(reasons, ["0", "1", "2"]);
+    }
+
+    #[test]
+    fn test_proto_messages() {
+        use crate::actor::Envelope;
+        use crate::serialization::{register_remote_message, register_remote_message_proto};
+        use std::sync::mpsc::channel;
+
+        #[derive(Clone, PartialEq, prost::Message)]
+        struct Quote {
+            #[prost(string, tag = "1")]
+            symbol: String,
+            #[prost(double, tag = "2")]
+            price: f64,
+        }
+        crate::define_message!(Quote);
+
+        register_remote_message_proto::<Quote>("Quote");
+        register_remote_message::<Reject>("Reject");
+        let sender = Arc::new(ZmqSender::new_test_recorder("tcp://localhost:5584"));
+        sender.sign_for("sink", b"secret");
+        let receiver = ZmqReceiver::new_test_injector(Arc::clone(&sender));
+        receiver.require_auth_for("sink", b"secret");
+        let (tx, rx) = channel::<Envelope>();
+        receiver.register("sink", ActorRef::new(tx, "sink".to_string()));
+
+        let quote = Quote { symbol: "ABC".to_string(), price: 1.5 };
+        sender.send_to("tcp://localhost:5584", "sink", Box::new(quote.clone()), None);
+        sender.send_to("tcp://localhost:5584", "sink", Box::new(Reject::new("Ping", "json", "")), None);
+
+        // Protobuf messages go in a codec frame, JSON ones stay bare on the same endpoint
+        let sends = sender.recorded_sends();
+        assert_eq!(&sends[0].1[..2], &[0x04, 0x01]);
+        assert_eq!(sends[1].1[0], b'{');
+        for (_, frame) in &sends {
+            receiver.inject(frame);
+        }
+        let received: Vec<Envelope> = rx.try_iter().collect();
+        assert_eq!(received[0].msg.as_any().downcast_ref::<Quote>(), Some(&quote));
+        assert_eq!(received[1].msg.as_any().downcast_ref::<Reject>().unwrap().reason, "json");
+
+        // Unsigned, the envelope is built without serializing the message at all
+        let (tx, rx) = channel::<Envelope>();
+        receiver.register("plain", ActorRef::new(tx, "plain".to_string()));
+        sender.send_to("tcp://localhost:5584", "plain", Box::new(quote.clone()), None);
+        let frame = sender.recorded_sends().pop().unwrap().1;
+        receiver.inject(&frame);
+        assert_eq!(rx.try_recv().unwrap().msg.as_any().downcast_ref::<Quote>(), Some(&quote));
+    }
+
+    #[test]
+    fn test_serialization_backends() {
+        use crate::actor::Envelope;
+        use crate::serialization::{register_remote_message, register_remote_message_msgpack};
+        use serde::{Deserialize, Serialize};
+        use std::sync::mpsc::channel;
+
+        #[derive(Serialize, Deserialize, Default, Debug, PartialEq, Clone)]
+        struct Fill {
+            order_id: u64,
+            qty: u32,
+        }
+        crate::define_message!(Fill);
+
+        register_remote_message_msgpack::<Fill>("Fill");
+        register_remote_message::<Reject>("Reject");
+        let mut sender = ZmqSender::new_test_recorder("tcp://localhost:5585");
+        sender.config = ZmqSenderConfig::default().with_serialization(SerializationBackend::Bincode);
+        let sender = Arc::new(sender);
+        sender.sign_for("sink", b"secret");
+        let receiver = ZmqReceiver::new_test_injector(Arc::clone(&sender));
+        receiver.require_auth_for("sink", b"secret");
+        let (tx, rx) = channel::<Envelope>();
+        receiver.register("sink", ActorRef::new(tx, "sink".to_string()));
+
+        let fill = Fill { order_id: 7, qty: 100 };
+        sender.send_to("tcp://localhost:5585", "sink", Box::new(fill.clone()), None);
+        sender.send_to("tcp://localhost:5585", "sink", Box::new(Reject::new("Ping", "bincode", "")), None);
+
+        // MessagePack types keep their codec; others follow the sender's backend
+        let sends = sender.recorded_sends();
+        assert_eq!(&sends[0].1[..2], &[0x04, 0x02]);
+        assert_eq!(&sends[1].1[..2], &[0x04, 0x03]);
+        for (_, frame) in &sends {
+            receiver.inject(frame);
+        }
+        let received: Vec<Envelope> = rx.try_iter().collect();
+        assert_eq!(received[0].msg.as_any().downcast_ref::<Fill>(), Some(&fill));
+        assert_eq!(received[1].msg.as_any().downcast_ref::<Reject>().unwrap().reason, "bincode");
+    }
+
+    #[test]
+    fn test_test_probe() {
+        use crate::messages::Shutdown;
+        use crate::serialization::register_remote_message;
+
+        register_remote_message::<Reject>("Reject");
+        let sender = Arc::new(ZmqSender::new("tcp://127.0.0.1:5575"));
+        let (receiver, probe) = ZmqReceiver::new_test_probe("tcp://127.0.0.1:5575", Arc::clone(&sender));
+        let _handle = receiver.start();
+
+        for reason in ["first", "second"] {
+            sender.send_to("tcp://127.0.0.1:5575", "anyone", Box::new(Reject::new("Ping", reason, "pong")), None);
+        }
+        let reject = probe.expect_message::<Reject>(Duration::from_secs(5)).unwrap();
+        assert_eq!(reject.reason, "first");
+
+        let err = probe.expect_message::<Shutdown>(Duration::from_secs(5)).unwrap_err();
+        assert_eq!(
+            err,
+            ProbeError::WrongType {
+                expected: std::any::type_name::<Shutdown>(),
+                received: std::any::type_name::<Reject>(),
+            }
+        );
+
+        let err = probe.expect_message::<Reject>(Duration::from_millis(50)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("Expected a {} but no message arrived within 50ms", std::any::type_name::<Reject>())
+        );
+    }
+
+    #[test]
+    fn test_publisher_topic_routing() {
+        use crate::actor::Envelope;
+        use crate::serialization::register_remote_message;
+        use std::sync::mpsc::channel;
+
+        register_remote_message::<Reject>("Reject");
+        let sender = Arc::new(ZmqSender::new(""));
+        let publisher = ZmqPublisher::new("tcp://127.0.0.1:5578", Arc::clone(&sender));
+        let receiver = ZmqReceiver::new_sub("tcp://127.0.0.1:5578", sender);
+        let (pong_tx, pong_rx) = channel::<Envelope>();
+        receiver.register("pong", ActorRef::new(pong_tx, "pong".to_string()));
+        let (dead_tx, dead_rx) = channel::<Envelope>();
+        receiver.set_dead_letter_actor(ActorRef::new(dead_tx, "dead".to_string()));
+        assert_eq!(receiver.topics(), vec!["pong\0".to_string()]);
+        assert!(receiver.bind_endpoints().is_empty());
+        let mut handle = receiver.start();
+
+        // Messages published before the subscription reaches the publisher are lost
+        let mut delivered = None;
+        for _ in 0..100 {
+            publisher.publish("pong2", Box::new(Reject::new("Ping", "other", "")), None);
+            publisher.publish("pong", Box::new(Reject::new("Ping", "mine", "")), None);
+            if let Ok(envelope) = pong_rx.recv_timeout(Duration::from_millis(50)) {
+                delivered = Some(envelope);
+                break;
+            }
+        }
+        let envelope = delivered.expect("no published message arrived");
+        assert_eq!(envelope.msg.as_any().downcast_ref::<Reject>().unwrap().reason, "mine");
+
+        // "pong2" is filtered by the publisher, so it never reaches the dead letters
+        thread::sleep(Duration::from_millis(100));
+        assert!(dead_rx.try_recv().is_err());
+        handle.stop();
+    }
+
+    #[test]
+    fn test_multiplexed_channels() {
+        use crate::actor::Envelope;
+        use std::sync::mpsc::channel;
+
+        let sender = Arc::new(ZmqSender::new(""));
+        let receiver = ZmqReceiver::new("tcp://127.0.0.1:5583", Arc::clone(&sender));
+        let (actor_tx, actor_rx) = channel::<Envelope>();
+        receiver.register("renderer", ActorRef::new(actor_tx, "renderer".to_string()));
+        let (log_tx, log_rx) = channel::<Envelope>();
+        receiver.register_channel("renderer", 2, ActorRef::new(log_tx, "log".to_string()));
+        let (dead_tx, dead_rx) = channel::<Envelope>();
+        receiver.set_dead_letter_actor(ActorRef::new(dead_tx, "dead".to_string()));
+        let mut handle = receiver.start();
+        thread::sleep(Duration::from_millis(100));
+
+        let endpoint = "tcp://127.0.0.1:5583";
+        sender.multiplex(endpoint, "renderer", 1, b"quote".to_vec());
+        sender.multiplex(endpoint, "renderer", 2, b"trade".to_vec());
+        sender.multiplex(endpoint, "missing", 1, b"lost".to_vec());
+
+        let data = |rx: &Receiver<Envelope>| {
+            let envelope = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+            let data = envelope.msg.as_any().downcast_ref::<ChannelData>().unwrap();
+            (data.channel_id, data.data.clone())
+        };
+        assert_eq!(data(&actor_rx), (1, b"quote".to_vec()));
+        assert_eq!(data(&log_rx), (2, b"trade".to_vec()));
+        let letter = dead_rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        let letter = letter.msg.as_any().downcast_ref::<RemoteDeadLetter>().unwrap();
+        assert_eq!(letter.original_target, "missing");
+        assert_eq!(letter.payload, b"lost");
+        assert!(actor_rx.try_recv().is_err());
+        handle.stop();
+    }
+
+    #[test]
+    fn test_channel_admission() {
+        use crate::actor::Envelope;
+        use std::sync::mpsc::channel;
+
+        let receiver = ZmqReceiver::new_test_injector(Arc::new(ZmqSender::new_test_recorder("tcp://localhost:5586")));
+        let registry = &receiver.registry;
+        let (dead_tx, dead_rx) = channel::<Envelope>();
+        receiver.set_dead_letter_actor(ActorRef::new(dead_tx, "dead".to_string()));
+        let reasons = || -> Vec<(DeadLetterReason, Vec<u8>)> {
+            dead_rx
+                .try_iter()
+                .map(|e| {
+                    let letter = e.msg.as_any().downcast_ref::<RemoteDeadLetter>().unwrap();
+                    (letter.reason, letter.payload.clone())
+                })
+                .collect()
+        };
+
+        // Channels cannot be signed, so actors requiring signatures get none
+        let (payments_tx, payments_rx) = channel::<Envelope>();
+        receiver.register("payments", ActorRef::new(payments_tx, "payments".to_string()));
+        receiver.require_auth_for("payments", b"secret");
+        assert!(!registry.route_channel("payments", 1, b"forged".to_vec()));
+        assert!(payments_rx.try_recv().is_err());
+        assert_eq!(reasons(), vec![(DeadLetterReason::Unauthenticated, b"forged".to_vec())]);
+
+        // Channel data is rate limited like any sender's messages
+        let (tx, rx) = channel::<Envelope>();
+        receiver.register("renderer", ActorRef::new(tx, "renderer".to_string()));
+        receiver.set_per_actor_rate_limit_with_burst("renderer", 1.0, 1);
+        assert!(registry.route_channel("renderer", 1, b"first".to_vec()));
+        assert!(registry.route_channel("renderer", 1, b"held".to_vec()));
+        assert!(!registry.route_channel("renderer", 1, b"over".to_vec()));
+        assert_eq!(rx.try_iter().count(), 1);
+        assert_eq!(reasons(), vec![(DeadLetterReason::RateLimited, b"over".to_vec())]);
+
+        // Data the mailbox does not take is dead-lettered
+        let (stopped_tx, stopped_rx) = channel::<Envelope>();
+        drop(stopped_rx);
+        receiver.register("stopped", ActorRef::new(stopped_tx, "stopped".to_string()));
+        assert!(!registry.route_channel("stopped", 1, b"late".to_vec()));
+        assert_eq!(reasons(), vec![(DeadLetterReason::ActorStopped, b"late".to_vec())]);
+    }
+
+    #[test]
+    fn test_dead_letter_actor() {
+        use crate::actor::Envelope;
+        use crate::serialization::register_remote_message;
+        use std::sync::mpsc::channel;
+
+        register_remote_message::<Reject>("Reject");
+        let sender = Arc::new(ZmqSender::new_test_recorder("tcp://localhost:5564"));
+        let receiver = ZmqReceiver::new_test_injector(sender);
+        let (tx, rx) = channel::<Envelope>();
+        receiver.set_dead_letter_actor(ActorRef::new(tx, "audit".to_string()));
+
+        let frame = serde_json::json!({
+            "sender_actor": "client",
+            "sender_endpoint": "tcp://localhost:5565",
+            "receiver": "ghost",
+            "message_type": "Ping",
+            "message": {}
+        });
+        receiver.inject(frame.to_string().as_bytes());
+
+        let envelope = rx.try_recv().unwrap();
+        let letter = envelope.msg.as_any().downcast_ref::<RemoteDeadLetter>().unwrap();
+        assert_eq!(letter.original_target, "ghost");
+        assert_eq!(letter.source_endpoint.as_deref(), Some("tcp://localhost:5565"));
+        assert_eq!(letter.reason, DeadLetterReason::UnknownActor);
+        let payload: serde_json::Value = serde_json::from_slice(&letter.payload).unwrap();
+        assert_eq!(payload, frame);
+    }
+
+    #[test]
+    fn test_per_actor_rate_limit() {
+        use crate::actor::Envelope;
+        use crate::serialization::register_remote_message;
+        use std::sync::mpsc::channel;
+
+        register_remote_message::<Reject>("Reject");
+        let sender = Arc::new(ZmqSender::new_test_recorder("tcp://localhost:5568"));
+        let receiver = ZmqReceiver::new_test_injector(Arc::clone(&sender));
+        let (tx, rx) = channel::<Envelope>();
+        receiver.register("pong", ActorRef::new(tx, "pong".to_string()));
+        let (dead_tx, dead_rx) = channel::<Envelope>();
+        receiver.set_dead_letter_actor(ActorRef::new(dead_tx, "audit".to_string()));
+        receiver.set_per_actor_rate_limit_with_burst("pong", 10.0, 1);
+
+        let frame = |endpoint: &str, reason: &str| {
+            serde_json::json!({
+                "sender_actor": "client",
+                "sender_endpoint": endpoint,
+                "receiver": "pong",
+                "message_type": "Reject",
+                "message": {"message_type": "Ping", "reason": reason, "rejected_by": ""}
+            })
+            .to_string()
+        };
+        let reasons = |rx: &Receiver<Envelope>| -> Vec<String> {
+            rx.try_iter()
+                .map(|e| e.msg.as_any().downcast_ref::<Reject>().unwrap().reason.clone())
+                .collect()
+        };
+
+        // A second's worth passes, one more is held, the next one overflows
+        for i in 0..12 {
+            receiver.inject(frame("tcp://localhost:5569", &i.to_string()).as_bytes());
+        }
+        assert_eq!(reasons(&rx), (0..10).map(|i| i.to_string()).collect::<Vec<_>>());
+        let letter = dead_rx.try_recv().unwrap();
+        let letter = letter.msg.as_any().downcast_ref::<RemoteDeadLetter>().unwrap();
+        assert_eq!(letter.reason, DeadLetterReason::RateLimited);
+        assert!(letter.payload.windows(4).any(|w| w == b"\"11\""));
+        assert_eq!(sender.recorded_sends()[0].0, "tcp://localhost:5569");
+
+        // Another sender is not limited; the held message follows once a token frees up
+        thread::sleep(Duration::from_millis(150));
+        receiver.inject(frame("tcp://localhost:5570", "other").as_bytes());
+        assert_eq!(reasons(&rx), vec!["10", "other"]);
+
+        // A held message the actor can no longer take is a dead letter
+        receiver.inject(frame("tcp://localhost:5569", "12").as_bytes());
+        drop(rx);
+        thread::sleep(Duration::from_millis(150));
+        receiver.inject(b"");
+        let letter = dead_rx.try_recv().unwrap();
+        let letter = letter.msg.as_any().downcast_ref::<RemoteDeadLetter>().unwrap();
+        assert_eq!(letter.reason, DeadLetterReason::ActorStopped);
+        assert!(letter.payload.windows(4).any(|w| w == b"\"12\""));
+    }
+
+    #[test]
+    fn test_require_auth_for() {
+        use crate::actor::Envelope;
+        use crate::serialization::register_remote_message;
+        use std::sync::mpsc::channel;
+
+        register_remote_message::<Reject>("Reject");
+        let client = ZmqSender::new_test_recorder("tcp://localhost:5566");
+        client.sign_for("payments", b"secret");
+        for receiver in ["payments", "health"] {
+            client.send_to("tcp://localhost:5567", receiver, Box::new(Reject::new("Ping", "", "")), None);
+        }
+        let signed = &client.recorded_sends()[0].1;
+
+        let sender = Arc::new(ZmqSender::new_test_recorder("tcp://localhost:5567"));
+        let receiver = ZmqReceiver::new_test_injector(Arc::clone(&sender));
+        receiver.require_auth_for("payments", b"secret");
+        let (payments_tx, payments_rx) = channel::<Envelope>();
+        receiver.register("payments", ActorRef::new(payments_tx, "payments".to_string()));
+        let (health_tx, health_rx) = channel::<Envelope>();
+        receiver.register("health", ActorRef::new(health_tx, "health".to_string()));
+
+        // Signed message is delivered
+        receiver.inject(signed);
+        assert!(payments_rx.try_recv().is_ok());
+
+        // Unsigned, wrongly signed and tampered messages are rejected
+        let mut frame: serde_json::Value = serde_json::from_slice(signed).unwrap();
+        frame["sender_actor"] = serde_json::json!("client");
+        frame["sender_endpoint"] = serde_json::json!("tcp://localhost:5566");
+        let mut tampered = frame.clone();
+        tampered["message"]["reason"] = serde_json::json!("changed");
+        let mut unsigned = frame.clone();
+        unsigned.as_object_mut().unwrap().remove("signature");
+        let mut wrong_key = frame.clone();
+        let mac = hmac_sha256::HMAC::mac(signed_content("payments", "Reject", &frame["message"]), b"other");
+        wrong_key["signature"] = serde_json::json!(to_hex(&mac));
+        for frame in [tampered, unsigned, wrong_key] {
+            receiver.inject(frame.to_string().as_bytes());
+        }
+        assert!(payments_rx.try_recv().is_err());
+        let rejects = sender.recorded_sends();
+        assert_eq!(rejects.len(), 3);
+        let reject: serde_json::Value = serde_json::from_slice(&rejects[0].1).unwrap();
+        assert_eq!(reject["receiver"], "client");
+
+        // Actors without a key take unsigned messages
+        receiver.inject(&client.recorded_sends()[1].1);
+        assert!(health_rx.try_recv().is_ok());
+    }
+
+    #[test]
+    fn test_replay_unacked() {
+        use crate::actor::Envelope;
+        use crate::serialization::register_remote_message;
+        use std::sync::mpsc::channel;
+
+        register_remote_message::<Reject>("Reject");
+        let dir = std::env::temp_dir().join(format!("actors-replay-receiver-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let sender = Arc::new(ZmqSender::new_test_recorder("tcp://localhost:5563"));
+        let receiver = ZmqReceiver::new_test_injector(sender);
+        let replay = receiver.enable_replay_log(dir.clone(), 1).unwrap();
+
+        // Arrives before the actor is registered: logged but not acked
+        let frame = serde_json::json!({
+            "receiver": "late",
+            "message_type": "Reject",
+            "message": Reject::new("Ping", "test", "late")
+        });
+        receiver.inject(frame.to_string().as_bytes());
+
+        let (tx, rx) = channel::<Envelope>();
+        receiver.register("late", ActorRef::new(tx, "late".to_string()));
+        assert_eq!(replay.replay_unacked(std::time::UNIX_EPOCH).unwrap(), 1);
+        assert!(rx.try_recv().is_ok());
+
+        // Now acked, so nothing more to replay
+        assert_eq!(replay.replay_unacked(std::time::UNIX_EPOCH).unwrap(), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_replay_after_full_mailbox() {
+        use crate::actor::ActorRuntime;
+        use crate::serialization::register_remote_message;
+
+        struct Sink;
+        impl crate::Actor for Sink {}
+
+        register_remote_message::<Reject>("Reject");
+        let dir = std::env::temp_dir().join(format!("actors-replay-full-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let sender = Arc::new(ZmqSender::new_test_recorder("tcp://localhost:5564"));
+        let receiver = ZmqReceiver::new_test_injector(sender);
+        let replay = receiver.enable_replay_log(dir.clone(), 1).unwrap();
+        let runtime = ActorRuntime::new("busy".to_string(), Box::new(Sink));
+        runtime.metrics.set_mailbox_capacity(Some(1));
+        receiver.register("busy", runtime.get_ref());
+
+        let frame = |n: u32| {
+            serde_json::json!({
+                "receiver": "busy",
+                "message_type": "Reject",
+                "message": Reject::new("Ping", &n.to_string(), "busy")
+            })
+        };
+        receiver.inject(frame(1).to_string().as_bytes());
+        receiver.inject(frame(2).to_string().as_bytes());
+
+        // The second is marked full and kept for replay
+        let log = std::fs::read_to_string(dir.join("replay.log")).unwrap();
+        assert_eq!(log.lines().filter(|line| line.contains("\"ack\"")).count(), 1);
+        assert_eq!(log.lines().filter(|line| line.contains("\"full\"")).count(), 1);
+
+        // Still full: stays in the log
+        assert_eq!(replay.replay_unacked(std::time::UNIX_EPOCH).unwrap(), 0);
+
+        // Once there is room it is delivered, and acked
+        runtime.receiver.recv().unwrap();
+        runtime.metrics.record_dequeued();
+        assert_eq!(replay.replay_unacked(std::time::UNIX_EPOCH).unwrap(), 1);
+        let replayed = runtime.receiver.try_recv().unwrap();
+        assert_eq!(replayed.msg.as_any().downcast_ref::<Reject>().unwrap().reason, "2");
+        assert_eq!(replay.replay_unacked(std::time::UNIX_EPOCH).unwrap(), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_actor_registry() {
         use std::sync::mpsc::channel;
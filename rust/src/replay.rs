@@ -0,0 +1,230 @@
+/*
+
+THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
+
+Copyright 2025 Vincent Maciejewski, & M2 Tech
+Contact:
+v@m2te.ch
+mayeski@gmail.com
+https://www.linkedin.com/in/vmayeski/
+http://m2te.ch/
+
+*/
+
+//! Write-ahead replay log for messages received by a ZmqReceiver.
+//!
+//! Every incoming envelope is appended to the log before it is routed and
+//! marked acked once the target actor's mailbox accepts it, or marked full
+//! if the mailbox was full. Envelopes that were never accepted can be
+//! re-injected with `ReplayHandle::replay_unacked`.
+//!
+//! The log is a pair of JSON-lines files in the log directory:
+//! `replay.log` (current) and `replay.log.1` (previous). When the current
+//! file reaches half of `max_size_mb` it replaces the previous one, so the
+//! log never uses more than `max_size_mb` on disk.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CURRENT_FILE: &str = "replay.log";
+const PREVIOUS_FILE: &str = "replay.log.1";
+
+/// A logged envelope that has not been acked.
+#[derive(Debug, Clone)]
+pub(crate) struct UnackedEntry {
+    pub(crate) seq: u64,
+    pub(crate) envelope: serde_json::Value,
+}
+
+/// Append-only log of received envelopes and their acks.
+pub(crate) struct ReplayLog {
+    dir: PathBuf,
+    file: File,
+    file_size: u64,
+    /// Size at which the current file is rotated
+    rotate_at: u64,
+    next_seq: u64,
+}
+
+impl ReplayLog {
+    /// Open (or create) the log in `dir`.
+    pub(crate) fn open(dir: &Path, max_size_mb: u64) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+
+        // Continue numbering after anything already in the log
+        let next_seq = read_records(dir)?
+            .iter()
+            .filter_map(|r| r.get("seq").and_then(|v| v.as_u64()))
+            .max()
+            .map_or(1, |seq| seq + 1);
+
+        let file = open_append(&dir.join(CURRENT_FILE))?;
+        let file_size = file.metadata()?.len();
+
+        Ok(ReplayLog {
+            dir: dir.to_path_buf(),
+            file,
+            file_size,
+            rotate_at: (max_size_mb * 1024 * 1024 / 2).max(1),
+            next_seq,
+        })
+    }
+
+    /// Record a received envelope, returning its sequence number.
+    pub(crate) fn append(&mut self, envelope: &serde_json::Value) -> io::Result<u64> {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        self.write_record(&serde_json::json!({
+            "seq": seq,
+            "timestamp_ms": timestamp_ms,
+            "envelope": envelope
+        }))?;
+        Ok(seq)
+    }
+
+    /// Mark an envelope as accepted by its actor's mailbox.
+    pub(crate) fn ack(&mut self, seq: u64) -> io::Result<()> {
+        self.write_record(&serde_json::json!({ "ack": seq }))
+    }
+
+    /// Mark an envelope as turned away by its actor's full mailbox. It stays
+    /// unacked, so `unacked_since` still returns it.
+    pub(crate) fn mark_full(&mut self, seq: u64) -> io::Result<()> {
+        self.write_record(&serde_json::json!({ "full": seq }))
+    }
+
+    /// Envelopes logged at or after `from` that were never acked, oldest first.
+    pub(crate) fn unacked_since(&self, from: SystemTime) -> io::Result<Vec<UnackedEntry>> {
+        let from_ms = from.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+
+        let mut pending = BTreeMap::new();
+        let mut acked = HashSet::new();
+        for record in read_records(&self.dir)? {
+            if let Some(seq) = record.get("ack").and_then(|v| v.as_u64()) {
+                acked.insert(seq);
+            } else if let Some(seq) = record.get("seq").and_then(|v| v.as_u64()) {
+                let timestamp_ms = record.get("timestamp_ms").and_then(|v| v.as_u64()).unwrap_or(0);
+                if timestamp_ms >= from_ms {
+                    pending.insert(seq, record["envelope"].clone());
+                }
+            }
+        }
+
+        Ok(pending
+            .into_iter()
+            .filter(|(seq, _)| !acked.contains(seq))
+            .map(|(seq, envelope)| UnackedEntry { seq, envelope })
+            .collect())
+    }
+
+    fn write_record(&mut self, record: &serde_json::Value) -> io::Result<()> {
+        let mut line = record.to_string();
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.file.flush()?;
+        self.file_size += line.len() as u64;
+
+        if self.file_size >= self.rotate_at {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+
+    /// Replace the previous file with the current one and start a new current file.
+    fn rotate(&mut self) -> io::Result<()> {
+        fs::rename(self.dir.join(CURRENT_FILE), self.dir.join(PREVIOUS_FILE))?;
+        self.file = open_append(&self.dir.join(CURRENT_FILE))?;
+        self.file_size = 0;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// All records in the log, previous file first. Unparseable lines are skipped.
+fn read_records(dir: &Path) -> io::Result<Vec<serde_json::Value>> {
+    let mut records = Vec::new();
+    for name in [PREVIOUS_FILE, CURRENT_FILE] {
+        let file = match File::open(dir.join(name)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        for line in BufReader::new(file).lines() {
+            if let Ok(record) = serde_json::from_str(&line?) {
+                records.push(record);
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Log shared between a ZmqReceiver's threads and its ReplayHandle.
+pub(crate) type SharedReplayLog = Arc<Mutex<ReplayLog>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("actors-replay-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_unacked_entries() {
+        let dir = temp_dir("unacked");
+        let mut log = ReplayLog::open(&dir, 1).unwrap();
+
+        let first = log.append(&serde_json::json!({"receiver": "a"})).unwrap();
+        let second = log.append(&serde_json::json!({"receiver": "b"})).unwrap();
+        log.ack(first).unwrap();
+        log.mark_full(second).unwrap();
+
+        let unacked = log.unacked_since(UNIX_EPOCH).unwrap();
+        assert_eq!(unacked.len(), 1);
+        assert_eq!(unacked[0].seq, second);
+        assert_eq!(unacked[0].envelope["receiver"], "b");
+
+        let later = SystemTime::now() + Duration::from_secs(60);
+        assert!(log.unacked_since(later).unwrap().is_empty());
+
+        // Reopening continues the sequence
+        drop(log);
+        let mut log = ReplayLog::open(&dir, 1).unwrap();
+        assert_eq!(log.append(&serde_json::json!({})).unwrap(), second + 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rotation_keeps_previous_file() {
+        let dir = temp_dir("rotate");
+        let mut log = ReplayLog::open(&dir, 1).unwrap();
+        log.rotate_at = 64;
+
+        for i in 0..4 {
+            log.append(&serde_json::json!({ "n": i })).unwrap();
+        }
+        assert!(dir.join(PREVIOUS_FILE).exists());
+
+        // Records from the previous file are still replayable
+        let unacked = log.unacked_since(UNIX_EPOCH).unwrap();
+        assert!(unacked.len() >= 2);
+        assert_eq!(unacked.last().unwrap().envelope["n"], 3);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
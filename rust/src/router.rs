@@ -0,0 +1,337 @@
+/*
+
+THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
+
+Copyright 2025 Vincent Maciejewski, & M2 Tech
+Contact:
+v@m2te.ch
+mayeski@gmail.com
+https://www.linkedin.com/in/vmayeski/
+http://m2te.ch/
+
+*/
+
+//! Routers: actors that spread the messages they receive over other actors.
+//!
+//! A router forwards each message with its sender, so routees reply to the
+//! original sender directly. It watches its routees and drops them from the
+//! rotation when they stop.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+
+use crate::actor::{Actor, ActorContext, ActorRef, PeekResult};
+use crate::define_message;
+use crate::messages::Start;
+use crate::watch::Terminated;
+use crate::Message;
+
+/// Points each routee gets on a consistent-hash ring
+const VIRTUAL_NODES: u32 = 100;
+
+/// Computes the key a consistent-hash router routes a message by
+pub type KeyExtractor = Box<dyn Fn(&dyn Message) -> u64 + Send>;
+
+/// Adds a routee to a router's rotation, see `RouterActor::add_routee`
+pub struct AddRoutee {
+    pub routee: ActorRef,
+}
+define_message!(AddRoutee);
+
+/// Removes a routee from a router's rotation, see `RouterActor::remove_routee`
+pub struct RemoveRoutee {
+    pub routee: ActorRef,
+}
+define_message!(RemoveRoutee);
+
+/// How a router picks the routee for a message
+enum Strategy {
+    RoundRobin {
+        /// Index of the routee for the next message
+        next: usize,
+    },
+    ConsistentHash {
+        key_extractor: KeyExtractor,
+        /// Virtual node hash -> index of its routee
+        ring: BTreeMap<u64, usize>,
+    },
+}
+
+/// Forwards every message it receives to one of its routees, chosen in turn
+/// (`round_robin`) or by a key of the message (`consistent_hash`).
+///
+/// Messages received while there are no routees go to the dead letter queue.
+///
+/// # Example
+/// ```
+/// # use actors::{define_message, ActorRef, Manager, RouterActor};
+/// # struct Job { id: u32 }
+/// # define_message!(Job);
+/// # fn example(mgr: &mut Manager, worker1: ActorRef, worker2: ActorRef, worker3: ActorRef, worker4: ActorRef) {
+/// let workers = vec![worker1, worker2, worker3];
+/// let router = mgr.manage_router("workers", workers);
+///
+/// router.send(Box::new(Job { id: 1 }), None); // to worker1
+/// router.send(Box::new(Job { id: 2 }), None); // to worker2
+///
+/// router.send(RouterActor::add_routee(worker4), None);
+/// # }
+/// ```
+pub struct RouterActor {
+    name: String,
+    routees: Vec<ActorRef>,
+    strategy: Strategy,
+}
+
+impl RouterActor {
+    /// Create a router that sends each message to the next of `routees`.
+    pub fn round_robin(name: &str, routees: Vec<ActorRef>) -> Box<dyn Actor> {
+        Box::new(RouterActor {
+            name: name.to_string(),
+            routees,
+            strategy: Strategy::RoundRobin { next: 0 },
+        })
+    }
+
+    /// Create a router that sends all messages with the same key to the same
+    /// routee, e.g. to keep per-user state in one worker.
+    ///
+    /// `key_extractor` computes the key of each message. Routees are placed
+    /// on a hash ring at several points each; a message goes to the routee
+    /// at the first point at or after its key. When a routee is removed or
+    /// stops, only its keys move, each to the next live routee on the ring.
+    ///
+    /// # Example
+    /// ```
+    /// # use actors::{define_message, ActorRef, Manager, RouterActor, ThreadConfig};
+    /// # struct UserEvent { user_id: u64 }
+    /// # define_message!(UserEvent);
+    /// # fn example(mgr: &mut Manager, workers: Vec<ActorRef>) {
+    /// let router = RouterActor::consistent_hash("sessions", workers, |msg| {
+    ///     msg.as_any().downcast_ref::<UserEvent>().map_or(0, |e| e.user_id)
+    /// });
+    /// let router_ref = mgr.manage("sessions", router, ThreadConfig::default());
+    /// # }
+    /// ```
+    pub fn consistent_hash<F>(name: &str, routees: Vec<ActorRef>, key_extractor: F) -> Box<dyn Actor>
+    where
+        F: Fn(&dyn Message) -> u64 + Send + 'static,
+    {
+        let mut router = RouterActor {
+            name: name.to_string(),
+            routees,
+            strategy: Strategy::ConsistentHash {
+                key_extractor: Box::new(key_extractor),
+                ring: BTreeMap::new(),
+            },
+        };
+        router.rebuild_ring();
+        Box::new(router)
+    }
+
+    /// Message adding `routee` to a router's rotation
+    pub fn add_routee(routee: ActorRef) -> Box<dyn Message> {
+        Box::new(AddRoutee { routee })
+    }
+
+    /// Message removing `routee` from a router's rotation
+    pub fn remove_routee(routee: ActorRef) -> Box<dyn Message> {
+        Box::new(RemoveRoutee { routee })
+    }
+
+    fn add(&mut self, routee: ActorRef) -> bool {
+        if self.routees.iter().any(|r| r.name() == routee.name()) {
+            return false;
+        }
+        self.routees.push(routee);
+        self.rebuild_ring();
+        true
+    }
+
+    fn remove(&mut self, name: &str) {
+        if let Some(index) = self.routees.iter().position(|r| r.name() == name) {
+            self.routees.remove(index);
+            if let Strategy::RoundRobin { next } = &mut self.strategy {
+                if index < *next {
+                    *next -= 1;
+                }
+            }
+            self.rebuild_ring();
+        }
+    }
+
+    /// Place each routee on the ring, at points derived from its name so
+    /// they do not depend on the order routees were added in
+    fn rebuild_ring(&mut self) {
+        let Strategy::ConsistentHash { ring, .. } = &mut self.strategy else {
+            return;
+        };
+        ring.clear();
+        for (index, routee) in self.routees.iter().enumerate() {
+            for point in 0..VIRTUAL_NODES {
+                let mut hasher = DefaultHasher::new();
+                (routee.name(), point).hash(&mut hasher);
+                ring.insert(hasher.finish(), index);
+            }
+        }
+    }
+
+    /// Whether `msg` is for the router itself rather than its routees
+    fn is_own(msg: &dyn Message) -> bool {
+        let msg = msg.as_any();
+        msg.is::<Start>() || msg.is::<Terminated>() || msg.is::<AddRoutee>() || msg.is::<RemoveRoutee>()
+    }
+}
+
+impl Actor for RouterActor {
+    fn pre_start(&mut self, ctx: &mut ActorContext) {
+        for routee in &self.routees {
+            ctx.watch(routee.clone());
+        }
+    }
+
+    fn peek(&self, msg: &dyn Message) -> PeekResult {
+        if self.routees.is_empty() && !Self::is_own(msg) {
+            return PeekResult::Reject { reason: format!("router '{}' has no routees", self.name) };
+        }
+        PeekResult::Accept
+    }
+
+    fn route(&mut self, msg: &dyn Message) -> Option<ActorRef> {
+        if Self::is_own(msg) || self.routees.is_empty() {
+            return None;
+        }
+        // A routee may stop before its Terminated arrives; skip it
+        match &mut self.strategy {
+            Strategy::RoundRobin { next } => {
+                for _ in 0..self.routees.len() {
+                    *next %= self.routees.len();
+                    let routee = &self.routees[*next];
+                    *next += 1;
+                    if routee.is_alive() {
+                        return Some(routee.clone());
+                    }
+                }
+            }
+            Strategy::ConsistentHash { key_extractor, ring } => {
+                let key = key_extractor(msg);
+                let clockwise = ring.range(key..).chain(ring.range(..key));
+                if let Some(&index) = clockwise.map(|(_, index)| index).find(|&&i| self.routees[i].is_alive()) {
+                    return Some(self.routees[index].clone());
+                }
+            }
+        }
+        // All stopped: the message goes to the dead letter queue
+        self.routees.first().cloned()
+    }
+
+    fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+        if let Some(add) = msg.as_any().downcast_ref::<AddRoutee>() {
+            if self.add(add.routee.clone()) {
+                ctx.watch(add.routee.clone());
+            }
+        } else if let Some(remove) = msg.as_any().downcast_ref::<RemoveRoutee>() {
+            self.remove(remove.routee.name());
+            ctx.unwatch(&remove.routee);
+        } else if let Some(terminated) = msg.as_any().downcast_ref::<Terminated>() {
+            self.remove(terminated.who.name());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::{channel, Receiver};
+
+    use crate::actor::{ActorRuntime, Envelope};
+    use crate::messages::{Shutdown, Timeout};
+
+    fn routee(name: &str) -> (ActorRef, Receiver<Envelope>) {
+        let (tx, rx) = channel();
+        (ActorRef::new(tx, name.to_string()), rx)
+    }
+
+    fn received(rx: &Receiver<Envelope>) -> Vec<u64> {
+        rx.try_iter()
+            .filter_map(|e| e.msg.as_any().downcast_ref::<Timeout>().map(|t| t.id))
+            .collect()
+    }
+
+    #[test]
+    fn test_round_robin_membership() {
+        let (a, a_rx) = routee("a");
+        let (b, b_rx) = routee("b");
+        let (c, c_rx) = routee("c");
+        let mut runtime = ActorRuntime::new("router".to_string(), RouterActor::round_robin("router", vec![a, b.clone()]));
+        let router = runtime.get_ref();
+
+        for id in 1..=3 {
+            router.send(Box::new(Timeout::new(id)), None);
+        }
+        router.send(RouterActor::add_routee(c), None);
+        router.send(RouterActor::remove_routee(b), None);
+        for id in 4..=6 {
+            router.send(Box::new(Timeout::new(id)), None);
+        }
+        router.send(Box::new(Shutdown), None);
+        runtime.run();
+
+        assert_eq!(received(&a_rx), vec![1, 3, 5]);
+        assert_eq!(received(&b_rx), vec![2]);
+        assert_eq!(received(&c_rx), vec![4, 6]);
+    }
+
+    #[test]
+    fn test_consistent_hash_is_sticky() {
+        let (a, a_rx) = routee("a");
+        let (b, b_rx) = routee("b");
+        let (c, c_rx) = routee("c");
+        let by_id = |msg: &dyn Message| msg.as_any().downcast_ref::<Timeout>().map_or(0, |t| t.id);
+        let router = RouterActor::consistent_hash("router", vec![a, b.clone(), c], by_id);
+        let mut runtime = ActorRuntime::new("router".to_string(), router);
+        let router = runtime.get_ref();
+
+        // Spread keys over the ring, each sent twice
+        let keys: Vec<u64> = (0..30).map(|i| i * (u64::MAX / 30)).collect();
+        for &key in keys.iter().chain(&keys) {
+            router.send(Box::new(Timeout::new(key)), None);
+        }
+        router.send(RouterActor::remove_routee(b), None);
+        for &key in &keys {
+            router.send(Box::new(Timeout::new(key)), None);
+        }
+        router.send(Box::new(Shutdown), None);
+        runtime.run();
+
+        let (a_keys, b_keys, c_keys) = (received(&a_rx), received(&b_rx), received(&c_rx));
+        assert!(!b_keys.is_empty());
+        assert_eq!(b_keys.len() % 2, 0);
+        // Keys that were on a or c stay there; only b's keys move
+        for key in keys {
+            let on_a = a_keys.iter().filter(|&&k| k == key).count();
+            let on_c = c_keys.iter().filter(|&&k| k == key).count();
+            if b_keys.contains(&key) {
+                assert_eq!(on_a + on_c, 1);
+            } else {
+                assert!(on_a == 3 || on_c == 3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_forward_keeps_sender() {
+        let (a, a_rx) = routee("a");
+        let (client, _client_rx) = routee("client");
+        let mut runtime = ActorRuntime::new("router".to_string(), RouterActor::round_robin("router", vec![a]));
+        let router = runtime.get_ref();
+
+        router.send(Box::new(Timeout::new(1)), Some(client));
+        router.send(Box::new(Shutdown), None);
+        runtime.run();
+
+        let forwarded = a_rx.try_recv().unwrap();
+        assert_eq!(forwarded.sender.unwrap().name(), "client");
+    }
+}
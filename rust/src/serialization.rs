@@ -13,8 +13,8 @@ http://m2te.ch/
 
 //! Message serialization for remote communication.
 //!
-//! Provides a registry for serializing/deserializing messages to JSON
-//! for transmission over ZMQ.
+//! Provides a registry for serializing/deserializing messages to JSON, or
+//! to Protocol Buffers, MessagePack or Bincode, for transmission over ZMQ.
 
 use std::any::TypeId;
 use std::collections::HashMap;
@@ -24,18 +24,31 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::Value;
 
+use crate::codec::{from_hex, to_hex, Codec};
 use crate::Message;
 
 /// Function type for serializing a message to JSON
 type SerializeFn = Box<dyn Fn(&dyn Message) -> Value + Send + Sync>;
 
-/// Function type for deserializing JSON to a message
-type DeserializeFn = Box<dyn Fn(Value) -> Box<dyn Message> + Send + Sync>;
+/// Function type for deserializing JSON to a message, failing with the reason
+type DeserializeFn = Box<dyn Fn(Value) -> Result<Box<dyn Message>, String> + Send + Sync>;
+
+/// Function type for encoding a message with a codec
+type EncodeFn = Box<dyn Fn(&dyn Message, Codec) -> Option<Vec<u8>> + Send + Sync>;
+
+/// Function type for decoding a message encoded with a codec to its
+/// serialized form and the message itself
+type DecodeFn = Box<dyn Fn(&[u8], Codec) -> Option<(Value, Box<dyn Message>)> + Send + Sync>;
 
 /// Registry entry containing serialize/deserialize functions
 struct RegistryEntry {
     serialize: SerializeFn,
     deserialize: DeserializeFn,
+    encode: EncodeFn,
+    decode: DecodeFn,
+    /// Codec the message is always sent with, instead of the sender's
+    /// `SerializationBackend`
+    codec: Option<Codec>,
 }
 
 /// Global message registry (type name -> entry)
@@ -64,33 +77,139 @@ pub fn register_remote_message<M>(type_name: &str)
 where
     M: Message + Serialize + DeserializeOwned + 'static,
 {
-    // Register TypeId -> name mapping for runtime lookup
-    {
-        let mut typeid_map = TYPEID_TO_NAME.lock().unwrap();
-        let map = typeid_map.get_or_insert_with(HashMap::new);
-        map.insert(TypeId::of::<M>(), type_name.to_string());
-    }
+    register::<M>(type_name, serde_entry::<M>(None));
+}
 
-    let mut reg = REGISTRY.lock().unwrap();
-    let map = reg.get_or_insert_with(HashMap::new);
+/// Register a message type for remote serialization with MessagePack.
+///
+/// Messages are always sent as MessagePack, whatever the sender's
+/// `SerializationBackend`, which is smaller and faster to parse than JSON.
+/// Both ends must register the type; peers in other languages only read
+/// JSON messages.
+///
+/// # Example
+/// ```rust
+/// use serde::{Serialize, Deserialize};
+/// use actors::{define_message, register_remote_message_msgpack};
+///
+/// #[derive(Serialize, Deserialize, Default)]
+/// struct Quote { symbol: String, price: f64 }
+/// define_message!(Quote);
+///
+/// register_remote_message_msgpack::<Quote>("Quote");
+/// ```
+pub fn register_remote_message_msgpack<M>(type_name: &str)
+where
+    M: Message + Serialize + DeserializeOwned + Default + 'static,
+{
+    register::<M>(type_name, serde_entry::<M>(Some(Codec::MessagePack)));
+}
 
+/// Registry entry of a serde message type, serialized to JSON and sent
+/// with `codec` or the sender's backend.
+fn serde_entry<M>(codec: Option<Codec>) -> RegistryEntry
+where
+    M: Message + Serialize + DeserializeOwned + 'static,
+{
     let serialize: SerializeFn = Box::new(move |msg: &dyn Message| {
         let typed = msg.as_any().downcast_ref::<M>().expect("Type mismatch in serialize");
         serde_json::to_value(typed).expect("Failed to serialize message")
     });
 
     let deserialize: DeserializeFn = Box::new(|val: Value| {
-        let msg: M = serde_json::from_value(val).expect("Failed to deserialize message");
-        Box::new(msg)
+        let msg: M = serde_json::from_value(val).map_err(|e| format!("Invalid message: {}", e))?;
+        Ok(Box::new(msg))
     });
 
-    map.insert(
-        type_name.to_string(),
-        RegistryEntry {
-            serialize,
-            deserialize,
-        },
-    );
+    let encode: EncodeFn = Box::new(|msg: &dyn Message, codec: Codec| {
+        let typed = msg.as_any().downcast_ref::<M>()?;
+        match codec {
+            Codec::Json => serde_json::to_vec(typed).ok(),
+            Codec::MessagePack => rmp_serde::to_vec_named(typed).ok(),
+            Codec::Bincode => bincode::serialize(typed).ok(),
+            Codec::Protobuf => None,
+        }
+    });
+
+    let decode: DecodeFn = Box::new(|bytes: &[u8], codec: Codec| {
+        let typed: M = match codec {
+            Codec::Json => serde_json::from_slice(bytes).ok()?,
+            Codec::MessagePack => rmp_serde::from_slice(bytes).ok()?,
+            Codec::Bincode => bincode::deserialize(bytes).ok()?,
+            Codec::Protobuf => return None,
+        };
+        Some((serde_json::to_value(&typed).ok()?, Box::new(typed)))
+    });
+
+    RegistryEntry { serialize, deserialize, encode, decode, codec }
+}
+
+/// Register a Protocol Buffers message type for remote serialization.
+///
+/// Messages are encoded with prost instead of JSON, which is smaller and
+/// faster for performance-critical paths. They are sent in a codec frame
+/// tagged as protobuf, so JSON and protobuf senders can share a receiver;
+/// both ends must register the type with this function. Peers in other
+/// languages only read JSON messages.
+///
+/// # Example
+/// ```rust
+/// use actors::{define_message, register_remote_message_proto};
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// struct Quote {
+///     #[prost(string, tag = "1")]
+///     symbol: String,
+///     #[prost(double, tag = "2")]
+///     price: f64,
+/// }
+/// define_message!(Quote);
+///
+/// register_remote_message_proto::<Quote>("Quote");
+/// ```
+pub fn register_remote_message_proto<M>(type_name: &str)
+where
+    M: Message + prost::Message + Default + 'static,
+{
+    let serialize: SerializeFn = Box::new(move |msg: &dyn Message| {
+        let typed = msg.as_any().downcast_ref::<M>().expect("Type mismatch in serialize");
+        Value::String(to_hex(&typed.encode_to_vec()))
+    });
+
+    let deserialize: DeserializeFn = Box::new(|val: Value| {
+        let bytes = val.as_str().and_then(from_hex).ok_or_else(|| "Invalid message: not hex".to_string())?;
+        let msg = M::decode(bytes.as_slice()).map_err(|e| format!("Invalid message: {}", e))?;
+        Ok(Box::new(msg))
+    });
+
+    let encode: EncodeFn = Box::new(|msg: &dyn Message, codec: Codec| match codec {
+        Codec::Protobuf => Some(msg.as_any().downcast_ref::<M>()?.encode_to_vec()),
+        _ => None,
+    });
+
+    // Only bytes that decode as M are accepted, for receivers trying codecs in turn
+    let decode: DecodeFn = Box::new(|bytes: &[u8], codec: Codec| match codec {
+        Codec::Protobuf => {
+            let typed = M::decode(bytes).ok()?;
+            Some((Value::String(to_hex(bytes)), Box::new(typed)))
+        }
+        _ => None,
+    });
+
+    let codec = Some(Codec::Protobuf);
+    register::<M>(type_name, RegistryEntry { serialize, deserialize, encode, decode, codec });
+}
+
+fn register<M: 'static>(type_name: &str, entry: RegistryEntry) {
+    // Register TypeId -> name mapping for runtime lookup
+    {
+        let mut typeid_map = TYPEID_TO_NAME.lock().unwrap();
+        let map = typeid_map.get_or_insert_with(HashMap::new);
+        map.insert(TypeId::of::<M>(), type_name.to_string());
+    }
+
+    let mut reg = REGISTRY.lock().unwrap();
+    reg.get_or_insert_with(HashMap::new).insert(type_name.to_string(), entry);
 }
 
 /// Get the registered type name for a message by its TypeId.
@@ -120,19 +239,21 @@ pub fn serialize_message(msg: &dyn Message, type_name: &str) -> Value {
 
 /// Deserialize a message from JSON using the registry.
 ///
-/// Panics if the message type is not registered.
+/// Panics if the message type is not registered or `value` is not a valid
+/// message of that type (see `try_deserialize_message`).
 pub fn deserialize_message(type_name: &str, value: Value) -> Box<dyn Message> {
     let reg = REGISTRY.lock().unwrap();
     let map = reg.as_ref().expect("No messages registered");
     let entry = map
         .get(type_name)
         .unwrap_or_else(|| panic!("Message type '{}' not registered", type_name));
-    (entry.deserialize)(value)
+    (entry.deserialize)(value).expect("Failed to deserialize message")
 }
 
 /// Try to deserialize a message from JSON using the registry.
 ///
-/// Returns Err with the reason if deserialization fails.
+/// Returns Err with the reason if the type is not registered or `value` is
+/// not a valid message of that type.
 pub fn try_deserialize_message(type_name: &str, value: Value) -> Result<Box<dyn Message>, String> {
     let reg = REGISTRY.lock().unwrap();
     let map = match reg.as_ref() {
@@ -145,8 +266,36 @@ pub fn try_deserialize_message(type_name: &str, value: Value) -> Result<Box<dyn
         None => return Err(format!("Unknown message type: {}", type_name)),
     };
 
-    // Try deserialization (currently panics on parse errors, could be improved)
-    Ok((entry.deserialize)(value))
+    (entry.deserialize)(value)
+}
+
+/// The codec messages of a registered type are always sent with, if any.
+pub(crate) fn message_codec(type_name: &str) -> Option<Codec> {
+    let reg = REGISTRY.lock().unwrap();
+    reg.as_ref()
+        .and_then(|map| map.get(type_name))
+        .and_then(|entry| entry.codec)
+}
+
+/// Encode a message of a registered type with `codec`.
+///
+/// Returns None if the type is not registered, `msg` is not of that type or
+/// cannot be sent with `codec`.
+pub(crate) fn encode_message(type_name: &str, codec: Codec, msg: &dyn Message) -> Option<Vec<u8>> {
+    let reg = REGISTRY.lock().unwrap();
+    let entry = reg.as_ref()?.get(type_name)?;
+    (entry.encode)(msg, codec)
+}
+
+/// Decode a message of a registered type encoded with `codec`, to its
+/// serialized form and the message itself.
+///
+/// Returns None if the type is not registered or `bytes` is not a valid
+/// message of that type.
+pub(crate) fn decode_message(type_name: &str, codec: Codec, bytes: &[u8]) -> Option<(Value, Box<dyn Message>)> {
+    let reg = REGISTRY.lock().unwrap();
+    let entry = reg.as_ref()?.get(type_name)?;
+    (entry.decode)(bytes, codec)
 }
 
 /// Check if a message type is registered.
@@ -201,6 +350,15 @@ mod tests {
         assert_eq!(typed.name, "hello");
     }
 
+    #[test]
+    fn test_try_deserialize_malformed() {
+        register_remote_message::<TestMsg>("TestMsg");
+
+        let json = serde_json::json!({"value": "not a number"});
+        assert!(try_deserialize_message("TestMsg", json).is_err());
+        assert!(try_deserialize_message("NotRegistered", serde_json::json!({})).is_err());
+    }
+
     #[test]
     fn test_get_type_name() {
         register_remote_message::<TestMsg>("TestMsg");
@@ -212,4 +370,63 @@ mod tests {
 
         assert_eq!(get_type_name(&msg), Some("TestMsg".to_string()));
     }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    struct ProtoMsg {
+        #[prost(int32, tag = "1")]
+        value: i32,
+        #[prost(string, tag = "2")]
+        name: String,
+    }
+    define_message!(ProtoMsg);
+
+    #[test]
+    fn test_register_proto() {
+        register_remote_message_proto::<ProtoMsg>("ProtoMsg");
+        register_remote_message::<TestMsg>("TestMsg");
+        assert_eq!(message_codec("ProtoMsg"), Some(Codec::Protobuf));
+        assert_eq!(message_codec("TestMsg"), None);
+
+        let msg = ProtoMsg { value: 1, name: "abc".to_string() };
+        let value = serialize_message(&msg, "ProtoMsg");
+        assert_eq!(value, "08011203616263");
+        let msg = deserialize_message("ProtoMsg", value);
+        assert_eq!(msg.as_any().downcast_ref::<ProtoMsg>().unwrap().name, "abc");
+
+        // Encoded straight from the message, and decoded once
+        let bytes = encode_message("ProtoMsg", Codec::Protobuf, msg.as_ref()).unwrap();
+        let (value, decoded) = decode_message("ProtoMsg", Codec::Protobuf, &bytes).unwrap();
+        assert_eq!(value, "08011203616263");
+        assert_eq!(decoded.as_any().downcast_ref::<ProtoMsg>().unwrap().value, 1);
+
+        // Malformed bodies are errors, not panics
+        assert!(try_deserialize_message("ProtoMsg", Value::String("zz".to_string())).is_err());
+        assert!(try_deserialize_message("ProtoMsg", Value::String("ff".to_string())).is_err());
+        assert!(decode_message("ProtoMsg", Codec::Protobuf, &[0xff]).is_none());
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
+    struct PackedMsg {
+        value: i32,
+        name: String,
+    }
+    define_message!(PackedMsg);
+
+    #[test]
+    fn test_register_msgpack() {
+        register_remote_message_msgpack::<PackedMsg>("PackedMsg");
+        assert_eq!(message_codec("PackedMsg"), Some(Codec::MessagePack));
+
+        // Serialized to JSON in the process, encoded only on the wire
+        let msg = PackedMsg { value: 1, name: "abc".to_string() };
+        let value = serialize_message(&msg, "PackedMsg");
+        assert_eq!(value, serde_json::json!({"value": 1, "name": "abc"}));
+        let bytes = encode_message("PackedMsg", Codec::MessagePack, &msg).unwrap();
+        assert_eq!(bytes, rmp_serde::to_vec_named(&msg).unwrap());
+        let (decoded_value, decoded) = decode_message("PackedMsg", Codec::MessagePack, &bytes).unwrap();
+        assert_eq!(decoded_value, value);
+        assert_eq!(decoded.as_any().downcast_ref::<PackedMsg>(), Some(&msg));
+        assert_eq!(encode_message("PackedMsg", Codec::Protobuf, &msg), None);
+        assert!(decode_message("PackedMsg", Codec::Bincode, &bytes[..3]).is_none());
+    }
 }
@@ -0,0 +1,216 @@
+/*
+
+THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
+
+Copyright 2025 Vincent Maciejewski, & M2 Tech
+Contact:
+v@m2te.ch
+mayeski@gmail.com
+https://www.linkedin.com/in/vmayeski/
+http://m2te.ch/
+
+*/
+
+//! Supervision of failed actors.
+//!
+//! A handler that panics (or times out, see
+//! `Manager::with_default_handler_timeout`) fails its actor. The runtime
+//! catches the failure, routes the message to the dead letter queue and
+//! applies the actor's `SupervisionStrategy`, set in `ThreadConfig` or with
+//! `Manager::set_supervisor`.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::actor::{Actor, ActorRef};
+use crate::define_message;
+
+/// What to do when an actor's handler fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SupervisionStrategy {
+    /// Replace the actor with a new instance from its factory, at most
+    /// `max_retries` times within `window`; after that it is stopped.
+    /// Without a factory (see `Manager::set_supervisor`) the actor is stopped.
+    Restart { max_retries: u32, window: Duration },
+    /// Stop the actor
+    #[default]
+    Stop,
+    /// Stop the actor and fail its parent (for children from
+    /// `ActorContext::spawn_child`; a top-level actor is just stopped)
+    Escalate,
+}
+
+/// Creates a fresh instance of an actor for `SupervisionStrategy::Restart`
+pub type ActorFactory = Box<dyn Fn() -> Box<dyn Actor> + Send>;
+
+/// Why an actor was replaced by a new instance, see `Actor::on_restart`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestartReason {
+    /// The previous instance failed; `message` says why, e.g.
+    /// "handler panicked: boom" or "handler timed out"
+    Panic { message: String },
+    /// Restarted on request, see `Manager::restart_actor`
+    Explicit,
+}
+
+/// Sent by `Manager::restart_actor`; intercepted by the actor's runtime.
+pub(crate) struct RestartActor;
+define_message!(RestartActor);
+
+/// Sent by `Manager::set_supervisor` and `ActorRef::upgrade_to_supervised`;
+/// intercepted by the actor's runtime.
+pub(crate) struct SetSupervisor {
+    pub(crate) strategy: SupervisionStrategy,
+    pub(crate) factory: Option<ActorFactory>,
+    /// Counts restarts from now on
+    pub(crate) restart_count: Arc<AtomicU32>,
+}
+define_message!(SetSupervisor);
+
+/// Error returned by `ActorRef::upgrade_to_supervised`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpgradeError {
+    /// Only local actors can be supervised
+    NotLocal(String),
+    /// The actor's mailbox is closed
+    ActorStopped(String),
+}
+
+impl std::fmt::Display for UpgradeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpgradeError::NotLocal(name) => write!(f, "Actor is not local: {}", name),
+            UpgradeError::ActorStopped(name) => write!(f, "Actor stopped: {}", name),
+        }
+    }
+}
+
+impl std::error::Error for UpgradeError {}
+
+/// Reference to an actor supervised after it was created, from
+/// `ActorRef::upgrade_to_supervised`.
+#[derive(Clone)]
+pub struct SupervisedActorRef {
+    inner: ActorRef,
+    restart_count: Arc<AtomicU32>,
+}
+
+impl SupervisedActorRef {
+    pub(crate) fn new(inner: ActorRef, restart_count: Arc<AtomicU32>) -> Self {
+        SupervisedActorRef { inner, restart_count }
+    }
+
+    /// Get the actor's name
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    /// How many times the actor has been restarted since the upgrade
+    pub fn restart_count(&self) -> u32 {
+        self.restart_count.load(Ordering::Relaxed)
+    }
+
+    /// Get an untyped ActorRef for this actor
+    pub fn actor_ref(&self) -> ActorRef {
+        self.inner.clone()
+    }
+}
+
+/// Sent to a parent when a child escalates its failure.
+///
+/// Intercepted by the parent's runtime, which fails as well.
+pub(crate) struct ChildFailed {
+    pub(crate) child: String,
+    pub(crate) reason: String,
+}
+define_message!(ChildFailed);
+
+/// Outcome of a failure
+pub(crate) enum Directive {
+    Restart(Box<dyn Actor>),
+    Stop,
+    Escalate,
+}
+
+/// A runtime's strategy, factory and recent restarts.
+#[derive(Default)]
+pub(crate) struct Supervisor {
+    pub(crate) strategy: SupervisionStrategy,
+    pub(crate) factory: Option<ActorFactory>,
+    /// Restarts so far, shared with a `SupervisedActorRef`
+    pub(crate) restart_count: Arc<AtomicU32>,
+    /// When the actor was restarted, within the current window
+    restarts: VecDeque<Instant>,
+}
+
+impl Supervisor {
+    /// Decide what to do about a failure at `now`.
+    pub(crate) fn on_failure(&mut self, now: Instant) -> Directive {
+        match self.strategy {
+            SupervisionStrategy::Stop => Directive::Stop,
+            SupervisionStrategy::Escalate => Directive::Escalate,
+            SupervisionStrategy::Restart { max_retries, window } => {
+                while self
+                    .restarts
+                    .front()
+                    .is_some_and(|&restart| now.duration_since(restart) > window)
+                {
+                    self.restarts.pop_front();
+                }
+                let factory = match &self.factory {
+                    Some(factory) if self.restarts.len() < max_retries as usize => factory,
+                    _ => return Directive::Stop,
+                };
+                self.restarts.push_back(now);
+                self.restart_count.fetch_add(1, Ordering::Relaxed);
+                Directive::Restart(factory())
+            }
+        }
+    }
+
+    /// A new instance for an explicit restart, if there is a factory.
+    ///
+    /// Counted in `restart_count` but not against the strategy's limit.
+    pub(crate) fn restart_now(&mut self) -> Option<Box<dyn Actor>> {
+        let factory = self.factory.as_ref()?;
+        self.restart_count.fetch_add(1, Ordering::Relaxed);
+        Some(factory())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct DummyActor;
+    impl Actor for DummyActor {}
+
+    #[test]
+    fn test_restart_limit_within_window() {
+        let mut supervisor = Supervisor {
+            strategy: SupervisionStrategy::Restart { max_retries: 2, window: Duration::from_secs(10) },
+            factory: Some(Box::new(|| Box::new(DummyActor))),
+            ..Default::default()
+        };
+        let start = Instant::now();
+
+        assert!(matches!(supervisor.on_failure(start), Directive::Restart(_)));
+        assert!(matches!(supervisor.on_failure(start + Duration::from_secs(1)), Directive::Restart(_)));
+        assert!(matches!(supervisor.on_failure(start + Duration::from_secs(2)), Directive::Stop));
+
+        // Restarts older than the window no longer count
+        assert!(matches!(supervisor.on_failure(start + Duration::from_secs(12)), Directive::Restart(_)));
+        assert_eq!(supervisor.restart_count.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_restart_without_factory_stops() {
+        let mut supervisor = Supervisor {
+            strategy: SupervisionStrategy::Restart { max_retries: 3, window: Duration::from_secs(1) },
+            ..Default::default()
+        };
+        assert!(matches!(supervisor.on_failure(Instant::now()), Directive::Stop));
+    }
+}
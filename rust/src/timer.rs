@@ -14,14 +14,20 @@ http://m2te.ch/
 //! Timer utilities for scheduling delayed and periodic messages.
 //!
 //! Timers send Timeout messages to actors after a specified delay.
+//! `ActorContext::schedule_once` and `schedule_periodic` instead share one
+//! timer thread per Manager and send any message.
 
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::BinaryHeap;
+use std::panic::{self as std_panic, AssertUnwindSafe};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crate::actor::ActorRef;
+use crate::actor::{ActorRef, Envelope, LocalActorRef};
 use crate::messages::Timeout;
+use crate::Message;
 
 /// A timer that sends Timeout messages to an actor.
 ///
@@ -29,9 +35,11 @@ use crate::messages::Timeout;
 /// ```
 /// use actors::{Timer, ActorRef};
 /// use std::time::Duration;
+/// # let (tx, _rx) = std::sync::mpsc::channel();
+/// # let actor_ref = ActorRef::new(tx, "worker".to_string());
 ///
 /// // One-shot timer
-/// let timer = Timer::once(actor_ref, Duration::from_secs(5), 42);
+/// let timer = Timer::once(actor_ref.clone(), Duration::from_secs(5), 42);
 ///
 /// // Periodic timer
 /// let timer = Timer::periodic(actor_ref, Duration::from_millis(100), 1);
@@ -55,7 +63,7 @@ impl Timer {
         let handle = thread::spawn(move || {
             thread::sleep(delay);
             if running_clone.load(Ordering::SeqCst) {
-                actor.send(Box::new(Timeout::new(id)), None);
+                let _ = actor.try_send(Box::new(Timeout::new(id)), None);
             }
         });
 
@@ -77,7 +85,7 @@ impl Timer {
             while running_clone.load(Ordering::SeqCst) {
                 thread::sleep(interval);
                 if running_clone.load(Ordering::SeqCst) {
-                    actor.send(Box::new(Timeout::new(id)), None);
+                    let _ = actor.try_send(Box::new(Timeout::new(id)), None);
                 }
             }
         });
@@ -110,6 +118,262 @@ impl Drop for Timer {
     }
 }
 
+/// Creates the message of each tick of a periodic timer
+pub(crate) type MessageFactory = Box<dyn Fn() -> Box<dyn Message> + Send>;
+
+/// What a scheduled timer sends
+enum Payload {
+    Once(Box<dyn Message>),
+    Periodic { interval: Duration, factory: MessageFactory },
+    /// Put back in the target's mailbox as it is, see `PeekResult::Defer`
+    Deferred(Envelope),
+}
+
+/// A timer waiting in a `TimerService`'s heap
+struct Scheduled {
+    due: Instant,
+    /// Orders timers due at the same instant by when they were scheduled
+    seq: u64,
+    target: ActorRef,
+    payload: Payload,
+    /// Shared with the timer's `TimerHandle`
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Scheduled {
+    /// Send the timer's message, returning the timer again if it is periodic.
+    ///
+    /// Cancelled timers, timers of stopped actors and periodic timers whose
+    /// factory panics or whose next tick is out of range are dropped.
+    fn fire(self, now: Instant) -> Option<Scheduled> {
+        if self.cancelled.load(Ordering::SeqCst) {
+            return None;
+        }
+        let Scheduled { due, seq, target, payload, cancelled } = self;
+        match payload {
+            Payload::Deferred(envelope) => {
+                deliver_deferred(&target, envelope);
+                None
+            }
+            _ if !target.is_alive() => None,
+            Payload::Once(msg) => {
+                let _ = target.try_send(msg, None);
+                None
+            }
+            Payload::Periodic { interval, factory } => {
+                // A panicking factory must not take down the timer thread of every actor
+                let msg = std_panic::catch_unwind(AssertUnwindSafe(&factory)).ok()?;
+                let _ = target.try_send(msg, None);
+                // Skip ticks missed while the thread was busy rather than catching up
+                let next = due.checked_add(interval)?;
+                let due = if next > now { next } else { now.checked_add(interval)? };
+                Some(Scheduled { due, seq, target, payload: Payload::Periodic { interval, factory }, cancelled })
+            }
+        }
+    }
+}
+
+/// Put a deferred envelope back in its actor's mailbox; it becomes a dead
+/// letter if the actor has stopped meanwhile.
+fn deliver_deferred(target: &ActorRef, envelope: Envelope) {
+    if let ActorRef::Local(target) = target {
+        let _ = target.enqueue(envelope);
+    }
+}
+
+/// Longest delay before a timer fires; longer delays are clamped to it, as
+/// an `Instant` that far ahead may not be representable
+const MAX_TIMER_DELAY: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+
+/// When a timer started now fires after `delay`
+fn due_after(delay: Duration) -> Instant {
+    let now = Instant::now();
+    now.checked_add(delay.min(MAX_TIMER_DELAY)).unwrap_or(now)
+}
+
+/// Timers of actors not run by a Manager, and of every `ActorRef::ask`,
+/// on one thread started with the first timer
+pub(crate) fn shared_timers() -> &'static TimerService {
+    static TIMERS: OnceLock<TimerService> = OnceLock::new();
+    TIMERS.get_or_init(TimerService::default)
+}
+
+// Reversed, so the BinaryHeap pops the earliest timer first
+impl Ord for Scheduled {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        other.due.cmp(&self.due).then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Scheduled {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Scheduled {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
+    }
+}
+
+impl Eq for Scheduled {}
+
+#[derive(Default)]
+struct TimerQueue {
+    heap: BinaryHeap<Scheduled>,
+    next_seq: u64,
+    /// Set by `TimerService::stop`; later timers are dropped
+    stopped: bool,
+}
+
+#[derive(Default)]
+struct TimerState {
+    queue: Mutex<TimerQueue>,
+    /// Signalled when a timer is scheduled or the service stops
+    wakeup: Condvar,
+    /// Started with the first timer
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// A Manager's timer thread, shared with its actor runtimes, see
+/// `ActorContext::schedule_once`.
+#[derive(Clone, Default)]
+pub(crate) struct TimerService {
+    state: Arc<TimerState>,
+}
+
+impl TimerService {
+    /// Send `msg` to `target` after `delay`, which is clamped to `MAX_TIMER_DELAY`.
+    pub(crate) fn schedule_once(&self, target: ActorRef, delay: Duration, msg: Box<dyn Message>) -> TimerHandle {
+        self.schedule(target, due_after(delay), Payload::Once(msg))
+    }
+
+    /// Send `msg` to `target` at `due`.
+    pub(crate) fn schedule_at(&self, target: ActorRef, due: Instant, msg: Box<dyn Message>) -> TimerHandle {
+        self.schedule(target, due, Payload::Once(msg))
+    }
+
+    /// Put `envelope` back in `target`'s mailbox at `due`. Not cancellable.
+    pub(crate) fn schedule_deferred(&self, target: LocalActorRef, due: Instant, envelope: Envelope) {
+        self.push(ActorRef::Local(target), due, Payload::Deferred(envelope), Arc::default());
+    }
+
+    /// Send a message from `factory` to `target` every `interval`.
+    pub(crate) fn schedule_periodic(&self, target: ActorRef, interval: Duration, factory: MessageFactory) -> TimerHandle {
+        assert!(!interval.is_zero(), "timer interval must be positive");
+        self.schedule(target, due_after(interval), Payload::Periodic { interval, factory })
+    }
+
+    fn schedule(&self, target: ActorRef, due: Instant, payload: Payload) -> TimerHandle {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.push(target, due, payload, Arc::clone(&cancelled));
+        TimerHandle { cancelled }
+    }
+
+    fn push(&self, target: ActorRef, due: Instant, payload: Payload, cancelled: Arc<AtomicBool>) {
+        let mut queue = self.state.queue.lock().unwrap();
+        if queue.stopped {
+            drop(queue);
+            if let Payload::Deferred(envelope) = payload {
+                deliver_deferred(&target, envelope);
+            }
+            return;
+        }
+        let seq = queue.next_seq;
+        queue.next_seq += 1;
+        queue.heap.push(Scheduled { due, seq, target, payload, cancelled });
+        // Cancelled timers wait in the heap until due; drop them as it doubles
+        if queue.heap.len() >= 64 && queue.heap.len().is_power_of_two() {
+            queue.heap.retain(|timer| !timer.cancelled.load(Ordering::SeqCst));
+        }
+        let mut thread = self.state.thread.lock().unwrap();
+        if thread.is_none() {
+            let state = Arc::clone(&self.state);
+            *thread = Some(thread::spawn(move || run_timers(&state)));
+        }
+        self.state.wakeup.notify_one();
+    }
+
+    /// Drop all timers and wait for the timer thread to finish.
+    ///
+    /// Deferred messages are still put back in their mailbox at once, so
+    /// those of stopped actors become dead letters.
+    pub(crate) fn stop(&self) {
+        let dropped = {
+            let mut queue = self.state.queue.lock().unwrap();
+            queue.stopped = true;
+            std::mem::take(&mut queue.heap)
+        };
+        for timer in dropped {
+            if let Payload::Deferred(envelope) = timer.payload {
+                deliver_deferred(&timer.target, envelope);
+            }
+        }
+        self.state.wakeup.notify_one();
+        if let Some(thread) = self.state.thread.lock().unwrap().take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// The timer thread: sleep until the earliest timer is due, then fire it.
+fn run_timers(state: &TimerState) {
+    let mut queue = state.queue.lock().unwrap();
+    loop {
+        if queue.stopped {
+            return;
+        }
+        let now = Instant::now();
+        let mut due = Vec::new();
+        while queue.heap.peek().is_some_and(|timer| timer.due <= now) {
+            due.extend(queue.heap.pop());
+        }
+        if due.is_empty() {
+            queue = match queue.heap.peek().map(|timer| timer.due - now) {
+                Some(wait) => state.wakeup.wait_timeout(queue, wait).unwrap().0,
+                None => state.wakeup.wait(queue).unwrap(),
+            };
+            continue;
+        }
+
+        // Send without the lock, so factories may schedule timers too
+        drop(queue);
+        let rescheduled: Vec<Scheduled> = due.into_iter().filter_map(|timer| timer.fire(now)).collect();
+        queue = state.queue.lock().unwrap();
+        if !queue.stopped {
+            queue.heap.extend(rescheduled);
+        }
+    }
+}
+
+/// Handle to a timer from `ActorContext::schedule_once` or
+/// `ActorContext::schedule_periodic`.
+///
+/// The timer is cancelled when `cancel()` is called or the handle is
+/// dropped, so keep it for as long as the timer should run.
+pub struct TimerHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl TimerHandle {
+    /// Cancel the timer; a message already sent is not taken back.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Check if the timer has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for TimerHandle {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
 /// Timer ID generator for unique timer IDs
 static NEXT_TIMER_ID: AtomicU64 = AtomicU64::new(1);
 
@@ -142,4 +406,62 @@ mod tests {
         timer.cancel();
         assert!(!timer.is_running());
     }
+
+    #[test]
+    fn test_timer_service_order_and_cancel() {
+        let (tx, rx) = channel();
+        let actor_ref = ActorRef::new(tx, "test".to_string());
+        let timers = TimerService::default();
+
+        let _late = timers.schedule_once(actor_ref.clone(), Duration::from_millis(40), Box::new(Timeout::new(2)));
+        let _early = timers.schedule_once(actor_ref.clone(), Duration::from_millis(10), Box::new(Timeout::new(1)));
+        let cancelled = timers.schedule_once(actor_ref.clone(), Duration::from_millis(20), Box::new(Timeout::new(3)));
+        drop(cancelled);
+
+        let ids: Vec<u64> = (0..2)
+            .map(|_| {
+                let envelope = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+                envelope.msg.as_any().downcast_ref::<Timeout>().unwrap().id
+            })
+            .collect();
+        assert_eq!(ids, vec![1, 2]);
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+        timers.stop();
+    }
+
+    #[test]
+    fn test_timer_service_periodic() {
+        let (tx, rx) = channel();
+        let actor_ref = ActorRef::new(tx, "test".to_string());
+        let timers = TimerService::default();
+
+        let ticks = timers.schedule_periodic(actor_ref, Duration::from_millis(5), Box::new(|| Box::new(Timeout::new(7))));
+        for _ in 0..3 {
+            assert!(rx.recv_timeout(Duration::from_secs(1)).is_ok());
+        }
+        drop(ticks);
+        // At most a tick already in flight arrives after the handle is dropped
+        thread::sleep(Duration::from_millis(20));
+        while rx.try_recv().is_ok() {}
+        assert!(rx.recv_timeout(Duration::from_millis(30)).is_err());
+        timers.stop();
+    }
+
+    #[test]
+    fn test_timer_service_survives_panicking_factory_and_huge_delay() {
+        let (tx, rx) = channel();
+        let actor_ref = ActorRef::new(tx, "test".to_string());
+        let timers = TimerService::default();
+
+        let _never = timers.schedule_once(actor_ref.clone(), Duration::MAX, Box::new(Timeout::new(1)));
+        let factory: MessageFactory = Box::new(|| panic!("boom"));
+        let _panics = timers.schedule_periodic(actor_ref.clone(), Duration::from_millis(5), factory);
+        thread::sleep(Duration::from_millis(20));
+
+        // The timer thread still fires other timers
+        let _later = timers.schedule_once(actor_ref, Duration::from_millis(5), Box::new(Timeout::new(2)));
+        let envelope = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(envelope.msg.as_any().downcast_ref::<Timeout>().unwrap().id, 2);
+        timers.stop();
+    }
 }
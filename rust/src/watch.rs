@@ -0,0 +1,133 @@
+/*
+
+THIS SOFTWARE IS OPEN SOURCE UNDER THE MIT LICENSE
+
+Copyright 2025 Vincent Maciejewski, & M2 Tech
+Contact:
+v@m2te.ch
+mayeski@gmail.com
+https://www.linkedin.com/in/vmayeski/
+http://m2te.ch/
+
+*/
+
+//! Death watch: notifications when another actor stops.
+//!
+//! An actor calls `ActorContext::watch` with the ref of an actor run by the
+//! same Manager. When the watched actor's thread exits, each watcher receives
+//! a `Terminated` message saying who stopped and why.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::actor::ActorRef;
+use crate::define_message;
+
+/// Why a watched actor stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminationReason {
+    /// It handled Shutdown, or its mailbox was closed
+    Normal,
+    /// A handler panicked and its supervision strategy stopped it
+    Panicked,
+    /// It was stopped for a handler timeout or a failed child
+    Killed,
+}
+
+/// Sent to each watcher when a watched actor stops.
+pub struct Terminated {
+    pub who: ActorRef,
+    pub reason: TerminationReason,
+}
+define_message!(Terminated);
+
+#[derive(Default)]
+struct WatchState {
+    /// Watched actor name -> its watchers
+    watchers: HashMap<String, Vec<ActorRef>>,
+    /// Actors that have stopped, so late watchers are told right away
+    stopped: HashMap<String, TerminationReason>,
+}
+
+/// A Manager's watchers, shared with its actors' contexts.
+#[derive(Clone, Default)]
+pub(crate) struct DeathWatch {
+    state: Arc<Mutex<WatchState>>,
+}
+
+impl DeathWatch {
+    /// Tell `watcher` when `target` stops (now, if it already has).
+    pub(crate) fn watch(&self, target: ActorRef, watcher: ActorRef) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(&reason) = state.stopped.get(target.name()) {
+            drop(state);
+            let _ = watcher.try_send(Box::new(Terminated { who: target, reason }), None);
+            return;
+        }
+        let watchers = state.watchers.entry(target.name().to_string()).or_default();
+        if !watchers.iter().any(|w| w.name() == watcher.name()) {
+            watchers.push(watcher);
+        }
+    }
+
+    /// Stop telling the actor named `watcher` about `target`.
+    pub(crate) fn unwatch(&self, target: &str, watcher: &str) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(watchers) = state.watchers.get_mut(target) {
+            watchers.retain(|w| w.name() != watcher);
+        }
+    }
+
+    /// Record that `who` stopped and send `Terminated` to its watchers.
+    pub(crate) fn notify(&self, who: ActorRef, reason: TerminationReason) {
+        let watchers = {
+            let mut state = self.state.lock().unwrap();
+            state.stopped.insert(who.name().to_string(), reason);
+            state.watchers.remove(who.name()).unwrap_or_default()
+        };
+        for watcher in watchers {
+            let terminated = Terminated {
+                who: who.clone(),
+                reason,
+            };
+            // A full mailbox sends it to the dead letter queue instead
+            let _ = watcher.try_send(Box::new(terminated), None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_watch_unwatch_and_late_watch() {
+        let (watcher_tx, watcher_rx) = channel();
+        let watcher = ActorRef::new(watcher_tx, "watcher".to_string());
+        let (target_tx, _target_rx) = channel();
+        let target = ActorRef::new(target_tx, "target".to_string());
+        let (other_tx, _other_rx) = channel();
+        let other = ActorRef::new(other_tx, "other".to_string());
+
+        let death_watch = DeathWatch::default();
+        death_watch.watch(target.clone(), watcher.clone());
+        death_watch.watch(target.clone(), watcher.clone());
+        death_watch.watch(other.clone(), watcher.clone());
+        death_watch.unwatch("other", "watcher");
+
+        death_watch.notify(other.clone(), TerminationReason::Normal);
+        death_watch.notify(target.clone(), TerminationReason::Panicked);
+        let envelope = watcher_rx.try_recv().unwrap();
+        let terminated = envelope.msg.as_any().downcast_ref::<Terminated>().unwrap();
+        assert_eq!(terminated.who.name(), "target");
+        assert_eq!(terminated.reason, TerminationReason::Panicked);
+        assert!(watcher_rx.try_recv().is_err());
+
+        // Watching an actor that already stopped notifies at once
+        death_watch.watch(other, watcher);
+        let envelope = watcher_rx.try_recv().unwrap();
+        let terminated = envelope.msg.as_any().downcast_ref::<Terminated>().unwrap();
+        assert_eq!(terminated.reason, TerminationReason::Normal);
+    }
+}